@@ -1,5 +1,6 @@
-use tyl_errors::TylError;
-use tyl_tracing::{Environment, SimpleTracer, TraceConfig, TracingManager};
+use tyl_tracing::{
+    format_trace, Environment, FormatOptions, SimpleTracer, TraceConfig, TracingManager,
+};
 
 fn main() -> Result<(), tyl_errors::TylError> {
     println!("=== TYL Tracing Basic Usage ===\n");
@@ -108,20 +109,8 @@ fn span_hierarchy_example() -> Result<(), tyl_errors::TylError> {
         completed_spans.len()
     );
 
-    for span in &completed_spans {
-        let indent = if span.parent_span_id.is_some() {
-            "  └─ "
-        } else {
-            "─ "
-        };
-        println!(
-            "{}[{}] {} ({}ms)",
-            indent,
-            &span.span_id[..8],
-            span.operation_name,
-            span.duration_ms().unwrap_or(0)
-        );
-    }
+    let options = FormatOptions::new().with_ids(true).with_all_attributes();
+    print!("{}", format_trace(&completed_spans, options));
 
     println!();
     Ok(())
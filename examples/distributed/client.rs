@@ -0,0 +1,85 @@
+//! Distributed example, calling service
+//!
+//! Calls the server from `distributed-server` with its span context and a
+//! `user.id` baggage entry in the request headers, then prints the reply and
+//! the `traceresponse` naming the server span that answered.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use tyl_errors::TylError;
+use tyl_tracing::{
+    BatchSpanProcessor, Context, ExporterConfig, HttpSpan, OtlpHttpExporter, SimpleTracer,
+    TraceConfig, TRACERESPONSE_HEADER,
+};
+
+pub const SERVICE_NAME: &str = "frontend";
+pub const SERVER_ADDRESS: &str = "127.0.0.1:7878";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let endpoint =
+        std::env::var("OTLP_ENDPOINT").unwrap_or_else(|_| "http://localhost:4318".to_string());
+    let tracer = tracer(&endpoint)?;
+    let reply = call(&tracer, SERVER_ADDRESS, "alice")?;
+    println!("{}", reply.body);
+    if let Some(traceresponse) = reply.traceresponse {
+        println!("answered by {}", traceresponse);
+    }
+    tracer.flush()?;
+    Ok(())
+}
+
+/// Tracer exporting to the collector at `endpoint`
+pub fn tracer(endpoint: &str) -> Result<SimpleTracer, TylError> {
+    let config = TraceConfig::new(SERVICE_NAME).with_sampling_rate(1.0);
+    let exporter = OtlpHttpExporter::from_config(SERVICE_NAME, &ExporterConfig::new(endpoint))?;
+    Ok(SimpleTracer::new(config).with_batch_processor(BatchSpanProcessor::new(exporter)))
+}
+
+/// What the server sent back
+pub struct Reply {
+    pub body: String,
+    pub traceresponse: Option<String>,
+}
+
+/// `GET /greet` on `address` on behalf of `user`
+pub fn call(tracer: &SimpleTracer, address: &str, user: &str) -> std::io::Result<Reply> {
+    // Baggage for this call only; it travels in the `baggage` header
+    let baggage = HashMap::from([("user.id".to_string(), user.to_string())]);
+    let _request = Context::default().with_baggage(baggage).attach();
+    let span = HttpSpan::client(tracer, "GET", &format!("http://{}/greet", address));
+
+    let mut headers: HashMap<String, String> = HashMap::new();
+    span.inject(&mut headers);
+    let mut request = format!(
+        "GET /greet HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        address
+    );
+    for (name, value) in &headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+
+    let mut stream = TcpStream::connect(address)?;
+    stream.write_all(request.as_bytes())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status_code = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .unwrap_or(502);
+    span.set_status_code(status_code);
+    let traceresponse = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case(TRACERESPONSE_HEADER)
+            .then(|| value.trim().to_string())
+    });
+    Ok(Reply {
+        body: body.to_string(),
+        traceresponse,
+    })
+}
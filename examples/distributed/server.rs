@@ -0,0 +1,100 @@
+//! Distributed example, downstream service
+//!
+//! Run the server, then the client in another terminal:
+//!
+//! ```sh
+//! cargo run --example distributed-server
+//! cargo run --example distributed-client
+//! ```
+//!
+//! Both processes export to `OTLP_ENDPOINT` (default `http://localhost:4318`),
+//! so the collector receives one trace spanning the client and server.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use tyl_errors::TylError;
+use tyl_tracing::{
+    BatchSpanProcessor, Context, ExporterConfig, HttpSpan, OtlpHttpExporter, SimpleTracer,
+    TraceConfig,
+};
+
+pub const SERVICE_NAME: &str = "greeter";
+pub const ADDRESS: &str = "127.0.0.1:7878";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let endpoint =
+        std::env::var("OTLP_ENDPOINT").unwrap_or_else(|_| "http://localhost:4318".to_string());
+    let tracer = tracer(&endpoint)?;
+    let listener = TcpListener::bind(ADDRESS)?;
+    println!("{} listening on http://{}", SERVICE_NAME, ADDRESS);
+    for stream in listener.incoming() {
+        handle(&tracer, stream?)?;
+        tracer.flush()?;
+    }
+    Ok(())
+}
+
+/// Tracer exporting to the collector at `endpoint`; the caller's `user.id`
+/// baggage is copied onto every span
+pub fn tracer(endpoint: &str) -> Result<SimpleTracer, TylError> {
+    let config = TraceConfig::new(SERVICE_NAME)
+        .with_sampling_rate(1.0)
+        .with_baggage_attributes(["user.id"]);
+    let exporter = OtlpHttpExporter::from_config(SERVICE_NAME, &ExporterConfig::new(endpoint))?;
+    Ok(SimpleTracer::new(config).with_batch_processor(BatchSpanProcessor::new(exporter)))
+}
+
+/// Serve one `GET /greet` request as part of the caller's trace
+pub fn handle(tracer: &SimpleTracer, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_string(), value.trim().to_string());
+        }
+    }
+    let mut request_line = request_line.split_whitespace();
+    let method = request_line.next().unwrap_or("GET");
+    let path = request_line.next().unwrap_or("/");
+
+    // The caller's baggage is visible to every span of this request and is
+    // dropped with the guard; the tracer's own baggage is left alone
+    let _request = Context::from_request(&headers).attach();
+    // Continues the caller's trace: same trace ID, the client span as parent
+    let span = HttpSpan::server_from_headers(tracer, method, "/greet", &headers);
+    span.set_path(path);
+    let _current = Context::with_span(span.span().span_id()).attach();
+
+    let user = {
+        let _lookup = span.span().child("load_profile");
+        tracer
+            .get_baggage("user.id")
+            .unwrap_or_else(|| "stranger".to_string())
+    };
+    let body = format!("Hello, {}!", user);
+
+    // Lets the caller (or a browser) see which server span answered
+    let mut response_headers: HashMap<String, String> = HashMap::new();
+    if let Some(context) = tracer.span_context(span.span().span_id()) {
+        context.inject_response(&mut response_headers);
+    }
+    span.set_status_code(200);
+
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (name, value) in &response_headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+    (&stream).write_all(response.as_bytes())
+}
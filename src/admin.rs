@@ -0,0 +1,177 @@
+//! Runtime administration module
+//!
+//! Contains the TracingAdmin trait (port) for live tuning during incidents:
+//! changing the sampling rate or filter, flushing, clearing in-memory stores,
+//! toggling tracing, and auditing sampling decisions. Services expose it through their admin endpoints,
+//! typically as an `Arc<dyn TracingAdmin>` next to the `DynTracer`.
+
+use crate::sampling_audit::SamplingDecision;
+use crate::tracer::{NoopTracer, SimpleTracer, Tracer, TracingResult};
+use std::sync::Arc;
+
+/// Port (Interface) - Runtime control over a tracer
+pub trait TracingAdmin: Send + Sync {
+    /// Sampling rate for spans started from now on (0.0 - 1.0)
+    fn set_sampling_rate(&self, rate: f64) -> TracingResult<()>;
+
+    /// Replace the span filter, e.g. `checkout=on,health*=off`
+    fn set_filter_str(&self, filter: &str) -> TracingResult<()>;
+
+    /// Export buffered spans now
+    fn flush(&self) -> TracingResult<()>;
+
+    /// Drop completed spans held in memory; returns how many were dropped
+    fn clear_stores(&self) -> usize;
+
+    fn set_enabled(&self, enabled: bool);
+
+    fn is_enabled(&self) -> bool;
+
+    /// Keep the last `capacity` sampling decisions; 0 turns the audit off
+    fn set_sampling_audit(&self, capacity: usize);
+
+    /// Audited sampling decisions, oldest first
+    fn sampling_decisions(&self) -> Vec<SamplingDecision>;
+}
+
+impl TracingAdmin for SimpleTracer {
+    fn set_sampling_rate(&self, rate: f64) -> TracingResult<()> {
+        SimpleTracer::set_sampling_rate(self, rate)
+    }
+
+    fn set_filter_str(&self, filter: &str) -> TracingResult<()> {
+        SimpleTracer::set_filter_str(self, filter)
+    }
+
+    fn flush(&self) -> TracingResult<()> {
+        SimpleTracer::flush(self)
+    }
+
+    fn clear_stores(&self) -> usize {
+        self.clear_completed_spans()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        SimpleTracer::set_enabled(self, enabled)
+    }
+
+    fn is_enabled(&self) -> bool {
+        SimpleTracer::is_enabled(self)
+    }
+
+    fn set_sampling_audit(&self, capacity: usize) {
+        SimpleTracer::set_sampling_audit(self, capacity)
+    }
+
+    fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        SimpleTracer::sampling_decisions(self)
+    }
+}
+
+/// Accepts every command; there is nothing to tune
+impl TracingAdmin for NoopTracer {
+    fn set_sampling_rate(&self, _rate: f64) -> TracingResult<()> {
+        Ok(())
+    }
+
+    fn set_filter_str(&self, _filter: &str) -> TracingResult<()> {
+        Ok(())
+    }
+
+    fn flush(&self) -> TracingResult<()> {
+        Ok(())
+    }
+
+    fn clear_stores(&self) -> usize {
+        0
+    }
+
+    fn set_enabled(&self, _enabled: bool) {}
+
+    fn is_enabled(&self) -> bool {
+        false
+    }
+
+    fn set_sampling_audit(&self, _capacity: usize) {}
+
+    fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        Vec::new()
+    }
+}
+
+impl TracingAdmin for Tracer {
+    fn set_sampling_rate(&self, rate: f64) -> TracingResult<()> {
+        self.admin().set_sampling_rate(rate)
+    }
+
+    fn set_filter_str(&self, filter: &str) -> TracingResult<()> {
+        self.admin().set_filter_str(filter)
+    }
+
+    fn flush(&self) -> TracingResult<()> {
+        self.admin().flush()
+    }
+
+    fn clear_stores(&self) -> usize {
+        self.admin().clear_stores()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.admin().set_enabled(enabled)
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.admin().is_enabled()
+    }
+
+    fn set_sampling_audit(&self, capacity: usize) {
+        self.admin().set_sampling_audit(capacity)
+    }
+
+    fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        self.admin().sampling_decisions()
+    }
+}
+
+impl Tracer {
+    fn admin(&self) -> &dyn TracingAdmin {
+        match self {
+            Tracer::Simple(tracer) => tracer.as_ref(),
+            Tracer::Noop(tracer) => tracer,
+        }
+    }
+}
+
+impl<T: TracingAdmin + ?Sized> TracingAdmin for Arc<T> {
+    fn set_sampling_rate(&self, rate: f64) -> TracingResult<()> {
+        (**self).set_sampling_rate(rate)
+    }
+
+    fn set_filter_str(&self, filter: &str) -> TracingResult<()> {
+        (**self).set_filter_str(filter)
+    }
+
+    fn flush(&self) -> TracingResult<()> {
+        (**self).flush()
+    }
+
+    fn clear_stores(&self) -> usize {
+        (**self).clear_stores()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        (**self).set_enabled(enabled)
+    }
+
+    fn is_enabled(&self) -> bool {
+        (**self).is_enabled()
+    }
+
+    fn set_sampling_audit(&self, capacity: usize) {
+        (**self).set_sampling_audit(capacity)
+    }
+
+    fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        (**self).sampling_decisions()
+    }
+}
@@ -0,0 +1,137 @@
+//! Allocation tracking module
+//!
+//! Contains TracingAllocator, an opt-in global allocator wrapper that counts
+//! allocations per thread. When it is installed, spans that start and end on
+//! the same thread get `alloc.bytes` and `alloc.count` attributes covering
+//! everything allocated on that thread in between (children included).
+//!
+//! ```rust,ignore
+//! #[global_allocator]
+//! static ALLOCATOR: tyl_tracing::TracingAllocator = tyl_tracing::TracingAllocator::new();
+//! ```
+
+use crate::ids::SpanId;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+/// Span attribute: bytes allocated while the span was active
+pub const ALLOC_BYTES_ATTRIBUTE: &str = "alloc.bytes";
+/// Span attribute: number of allocations while the span was active
+pub const ALLOC_COUNT_ATTRIBUTE: &str = "alloc.count";
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Global allocator wrapper counting allocations per thread
+#[derive(Debug, Default)]
+pub struct TracingAllocator<A = System> {
+    inner: A,
+}
+
+impl TracingAllocator<System> {
+    pub const fn new() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl<A> TracingAllocator<A> {
+    /// Count allocations made through another allocator
+    pub const fn wrap(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+fn record(bytes: usize) {
+    // try_with: allocations can happen while thread-locals are torn down
+    let _ = BYTES.try_with(|total| total.set(total.get().wrapping_add(bytes as u64)));
+    let _ = COUNT.try_with(|count| count.set(count.get().wrapping_add(1)));
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to the wrapped allocator
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TracingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size.saturating_sub(layout.size()));
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Allocation totals for the current thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    pub bytes: u64,
+    pub count: u64,
+}
+
+/// Totals allocated by the calling thread since it started
+pub fn thread_allocations() -> AllocationStats {
+    AllocationStats {
+        bytes: BYTES.try_with(Cell::get).unwrap_or(0),
+        count: COUNT.try_with(Cell::get).unwrap_or(0),
+    }
+}
+
+/// Whether a TracingAllocator has seen any allocation in this process
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Allocation counters of active spans
+#[derive(Debug, Default)]
+pub(crate) struct AllocationTracker {
+    starts: Mutex<HashMap<SpanId, (ThreadId, AllocationStats)>>,
+}
+
+impl AllocationTracker {
+    pub(crate) fn start(&self, span_id: SpanId) {
+        if !is_installed() {
+            return;
+        }
+        let mut starts = self.starts.lock().unwrap();
+        starts.insert(
+            span_id,
+            (std::thread::current().id(), AllocationStats::default()),
+        );
+        // Snapshot after the insert so the bookkeeping itself is not counted
+        if let Some(start) = starts.get_mut(&span_id) {
+            start.1 = thread_allocations();
+        }
+    }
+
+    /// Allocations since `start`, if the span ends on the same thread
+    pub(crate) fn finish(&self, span_id: SpanId) -> Option<AllocationStats> {
+        if !is_installed() {
+            return None;
+        }
+        let now = thread_allocations();
+        let (thread, start) = self.starts.lock().unwrap().remove(&span_id)?;
+        (thread == std::thread::current().id()).then(|| AllocationStats {
+            bytes: now.bytes.wrapping_sub(start.bytes),
+            count: now.count.wrapping_sub(start.count),
+        })
+    }
+}
@@ -0,0 +1,167 @@
+//! Duration anomaly detection module
+//!
+//! Keeps an exponentially weighted mean/variance of span durations per operation
+//! and flags spans whose duration deviates more than a configured number of
+//! standard deviations, invoking registered callbacks for lightweight alerting.
+
+use crate::ids::{SpanId, TraceId};
+use crate::span::Span;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Attribute set on a flagged span with its z-score
+pub const ANOMALY_Z_SCORE_ATTRIBUTE: &str = "anomaly.z_score";
+
+/// Attribute set on a flagged span with the expected (mean) duration
+pub const ANOMALY_EXPECTED_MS_ATTRIBUTE: &str = "anomaly.expected_ms";
+
+/// Durations are measured in whole milliseconds, so deviations are never judged
+/// against a standard deviation smaller than the timer resolution
+const MIN_STD_DEV_MS: f64 = 1.0;
+
+/// A span whose duration deviated from its operation's baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub span_id: SpanId,
+    pub trace_id: TraceId,
+    pub operation_name: String,
+    pub duration_ms: u64,
+    pub expected_ms: f64,
+    pub std_dev_ms: f64,
+    pub z_score: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct OperationStats {
+    mean: f64,
+    variance: f64,
+    samples: u64,
+}
+
+type AnomalyCallback = Box<dyn Fn(&Anomaly) + Send + Sync>;
+
+/// Online EWMA/z-score detector for span durations
+pub struct AnomalyDetector {
+    sigma: f64,
+    alpha: f64,
+    min_samples: u64,
+    stats: Mutex<HashMap<String, OperationStats>>,
+    callbacks: Vec<AnomalyCallback>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector flagging spans more than `sigma` standard deviations away
+    pub fn new(sigma: f64) -> Self {
+        Self {
+            sigma: sigma.max(0.0),
+            alpha: 0.1,
+            min_samples: 20,
+            stats: Mutex::new(HashMap::new()),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Smoothing factor of the moving averages (higher reacts faster)
+    pub fn with_alpha(mut self, alpha: f64) -> Self {
+        self.alpha = alpha.clamp(f64::EPSILON, 1.0);
+        self
+    }
+
+    /// Number of observations per operation before anything is flagged
+    pub fn with_min_samples(mut self, min_samples: u64) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Register a callback invoked for every detected anomaly
+    pub fn on_anomaly<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Anomaly) + Send + Sync + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    /// Feed a completed span into the detector
+    ///
+    /// Returns the anomaly (after invoking callbacks) when the span's duration is
+    /// outside the configured band. Active spans are ignored.
+    pub fn observe(&self, span: &Span) -> Option<Anomaly> {
+        let duration_ms = span.duration_ms()?;
+        let anomaly = {
+            let mut stats = self.stats.lock().unwrap();
+            let entry = stats.entry(span.operation_name.clone()).or_default();
+            let anomaly = self.check(entry, span, duration_ms);
+            update(entry, duration_ms as f64, self.alpha);
+            anomaly
+        };
+
+        if let Some(anomaly) = &anomaly {
+            for callback in &self.callbacks {
+                callback(anomaly);
+            }
+        }
+        anomaly
+    }
+
+    /// Current (mean, standard deviation) baseline for an operation
+    pub fn baseline(&self, operation_name: &str) -> Option<(f64, f64)> {
+        let stats = self.stats.lock().unwrap();
+        stats
+            .get(operation_name)
+            .map(|s| (s.mean, s.variance.sqrt()))
+    }
+
+    /// Forget all learned baselines
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+
+    fn check(&self, stats: &OperationStats, span: &Span, duration_ms: u64) -> Option<Anomaly> {
+        if stats.samples < self.min_samples.max(1) {
+            return None;
+        }
+        let std_dev_ms = stats.variance.sqrt();
+        let z_score = (duration_ms as f64 - stats.mean) / std_dev_ms.max(MIN_STD_DEV_MS);
+        if z_score.abs() <= self.sigma {
+            return None;
+        }
+        Some(Anomaly {
+            span_id: span.span_id,
+            trace_id: span.trace_id,
+            operation_name: span.operation_name.clone(),
+            duration_ms,
+            expected_ms: stats.mean,
+            std_dev_ms,
+            z_score,
+        })
+    }
+}
+
+impl std::fmt::Debug for AnomalyDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnomalyDetector")
+            .field("sigma", &self.sigma)
+            .field("alpha", &self.alpha)
+            .field("min_samples", &self.min_samples)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+fn update(stats: &mut OperationStats, value: f64, alpha: f64) {
+    if stats.samples == 0 {
+        stats.mean = value;
+        stats.variance = 0.0;
+    } else {
+        let diff = value - stats.mean;
+        stats.mean += alpha * diff;
+        stats.variance = (1.0 - alpha) * (stats.variance + alpha * diff * diff);
+    }
+    stats.samples += 1;
+}
@@ -0,0 +1,282 @@
+//! Arbitrary module
+//!
+//! Contains `proptest::arbitrary::Arbitrary` and `quickcheck::Arbitrary`
+//! implementations for Span, SpanStatus, SpanKind, TraceContext and the ID
+//! types, so users can property-test serialization round-trips, exporters
+//! and processors against spans of every shape.
+//!
+//! Generated values are well-formed: IDs are never all-zero, only
+//! `SpanStatus::Active` spans lack an end time, end times never precede start
+//! times, and attribute values are JSON scalars that survive a round-trip.
+
+use crate::attributes::Attributes;
+use crate::ids::{SpanId, TraceId, SPAN_ID_BYTES, TRACE_ID_BYTES};
+use crate::propagation::TraceContext;
+use crate::span::{Span, SpanKind, SpanStatus};
+use proptest::prelude::{any, prop_oneof, BoxedStrategy, Just, Strategy};
+use serde_json::Value;
+
+/// Latest generated start time (milliseconds since the epoch, year 2100)
+const MAX_START_MS: u64 = 4_102_444_800_000;
+/// Longest generated span (one day)
+const MAX_DURATION_MS: u64 = 86_400_000;
+const MAX_ATTRIBUTES: usize = 8;
+
+/// Characters of generated operation names and attribute keys
+const NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_.";
+
+const SPAN_KINDS: [SpanKind; 5] = [
+    SpanKind::Internal,
+    SpanKind::Server,
+    SpanKind::Client,
+    SpanKind::Producer,
+    SpanKind::Consumer,
+];
+
+/// Force a nonzero ID, the only bytes the ID types reject
+fn nonzero<const N: usize>(mut bytes: [u8; N]) -> [u8; N] {
+    if bytes.iter().all(|byte| *byte == 0) {
+        bytes[N - 1] = 1;
+    }
+    bytes
+}
+
+/// Assemble a well-formed span from generated parts
+#[allow(clippy::too_many_arguments)]
+fn build_span(
+    trace_id: TraceId,
+    span_id: SpanId,
+    parent_span_id: Option<SpanId>,
+    operation_name: String,
+    start_time: u64,
+    duration_ms: u64,
+    attributes: Vec<(String, Value)>,
+    status: SpanStatus,
+    kind: SpanKind,
+) -> Span {
+    let mut span = Span::in_trace(trace_id, operation_name, parent_span_id).with_kind(kind);
+    span.span_id = span_id;
+    span.start_time = start_time;
+    span.end_time = match status {
+        SpanStatus::Active => None,
+        _ => Some(start_time + duration_ms),
+    };
+    span.attributes = attributes.into_iter().collect::<Attributes>();
+    span.status = status;
+    span
+}
+
+mod proptest_impls {
+    use super::*;
+    use proptest::arbitrary::Arbitrary;
+    use proptest::collection::vec;
+    use proptest::option;
+
+    fn name() -> impl Strategy<Value = String> {
+        "[a-z][a-z0-9_.]{0,31}"
+    }
+
+    fn attribute_value() -> impl Strategy<Value = Value> {
+        prop_oneof![
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            "[ -~]{0,32}".prop_map(Value::from),
+        ]
+    }
+
+    impl Arbitrary for TraceId {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            any::<[u8; TRACE_ID_BYTES]>()
+                .prop_map(|bytes| TraceId::from_bytes(nonzero(bytes)))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for SpanId {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            any::<[u8; SPAN_ID_BYTES]>()
+                .prop_map(|bytes| SpanId::from_bytes(nonzero(bytes)))
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for SpanKind {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            proptest::sample::select(&SPAN_KINDS[..]).boxed()
+        }
+    }
+
+    impl Arbitrary for SpanStatus {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            prop_oneof![
+                Just(SpanStatus::Active),
+                Just(SpanStatus::Completed),
+                "[ -~]{0,64}".prop_map(|message| SpanStatus::Error { message }),
+            ]
+            .boxed()
+        }
+    }
+
+    impl Arbitrary for TraceContext {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            (any::<TraceId>(), any::<SpanId>(), any::<bool>())
+                .prop_map(|(trace_id, span_id, sampled)| {
+                    TraceContext::new(trace_id, span_id, sampled)
+                })
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Span {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            (
+                any::<TraceId>(),
+                any::<SpanId>(),
+                option::of(any::<SpanId>()),
+                name(),
+                0..=MAX_START_MS,
+                0..=MAX_DURATION_MS,
+                vec((name(), attribute_value()), 0..=MAX_ATTRIBUTES),
+                any::<SpanStatus>(),
+                any::<SpanKind>(),
+            )
+                .prop_map(
+                    |(
+                        trace_id,
+                        span_id,
+                        parent,
+                        name,
+                        start,
+                        duration,
+                        attributes,
+                        status,
+                        kind,
+                    )| {
+                        build_span(
+                            trace_id, span_id, parent, name, start, duration, attributes, status,
+                            kind,
+                        )
+                    },
+                )
+                .boxed()
+        }
+    }
+}
+
+mod quickcheck_impls {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen};
+
+    fn bytes<const N: usize>(g: &mut Gen) -> [u8; N] {
+        let mut bytes = [0; N];
+        bytes.iter_mut().for_each(|byte| *byte = u8::arbitrary(g));
+        nonzero(bytes)
+    }
+
+    fn name(g: &mut Gen) -> String {
+        let len = 1 + usize::arbitrary(g) % 32;
+        (0..len)
+            .map(|i| {
+                // Names start with a letter
+                let chars = if i == 0 {
+                    &NAME_CHARS[..26]
+                } else {
+                    NAME_CHARS
+                };
+                *g.choose(chars).unwrap() as char
+            })
+            .collect()
+    }
+
+    fn printable(g: &mut Gen, max_len: usize) -> String {
+        let len = usize::arbitrary(g) % (max_len + 1);
+        (0..len)
+            .map(|_| (b' ' + u8::arbitrary(g) % 95) as char)
+            .collect()
+    }
+
+    fn attribute_value(g: &mut Gen) -> Value {
+        match u8::arbitrary(g) % 3 {
+            0 => Value::from(bool::arbitrary(g)),
+            1 => Value::from(i64::arbitrary(g)),
+            _ => Value::from(printable(g, 32)),
+        }
+    }
+
+    impl Arbitrary for TraceId {
+        fn arbitrary(g: &mut Gen) -> Self {
+            TraceId::from_bytes(bytes(g))
+        }
+    }
+
+    impl Arbitrary for SpanId {
+        fn arbitrary(g: &mut Gen) -> Self {
+            SpanId::from_bytes(bytes(g))
+        }
+    }
+
+    impl Arbitrary for SpanKind {
+        fn arbitrary(g: &mut Gen) -> Self {
+            *g.choose(&SPAN_KINDS).unwrap()
+        }
+    }
+
+    impl Arbitrary for SpanStatus {
+        fn arbitrary(g: &mut Gen) -> Self {
+            match u8::arbitrary(g) % 3 {
+                0 => SpanStatus::Active,
+                1 => SpanStatus::Completed,
+                _ => SpanStatus::Error {
+                    message: printable(g, 64),
+                },
+            }
+        }
+    }
+
+    impl Arbitrary for TraceContext {
+        fn arbitrary(g: &mut Gen) -> Self {
+            TraceContext::new(
+                TraceId::arbitrary(g),
+                SpanId::arbitrary(g),
+                bool::arbitrary(g),
+            )
+        }
+    }
+
+    impl Arbitrary for Span {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let attributes = (0..usize::arbitrary(g) % (MAX_ATTRIBUTES + 1))
+                .map(|_| (name(g), attribute_value(g)))
+                .collect();
+            build_span(
+                TraceId::arbitrary(g),
+                SpanId::arbitrary(g),
+                Option::<SpanId>::arbitrary(g),
+                name(g),
+                u64::arbitrary(g) % (MAX_START_MS + 1),
+                u64::arbitrary(g) % (MAX_DURATION_MS + 1),
+                attributes,
+                SpanStatus::arbitrary(g),
+                SpanKind::arbitrary(g),
+            )
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Span attribute storage module
+//!
+//! Contains Attributes, the key/value container of a span. Most spans carry
+//! only a handful of attributes, so up to [`INLINE_ATTRIBUTES`] entries are
+//! stored inline in the span without a heap allocation, and keys are
+//! `Cow<'static, str>` so constant keys (`"http.method"`) are never copied.
+//! Lookups scan the entries, which beats hashing at these sizes; entries keep
+//! their insertion order.
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+
+/// Entries stored without a heap allocation
+pub const INLINE_ATTRIBUTES: usize = 8;
+
+/// Attribute key; `&'static str` keys are stored without copying
+pub type AttributeKey = Cow<'static, str>;
+
+/// Small insertion-ordered map of span attributes
+#[derive(Clone, Default)]
+pub struct Attributes {
+    entries: SmallVec<[(AttributeKey, Value); INLINE_ATTRIBUTES]>,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// True when the entries no longer fit inline
+    pub fn spilled(&self) -> bool {
+        self.entries.spilled()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.position(key).map(|index| &mut self.entries[index].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Set an attribute, returning the value it replaced
+    pub fn insert(&mut self, key: impl Into<AttributeKey>, value: Value) -> Option<Value> {
+        let key = key.into();
+        match self.position(&key) {
+            Some(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.position(key).map(|index| self.entries.remove(index).1)
+    }
+
+    /// Set an attribute unless it is already set
+    pub fn insert_if_absent(&mut self, key: impl Into<AttributeKey>, value: Value) {
+        let key = key.into();
+        if !self.contains_key(&key) {
+            self.entries.push((key, value));
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &mut Value) -> bool) {
+        self.entries.retain(|(key, value)| keep(key, value));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Value)> {
+        self.entries.iter_mut().map(|(key, value)| (&**key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| key.as_ref())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(existing, _)| existing.as_ref() == key)
+    }
+}
+
+impl<K: Into<AttributeKey>> Extend<(K, Value)> for Attributes {
+    fn extend<I: IntoIterator<Item = (K, Value)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Into<AttributeKey>> FromIterator<(K, Value)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(entries: I) -> Self {
+        let mut attributes = Self::new();
+        attributes.extend(entries);
+        attributes
+    }
+}
+
+impl<K: Into<AttributeKey>, const N: usize> From<[(K, Value); N]> for Attributes {
+    fn from(entries: [(K, Value); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<HashMap<String, Value>> for Attributes {
+    fn from(entries: HashMap<String, Value>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<Attributes> for HashMap<String, Value> {
+    fn from(attributes: Attributes) -> Self {
+        attributes
+            .into_iter()
+            .map(|(key, value)| (key.into_owned(), value))
+            .collect()
+    }
+}
+
+impl IntoIterator for Attributes {
+    type Item = (AttributeKey, Value);
+    type IntoIter = smallvec::IntoIter<[(AttributeKey, Value); INLINE_ATTRIBUTES]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = (&'a str, &'a Value);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a Value)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl Index<&str> for Attributes {
+    type Output = Value;
+
+    /// Panics if the attribute is not set, like `HashMap`
+    fn index(&self, key: &str) -> &Value {
+        self.get(key)
+            .unwrap_or_else(|| panic!("attribute '{}' is not set", key))
+    }
+}
+
+/// Equal when the same keys map to equal values, regardless of order
+impl PartialEq for Attributes {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Serialize for Attributes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Attributes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AttributesVisitor;
+
+        impl<'de> Visitor<'de> for AttributesVisitor {
+            type Value = Attributes;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of span attributes")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Attributes, A::Error> {
+                let mut attributes = Attributes::new();
+                while let Some((key, value)) = map.next_entry::<String, Value>()? {
+                    attributes.insert(key, value);
+                }
+                Ok(attributes)
+            }
+        }
+
+        deserializer.deserialize_map(AttributesVisitor)
+    }
+}
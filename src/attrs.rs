@@ -0,0 +1,38 @@
+//! Unit-aware attribute module
+//!
+//! Contains helpers that build attributes in this crate's canonical units, so
+//! every team records durations and sizes the same way: durations as
+//! fractional milliseconds under a `_ms` key, sizes as whole bytes under a
+//! `_bytes` key. Each returns a key-value pair for
+//! `SpanHandle::set_attributes` or `Attributes::extend`.
+
+use crate::attributes::AttributeKey;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Key suffix of duration attributes
+pub const DURATION_SUFFIX: &str = "_ms";
+/// Key suffix of size attributes
+pub const BYTES_SUFFIX: &str = "_bytes";
+
+/// `{key}_ms` holding `duration` in fractional milliseconds
+///
+/// `attrs::duration("db.lock_wait", d)` records `db.lock_wait_ms`.
+pub fn duration(key: &str, duration: Duration) -> (AttributeKey, Value) {
+    let millis = duration.as_secs_f64() * 1000.0;
+    (with_suffix(key, DURATION_SUFFIX), Value::from(millis))
+}
+
+/// `{key}_bytes` holding a size in bytes
+pub fn bytes(key: &str, bytes: u64) -> (AttributeKey, Value) {
+    (with_suffix(key, BYTES_SUFFIX), Value::from(bytes))
+}
+
+/// Append `suffix` unless the key already ends with it
+fn with_suffix(key: &str, suffix: &str) -> AttributeKey {
+    if key.ends_with(suffix) {
+        AttributeKey::Owned(key.to_string())
+    } else {
+        AttributeKey::Owned(format!("{}{}", key, suffix))
+    }
+}
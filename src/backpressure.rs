@@ -0,0 +1,138 @@
+//! Backpressure module
+//!
+//! Contains BackpressureSignal, a callback fired when one of the tracer's
+//! queues crosses its high-water mark (and again once it drains below the
+//! low-water mark), so instrumented services can shed optional spans such as
+//! per-item spans under load instead of having everything dropped at the caps.
+//!
+//! Watched queues: active spans relative to `max_active_spans`, and the export
+//! pipeline, which fills up as consecutive exports fail.
+
+use crate::health::UNHEALTHY_EXPORT_FAILURES;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Level at which a queue counts as saturated
+pub const DEFAULT_HIGH_WATER: f64 = 0.8;
+/// Level a saturated queue must drain to before it is reported relieved
+pub const DEFAULT_LOW_WATER: f64 = 0.6;
+
+/// Queue whose level crossed a watermark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackpressureSource {
+    /// Active spans relative to `max_active_spans`
+    ActiveSpans,
+    /// Consecutive failed exports relative to the unhealthy threshold
+    Export,
+}
+
+/// Watermark crossing reported to a BackpressureSignal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackpressureEvent {
+    pub source: BackpressureSource,
+    /// True when the high-water mark was crossed, false once relieved
+    pub saturated: bool,
+    /// Fill level of the queue (0.0 - 1.0)
+    pub level: f64,
+}
+
+/// Callback fired on watermark crossings, with its thresholds
+#[derive(Clone)]
+pub struct BackpressureSignal {
+    high_water: f64,
+    low_water: f64,
+    callback: Arc<dyn Fn(BackpressureEvent) + Send + Sync>,
+}
+
+impl BackpressureSignal {
+    /// The callback runs on the thread that started or ended the span
+    pub fn new(callback: impl Fn(BackpressureEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            high_water: DEFAULT_HIGH_WATER,
+            low_water: DEFAULT_LOW_WATER,
+            callback: Arc::new(callback),
+        }
+    }
+
+    pub fn with_high_water(mut self, high_water: f64) -> Self {
+        self.high_water = high_water.clamp(0.0, 1.0);
+        self.low_water = self.low_water.min(self.high_water);
+        self
+    }
+
+    pub fn with_low_water(mut self, low_water: f64) -> Self {
+        self.low_water = low_water.clamp(0.0, self.high_water);
+        self
+    }
+
+    pub fn high_water(&self) -> f64 {
+        self.high_water
+    }
+
+    pub fn low_water(&self) -> f64 {
+        self.low_water
+    }
+}
+
+impl fmt::Debug for BackpressureSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackpressureSignal")
+            .field("high_water", &self.high_water)
+            .field("low_water", &self.low_water)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Fill level of the export pipeline after `consecutive_failures` failures
+pub(crate) fn export_level(consecutive_failures: u64) -> f64 {
+    (consecutive_failures as f64 / UNHEALTHY_EXPORT_FAILURES as f64).min(1.0)
+}
+
+/// Edge detection for a signal, one saturation flag per source
+#[derive(Debug)]
+pub(crate) struct BackpressureMonitor {
+    signal: BackpressureSignal,
+    active_spans: AtomicBool,
+    export: AtomicBool,
+}
+
+impl BackpressureMonitor {
+    pub(crate) fn new(signal: BackpressureSignal) -> Self {
+        Self {
+            signal,
+            active_spans: AtomicBool::new(false),
+            export: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn high_water(&self) -> f64 {
+        self.signal.high_water
+    }
+
+    /// Fire the callback if `level` crossed a watermark since the last call
+    pub(crate) fn observe(&self, source: BackpressureSource, level: f64) {
+        let saturated = match source {
+            BackpressureSource::ActiveSpans => &self.active_spans,
+            BackpressureSource::Export => &self.export,
+        };
+        let crossed = if level >= self.signal.high_water {
+            saturated
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        } else if level <= self.signal.low_water {
+            saturated
+                .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+        } else {
+            false
+        };
+        if crossed {
+            (self.signal.callback)(BackpressureEvent {
+                source,
+                saturated: level >= self.signal.high_water,
+                level,
+            });
+        }
+    }
+}
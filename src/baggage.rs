@@ -0,0 +1,181 @@
+//! Baggage validation module
+//!
+//! Enforces the W3C Baggage limits on entries before they are stored, so a
+//! single oversized or malformed entry cannot break the `baggage` header for
+//! every downstream service. Keys must be HTTP tokens; values may hold any
+//! printable text (it is percent-encoded on the wire) but no control
+//! characters.
+//!
+//! `parse_baggage` reads an incoming `baggage` header under the same rules,
+//! dropping entries that break them instead of rejecting the whole header;
+//! `format_baggage` writes one.
+
+use crate::tracer::TracingResult;
+use std::collections::HashMap;
+use tyl_errors::TylError;
+
+/// Header holding W3C baggage
+pub const BAGGAGE_HEADER: &str = "baggage";
+
+/// Entries a baggage set may hold (W3C minimum supported list length)
+pub const MAX_BAGGAGE_ENTRIES: usize = 64;
+/// Bytes of a single `key=value` entry
+pub const MAX_BAGGAGE_ENTRY_BYTES: usize = 4096;
+/// Bytes of all entries together, the W3C header size limit
+pub const MAX_BAGGAGE_TOTAL_BYTES: usize = 8192;
+
+/// Check a key and value against the W3C character and size rules
+pub fn validate_baggage_entry(key: &str, value: &str) -> TracingResult<()> {
+    if key.is_empty() {
+        return Err(TylError::validation("baggage", "key cannot be empty"));
+    }
+    if let Some(invalid) = key.chars().find(|c| !is_token_char(*c)) {
+        return Err(TylError::validation(
+            "baggage",
+            format!(
+                "key '{}' contains '{}'; keys must be HTTP tokens (letters, digits, !#$%&'*+-.^_`|~)",
+                key.escape_default(),
+                invalid.escape_default()
+            ),
+        ));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(TylError::validation(
+            "baggage",
+            format!("value of '{}' contains control characters", key),
+        ));
+    }
+    let size = entry_bytes(key, value);
+    if size > MAX_BAGGAGE_ENTRY_BYTES {
+        return Err(TylError::validation(
+            "baggage",
+            format!(
+                "entry '{}' is {} bytes (max {})",
+                key, size, MAX_BAGGAGE_ENTRY_BYTES
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Validate an entry and check that adding it keeps `baggage` within limits
+pub(crate) fn validate_baggage_insert(
+    baggage: &HashMap<String, String>,
+    key: &str,
+    value: &str,
+) -> TracingResult<()> {
+    validate_baggage_entry(key, value)?;
+    let replaced = baggage.get(key);
+    if replaced.is_none() && baggage.len() >= MAX_BAGGAGE_ENTRIES {
+        return Err(TylError::validation(
+            "baggage",
+            format!(
+                "cannot add '{}': baggage already holds {} entries (max {})",
+                key,
+                baggage.len(),
+                MAX_BAGGAGE_ENTRIES
+            ),
+        ));
+    }
+    let total: usize = baggage
+        .iter()
+        .map(|(key, value)| entry_bytes(key, value))
+        .sum::<usize>()
+        - replaced.map_or(0, |old| entry_bytes(key, old))
+        + entry_bytes(key, value);
+    if total > MAX_BAGGAGE_TOTAL_BYTES {
+        return Err(TylError::validation(
+            "baggage",
+            format!(
+                "cannot add '{}': baggage would be {} bytes (max {})",
+                key, total, MAX_BAGGAGE_TOTAL_BYTES
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Entries of a `baggage` header value, with values percent-decoded
+///
+/// Entry properties (`;prop=...`) are ignored. Malformed entries and those
+/// past the size limits are skipped, so one bad member from upstream does
+/// not cost the rest.
+pub fn parse_baggage(header: &str) -> HashMap<String, String> {
+    let mut baggage = HashMap::new();
+    for member in header.split(',') {
+        let entry = member.split(';').next().unwrap_or_default();
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        let Some(value) = percent_decode(value.trim()) else {
+            continue;
+        };
+        let key = key.trim();
+        if validate_baggage_insert(&baggage, key, &value).is_ok() {
+            baggage.insert(key.to_string(), value);
+        }
+    }
+    baggage
+}
+
+/// `baggage` header value for `baggage`, keys sorted and values
+/// percent-encoded where the header syntax requires it
+pub fn format_baggage<'a>(baggage: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut entries: Vec<_> = baggage.into_iter().collect();
+    entries.sort_unstable();
+    entries
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Escape bytes outside the W3C `baggage-octet` set
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'!' | b'#'..=b'+' | b'-'..=b':' | b'<'..=b'[' | b']'..=b'~' if byte != b'%' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decode `%XX` escapes; `None` if the result is not UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Size of `key=value`
+fn entry_bytes(key: &str, value: &str) -> usize {
+    key.len() + 1 + value.len()
+}
+
+/// RFC 7230 `tchar`
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
@@ -0,0 +1,266 @@
+//! Benchmark module
+//!
+//! Contains Workload, a synthetic load generator that drives any
+//! TracingManager with configurable trace depth, attribute count, span rate
+//! and concurrency, and reports throughput, per-span latency and drops as a
+//! BenchReport. Running the same workload against SimpleTracer and the
+//! batch/OTLP stack gives comparable numbers before a rollout.
+
+use crate::tracer::{TracingManager, TracingResult};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tyl_errors::TylError;
+
+/// Synthetic tracing workload
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workload {
+    traces: usize,
+    depth: usize,
+    attributes: usize,
+    concurrency: usize,
+    spans_per_second: Option<u32>,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            traces: 1_000,
+            depth: 3,
+            attributes: 4,
+            concurrency: 1,
+            spans_per_second: None,
+        }
+    }
+}
+
+impl Workload {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total traces, split evenly across the workers
+    pub fn with_traces(mut self, traces: usize) -> Self {
+        self.traces = traces;
+        self
+    }
+
+    /// Nesting depth of each trace: a root span and `depth - 1` descendants
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Attributes added to every span
+    pub fn with_attributes(mut self, attributes: usize) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Worker threads starting spans in parallel
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Target span rate across all workers; unthrottled by default
+    pub fn with_spans_per_second(mut self, spans_per_second: u32) -> Self {
+        self.spans_per_second = Some(spans_per_second);
+        self
+    }
+
+    pub fn total_spans(&self) -> usize {
+        self.traces * self.depth
+    }
+
+    /// Run the workload against a tracer and measure it
+    pub fn run<M: TracingManager + ?Sized>(&self, tracer: &M) -> TracingResult<BenchReport> {
+        self.validate()?;
+        let completed_before = tracer.get_completed_spans().len();
+        // Pace per worker, so the workers together reach the target rate
+        let interval = self
+            .spans_per_second
+            .map(|rate| Duration::from_secs(1) * self.concurrency as u32 / rate);
+
+        let started = Instant::now();
+        let workers: Vec<WorkerStats> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.concurrency)
+                .map(|worker| {
+                    let traces = self.traces / self.concurrency
+                        + usize::from(worker < self.traces % self.concurrency);
+                    scope.spawn(move || self.run_worker(tracer, worker, traces, interval))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("benchmark worker panicked"))
+                .collect()
+        });
+        let elapsed = started.elapsed();
+
+        let mut latencies = Vec::with_capacity(self.total_spans());
+        let mut errors = 0;
+        for stats in workers {
+            latencies.extend(stats.latencies);
+            errors += stats.errors;
+        }
+        let spans = latencies.len();
+        let completed = tracer
+            .get_completed_spans()
+            .len()
+            .saturating_sub(completed_before);
+        Ok(BenchReport {
+            spans,
+            errors,
+            dropped: spans.saturating_sub(completed),
+            elapsed,
+            latency: LatencySummary::from_samples(&mut latencies),
+        })
+    }
+
+    fn validate(&self) -> TracingResult<()> {
+        if self.depth == 0 {
+            return Err(TylError::validation("depth", "depth must be at least 1"));
+        }
+        if self.concurrency == 0 {
+            return Err(TylError::validation(
+                "concurrency",
+                "concurrency must be at least 1",
+            ));
+        }
+        if self.spans_per_second == Some(0) {
+            return Err(TylError::validation(
+                "spans_per_second",
+                "span rate must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+
+    fn run_worker<M: TracingManager + ?Sized>(
+        &self,
+        tracer: &M,
+        worker: usize,
+        traces: usize,
+        interval: Option<Duration>,
+    ) -> WorkerStats {
+        let mut stats = WorkerStats {
+            latencies: Vec::with_capacity(traces * self.depth),
+            errors: 0,
+        };
+        let started = Instant::now();
+        let mut scheduled = 0u32;
+        for trace in 0..traces {
+            let mut stack: Vec<(String, Duration)> = Vec::with_capacity(self.depth);
+            for level in 0..self.depth {
+                if let Some(interval) = interval {
+                    let due = started + interval * scheduled;
+                    scheduled += 1;
+                    if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+                let parent = stack.last().map(|(span_id, _)| span_id.clone());
+                let call = Instant::now();
+                let span_id = match tracer.start_span(&format!("bench.level{}", level), parent) {
+                    Ok(span_id) => span_id,
+                    Err(_) => {
+                        stats.errors += 1;
+                        break;
+                    }
+                };
+                for attribute in 0..self.attributes {
+                    let value = serde_json::json!(worker * traces + trace + attribute);
+                    if tracer
+                        .add_span_attribute(&span_id, &format!("bench.attr{}", attribute), value)
+                        .is_err()
+                    {
+                        stats.errors += 1;
+                    }
+                }
+                stack.push((span_id, call.elapsed()));
+            }
+            // Children end before their parents
+            while let Some((span_id, latency)) = stack.pop() {
+                let call = Instant::now();
+                match tracer.end_span(span_id) {
+                    Ok(()) => stats.latencies.push(latency + call.elapsed()),
+                    Err(_) => stats.errors += 1,
+                }
+            }
+        }
+        stats
+    }
+}
+
+struct WorkerStats {
+    /// Time spent in tracer calls per span
+    latencies: Vec<Duration>,
+    errors: usize,
+}
+
+/// Distribution of the time spent in tracer calls per span
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencySummary {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    fn from_samples(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Results of a workload run
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// Spans started and ended successfully
+    pub spans: usize,
+    /// Tracer calls that returned an error
+    pub errors: usize,
+    /// Ended spans that never showed up in `get_completed_spans`
+    pub dropped: usize,
+    pub elapsed: Duration,
+    pub latency: LatencySummary,
+}
+
+impl BenchReport {
+    /// Ended spans per second of wall-clock time
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.spans as f64 / seconds
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} spans in {:?} ({:.0} spans/s), p50 {:?}, p90 {:?}, p99 {:?}, max {:?}, {} errors, {} dropped",
+            self.spans,
+            self.elapsed,
+            self.throughput(),
+            self.latency.p50,
+            self.latency.p90,
+            self.latency.p99,
+            self.latency.max,
+            self.errors,
+            self.dropped
+        )
+    }
+}
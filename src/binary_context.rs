@@ -0,0 +1,166 @@
+//! Binary context module
+//!
+//! Contains a compact binary encoding of TraceContext for transports where
+//! text headers are expensive (UDP datagrams, embedded protocols). The
+//! context is a fixed 26-byte header; baggage, when present, follows it as
+//! length-prefixed entries:
+//!
+//! ```text
+//! version (1) | trace_id (16) | span_id (8) | flags (1)
+//! [ count (1) | { key_len (1) | key | value_len (2, BE) | value } * count ]
+//! ```
+//!
+//! Decoding treats its input as untrusted: every length is bounds-checked,
+//! baggage is held to the W3C limits, and unknown versions, duplicate keys or
+//! trailing bytes are errors rather than panics.
+
+use crate::baggage::{validate_baggage_insert, MAX_BAGGAGE_ENTRIES};
+use crate::ids::{SpanId, TraceId, SPAN_ID_BYTES, TRACE_ID_BYTES};
+use crate::propagation::TraceContext;
+use crate::tracer::TracingResult;
+use std::collections::HashMap;
+use tyl_errors::TylError;
+
+/// Version written in the first byte
+pub const BINARY_CONTEXT_VERSION: u8 = 0;
+/// Size of the fixed context header
+pub const BINARY_CONTEXT_LEN: usize = 1 + TRACE_ID_BYTES + SPAN_ID_BYTES + 1;
+
+const FLAG_SAMPLED: u8 = 0x01;
+
+impl TraceContext {
+    /// Fixed-size binary form, without baggage
+    pub fn to_bytes(&self) -> [u8; BINARY_CONTEXT_LEN] {
+        let mut bytes = [0u8; BINARY_CONTEXT_LEN];
+        bytes[0] = BINARY_CONTEXT_VERSION;
+        bytes[1..1 + TRACE_ID_BYTES].copy_from_slice(&self.trace_id.to_bytes());
+        bytes[1 + TRACE_ID_BYTES..BINARY_CONTEXT_LEN - 1].copy_from_slice(&self.span_id.to_bytes());
+        bytes[BINARY_CONTEXT_LEN - 1] = if self.sampled { FLAG_SAMPLED } else { 0 };
+        bytes
+    }
+
+    /// Parse the binary form; baggage or other trailing bytes are rejected
+    pub fn from_bytes(bytes: &[u8]) -> TracingResult<Self> {
+        let (context, rest) = decode_header(bytes)?;
+        if !rest.is_empty() {
+            return Err(invalid(format!(
+                "{} unexpected bytes after the context",
+                rest.len()
+            )));
+        }
+        Ok(context)
+    }
+}
+
+/// Binary form of `context` followed by `baggage`
+///
+/// Entries are written in key order so equal input encodes identically.
+pub fn encode_context(
+    context: &TraceContext,
+    baggage: &HashMap<String, String>,
+) -> TracingResult<Vec<u8>> {
+    let mut bytes = context.to_bytes().to_vec();
+    if baggage.is_empty() {
+        return Ok(bytes);
+    }
+    let mut checked = HashMap::with_capacity(baggage.len());
+    for (key, value) in baggage {
+        validate_baggage_insert(&checked, key, value)?;
+        if key.len() > u8::MAX as usize {
+            return Err(TylError::validation(
+                "baggage",
+                format!("key '{}' is longer than {} bytes", key, u8::MAX),
+            ));
+        }
+        checked.insert(key.clone(), value.clone());
+    }
+    let mut entries: Vec<_> = baggage.iter().collect();
+    entries.sort_unstable();
+    bytes.push(entries.len() as u8);
+    for (key, value) in entries {
+        bytes.push(key.len() as u8);
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Parse the output of `encode_context`
+pub fn decode_context(bytes: &[u8]) -> TracingResult<(TraceContext, HashMap<String, String>)> {
+    let (context, mut rest) = decode_header(bytes)?;
+    let mut baggage = HashMap::new();
+    if rest.is_empty() {
+        return Ok((context, baggage));
+    }
+    let count = take(&mut rest, 1, "baggage count")?[0] as usize;
+    if count == 0 || count > MAX_BAGGAGE_ENTRIES {
+        return Err(invalid(format!(
+            "baggage count {} is outside 1..={}",
+            count, MAX_BAGGAGE_ENTRIES
+        )));
+    }
+    for _ in 0..count {
+        let key_len = take(&mut rest, 1, "key length")?[0] as usize;
+        let key = utf8(take(&mut rest, key_len, "key")?, "key")?;
+        let value_len = take(&mut rest, 2, "value length")?;
+        let value_len = u16::from_be_bytes([value_len[0], value_len[1]]) as usize;
+        let value = utf8(take(&mut rest, value_len, "value")?, "value")?;
+        if baggage.contains_key(key) {
+            return Err(invalid(format!("duplicate baggage key '{}'", key)));
+        }
+        validate_baggage_insert(&baggage, key, value)?;
+        baggage.insert(key.to_string(), value.to_string());
+    }
+    if !rest.is_empty() {
+        return Err(invalid(format!(
+            "{} unexpected bytes after the baggage",
+            rest.len()
+        )));
+    }
+    Ok((context, baggage))
+}
+
+/// Parse the fixed header, returning the bytes after it
+fn decode_header(bytes: &[u8]) -> TracingResult<(TraceContext, &[u8])> {
+    let mut rest = bytes;
+    let header = take(&mut rest, BINARY_CONTEXT_LEN, "context header")?;
+    if header[0] != BINARY_CONTEXT_VERSION {
+        return Err(invalid(format!("unsupported version {}", header[0])));
+    }
+    let mut trace_id = [0u8; TRACE_ID_BYTES];
+    trace_id.copy_from_slice(&header[1..1 + TRACE_ID_BYTES]);
+    let mut span_id = [0u8; SPAN_ID_BYTES];
+    span_id.copy_from_slice(&header[1 + TRACE_ID_BYTES..BINARY_CONTEXT_LEN - 1]);
+    let trace_id = TraceId::from_bytes(trace_id);
+    let span_id = SpanId::from_bytes(span_id);
+    if !trace_id.is_valid() || !span_id.is_valid() {
+        return Err(invalid("all-zero trace or span ID".to_string()));
+    }
+    // Unknown flag bits are reserved and ignored, as in `traceparent`
+    let sampled = header[BINARY_CONTEXT_LEN - 1] & FLAG_SAMPLED != 0;
+    Ok((TraceContext::new(trace_id, span_id, sampled), rest))
+}
+
+/// Split `len` bytes off the front of `bytes`
+fn take<'b>(bytes: &mut &'b [u8], len: usize, what: &str) -> TracingResult<&'b [u8]> {
+    if bytes.len() < len {
+        return Err(invalid(format!(
+            "truncated {}: need {} bytes, have {}",
+            what,
+            len,
+            bytes.len()
+        )));
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn utf8<'b>(bytes: &'b [u8], what: &str) -> TracingResult<&'b str> {
+    std::str::from_utf8(bytes).map_err(|_| invalid(format!("baggage {} is not UTF-8", what)))
+}
+
+fn invalid(message: String) -> TylError {
+    TylError::validation("context", message)
+}
@@ -0,0 +1,185 @@
+//! Traced channel module
+//!
+//! Contains the [`Traced`] envelope, which captures the sender's [`Context`]
+//! alongside a message, and send/receive extension traits for std, crossbeam
+//! (`crossbeam-channel` feature), and tokio (`tokio` feature) channels of
+//! `Traced<T>`, so pipeline stages connected by channels keep parentage.
+//!
+//! ```rust
+//! use std::sync::mpsc;
+//! use tyl_tracing::channel::{TracedRecv, TracedSend};
+//! use tyl_tracing::Traced;
+//!
+//! let (tx, rx) = mpsc::channel::<Traced<u32>>();
+//! tx.send_traced(42).unwrap();
+//! let message = rx.recv_traced().unwrap();
+//! assert_eq!(*message, 42);
+//! ```
+
+use crate::context::{Context, ContextGuard};
+use std::ops::{Deref, DerefMut};
+
+/// A message together with the context it was sent from
+#[derive(Debug, Clone)]
+pub struct Traced<T> {
+    context: Context,
+    value: T,
+}
+
+impl<T> Traced<T> {
+    /// Wrap `value` with the calling thread's current context
+    pub fn new(value: T) -> Self {
+        Self::with_context(value, Context::current())
+    }
+
+    pub fn with_context(value: T, context: Context) -> Self {
+        Self { context, value }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn into_parts(self) -> (T, Context) {
+        (self.value, self.context)
+    }
+
+    /// Attach the sender's context for as long as the result is alive
+    ///
+    /// The result is not `Send`; in async code prefer [`Traced::in_context`]
+    /// so the context is not held across an `.await`.
+    pub fn attach(self) -> Received<T> {
+        let guard = self.context.attach();
+        Received {
+            value: self.value,
+            _guard: guard,
+        }
+    }
+
+    /// Run `f` on the value with the sender's context attached
+    pub fn in_context<R>(self, f: impl FnOnce(T) -> R) -> R {
+        let _guard = self.context.attach();
+        f(self.value)
+    }
+}
+
+/// A received message whose sender's context stays attached until dropped
+#[derive(Debug)]
+pub struct Received<T> {
+    value: T,
+    _guard: ContextGuard,
+}
+
+impl<T> Received<T> {
+    /// Take the value, detaching the sender's context
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Received<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Received<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Senders of `Traced<T>` that wrap values with the current context
+pub trait TracedSend<T> {
+    type Error;
+
+    fn send_traced(&self, value: T) -> Result<(), Self::Error>;
+}
+
+/// Blocking receivers of `Traced<T>` that attach the sender's context
+pub trait TracedRecv<T> {
+    type Error;
+
+    fn recv_traced(&self) -> Result<Received<T>, Self::Error>;
+}
+
+impl<T> TracedSend<T> for std::sync::mpsc::Sender<Traced<T>> {
+    type Error = std::sync::mpsc::SendError<Traced<T>>;
+
+    fn send_traced(&self, value: T) -> Result<(), Self::Error> {
+        self.send(Traced::new(value))
+    }
+}
+
+impl<T> TracedSend<T> for std::sync::mpsc::SyncSender<Traced<T>> {
+    type Error = std::sync::mpsc::SendError<Traced<T>>;
+
+    fn send_traced(&self, value: T) -> Result<(), Self::Error> {
+        self.send(Traced::new(value))
+    }
+}
+
+impl<T> TracedRecv<T> for std::sync::mpsc::Receiver<Traced<T>> {
+    type Error = std::sync::mpsc::RecvError;
+
+    fn recv_traced(&self) -> Result<Received<T>, Self::Error> {
+        self.recv().map(Traced::attach)
+    }
+}
+
+#[cfg(feature = "crossbeam-channel")]
+impl<T> TracedSend<T> for crossbeam_channel::Sender<Traced<T>> {
+    type Error = crossbeam_channel::SendError<Traced<T>>;
+
+    fn send_traced(&self, value: T) -> Result<(), Self::Error> {
+        self.send(Traced::new(value))
+    }
+}
+
+#[cfg(feature = "crossbeam-channel")]
+impl<T> TracedRecv<T> for crossbeam_channel::Receiver<Traced<T>> {
+    type Error = crossbeam_channel::RecvError;
+
+    fn recv_traced(&self) -> Result<Received<T>, Self::Error> {
+        self.recv().map(Traced::attach)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> TracedSend<T> for tokio::sync::mpsc::UnboundedSender<Traced<T>> {
+    type Error = tokio::sync::mpsc::error::SendError<Traced<T>>;
+
+    fn send_traced(&self, value: T) -> Result<(), Self::Error> {
+        self.send(Traced::new(value))
+    }
+}
+
+/// Bounded tokio senders of `Traced<T>`
+///
+/// The context is captured when `send_traced` is called, not when the send
+/// completes. Receive with `recv().await` and use [`Traced::in_context`].
+#[cfg(feature = "tokio")]
+pub trait TracedAsyncSend<T: Send> {
+    fn send_traced(
+        &self,
+        value: T,
+    ) -> impl std::future::Future<Output = Result<(), tokio::sync::mpsc::error::SendError<Traced<T>>>>
+           + Send;
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Send> TracedAsyncSend<T> for tokio::sync::mpsc::Sender<Traced<T>> {
+    fn send_traced(
+        &self,
+        value: T,
+    ) -> impl std::future::Future<Output = Result<(), tokio::sync::mpsc::error::SendError<Traced<T>>>>
+           + Send {
+        self.send(Traced::new(value))
+    }
+}
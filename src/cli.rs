@@ -0,0 +1,115 @@
+//! CLI tracing module
+//!
+//! Contains [`trace_main`], which runs a one-shot program's body inside a root
+//! span, records its exit code (and panics), and flushes the exporter before
+//! returning, so short-lived tools export their traces before the process
+//! exits.
+//!
+//! ```ignore
+//! fn main() -> Result<(), TylError> {
+//!     let tracer = SimpleTracer::new(TraceConfig::from_env()).with_exporter(exporter);
+//!     trace_main(&tracer, "db-migrate", |_ctx| run_migrations())
+//! }
+//! ```
+
+use crate::context::Context;
+use crate::span::SpanStatus;
+use crate::tracer::{SimpleTracer, TracingManager};
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Span attribute: exit code the program's body returned (101 on panic)
+pub const EXIT_CODE_ATTRIBUTE: &str = "process.exit.code";
+/// Exit code Rust uses for a panicking `main`
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Return value of a program body, mapped to the exit code `main` would have
+pub trait ExitStatus {
+    fn exit_code(&self) -> i32;
+
+    /// Error reported for a non-zero exit
+    fn failure_message(&self) -> Option<String> {
+        None
+    }
+}
+
+impl ExitStatus for () {
+    fn exit_code(&self) -> i32 {
+        0
+    }
+}
+
+impl ExitStatus for i32 {
+    fn exit_code(&self) -> i32 {
+        *self
+    }
+}
+
+/// Like `main` returning `Result`: errors exit with 1, reported in Debug form
+impl<T, E: Debug> ExitStatus for Result<T, E> {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Ok(_) => 0,
+            Err(_) => 1,
+        }
+    }
+
+    fn failure_message(&self) -> Option<String> {
+        self.as_ref().err().map(|error| format!("{:?}", error))
+    }
+}
+
+/// Run `body` in a root span `name` attached as the current context, then
+/// record its exit code, end the span, and flush the exporter
+///
+/// A panic is recorded (exit code 101) and flushed before it is resumed.
+pub fn trace_main<T: ExitStatus>(
+    tracer: &SimpleTracer,
+    name: &str,
+    body: impl FnOnce(&Context) -> T,
+) -> T {
+    let span_id = tracer.start_span(name, None).ok();
+    let context = span_id
+        .as_deref()
+        .map(Context::with_span)
+        .unwrap_or_default();
+
+    let outcome = {
+        let _guard = context.attach();
+        panic::catch_unwind(AssertUnwindSafe(|| body(&context)))
+    };
+    let (exit_code, failure) = match &outcome {
+        Ok(status) => (
+            status.exit_code(),
+            status.failure_message().or_else(|| {
+                (status.exit_code() != 0).then(|| format!("exit code {}", status.exit_code()))
+            }),
+        ),
+        Err(payload) => (
+            PANIC_EXIT_CODE,
+            Some(match payload.downcast_ref::<&str>() {
+                Some(message) => format!("panicked: {}", message),
+                None => match payload.downcast_ref::<String>() {
+                    Some(message) => format!("panicked: {}", message),
+                    None => "panicked".to_string(),
+                },
+            }),
+        ),
+    };
+
+    if let Some(span_id) = span_id {
+        let _ = tracer.add_span_attribute(&span_id, EXIT_CODE_ATTRIBUTE, exit_code.into());
+        if let Some(message) = failure {
+            let _ = tracer.update_span(&span_id, |span| {
+                span.status = SpanStatus::Error { message };
+            });
+        }
+        let _ = tracer.end_span(span_id);
+    }
+    let _ = tracer.flush();
+
+    match outcome {
+        Ok(status) => status,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
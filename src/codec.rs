@@ -0,0 +1,120 @@
+//! Binary encoding module
+//!
+//! Contains compact binary span encodings for message queues and UDP:
+//! MessagePack (`msgpack` feature) and CBOR (`cbor` feature). Both carry the
+//! same fields as the JSON form and decode back into identical spans.
+//! The `*_with_limits` decoders are for payloads from untrusted senders.
+
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+use crate::limits::DecodeLimits;
+use crate::span::Span;
+use crate::tracer::TracingResult;
+use tyl_errors::TylError;
+
+#[cfg(feature = "msgpack")]
+impl Span {
+    /// MessagePack encoding (fields keyed by name, like the JSON form)
+    pub fn to_msgpack(&self) -> TracingResult<Vec<u8>> {
+        to_msgpack(self)
+    }
+
+    pub fn from_msgpack(bytes: &[u8]) -> TracingResult<Self> {
+        from_msgpack(bytes)
+    }
+
+    pub fn from_msgpack_with_limits(bytes: &[u8], limits: &DecodeLimits) -> TracingResult<Self> {
+        limits.check_input(bytes)?;
+        let span = from_msgpack(bytes)?;
+        limits.check_span(&span)?;
+        Ok(span)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl Span {
+    pub fn to_cbor(&self) -> TracingResult<Vec<u8>> {
+        to_cbor(self)
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> TracingResult<Self> {
+        from_cbor(bytes)
+    }
+
+    pub fn from_cbor_with_limits(bytes: &[u8], limits: &DecodeLimits) -> TracingResult<Self> {
+        limits.check_input(bytes)?;
+        let span = from_cbor(bytes)?;
+        limits.check_span(&span)?;
+        Ok(span)
+    }
+}
+
+/// MessagePack encoding of a batch of spans
+#[cfg(feature = "msgpack")]
+pub fn spans_to_msgpack(spans: &[Span]) -> TracingResult<Vec<u8>> {
+    to_msgpack(spans)
+}
+
+#[cfg(feature = "msgpack")]
+pub fn spans_from_msgpack(bytes: &[u8]) -> TracingResult<Vec<Span>> {
+    from_msgpack(bytes)
+}
+
+#[cfg(feature = "msgpack")]
+pub fn spans_from_msgpack_with_limits(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> TracingResult<Vec<Span>> {
+    limits.check_input(bytes)?;
+    let spans = from_msgpack(bytes)?;
+    limits.check_spans(&spans)?;
+    Ok(spans)
+}
+
+/// CBOR encoding of a batch of spans
+#[cfg(feature = "cbor")]
+pub fn spans_to_cbor(spans: &[Span]) -> TracingResult<Vec<u8>> {
+    to_cbor(spans)
+}
+
+#[cfg(feature = "cbor")]
+pub fn spans_from_cbor(bytes: &[u8]) -> TracingResult<Vec<Span>> {
+    from_cbor(bytes)
+}
+
+#[cfg(feature = "cbor")]
+pub fn spans_from_cbor_with_limits(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> TracingResult<Vec<Span>> {
+    limits.check_input(bytes)?;
+    let spans = from_cbor(bytes)?;
+    limits.check_spans(&spans)?;
+    Ok(spans)
+}
+
+// Named (map) encoding: array encoding breaks `skip_serializing_if` fields
+#[cfg(feature = "msgpack")]
+fn to_msgpack<T: serde::Serialize + ?Sized>(value: &T) -> TracingResult<Vec<u8>> {
+    rmp_serde::to_vec_named(value)
+        .map_err(|e| TylError::serialization(format!("MessagePack encoding failed: {}", e)))
+}
+
+#[cfg(feature = "msgpack")]
+fn from_msgpack<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> TracingResult<T> {
+    rmp_serde::from_slice(bytes)
+        .map_err(|e| TylError::serialization(format!("MessagePack decoding failed: {}", e)))
+}
+
+#[cfg(feature = "cbor")]
+fn to_cbor<T: serde::Serialize + ?Sized>(value: &T) -> TracingResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)
+        .map_err(|e| TylError::serialization(format!("CBOR encoding failed: {}", e)))?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "cbor")]
+fn from_cbor<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> TracingResult<T> {
+    ciborium::from_reader(bytes)
+        .map_err(|e| TylError::serialization(format!("CBOR decoding failed: {}", e)))
+}
@@ -0,0 +1,183 @@
+//! Columnar export module
+//!
+//! Contains the Arrow representation of spans (`arrow` cargo feature), for
+//! DataFusion/Polars pipelines, and ParquetExporter (`parquet` feature), which
+//! writes completed spans to Parquet files for analysis with DuckDB, Spark,
+//! and similar engines. Attributes are
+//! flattened into a `map<string, string>` column; non-string values are stored
+//! as JSON text. Span events are not included.
+
+use crate::span::{Span, SpanKind, SpanStatus};
+use arrow_array::builder::{MapBuilder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::SchemaRef;
+use std::sync::Arc;
+#[cfg(feature = "parquet")]
+use {
+    crate::export::{io_error, SpanExporter},
+    crate::span::current_timestamp,
+    crate::tracer::TracingResult,
+    parquet::arrow::ArrowWriter,
+    std::fs::{self, File},
+    std::path::{Path, PathBuf},
+    tyl_errors::TylError,
+    uuid::Uuid,
+};
+
+/// Schema of the batches produced by [`to_arrow`]
+pub fn span_schema() -> SchemaRef {
+    to_arrow(&[]).schema()
+}
+
+/// Spans as an Arrow record batch, one row per span
+pub fn to_arrow(spans: &[Span]) -> RecordBatch {
+    let strings = |value: fn(&Span) -> Option<&str>| -> ArrayRef {
+        Arc::new(spans.iter().map(value).collect::<StringArray>())
+    };
+    // IDs are rendered as lowercase hex
+    let ids = |value: fn(&Span) -> Option<String>| -> ArrayRef {
+        Arc::new(spans.iter().map(value).collect::<StringArray>())
+    };
+    let timestamps = |value: fn(&Span) -> Option<u64>| -> ArrayRef {
+        Arc::new(
+            spans
+                .iter()
+                .map(|span| value(span).map(|ms| ms as i64))
+                .collect::<TimestampMillisecondArray>()
+                .with_timezone("UTC"),
+        )
+    };
+
+    let mut attributes = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+    for span in spans {
+        let mut entries: Vec<_> = span.attributes.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in entries {
+            attributes.keys().append_value(key);
+            match value {
+                serde_json::Value::String(text) => attributes.values().append_value(text),
+                other => attributes.values().append_value(other.to_string()),
+            }
+        }
+        attributes
+            .append(true)
+            .expect("attribute keys and values are appended in pairs");
+    }
+
+    RecordBatch::try_from_iter_with_nullable([
+        (
+            "trace_id",
+            ids(|span| Some(span.trace_id.to_string())),
+            false,
+        ),
+        ("span_id", ids(|span| Some(span.span_id.to_string())), false),
+        (
+            "parent_span_id",
+            ids(|span| span.parent_span_id.map(|id| id.to_string())),
+            true,
+        ),
+        (
+            "operation_name",
+            strings(|span| Some(&span.operation_name)),
+            false,
+        ),
+        ("kind", strings(|span| Some(kind_name(span))), false),
+        (
+            "start_time",
+            timestamps(|span| Some(span.start_time)),
+            false,
+        ),
+        ("end_time", timestamps(|span| span.end_time), true),
+        (
+            "duration_ms",
+            Arc::new(spans.iter().map(Span::duration_ms).collect::<UInt64Array>()) as ArrayRef,
+            true,
+        ),
+        ("status", strings(|span| Some(status_name(span))), false),
+        ("status_message", strings(status_message), true),
+        (
+            "attributes",
+            Arc::new(attributes.finish()) as ArrayRef,
+            false,
+        ),
+    ])
+    .expect("span columns have matching lengths")
+}
+
+fn kind_name(span: &Span) -> &'static str {
+    match span.kind {
+        SpanKind::Internal => "internal",
+        SpanKind::Server => "server",
+        SpanKind::Client => "client",
+        SpanKind::Producer => "producer",
+        SpanKind::Consumer => "consumer",
+    }
+}
+
+fn status_name(span: &Span) -> &'static str {
+    match span.status {
+        SpanStatus::Active => "active",
+        SpanStatus::Completed => "ok",
+        SpanStatus::Error { .. } => "error",
+    }
+}
+
+fn status_message(span: &Span) -> Option<&str> {
+    match &span.status {
+        SpanStatus::Error { message } => Some(message),
+        _ => None,
+    }
+}
+
+/// Write spans to a new Parquet file at `path`
+#[cfg(feature = "parquet")]
+pub fn write_parquet(path: impl AsRef<Path>, spans: &[Span]) -> TracingResult<()> {
+    let path = path.as_ref();
+    let parquet_error = |e: parquet::errors::ParquetError| {
+        TylError::serialization(format!("{}: {}", path.display(), e))
+    };
+    let batch = to_arrow(spans);
+    let file = File::create(path).map_err(|e| io_error(path, e))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(parquet_error)?;
+    writer.write(&batch).map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+/// Adapter - Writes each exported batch to its own Parquet file in a directory
+///
+/// Parquet files cannot be appended to, so files are named
+/// `spans-<unix ms>-<id>.parquet`; query the directory as one dataset
+/// (e.g. `read_parquet('dir/*.parquet')` in DuckDB).
+#[cfg(feature = "parquet")]
+#[derive(Debug, Clone)]
+pub struct ParquetExporter {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetExporter {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl SpanExporter for ParquetExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+        let file_name = format!(
+            "spans-{}-{}.parquet",
+            current_timestamp(),
+            Uuid::new_v4().simple()
+        );
+        write_parquet(self.dir.join(file_name), batch)
+    }
+}
@@ -0,0 +1,133 @@
+//! Export payload compression module
+//!
+//! Contains the Compression setting shared by exporters. Gzip and zstd support
+//! are enabled with the `gzip` and `zstd` cargo features respectively.
+
+use crate::tracer::TracingResult;
+use serde::{Deserialize, Serialize};
+use tyl_errors::TylError;
+
+/// Compression applied to exported payloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    #[default]
+    None,
+    /// Gzip with level 0-9 (requires the `gzip` feature)
+    Gzip { level: u32 },
+    /// Zstandard with level 1-22 (requires the `zstd` feature)
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    pub fn gzip(level: u32) -> Self {
+        Compression::Gzip {
+            level: level.min(9),
+        }
+    }
+
+    pub fn zstd(level: i32) -> Self {
+        Compression::Zstd {
+            level: level.clamp(1, 22),
+        }
+    }
+
+    /// Value for the HTTP `Content-Encoding` header, if any
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip { .. } => Some("gzip"),
+            Compression::Zstd { .. } => Some("zstd"),
+        }
+    }
+
+    /// Conventional file extension suffix (e.g. `.gz`)
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip { .. } => ".gz",
+            Compression::Zstd { .. } => ".zst",
+        }
+    }
+
+    /// Check that the required cargo feature is enabled
+    pub fn validate(&self) -> TracingResult<()> {
+        self.compress(&[]).map(|_| ())
+    }
+
+    pub fn compress(&self, payload: &[u8]) -> TracingResult<Vec<u8>> {
+        match *self {
+            Compression::None => Ok(payload.to_vec()),
+            Compression::Gzip { level } => gzip_compress(payload, level),
+            Compression::Zstd { level } => zstd_compress(payload, level),
+        }
+    }
+
+    /// Decompress a payload; concatenated gzip members / zstd frames are joined
+    pub fn decompress(&self, payload: &[u8]) -> TracingResult<Vec<u8>> {
+        match *self {
+            Compression::None => Ok(payload.to_vec()),
+            Compression::Gzip { .. } => gzip_decompress(payload),
+            Compression::Zstd { .. } => zstd_decompress(payload),
+        }
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_compress(payload: &[u8], level: u32) -> TracingResult<Vec<u8>> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder
+        .write_all(payload)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| TylError::internal(format!("gzip compression failed: {}", e)))
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_decompress(payload: &[u8]) -> TracingResult<Vec<u8>> {
+    use std::io::Read;
+    let mut decoded = Vec::new();
+    flate2::read::MultiGzDecoder::new(payload)
+        .read_to_end(&mut decoded)
+        .map_err(|e| TylError::internal(format!("gzip decompression failed: {}", e)))?;
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_compress(_payload: &[u8], _level: u32) -> TracingResult<Vec<u8>> {
+    Err(feature_disabled("gzip"))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_decompress(_payload: &[u8]) -> TracingResult<Vec<u8>> {
+    Err(feature_disabled("gzip"))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_compress(payload: &[u8], level: i32) -> TracingResult<Vec<u8>> {
+    zstd::stream::encode_all(payload, level)
+        .map_err(|e| TylError::internal(format!("zstd compression failed: {}", e)))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decompress(payload: &[u8]) -> TracingResult<Vec<u8>> {
+    zstd::stream::decode_all(payload)
+        .map_err(|e| TylError::internal(format!("zstd decompression failed: {}", e)))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_compress(_payload: &[u8], _level: i32) -> TracingResult<Vec<u8>> {
+    Err(feature_disabled("zstd"))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decompress(_payload: &[u8]) -> TracingResult<Vec<u8>> {
+    Err(feature_disabled("zstd"))
+}
+
+#[cfg(any(not(feature = "gzip"), not(feature = "zstd")))]
+fn feature_disabled(feature: &str) -> TylError {
+    TylError::configuration(format!(
+        "{} compression requires the `{}` feature of tyl-tracing",
+        feature, feature
+    ))
+}
@@ -1,8 +1,19 @@
 //! Tracing configuration module
 //!
-//! Contains the TraceConfig struct, Environment enum, and ConfigPlugin implementation.
+//! Contains the TraceConfig struct, Environment enum, exporter connection settings,
+//! config file loading with per-environment profiles, and ConfigPlugin implementation.
 
+use crate::compression::Compression;
+use crate::export::DEFAULT_BATCH_SIZE;
+use crate::filter::SpanFilter;
+use crate::ids::IdFormat;
+use crate::lint::KeyValidation;
+use crate::propagator::{Propagator, DEFAULT_PROPAGATORS};
+use crate::tenant::TenantConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tyl_config::{ConfigPlugin, ConfigResult};
 use tyl_errors::TylError;
 
@@ -13,33 +24,221 @@ pub struct TraceConfig {
     pub environment: Environment,
     pub sampling_rate: f64,
     pub max_spans: usize,
+    #[serde(default)]
+    pub exporter: Option<ExporterConfig>,
+    /// Export buffered spans on this schedule even if the batch is not full
+    /// (milliseconds in serialized form)
+    #[serde(default, with = "duration_ms")]
+    pub flush_interval: Option<Duration>,
+    /// Per-operation filter directives, e.g. `checkout=on,health*=off,db.*=0.01`
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Per-tenant sampling rates and span quotas, keyed by tenant ID
+    #[serde(default)]
+    pub tenants: BTreeMap<String, TenantConfig>,
+    /// Spans recorded per trace before further spans are dropped
+    #[serde(default)]
+    pub max_spans_per_trace: Option<usize>,
+    /// Spans open at once before new spans are dropped
+    #[serde(default)]
+    pub max_active_spans: Option<usize>,
+    /// Baggage keys copied onto every span as attributes when it ends
+    #[serde(default)]
+    pub baggage_attributes: Vec<String>,
+    /// Completed spans older than this are pruned in the background
+    /// (milliseconds in serialized form)
+    #[serde(default, with = "duration_ms")]
+    pub retention: Option<Duration>,
+    /// Export each trace as one unit when its root span ends, holding back
+    /// its other spans until then (for Tempo and tail sampling)
+    #[serde(default)]
+    pub export_whole_traces: bool,
+    /// Check keys passed to `add_span_attribute` against the OTel naming rules
+    #[serde(default)]
+    pub attribute_key_validation: KeyValidation,
+    /// Textual form of generated trace and span IDs
+    #[serde(default)]
+    pub id_format: IdFormat,
+    /// Header schemes context is emitted in and accepted from
+    #[serde(default = "default_propagators")]
+    pub propagators: Vec<Propagator>,
+    /// Settings chosen with `with_*` methods, which environment defaults
+    /// leave alone
+    #[serde(skip)]
+    explicit: ExplicitSettings,
+}
+
+/// The environment-dependent settings a builder method has set
+#[derive(Debug, Clone, Copy, Default)]
+struct ExplicitSettings {
+    sampling_rate: bool,
+    exporter: bool,
+    flush_interval: bool,
 }
 
 /// Runtime environment detection
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+///
+/// Serialized as its name; unrecognized names become `Custom`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
 pub enum Environment {
     Development,
     Testing,
+    Staging,
     Production,
+    Custom(String),
+}
+
+/// Default OTLP/HTTP collector endpoint used by the production defaults
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318";
+
+/// Where exported spans go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExporterKind {
+    /// OTLP collector at `endpoint`
+    #[default]
+    Otlp,
+    /// Pretty-printed spans on stdout
+    Console,
+    /// Grafana Tempo's OTLP endpoint; `org_id` and span tenants select the
+    /// `X-Scope-OrgID` header
+    Tempo,
+}
+
+/// Wire protocol used to reach the collector (OTLP naming)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExporterProtocol {
+    #[default]
+    #[serde(rename = "http/json")]
+    HttpJson,
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+    #[serde(rename = "grpc")]
+    Grpc,
+}
+
+/// Default time allowed for a single export request
+pub const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connection settings for exporters that talk to a remote collector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExporterConfig {
+    #[serde(default)]
+    pub kind: ExporterKind,
+    /// Collector URL (unused by the console exporter)
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub protocol: ExporterProtocol,
+    /// Per-request timeout, [`DEFAULT_EXPORT_TIMEOUT`] when unset
+    /// (milliseconds in serialized form)
+    #[serde(default, with = "duration_ms")]
+    pub timeout: Option<Duration>,
+    /// Spans buffered before an export is sent
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub auth: ExporterAuth,
+    /// Tempo tenant for spans without a `tenant.id` (Tempo exporter only)
+    #[serde(default)]
+    pub org_id: Option<String>,
+    /// Request body encoding, sent as `Content-Encoding`
+    #[serde(default)]
+    pub compression: Compression,
+}
+
+/// TLS settings; a client certificate and key together enable mTLS
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub ca_bundle: Option<PathBuf>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    /// Override the server name checked against the collector certificate
+    pub server_name: Option<String>,
+}
+
+/// Credentials sent to the collector with every export request
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExporterAuth {
+    #[default]
+    None,
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        header: String,
+        key: String,
+    },
 }
 
 impl TraceConfig {
+    /// New config with the defaults of the detected environment
     pub fn new(service_name: impl Into<String>) -> Self {
         Self {
             service_name: service_name.into(),
-            environment: Environment::from_env(),
+            environment: Environment::Development,
             sampling_rate: 1.0,
             max_spans: 1000,
+            exporter: None,
+            flush_interval: None,
+            filter: None,
+            tenants: BTreeMap::new(),
+            max_spans_per_trace: None,
+            max_active_spans: None,
+            baggage_attributes: Vec::new(),
+            retention: None,
+            export_whole_traces: false,
+            attribute_key_validation: KeyValidation::Off,
+            id_format: IdFormat::Uuid,
+            propagators: default_propagators(),
+            explicit: ExplicitSettings::default(),
         }
+        .with_environment(Environment::from_env())
     }
 
+    /// Switch environment and apply its defaults
+    ///
+    /// Development samples everything and pretty-prints to the console;
+    /// staging and production batch spans to an OTLP collector at 25% and 5%
+    /// sampling. Settings already chosen with `with_sampling_rate`,
+    /// `with_exporter` or `with_flush_interval` are kept, so the call order
+    /// does not matter; fields assigned directly count as defaults.
     pub fn with_environment(mut self, environment: Environment) -> Self {
+        let (sampling_rate, exporter, flush_interval) = match environment {
+            Environment::Development => (1.0, Some(ExporterConfig::console()), None),
+            Environment::Staging => (
+                0.25,
+                Some(ExporterConfig::new(DEFAULT_OTLP_ENDPOINT)),
+                Some(Duration::from_secs(5)),
+            ),
+            Environment::Production => (
+                0.05,
+                Some(ExporterConfig::new(DEFAULT_OTLP_ENDPOINT)),
+                Some(Duration::from_secs(5)),
+            ),
+            Environment::Testing | Environment::Custom(_) => (1.0, None, None),
+        };
+        if !self.explicit.sampling_rate {
+            self.sampling_rate = sampling_rate;
+        }
+        if !self.explicit.exporter {
+            self.exporter = exporter;
+        }
+        if !self.explicit.flush_interval {
+            self.flush_interval = flush_interval;
+        }
         self.environment = environment;
         self
     }
 
     pub fn with_sampling_rate(mut self, rate: f64) -> Self {
         self.sampling_rate = rate.clamp(0.0, 1.0);
+        self.explicit.sampling_rate = true;
         self
     }
 
@@ -47,31 +246,758 @@ impl TraceConfig {
         self.max_spans = max_spans;
         self
     }
-}
 
-impl ConfigPlugin for TraceConfig {
-    fn name(&self) -> &'static str {
-        "tracing"
+    pub fn with_exporter(mut self, exporter: ExporterConfig) -> Self {
+        self.exporter = Some(exporter);
+        self.explicit.exporter = true;
+        self
     }
 
-    fn env_prefix(&self) -> &'static str {
-        "TRACE"
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self.explicit.flush_interval = true;
+        self
     }
 
-    fn validate(&self) -> ConfigResult<()> {
-        if self.service_name.is_empty() {
-            return Err(TylError::validation("service_name", "cannot be empty"));
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Prune completed spans older than `retention`, on top of the
+    /// `max_spans` cap
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Hand each trace to the exporter in one batch when its root span ends
+    pub fn with_export_whole_traces(mut self, enabled: bool) -> Self {
+        self.export_whole_traces = enabled;
+        self
+    }
+
+    /// Lint attribute keys, warning about or rejecting non-conforming ones
+    pub fn with_attribute_key_validation(mut self, mode: KeyValidation) -> Self {
+        self.attribute_key_validation = mode;
+        self
+    }
+
+    /// Render generated IDs as UUIDs, hex, or base64
+    pub fn with_id_format(mut self, format: IdFormat) -> Self {
+        self.id_format = format;
+        self
+    }
+
+    /// Emit context in every one of these schemes and accept any of them,
+    /// e.g. `[W3c, B3, Baggage]` while callers migrate from Zipkin headers
+    pub fn with_propagators(mut self, propagators: impl IntoIterator<Item = Propagator>) -> Self {
+        self.propagators = propagators.into_iter().collect();
+        self
+    }
+
+    pub fn with_max_spans_per_trace(mut self, max_spans: usize) -> Self {
+        self.max_spans_per_trace = Some(max_spans);
+        self
+    }
+
+    pub fn with_max_active_spans(mut self, max_spans: usize) -> Self {
+        self.max_active_spans = Some(max_spans);
+        self
+    }
+
+    /// Copy these baggage keys onto spans as attributes, for backends that
+    /// cannot query baggage; existing span attributes win
+    pub fn with_baggage_attributes<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.baggage_attributes = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_tenant_config(
+        mut self,
+        tenant_id: impl Into<String>,
+        tenant: TenantConfig,
+    ) -> Self {
+        self.tenants.insert(tenant_id.into(), tenant);
+        self
+    }
+
+    /// Load configuration from a TOML, YAML, or JSON file
+    ///
+    /// Top-level keys form the base configuration; a `[profiles.<env>]` section
+    /// (`development`/`dev`, `testing`/`test`, `staging`/`stage`,
+    /// `production`/`prod`, or a custom environment's name) matching the
+    /// active environment is merged over it, then environment variables are
+    /// applied and the result is validated. The active environment comes from
+    /// `TYL_ENVIRONMENT`/`ENVIRONMENT`, falling back to the file's `environment`.
+    ///
+    /// ```toml
+    /// service_name = "checkout"
+    /// sampling_rate = 1.0
+    ///
+    /// [profiles.production]
+    /// sampling_rate = 0.05
+    /// flush_interval = 5000
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> ConfigResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TylError::configuration(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let document = parse_config_document(&format, &contents).map_err(|e| {
+            TylError::configuration(format!("invalid config file {}: {}", path.display(), e))
+        })?;
+        Self::from_document(document)
+    }
+
+    fn from_document(mut document: serde_json::Value) -> ConfigResult<Self> {
+        let profiles = document
+            .as_object_mut()
+            .and_then(|root| root.remove("profiles"))
+            .unwrap_or(serde_json::Value::Null);
+
+        let environment = environment_from_env_vars()?
+            .or_else(|| {
+                document
+                    .get("environment")
+                    .and_then(|env| serde_json::from_value(env.clone()).ok())
+            })
+            .unwrap_or_else(Environment::from_env);
+
+        let profile = environment
+            .profile_names()
+            .iter()
+            .find_map(|name| profiles.get(*name))
+            .cloned();
+
+        let mut merged =
+            serde_json::to_value(Self::new("app").with_environment(environment.clone()))
+                .map_err(|e| TylError::configuration(format!("invalid defaults: {}", e)))?;
+        // An exporter given in the file replaces the environment default outright
+        let configures_exporter = |value: &serde_json::Value| value.get("exporter").is_some();
+        if configures_exporter(&document) || profile.as_ref().is_some_and(configures_exporter) {
+            merged["exporter"] = serde_json::Value::Null;
+        }
+        merge_values(&mut merged, document);
+        if let Some(profile) = profile {
+            merge_values(&mut merged, profile);
+        }
+
+        let mut config: Self = serde_json::from_value(merged)
+            .map_err(|e| TylError::configuration(format!("invalid tracing config: {}", e)))?;
+        config.environment = environment;
+        config.merge_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl TraceConfig {
+    /// Start a builder that validates everything at once in `build()`
+    pub fn builder(service_name: impl Into<String>) -> TraceConfigBuilder {
+        TraceConfigBuilder::new(service_name)
+    }
+
+    /// Every validation problem, in field order (empty when valid)
+    pub fn validation_errors(&self) -> Vec<TylError> {
+        let mut errors = Vec::new();
+        if self.service_name.trim().is_empty() {
+            errors.push(TylError::validation("service_name", "cannot be empty"));
         }
         if !(0.0..=1.0).contains(&self.sampling_rate) {
-            return Err(TylError::validation(
+            errors.push(TylError::validation(
                 "sampling_rate",
                 "must be between 0.0 and 1.0",
             ));
         }
         if self.max_spans == 0 {
-            return Err(TylError::validation("max_spans", "must be greater than 0"));
+            errors.push(TylError::validation("max_spans", "must be greater than 0"));
+        }
+        if self.max_spans_per_trace == Some(0) {
+            errors.push(TylError::validation(
+                "max_spans_per_trace",
+                "must be greater than 0",
+            ));
+        }
+        if self.max_active_spans == Some(0) {
+            errors.push(TylError::validation(
+                "max_active_spans",
+                "must be greater than 0",
+            ));
+        }
+        if self.flush_interval == Some(Duration::ZERO) {
+            errors.push(TylError::validation(
+                "flush_interval",
+                "must be greater than 0",
+            ));
+        }
+        if self.retention == Some(Duration::ZERO) {
+            errors.push(TylError::validation("retention", "must be greater than 0"));
+        }
+        if let Some(Err(error)) = self.filter.as_deref().map(SpanFilter::parse) {
+            errors.push(error);
+        }
+        if let Some(exporter) = &self.exporter {
+            errors.extend(exporter.validation_errors());
+        }
+        for (tenant_id, tenant) in &self.tenants {
+            if tenant_id.trim().is_empty() {
+                errors.push(TylError::validation("tenants", "tenant ID cannot be empty"));
+            }
+            if tenant
+                .sampling_rate
+                .is_some_and(|rate| !(0.0..=1.0).contains(&rate))
+            {
+                errors.push(TylError::validation(
+                    format!("tenants.{}.sampling_rate", tenant_id),
+                    "must be between 0.0 and 1.0",
+                ));
+            }
+            if tenant.spans_per_minute == Some(0) {
+                errors.push(TylError::validation(
+                    format!("tenants.{}.spans_per_minute", tenant_id),
+                    "must be greater than 0",
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Builder for [`TraceConfig`] that reports every problem from `build()`
+///
+/// Unlike the `with_*` methods on TraceConfig, values are taken as given
+/// (no clamping), so out-of-range settings surface as errors at startup.
+#[derive(Debug, Clone)]
+pub struct TraceConfigBuilder {
+    service_name: String,
+    environment: Option<Environment>,
+    sampling_rate: Option<f64>,
+    max_spans: Option<usize>,
+    max_spans_per_trace: Option<usize>,
+    max_active_spans: Option<usize>,
+    exporter: Option<Option<ExporterConfig>>,
+    flush_interval: Option<Duration>,
+    filter: Option<String>,
+    baggage_attributes: Option<Vec<String>>,
+    retention: Option<Duration>,
+    export_whole_traces: Option<bool>,
+    attribute_key_validation: Option<KeyValidation>,
+    id_format: Option<IdFormat>,
+    propagators: Option<Vec<Propagator>>,
+}
+
+impl TraceConfigBuilder {
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            environment: None,
+            sampling_rate: None,
+            max_spans: None,
+            max_spans_per_trace: None,
+            max_active_spans: None,
+            exporter: None,
+            flush_interval: None,
+            filter: None,
+            baggage_attributes: None,
+            retention: None,
+            export_whole_traces: None,
+            attribute_key_validation: None,
+            id_format: None,
+            propagators: None,
+        }
+    }
+
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    pub fn with_sampling_rate(mut self, rate: f64) -> Self {
+        self.sampling_rate = Some(rate);
+        self
+    }
+
+    pub fn with_max_spans(mut self, max_spans: usize) -> Self {
+        self.max_spans = Some(max_spans);
+        self
+    }
+
+    pub fn with_max_spans_per_trace(mut self, max_spans: usize) -> Self {
+        self.max_spans_per_trace = Some(max_spans);
+        self
+    }
+
+    pub fn with_max_active_spans(mut self, max_spans: usize) -> Self {
+        self.max_active_spans = Some(max_spans);
+        self
+    }
+
+    pub fn with_exporter(mut self, exporter: ExporterConfig) -> Self {
+        self.exporter = Some(Some(exporter));
+        self
+    }
+
+    /// Drop the environment's default exporter
+    pub fn without_exporter(mut self) -> Self {
+        self.exporter = Some(None);
+        self
+    }
+
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    pub fn with_export_whole_traces(mut self, enabled: bool) -> Self {
+        self.export_whole_traces = Some(enabled);
+        self
+    }
+
+    pub fn with_attribute_key_validation(mut self, mode: KeyValidation) -> Self {
+        self.attribute_key_validation = Some(mode);
+        self
+    }
+
+    pub fn with_id_format(mut self, format: IdFormat) -> Self {
+        self.id_format = Some(format);
+        self
+    }
+
+    pub fn with_propagators(mut self, propagators: impl IntoIterator<Item = Propagator>) -> Self {
+        self.propagators = Some(propagators.into_iter().collect());
+        self
+    }
+
+    pub fn with_baggage_attributes<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.baggage_attributes = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Build and validate; all problems are reported in a single error
+    ///
+    /// Use [`TraceConfig::validation_errors`] on an unvalidated config to get
+    /// them individually.
+    pub fn build(self) -> ConfigResult<TraceConfig> {
+        let config = self.build_unchecked();
+        let errors = config.validation_errors();
+        match errors.len() {
+            0 => Ok(config),
+            1 => Err(errors.into_iter().next().unwrap()),
+            count => Err(TylError::validation(
+                "trace_config",
+                format!(
+                    "{} problems: {}",
+                    count,
+                    errors
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ),
+            )),
+        }
+    }
+
+    /// The config as specified, without validation
+    pub fn build_unchecked(self) -> TraceConfig {
+        let mut config = TraceConfig::new(self.service_name);
+        if let Some(environment) = self.environment {
+            config = config.with_environment(environment);
+        }
+        if let Some(rate) = self.sampling_rate {
+            config.sampling_rate = rate;
+        }
+        if let Some(max_spans) = self.max_spans {
+            config.max_spans = max_spans;
+        }
+        if self.max_spans_per_trace.is_some() {
+            config.max_spans_per_trace = self.max_spans_per_trace;
+        }
+        if self.max_active_spans.is_some() {
+            config.max_active_spans = self.max_active_spans;
+        }
+        if let Some(exporter) = self.exporter {
+            config.exporter = exporter;
+        }
+        if let Some(interval) = self.flush_interval {
+            config.flush_interval = Some(interval);
+        }
+        if let Some(filter) = self.filter {
+            config.filter = Some(filter);
+        }
+        if let Some(keys) = self.baggage_attributes {
+            config.baggage_attributes = keys;
+        }
+        if let Some(retention) = self.retention {
+            config.retention = Some(retention);
+        }
+        if let Some(enabled) = self.export_whole_traces {
+            config.export_whole_traces = enabled;
+        }
+        if let Some(mode) = self.attribute_key_validation {
+            config.attribute_key_validation = mode;
+        }
+        if let Some(format) = self.id_format {
+            config.id_format = format;
+        }
+        if let Some(propagators) = self.propagators {
+            config.propagators = propagators;
+        }
+        config
+    }
+}
+
+impl ExporterConfig {
+    /// OTLP exporter sending to `endpoint`
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            kind: ExporterKind::Otlp,
+            endpoint: endpoint.into(),
+            protocol: ExporterProtocol::HttpJson,
+            timeout: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            headers: BTreeMap::new(),
+            tls: None,
+            auth: ExporterAuth::None,
+            org_id: None,
+            compression: Compression::None,
+        }
+    }
+
+    /// Grafana Tempo exporter sending OTLP to `endpoint`
+    pub fn tempo(endpoint: impl Into<String>) -> Self {
+        Self {
+            kind: ExporterKind::Tempo,
+            ..Self::new(endpoint)
+        }
+    }
+
+    pub fn with_org_id(mut self, org_id: impl Into<String>) -> Self {
+        self.org_id = Some(org_id.into());
+        self
+    }
+
+    /// Console exporter pretty-printing spans to stdout as they end
+    pub fn console() -> Self {
+        Self {
+            kind: ExporterKind::Console,
+            batch_size: 1,
+            ..Self::new("")
+        }
+    }
+
+    pub fn with_protocol(mut self, protocol: ExporterProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_EXPORT_TIMEOUT)
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn with_auth(mut self, auth: ExporterAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Compress request bodies (gzip/zstd need their cargo features)
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Custom headers plus the authentication header, as sent on each request
+    pub fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        if let Some(auth_header) = self.auth.header() {
+            headers.push(auth_header);
+        }
+        headers
+    }
+
+    /// Check endpoint, headers, credentials, and that TLS files are readable
+    pub fn validate(&self) -> ConfigResult<()> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem [`validate`](Self::validate) checks for, in field order
+    pub fn validation_errors(&self) -> Vec<TylError> {
+        let mut errors = Vec::new();
+        if self.batch_size == 0 {
+            errors.push(TylError::validation(
+                "exporter.batch_size",
+                "must be greater than 0",
+            ));
+        }
+        if self.timeout == Some(Duration::ZERO) {
+            errors.push(TylError::validation(
+                "exporter.timeout",
+                "must be greater than 0",
+            ));
+        }
+        if self.kind == ExporterKind::Console {
+            return errors;
+        }
+        if self.protocol != ExporterProtocol::HttpJson {
+            errors.push(TylError::validation(
+                "exporter.protocol",
+                format!(
+                    "{:?} is not supported by the built-in exporters (use http/json)",
+                    self.protocol
+                ),
+            ));
+        }
+        let scheme = self.endpoint.split("://").next().unwrap_or_default();
+        if !self.endpoint.contains("://") || !matches!(scheme, "http" | "https") {
+            errors.push(TylError::validation(
+                "exporter.endpoint",
+                format!("must be an http(s) URL, got '{}'", self.endpoint),
+            ));
+        } else if self.tls.is_some() && scheme != "https" {
+            errors.push(TylError::validation(
+                "exporter.endpoint",
+                "TLS settings require an https endpoint",
+            ));
+        } else if scheme == "https" && !cfg!(feature = "tls") {
+            errors.push(TylError::validation(
+                "exporter.endpoint",
+                "https endpoints need the `tls` cargo feature",
+            ));
+        }
+        for (name, value) in &self.headers {
+            errors.extend(validate_header("exporter.headers", name, value).err());
+        }
+        errors.extend(self.auth.validate().err());
+        errors.extend(
+            self.compression
+                .validate()
+                .err()
+                .map(|e| TylError::validation("exporter.compression", e.to_string())),
+        );
+        if let Some(tls) = &self.tls {
+            errors.extend(tls.validation_errors());
+        }
+        errors
+    }
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// Client certificate and private key (PEM) for mutual TLS
+    pub fn with_client_identity(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_cert = Some(cert.into());
+        self.client_key = Some(key.into());
+        self
+    }
+
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    pub fn is_mutual(&self) -> bool {
+        self.client_cert.is_some() && self.client_key.is_some()
+    }
+
+    pub fn validate(&self) -> ConfigResult<()> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Every TLS problem, in field order
+    pub fn validation_errors(&self) -> Vec<TylError> {
+        let mut errors = Vec::new();
+        if self.client_cert.is_some() != self.client_key.is_some() {
+            errors.push(TylError::validation(
+                "exporter.tls",
+                "client_cert and client_key must be set together",
+            ));
+        }
+        let files = [
+            ("exporter.tls.ca_bundle", &self.ca_bundle),
+            ("exporter.tls.client_cert", &self.client_cert),
+            ("exporter.tls.client_key", &self.client_key),
+        ];
+        for (field, path) in files {
+            if let Some(path) = path {
+                errors.extend(validate_readable_file(field, path).err());
+            }
+        }
+        errors
+    }
+}
+
+impl ExporterAuth {
+    pub fn bearer(token: impl Into<String>) -> Self {
+        ExporterAuth::Bearer {
+            token: token.into(),
+        }
+    }
+
+    pub fn api_key(header: impl Into<String>, key: impl Into<String>) -> Self {
+        ExporterAuth::ApiKey {
+            header: header.into(),
+            key: key.into(),
+        }
+    }
+
+    /// The header carrying these credentials, if any
+    pub fn header(&self) -> Option<(String, String)> {
+        match self {
+            ExporterAuth::None => None,
+            ExporterAuth::Bearer { token } => {
+                Some(("Authorization".to_string(), format!("Bearer {}", token)))
+            }
+            ExporterAuth::ApiKey { header, key } => Some((header.clone(), key.clone())),
+        }
+    }
+
+    pub fn validate(&self) -> ConfigResult<()> {
+        match self {
+            ExporterAuth::None => Ok(()),
+            ExporterAuth::Bearer { token } if token.trim().is_empty() => Err(TylError::validation(
+                "exporter.auth",
+                "bearer token cannot be empty",
+            )),
+            ExporterAuth::ApiKey { key, .. } if key.trim().is_empty() => Err(TylError::validation(
+                "exporter.auth",
+                "API key cannot be empty",
+            )),
+            _ => match self.header() {
+                Some((name, value)) => validate_header("exporter.auth", &name, &value),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+// Credentials must never end up in logs
+impl std::fmt::Debug for ExporterAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExporterAuth::None => write!(f, "None"),
+            ExporterAuth::Bearer { .. } => write!(f, "Bearer {{ token: \"<redacted>\" }}"),
+            ExporterAuth::ApiKey { header, .. } => {
+                write!(f, "ApiKey {{ header: {:?}, key: \"<redacted>\" }}", header)
+            }
+        }
+    }
+}
+
+fn validate_header(field: &str, name: &str, value: &str) -> ConfigResult<()> {
+    // RFC 7230 token characters
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+    if !valid_name {
+        return Err(TylError::validation(
+            field,
+            format!("invalid header name '{}'", name),
+        ));
+    }
+    if value.chars().any(|c| c == '\r' || c == '\n' || c == '\0') {
+        return Err(TylError::validation(
+            field,
+            format!("header '{}' contains control characters", name),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_readable_file(field: &str, path: &Path) -> ConfigResult<()> {
+    match std::fs::File::open(path) {
+        Ok(file) if file.metadata().map(|m| m.is_file()).unwrap_or(false) => Ok(()),
+        Ok(_) => Err(TylError::validation(
+            field,
+            format!("{} is not a file", path.display()),
+        )),
+        Err(e) => Err(TylError::validation(
+            field,
+            format!("cannot read {}: {}", path.display(), e),
+        )),
+    }
+}
+
+impl ConfigPlugin for TraceConfig {
+    fn name(&self) -> &'static str {
+        "tracing"
+    }
+
+    fn env_prefix(&self) -> &'static str {
+        "TRACE"
+    }
+
+    fn validate(&self) -> ConfigResult<()> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     fn load_from_env(&self) -> ConfigResult<Self> {
@@ -92,10 +1018,16 @@ impl ConfigPlugin for TraceConfig {
         if let Ok(rate_str) = std::env::var("TYL_TRACE_SAMPLING_RATE")
             .or_else(|_| std::env::var("TRACE_SAMPLING_RATE"))
         {
-            self.sampling_rate = rate_str
+            let rate = rate_str
                 .parse::<f64>()
-                .map_err(|e| TylError::configuration(format!("invalid sampling rate: {}", e)))?
-                .clamp(0.0, 1.0);
+                .map_err(|e| TylError::configuration(format!("invalid sampling rate: {}", e)))?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(TylError::configuration(format!(
+                    "invalid sampling rate: {} is not between 0.0 and 1.0",
+                    rate
+                )));
+            }
+            self.sampling_rate = rate;
         }
 
         // TYL_TRACE_MAX_SPANS or TRACE_MAX_SPANS
@@ -107,21 +1039,111 @@ impl ConfigPlugin for TraceConfig {
                 .map_err(|e| TylError::configuration(format!("invalid max spans: {}", e)))?;
         }
 
-        // TYL_ENVIRONMENT or ENVIRONMENT
-        if let Ok(env_str) =
-            std::env::var("TYL_ENVIRONMENT").or_else(|_| std::env::var("ENVIRONMENT"))
+        // TYL_TRACE_FLUSH_INTERVAL_MS or TRACE_FLUSH_INTERVAL_MS
+        if let Ok(interval_str) = std::env::var("TYL_TRACE_FLUSH_INTERVAL_MS")
+            .or_else(|_| std::env::var("TRACE_FLUSH_INTERVAL_MS"))
+        {
+            let millis = interval_str
+                .parse::<u64>()
+                .map_err(|e| TylError::configuration(format!("invalid flush interval: {}", e)))?;
+            self.flush_interval = Some(Duration::from_millis(millis));
+        }
+
+        // TYL_TRACE_FILTER or TRACE_FILTER
+        if let Ok(filter) =
+            std::env::var("TYL_TRACE_FILTER").or_else(|_| std::env::var("TRACE_FILTER"))
+        {
+            SpanFilter::parse(&filter)?;
+            self.filter = Some(filter);
+        }
+
+        // TYL_TRACE_BAGGAGE_ATTRIBUTES or TRACE_BAGGAGE_ATTRIBUTES (comma-separated keys)
+        if let Ok(keys) = std::env::var("TYL_TRACE_BAGGAGE_ATTRIBUTES")
+            .or_else(|_| std::env::var("TRACE_BAGGAGE_ATTRIBUTES"))
         {
-            self.environment = match env_str.to_lowercase().as_str() {
-                "development" | "dev" => Environment::Development,
-                "production" | "prod" => Environment::Production,
-                "test" | "testing" => Environment::Testing,
-                _ => {
+            self.baggage_attributes = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        // TYL_TRACE_PROPAGATORS or TRACE_PROPAGATORS (comma-separated; OTel
+        // names are accepted too)
+        if let Ok(names) =
+            std::env::var("TYL_TRACE_PROPAGATORS").or_else(|_| std::env::var("TRACE_PROPAGATORS"))
+        {
+            let mut propagators = Vec::new();
+            for name in names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                propagators.push(match name.to_lowercase().as_str() {
+                    "w3c" | "tracecontext" => Propagator::W3c,
+                    "b3" => Propagator::B3,
+                    "b3multi" => Propagator::B3Multi,
+                    "baggage" => Propagator::Baggage,
+                    other => {
+                        return Err(TylError::configuration(format!(
+                            "invalid propagator '{}' (expected w3c, b3, b3multi, or baggage)",
+                            other
+                        )))
+                    }
+                });
+            }
+            self.propagators = propagators;
+        }
+
+        // TYL_TRACE_EXPORTER or TRACE_EXPORTER (otlp, tempo, console, none)
+        let mut exporter_from_env = false;
+        if let Ok(kind) =
+            std::env::var("TYL_TRACE_EXPORTER").or_else(|_| std::env::var("TRACE_EXPORTER"))
+        {
+            let kind = match kind.trim().to_lowercase().as_str() {
+                "none" | "off" => None,
+                "otlp" => Some(ExporterKind::Otlp),
+                "console" | "stdout" => Some(ExporterKind::Console),
+                "tempo" => Some(ExporterKind::Tempo),
+                other => {
                     return Err(TylError::configuration(format!(
-                        "invalid environment: {}",
-                        env_str
+                        "invalid exporter '{}' (expected otlp, tempo, console, or none)",
+                        other
                     )))
                 }
             };
+            self.exporter = match (kind, self.exporter.take()) {
+                (None, _) => None,
+                (Some(kind), Some(exporter)) if exporter.kind == kind => Some(exporter),
+                (Some(ExporterKind::Console), _) => Some(ExporterConfig::console()),
+                (Some(ExporterKind::Otlp), _) => Some(ExporterConfig::new(DEFAULT_OTLP_ENDPOINT)),
+                (Some(ExporterKind::Tempo), _) => {
+                    Some(ExporterConfig::tempo(DEFAULT_OTLP_ENDPOINT))
+                }
+            };
+            exporter_from_env = true;
+        }
+
+        // TYL_TRACE_ENDPOINT or TRACE_ENDPOINT; implies OTLP unless the
+        // exporter was chosen explicitly above
+        if let Ok(endpoint) =
+            std::env::var("TYL_TRACE_ENDPOINT").or_else(|_| std::env::var("TRACE_ENDPOINT"))
+        {
+            match &mut self.exporter {
+                Some(exporter) if exporter.kind != ExporterKind::Console => {
+                    exporter.endpoint = endpoint
+                }
+                _ if exporter_from_env => {}
+                _ => self.exporter = Some(ExporterConfig::new(endpoint)),
+            }
+        }
+
+        // TYL_ENVIRONMENT or ENVIRONMENT
+        if let Ok(env_str) =
+            std::env::var("TYL_ENVIRONMENT").or_else(|_| std::env::var("ENVIRONMENT"))
+        {
+            self.environment = parse_environment(&env_str)?;
         }
 
         Ok(())
@@ -129,15 +1151,137 @@ impl ConfigPlugin for TraceConfig {
 }
 
 impl Environment {
+    /// Parse an environment name; unknown names become `Custom`
+    pub fn parse(name: &str) -> Self {
+        let name = name.trim();
+        match name.to_lowercase().as_str() {
+            "development" | "dev" => Environment::Development,
+            "testing" | "test" => Environment::Testing,
+            "staging" | "stage" => Environment::Staging,
+            "production" | "prod" => Environment::Production,
+            _ => Environment::Custom(name.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Environment::Development => "Development",
+            Environment::Testing => "Testing",
+            Environment::Staging => "Staging",
+            Environment::Production => "Production",
+            Environment::Custom(name) => name,
+        }
+    }
+
+    /// Profile section names matching this environment in config files
+    pub fn profile_names(&self) -> Vec<&str> {
+        match self {
+            Environment::Development => vec!["development", "dev"],
+            Environment::Testing => vec!["testing", "test"],
+            Environment::Staging => vec!["staging", "stage"],
+            Environment::Production => vec!["production", "prod"],
+            Environment::Custom(name) => vec![name.as_str()],
+        }
+    }
+
     pub fn from_env() -> Self {
-        match std::env::var("ENVIRONMENT")
-            .unwrap_or_else(|_| "development".to_string())
-            .to_lowercase()
-            .as_str()
-        {
-            "prod" | "production" => Environment::Production,
-            "test" | "testing" => Environment::Testing,
-            _ => Environment::Development,
+        std::env::var("ENVIRONMENT")
+            .ok()
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| Environment::parse(&name))
+            .unwrap_or(Environment::Development)
+    }
+}
+
+impl From<String> for Environment {
+    fn from(name: String) -> Self {
+        if name.trim().is_empty() {
+            Environment::Development
+        } else {
+            Environment::parse(&name)
         }
     }
 }
+
+impl From<Environment> for String {
+    fn from(environment: Environment) -> Self {
+        environment.name().to_string()
+    }
+}
+
+impl std::fmt::Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn parse_environment(env_str: &str) -> ConfigResult<Environment> {
+    if env_str.trim().is_empty() {
+        return Err(TylError::configuration("invalid environment: empty name"));
+    }
+    Ok(Environment::parse(env_str))
+}
+
+/// Environment named by `TYL_ENVIRONMENT`/`ENVIRONMENT`, if either is set
+fn environment_from_env_vars() -> ConfigResult<Option<Environment>> {
+    match std::env::var("TYL_ENVIRONMENT").or_else(|_| std::env::var("ENVIRONMENT")) {
+        Ok(env_str) => parse_environment(&env_str).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_config_document(format: &str, contents: &str) -> Result<serde_json::Value, String> {
+    match format {
+        "json" => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        #[cfg(feature = "toml")]
+        "toml" => toml::from_str(contents).map_err(|e| e.to_string()),
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+        other => Err(format!(
+            "unsupported format '{}' (enable the `toml`/`yaml` features for those files)",
+            other
+        )),
+    }
+}
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH_SIZE
+}
+
+fn default_propagators() -> Vec<Propagator> {
+    DEFAULT_PROPAGATORS.to_vec()
+}
+
+/// Recursively overlay `overlay` onto `base`; objects merge, everything else replaces
+fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_values(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Serialize `Option<Duration>` as whole milliseconds
+mod duration_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
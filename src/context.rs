@@ -0,0 +1,135 @@
+//! Context module
+//!
+//! Contains the per-thread current span context. A span attached with
+//! [`Context::attach`] becomes the parent of spans started on that thread
+//! without an explicit parent, until the returned guard is dropped.
+//!
+//! A context may also carry the baggage of the request it serves
+//! ([`Context::from_request`]). Attaching it for the duration of a request -
+//! or polling the handler future with it, see `FutureContextExt` - makes that
+//! baggage visible to every span of the request and drops it with the guard,
+//! leaving the tracer's own baggage untouched. Contexts attached inside
+//! without baggage of their own keep the enclosing request's.
+
+use crate::baggage::{parse_baggage, BAGGAGE_HEADER};
+use crate::propagation::Extractor;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+thread_local! {
+    static STACK: RefCell<Vec<Context>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The span work on a thread runs under
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    span_id: Option<String>,
+    baggage: Option<Arc<HashMap<String, String>>>,
+}
+
+impl Context {
+    /// Context attached to the calling thread (empty if none)
+    pub fn current() -> Self {
+        STACK.with(|stack| stack.borrow().last().cloned().unwrap_or_default())
+    }
+
+    /// Context whose current span is `span_id`
+    pub fn with_span(span_id: impl Into<String>) -> Self {
+        Self {
+            span_id: Some(span_id.into()),
+            baggage: None,
+        }
+    }
+
+    /// Context holding the baggage of an incoming request's `baggage` header
+    pub fn from_request(carrier: &dyn Extractor) -> Self {
+        let baggage = carrier.get(BAGGAGE_HEADER).map(parse_baggage);
+        Self::default().with_baggage(baggage.unwrap_or_default())
+    }
+
+    /// This context with `baggage` in place of any it inherits
+    pub fn with_baggage(mut self, baggage: HashMap<String, String>) -> Self {
+        self.baggage = Some(Arc::new(baggage));
+        self
+    }
+
+    pub fn span_id(&self) -> Option<&str> {
+        self.span_id.as_deref()
+    }
+
+    /// Request-scoped baggage entry
+    pub fn baggage(&self, key: &str) -> Option<&str> {
+        self.baggage.as_ref()?.get(key).map(String::as_str)
+    }
+
+    /// Every request-scoped baggage entry
+    pub fn baggage_entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.baggage
+            .iter()
+            .flat_map(|baggage| baggage.iter())
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Whether this context carries request-scoped baggage
+    pub fn has_baggage(&self) -> bool {
+        self.baggage.is_some()
+    }
+
+    /// Make this the thread's current context until the guard is dropped
+    pub fn attach(&self) -> ContextGuard {
+        push(self.clone());
+        ContextGuard {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+/// ID of the span attached to the calling thread, if any
+pub fn current_span_id() -> Option<String> {
+    Context::current().span_id
+}
+
+/// Attach without a guard; every push must be matched by a [`pop`]
+pub(crate) fn push(mut context: Context) {
+    STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if context.baggage.is_none() {
+            context.baggage = stack.last().and_then(|current| current.baggage.clone());
+        }
+        stack.push(context);
+    });
+}
+
+pub(crate) fn pop() {
+    let _ = STACK.try_with(|stack| stack.borrow_mut().pop());
+}
+
+/// Remove the latest entry for `context`'s span even if others were attached
+/// after it
+pub(crate) fn remove(context: &Context) {
+    let _ = STACK.try_with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if let Some(index) = stack
+            .iter()
+            .rposition(|entry| entry.span_id == context.span_id)
+        {
+            stack.remove(index);
+        }
+    });
+}
+
+/// Restores the previous context when dropped
+#[must_use = "the context is detached as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ContextGuard {
+    // Must be dropped on the thread that attached it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        pop();
+    }
+}
@@ -0,0 +1,75 @@
+//! CPU time module
+//!
+//! Measures the thread CPU time consumed between span start and end (enabled
+//! with the `cpu-time` cargo feature). Comparing it with the wall-clock
+//! duration separates compute from waiting on I/O or locks. Only spans that
+//! start and end on the same thread are measured; CPU time is read with
+//! `CLOCK_THREAD_CPUTIME_ID` on Unix and is unavailable elsewhere.
+
+use crate::ids::SpanId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread::ThreadId;
+use std::time::Instant;
+
+/// Span attribute: thread CPU time spent in the span, in nanoseconds
+pub const CPU_TIME_ATTRIBUTE: &str = "cpu.time_ns";
+/// Span attribute: CPU time divided by wall-clock time (0.0-1.0)
+pub const CPU_UTILIZATION_ATTRIBUTE: &str = "cpu.utilization";
+
+/// CPU time consumed by the calling thread so far
+pub fn thread_cpu_time_ns() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `time` is a valid, writable timespec for the duration of the call
+        let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+        (result == 0).then(|| time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+#[derive(Debug)]
+struct CpuStart {
+    thread: ThreadId,
+    cpu_ns: u64,
+    wall: Instant,
+}
+
+/// CPU clock readings of active spans
+#[derive(Debug, Default)]
+pub(crate) struct CpuClock {
+    starts: Mutex<HashMap<SpanId, CpuStart>>,
+}
+
+impl CpuClock {
+    pub(crate) fn start(&self, span_id: SpanId) {
+        if let Some(cpu_ns) = thread_cpu_time_ns() {
+            self.starts.lock().unwrap().insert(
+                span_id,
+                CpuStart {
+                    thread: std::thread::current().id(),
+                    cpu_ns,
+                    wall: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// CPU nanoseconds and utilization since `start`, if on the same thread
+    pub(crate) fn finish(&self, span_id: SpanId) -> Option<(u64, f64)> {
+        let start = self.starts.lock().unwrap().remove(&span_id)?;
+        if start.thread != std::thread::current().id() {
+            return None;
+        }
+        let cpu_ns = thread_cpu_time_ns()?.saturating_sub(start.cpu_ns);
+        let wall_ns = start.wall.elapsed().as_nanos().max(1) as f64;
+        Some((cpu_ns, (cpu_ns as f64 / wall_ns).min(1.0)))
+    }
+}
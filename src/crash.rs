@@ -0,0 +1,343 @@
+//! Crash report module
+//!
+//! Contains `install_crash_handler`, which writes the tracer's active,
+//! unexported, and kept spans to a crash file when the process panics (also
+//! with `panic = "abort"`) or, with the `unix` cargo feature, receives a fatal
+//! signal (SIGSEGV, SIGBUS, SIGILL, SIGFPE, SIGABRT). The write is best-effort
+//! and synchronous: stores whose locks are held at the time are skipped.
+//!
+//! The file starts with a JSON line describing the crash, followed by one JSON
+//! line per span carrying a `crash.state` of `active`, `queued`, or `completed`.
+//!
+//! A signal handler may not allocate or take locks, so for signals the spans
+//! are serialized ahead of time, every [`SIGNAL_SNAPSHOT_INTERVAL`], and the
+//! handler only writes those bytes with async-signal-safe calls; the report
+//! shows the spans as of the last snapshot. The handlers installed before
+//! (such as Rust's stack overflow reporter) run afterwards.
+
+use crate::span::{current_timestamp, Span};
+use crate::tracer::{SimpleTracer, TracingResult};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, TryLockError, Weak};
+use std::time::Duration;
+use tyl_errors::TylError;
+
+/// How often the spans written on a fatal signal are serialized
+pub const SIGNAL_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+struct CrashTarget {
+    tracer: Weak<SimpleTracer>,
+    path: PathBuf,
+}
+
+static CRASH_TARGET: OnceLock<CrashTarget> = OnceLock::new();
+
+/// Write a crash report to `tyl-tracing-crash-<pid>.jsonl` in the temp dir;
+/// returns the path
+pub fn install_crash_handler(tracer: &Arc<SimpleTracer>) -> TracingResult<PathBuf> {
+    let path = std::env::temp_dir().join(format!("tyl-tracing-crash-{}.jsonl", std::process::id()));
+    install_crash_handler_to(tracer, &path)?;
+    Ok(path)
+}
+
+/// Write a crash report to `path` (truncated on every crash)
+///
+/// Only one crash handler can be installed per process. The previous panic
+/// hook still runs afterwards; note that panics later caught with
+/// `catch_unwind` also produce a report.
+pub fn install_crash_handler_to(
+    tracer: &Arc<SimpleTracer>,
+    path: impl Into<PathBuf>,
+) -> TracingResult<()> {
+    let target = CrashTarget {
+        tracer: Arc::downgrade(tracer),
+        path: path.into(),
+    };
+    CRASH_TARGET
+        .set(target)
+        .map_err(|_| TylError::configuration("a crash handler is already installed"))?;
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        write_crash_report("panic", &info.to_string());
+        previous(info);
+    }));
+    #[cfg(all(unix, feature = "unix"))]
+    if let Some(target) = CRASH_TARGET.get() {
+        fatal_signals::install(target)?;
+    }
+    Ok(())
+}
+
+/// Write the crash file for the installed handler, ignoring all errors
+fn write_crash_report(reason: &str, message: &str) {
+    let Some(target) = CRASH_TARGET.get() else {
+        return;
+    };
+    let Some(tracer) = target.tracer.upgrade() else {
+        return;
+    };
+    let _ = write_report(&target.path, &tracer, reason, message);
+}
+
+fn write_report(
+    path: &Path,
+    tracer: &SimpleTracer,
+    reason: &str,
+    message: &str,
+) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let spans = tracer.crash_snapshot();
+    let header = json!({
+        "crash.reason": reason,
+        "crash.message": message,
+        "crash.timestamp": current_timestamp(),
+        "crash.spans": spans.len(),
+    });
+    writeln!(file, "{}", header)?;
+    file.write_all(&span_records(spans))?;
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+/// One JSON line per span, tagged with its `crash.state`
+fn span_records(spans: Vec<(&'static str, Span)>) -> Vec<u8> {
+    let mut records = Vec::new();
+    for (state, span) in spans {
+        let mut record = serde_json::to_value(&span).unwrap_or_default();
+        if let Some(fields) = record.as_object_mut() {
+            fields.insert("crash.state".to_string(), state.into());
+        }
+        let _ = writeln!(records, "{}", record);
+    }
+    records
+}
+
+/// Lock without blocking; a poisoned lock (the panicking thread held it) is
+/// still read
+pub(crate) fn try_lock_for_crash<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+#[cfg(all(unix, feature = "unix"))]
+mod fatal_signals {
+    use super::{span_records, CrashTarget, SIGNAL_SNAPSHOT_INTERVAL};
+    use crate::tracer::TracingResult;
+    use std::cell::UnsafeCell;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+    use std::sync::{OnceLock, Weak};
+    use tyl_errors::TylError;
+
+    const FATAL_SIGNALS: [libc::c_int; 5] = [
+        libc::SIGSEGV,
+        libc::SIGBUS,
+        libc::SIGILL,
+        libc::SIGFPE,
+        libc::SIGABRT,
+    ];
+
+    /// Spans serialized ahead of a crash
+    struct Snapshot {
+        records: Vec<u8>,
+        spans: usize,
+    }
+
+    /// Latest snapshot; replaced snapshots are freed unless a handler may
+    /// already be reading them
+    static SNAPSHOT: AtomicPtr<Snapshot> = AtomicPtr::new(std::ptr::null_mut());
+    static CRASHING: AtomicBool = AtomicBool::new(false);
+    static REPORT_PATH: OnceLock<CString> = OnceLock::new();
+
+    /// Actions in place before ours, in `FATAL_SIGNALS` order; written once
+    /// before our handlers are installed, read-only afterwards
+    struct PreviousActions(UnsafeCell<[libc::sigaction; FATAL_SIGNALS.len()]>);
+
+    // SAFETY: see the type's invariant: no writes once handlers can run
+    unsafe impl Sync for PreviousActions {}
+
+    // SAFETY: sigaction is plain data; all zeros is SIG_DFL with no flags
+    static PREVIOUS: PreviousActions =
+        PreviousActions(UnsafeCell::new(unsafe { std::mem::zeroed() }));
+
+    pub(super) fn install(target: &CrashTarget) -> TracingResult<()> {
+        let path = CString::new(target.path.as_os_str().as_bytes())
+            .map_err(|_| TylError::configuration("crash report path contains a NUL byte"))?;
+        let _ = REPORT_PATH.set(path);
+        refresh(&target.tracer);
+        let tracer = target.tracer.clone();
+        std::thread::Builder::new()
+            .name("tyl-tracing-crash-snapshot".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(SIGNAL_SNAPSHOT_INTERVAL);
+                if !refresh(&tracer) {
+                    return;
+                }
+            })
+            .map_err(|e| TylError::internal(format!("cannot start crash snapshots: {}", e)))?;
+
+        for (index, signal) in FATAL_SIGNALS.into_iter().enumerate() {
+            // SAFETY: the sigaction struct is fully initialized before use,
+            // and PREVIOUS[index] is written before our handler can run
+            let installed = unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = on_fatal_signal
+                    as extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void)
+                    as libc::sighandler_t;
+                action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+                libc::sigemptyset(&mut action.sa_mask);
+                let previous = &mut (*PREVIOUS.0.get())[index];
+                libc::sigaction(signal, &action, previous) == 0
+            };
+            if !installed {
+                return Err(TylError::internal(format!(
+                    "cannot install handler for signal {}: {}",
+                    signal,
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the tracer's spans for the signal handler; false once the
+    /// tracer is gone
+    fn refresh(tracer: &Weak<crate::tracer::SimpleTracer>) -> bool {
+        let Some(tracer) = tracer.upgrade() else {
+            return false;
+        };
+        let spans = tracer.crash_snapshot();
+        let snapshot = Box::new(Snapshot {
+            spans: spans.len(),
+            records: span_records(spans),
+        });
+        let replaced = SNAPSHOT.swap(Box::into_raw(snapshot), Ordering::SeqCst);
+        // A handler sets CRASHING before loading SNAPSHOT, so if it could
+        // have loaded `replaced`, the flag is visible here
+        if !replaced.is_null() && !CRASHING.load(Ordering::SeqCst) {
+            // SAFETY: created by Box::into_raw above and no longer reachable
+            drop(unsafe { Box::from_raw(replaced) });
+        }
+        true
+    }
+
+    extern "C" fn on_fatal_signal(
+        signal: libc::c_int,
+        info: *mut libc::siginfo_t,
+        context: *mut libc::c_void,
+    ) {
+        if !CRASHING.swap(true, Ordering::SeqCst) {
+            // SAFETY: never freed once CRASHING is set
+            let snapshot = unsafe { SNAPSHOT.load(Ordering::SeqCst).as_ref() };
+            if let (Some(snapshot), Some(path)) = (snapshot, REPORT_PATH.get()) {
+                write_report(path, signal, snapshot);
+            }
+        }
+        chain(signal, info, context);
+    }
+
+    /// Hand the signal to the action installed before ours
+    fn chain(signal: libc::c_int, info: *mut libc::siginfo_t, context: *mut libc::c_void) {
+        let Some(index) = FATAL_SIGNALS.iter().position(|&fatal| fatal == signal) else {
+            return;
+        };
+        // SAFETY: PREVIOUS is read-only once handlers run; restoring the
+        // previous action means a repeated fault goes straight to it
+        unsafe {
+            let previous = &(*PREVIOUS.0.get())[index];
+            libc::sigaction(signal, previous, std::ptr::null_mut());
+            match previous.sa_sigaction {
+                // Pending until we return, then handled by the default action
+                libc::SIG_DFL => {
+                    libc::raise(signal);
+                }
+                libc::SIG_IGN => {}
+                handler if previous.sa_flags & libc::SA_SIGINFO != 0 => {
+                    let handler = std::mem::transmute::<
+                        libc::sighandler_t,
+                        extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void),
+                    >(handler);
+                    handler(signal, info, context);
+                }
+                handler => {
+                    let handler = std::mem::transmute::<
+                        libc::sighandler_t,
+                        extern "C" fn(libc::c_int),
+                    >(handler);
+                    handler(signal);
+                }
+            }
+        }
+    }
+
+    /// Write the report with open/write/close only
+    fn write_report(path: &CString, signal: libc::c_int, snapshot: &Snapshot) {
+        // SAFETY: async-signal-safe calls on a valid, NUL-terminated path
+        let fd = unsafe {
+            libc::open(
+                path.as_ptr(),
+                libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+                0o644,
+            )
+        };
+        if fd < 0 {
+            return;
+        }
+        let mut digits = [0u8; 20];
+        write_all(fd, b"{\"crash.message\":\"fatal signal ");
+        write_all(fd, format_u64(signal as u64, &mut digits));
+        write_all(fd, b"\",\"crash.reason\":\"signal\",\"crash.spans\":");
+        write_all(fd, format_u64(snapshot.spans as u64, &mut digits));
+        write_all(fd, b",\"crash.timestamp\":");
+        write_all(fd, format_u64(now_ms(), &mut digits));
+        write_all(fd, b"}\n");
+        write_all(fd, &snapshot.records);
+        // SAFETY: fd was opened above
+        unsafe {
+            libc::fsync(fd);
+            libc::close(fd);
+        }
+    }
+
+    fn write_all(fd: libc::c_int, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            // SAFETY: the pointer and length describe `bytes`
+            let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+            if written < 0 {
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            bytes = &bytes[written as usize..];
+        }
+    }
+
+    /// Decimal digits of `value` in `buffer`, without allocating
+    fn format_u64(mut value: u64, buffer: &mut [u8; 20]) -> &[u8] {
+        let mut start = buffer.len();
+        loop {
+            start -= 1;
+            buffer[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                return &buffer[start..];
+            }
+        }
+    }
+
+    /// Wall clock in milliseconds via clock_gettime (async-signal-safe)
+    fn now_ms() -> u64 {
+        // SAFETY: clock_gettime only writes the timespec
+        let mut time: libc::timespec = unsafe { std::mem::zeroed() };
+        unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, &mut time) };
+        time.tv_sec as u64 * 1000 + time.tv_nsec as u64 / 1_000_000
+    }
+}
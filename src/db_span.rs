@@ -0,0 +1,163 @@
+//! Database span module
+//!
+//! Contains DbSpan, a SpanHandle for one database call that records the
+//! `db.*` attributes of the OpenTelemetry database semantic conventions. The
+//! statement is sanitized before it is recorded: string and numeric literals
+//! become `?`, so parameters inlined into SQL never reach a trace backend.
+//! Works for any client; it only needs the system name and the statement.
+
+use crate::handle::SpanHandle;
+use crate::span::SpanKind;
+use crate::tracer::SimpleTracer;
+
+/// Database product, e.g. `postgresql`, `mysql`, `sqlite`
+pub const DB_SYSTEM: &str = "db.system";
+pub const DB_NAME: &str = "db.name";
+/// Sanitized statement text
+pub const DB_STATEMENT: &str = "db.statement";
+/// Leading SQL keyword, uppercased: `SELECT`, `INSERT`, ...
+pub const DB_OPERATION: &str = "db.operation";
+/// Main table of the statement, when it can be read off the SQL
+pub const DB_SQL_TABLE: &str = "db.sql.table";
+pub const DB_ROWS_RETURNED: &str = "db.response.returned_rows";
+
+/// Span of one database call; ends when dropped
+#[must_use = "the span ends when the DbSpan is dropped"]
+pub struct DbSpan<'a> {
+    span: SpanHandle<'a>,
+}
+
+impl<'a> DbSpan<'a> {
+    /// Client span for `statement` on `system`, named `"{operation} {table}"`
+    pub fn query(tracer: &'a SimpleTracer, system: &str, statement: &str) -> Self {
+        let operation = sql_operation(statement);
+        let table = operation.as_deref().and_then(|op| sql_table(op, statement));
+        let name = match (&operation, &table) {
+            (Some(operation), Some(table)) => format!("{} {}", operation, table),
+            (Some(operation), None) => operation.clone(),
+            (None, _) => system.to_string(),
+        };
+        let span = tracer.start_with_kind(&name, SpanKind::Client);
+        if span.is_recording() {
+            span.set_attribute(DB_SYSTEM, system.to_string());
+            span.set_attribute(DB_STATEMENT, sanitize_statement(statement));
+            if let Some(operation) = operation {
+                span.set_attribute(DB_OPERATION, operation);
+            }
+            if let Some(table) = table {
+                span.set_attribute(DB_SQL_TABLE, table);
+            }
+        }
+        Self { span }
+    }
+
+    /// Handle of the underlying span
+    pub fn span(&self) -> &SpanHandle<'a> {
+        &self.span
+    }
+
+    /// Database (schema) the statement ran against
+    pub fn set_database(&self, name: &str) {
+        self.span.set_attribute(DB_NAME, name.to_string());
+    }
+
+    pub fn set_rows_returned(&self, rows: u64) {
+        self.span.set_attribute(DB_ROWS_RETURNED, rows);
+    }
+
+    /// Mark the call failed with `message`
+    pub fn set_error(&self, message: impl Into<String>) {
+        self.span.set_error(message);
+    }
+
+    /// End now instead of at the end of the scope
+    pub fn end(self) {
+        self.span.end();
+    }
+}
+
+/// Replace string and numeric literals with `?`
+///
+/// Quoted identifiers (`"name"`, `` `name` ``), placeholders (`$1`, `?`,
+/// `:id`) and digits inside identifiers (`table2`) are kept.
+pub fn sanitize_statement(statement: &str) -> String {
+    let mut sanitized = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    let mut previous = ' ';
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // '' is an escaped quote inside the literal
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+                sanitized.push('?');
+            }
+            '"' | '`' => {
+                sanitized.push(c);
+                for quoted in chars.by_ref() {
+                    sanitized.push(quoted);
+                    if quoted == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_digit() && !is_identifier_char(previous) && previous != '$' => {
+                while chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '_')
+                    .is_some()
+                {}
+                sanitized.push('?');
+            }
+            c => sanitized.push(c),
+        }
+        previous = sanitized.chars().next_back().unwrap_or(' ');
+    }
+    sanitized
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Leading keyword of a statement, skipping comments and parentheses
+fn sql_operation(statement: &str) -> Option<String> {
+    let mut rest = statement.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else if let Some(inner) = rest.strip_prefix('(') {
+            rest = inner;
+        } else {
+            break;
+        }
+        rest = rest.trim_start();
+    }
+    let keyword: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    (!keyword.is_empty()).then(|| keyword.to_ascii_uppercase())
+}
+
+/// Table named after `FROM`, `INTO`, or `UPDATE`, for simple statements
+fn sql_table(operation: &str, statement: &str) -> Option<String> {
+    let marker = match operation {
+        "SELECT" | "DELETE" => "FROM",
+        "INSERT" | "REPLACE" => "INTO",
+        "UPDATE" => "UPDATE",
+        _ => return None,
+    };
+    let mut words = statement.split_whitespace();
+    words.find(|word| word.eq_ignore_ascii_case(marker))?;
+    let table = words
+        .next()?
+        .trim_end_matches([',', ';', '('])
+        .trim_matches(['"', '`']);
+    let valid = !table.is_empty() && table.chars().all(|c| is_identifier_char(c) || c == '.');
+    valid.then(|| table.to_string())
+}
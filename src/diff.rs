@@ -0,0 +1,135 @@
+//! Trace comparison module
+//!
+//! Compares two traces operation by operation so that structural changes and
+//! duration regressions between two runs can be asserted on.
+
+use crate::ids::SpanId;
+use crate::span::Span;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Separator used between operation names in a span path
+pub const PATH_SEPARATOR: &str = " > ";
+
+/// Duration change of an operation present in both traces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanDelta {
+    pub path: String,
+    pub duration_a_ms: u64,
+    pub duration_b_ms: u64,
+    pub delta_ms: i64,
+}
+
+impl SpanDelta {
+    /// Relative change against the first trace (0.5 == 50% slower)
+    pub fn delta_ratio(&self) -> f64 {
+        if self.duration_a_ms == 0 {
+            return if self.duration_b_ms == 0 {
+                0.0
+            } else {
+                f64::INFINITY
+            };
+        }
+        self.delta_ms as f64 / self.duration_a_ms as f64
+    }
+}
+
+/// Differences between two traces
+///
+/// Spans are matched by their operation path from the root (e.g.
+/// `http_request > db_query`); repeated siblings with the same name are matched
+/// in start order and suffixed with `[n]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TraceDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub deltas: Vec<SpanDelta>,
+}
+
+impl TraceDiff {
+    /// Compare a baseline trace (`trace_a`) with a candidate trace (`trace_b`)
+    pub fn compare(trace_a: &[Span], trace_b: &[Span]) -> Self {
+        let a = index_by_path(trace_a);
+        let mut b = index_by_path(trace_b);
+
+        let mut diff = TraceDiff::default();
+        for (path, duration_a_ms) in a {
+            match b.remove(&path) {
+                Some(duration_b_ms) => diff.deltas.push(SpanDelta {
+                    path,
+                    duration_a_ms,
+                    duration_b_ms,
+                    delta_ms: duration_b_ms as i64 - duration_a_ms as i64,
+                }),
+                None => diff.removed.push(path),
+            }
+        }
+        diff.added = b.into_keys().collect();
+        diff
+    }
+
+    /// True when both traces contain exactly the same operations
+    pub fn same_structure(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Look up the delta for an operation path
+    pub fn delta(&self, path: &str) -> Option<&SpanDelta> {
+        self.deltas.iter().find(|d| d.path == path)
+    }
+
+    /// Operations that became slower by more than `threshold_ms`
+    pub fn regressions(&self, threshold_ms: u64) -> Vec<&SpanDelta> {
+        self.deltas
+            .iter()
+            .filter(|d| d.delta_ms > threshold_ms as i64)
+            .collect()
+    }
+
+    /// The operation with the largest slowdown, if any got slower
+    pub fn max_regression(&self) -> Option<&SpanDelta> {
+        self.deltas
+            .iter()
+            .filter(|d| d.delta_ms > 0)
+            .max_by_key(|d| d.delta_ms)
+    }
+}
+
+/// Map each span of a trace to its unique operation path and duration
+fn index_by_path(trace: &[Span]) -> BTreeMap<String, u64> {
+    let by_id: HashMap<SpanId, &Span> = trace.iter().map(|s| (s.span_id, s)).collect();
+
+    let mut ordered: Vec<&Span> = trace.iter().collect();
+    ordered.sort_by_key(|s| s.start_time);
+
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut index = BTreeMap::new();
+    for span in ordered {
+        let path = operation_path(span, &by_id);
+        let seen = occurrences.entry(path.clone()).or_insert(0);
+        let key = if *seen == 0 {
+            path
+        } else {
+            format!("{}[{}]", path, seen)
+        };
+        *seen += 1;
+        index.insert(key, span.duration_ms().unwrap_or(0));
+    }
+    index
+}
+
+fn operation_path(span: &Span, by_id: &HashMap<SpanId, &Span>) -> String {
+    let mut names = vec![span.operation_name.as_str()];
+    let mut current = span;
+    // Bounded walk so a malformed (cyclic) trace cannot loop forever
+    while let Some(parent) = current
+        .parent_span_id
+        .and_then(|id| by_id.get(&id))
+        .filter(|_| names.len() <= by_id.len())
+    {
+        names.push(parent.operation_name.as_str());
+        current = parent;
+    }
+    names.reverse();
+    names.join(PATH_SEPARATOR)
+}
@@ -0,0 +1,397 @@
+//! Compiled-out tracer module
+//!
+//! With the `disabled` feature, `SimpleTracer` is the empty struct defined
+//! here: it has every method of the recording tracer, so call sites compile
+//! unchanged, but holds no locks or stores and each method is an inlined
+//! no-op. Span IDs are the empty [`NOOP_SPAN_ID`], which never allocates.
+
+use crate::anomaly::AnomalyDetector;
+use crate::backpressure::BackpressureSignal;
+use crate::config::TraceConfig;
+use crate::drops::DropCounts;
+use crate::export::{BatchSpanProcessor, SpanExporter};
+use crate::filter::SpanFilter;
+use crate::health::TracerHealth;
+use crate::inflight::InFlightReport;
+use crate::propagation::TraceContext;
+use crate::sampling_audit::SamplingDecision;
+use crate::section::SectionTimer;
+use crate::span::{Span, SpanEvent, SpanKind};
+use crate::tenant::TenantTracer;
+use crate::tracer::{DroppedSpanCounts, TracingManager, TracingResult, NOOP_SPAN_ID};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tyl_errors::TylError;
+
+/// Adapter - Tracer that records nothing (the `disabled` feature)
+#[derive(Default)]
+pub struct SimpleTracer;
+
+impl SimpleTracer {
+    #[inline]
+    pub fn new(_config: TraceConfig) -> Self {
+        Self
+    }
+
+    #[inline]
+    pub fn set_filter(&self, _filter: SpanFilter) {}
+
+    #[inline]
+    pub fn set_filter_str(&self, _filter: &str) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn filter(&self) -> SpanFilter {
+        SpanFilter::default()
+    }
+
+    #[inline]
+    pub fn set_sampling_rate(&self, _rate: f64) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn reload_config(&self, _config: TraceConfig) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn with_tenant<'a>(&'a self, tenant_id: &'a str) -> TenantTracer<'a> {
+        TenantTracer::new(self, tenant_id)
+    }
+
+    #[inline]
+    pub fn completed_spans_for_tenant(&self, _tenant_id: &str) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn tenant_dropped_spans(&self, _tenant_id: &str) -> u64 {
+        0
+    }
+
+    #[inline]
+    pub(crate) fn start_tenant_span(
+        &self,
+        _operation_name: &str,
+        _parent_span_id: Option<String>,
+        _kind: SpanKind,
+        _tenant_id: Option<&str>,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    pub fn start_span_with_remote_parent(
+        &self,
+        _operation_name: &str,
+        _parent: &TraceContext,
+        _kind: SpanKind,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    pub(crate) fn check_attribute_key(&self, _key: &str) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn attribute_key_violations(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn set_enabled(&self, _enabled: bool) {}
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn with_exporter(self, _exporter: impl SpanExporter + 'static) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn with_batch_processor(self, _processor: BatchSpanProcessor) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn batch_processor(&self) -> Option<&BatchSpanProcessor> {
+        None
+    }
+
+    #[inline]
+    pub fn flush(&self) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-metrics")]
+    #[inline]
+    pub fn with_runtime_metrics(self) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn with_backpressure_signal(self, _signal: BackpressureSignal) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn is_saturated(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn with_anomaly_detector(self, _detector: AnomalyDetector) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn trace_id(&self, _span_id: &str) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn set_trace_attribute(
+        &self,
+        _trace_id: &str,
+        _key: &str,
+        _value: Value,
+    ) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn span_heartbeat(&self, _span_id: &str, _progress: Value) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn add_span_event(
+        &self,
+        _span_id: &str,
+        _name: &str,
+        _attributes: HashMap<String, Value>,
+    ) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn record_error(&self, _span_id: &str, _error: &TylError) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn push_event(&self, _span_id: &str, _event: SpanEvent) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn update_span(
+        &self,
+        _span_id: &str,
+        _update: impl FnOnce(&mut Span),
+    ) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn section_timer<'a>(&'a self, _span_id: &str, name: &str) -> SectionTimer<'a> {
+        SectionTimer::new(self, NOOP_SPAN_ID, name)
+    }
+
+    #[inline]
+    pub fn time_section<R>(&self, _span_id: &str, _name: &str, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    #[inline]
+    pub fn time_child_span<R>(
+        &self,
+        _parent_span_id: &str,
+        _operation_name: &str,
+        f: impl FnOnce() -> R,
+    ) -> TracingResult<R> {
+        Ok(f())
+    }
+
+    #[inline]
+    pub fn try_set_baggage(&self, _key: &str, _value: &str) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn rejected_baggage_entries(&self) -> u64 {
+        0
+    }
+
+    #[inline]
+    pub(crate) fn baggage_snapshot(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    #[inline]
+    pub(crate) fn crash_snapshot(&self) -> Vec<(&'static str, Span)> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn active_spans(&self) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub(crate) fn active_span_count(&self) -> usize {
+        0
+    }
+
+    #[inline]
+    pub fn stale_spans(&self, _max_idle: Duration) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn dropped_span_counts(&self) -> DroppedSpanCounts {
+        DroppedSpanCounts::default()
+    }
+
+    #[inline]
+    pub fn started_spans(&self) -> u64 {
+        0
+    }
+
+    #[inline]
+    pub fn ended_spans(&self) -> u64 {
+        0
+    }
+
+    #[inline]
+    pub(crate) fn tenant_quota_drops(&self) -> u64 {
+        0
+    }
+
+    /// Shared default configuration; the one passed to `new` is not kept
+    pub fn config(&self) -> Arc<TraceConfig> {
+        static CONFIG: OnceLock<Arc<TraceConfig>> = OnceLock::new();
+        CONFIG
+            .get_or_init(|| Arc::new(TraceConfig::new("default-service")))
+            .clone()
+    }
+
+    #[inline]
+    pub fn clear_completed_spans(&self) -> usize {
+        0
+    }
+
+    #[inline]
+    pub fn get_trace(&self, _trace_id: &str) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn completed_spans_between(&self, _from: u64, _to: u64) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn health(&self) -> TracerHealth {
+        TracerHealth::disabled()
+    }
+
+    #[inline]
+    pub fn set_sampling_audit(&self, _capacity: usize) {}
+
+    #[inline]
+    pub fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn sampling_decisions_for_trace(&self, _trace_id: &str) -> Vec<SamplingDecision> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn in_flight_report(&self) -> InFlightReport {
+        InFlightReport::default()
+    }
+
+    #[inline]
+    pub fn span_context(&self, _span_id: &str) -> Option<TraceContext> {
+        None
+    }
+
+    #[inline]
+    pub fn register_on_end(&self, _observer: impl FnMut(&mut Span) + Send + 'static) {}
+
+    #[inline]
+    pub fn dropped_spans_by_reason(&self) -> DropCounts {
+        DropCounts::default()
+    }
+
+    #[inline]
+    pub fn with_drop_summary(self, _interval: Duration) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn purge_spans(&self, _predicate: impl Fn(&Span) -> bool) -> usize {
+        0
+    }
+
+    #[inline]
+    pub fn purge_by_attribute(&self, _key: &str, _value: impl Into<Value>) -> usize {
+        0
+    }
+}
+
+impl TracingManager for SimpleTracer {
+    #[inline]
+    fn start_span(
+        &self,
+        _operation_name: &str,
+        _parent_span_id: Option<String>,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    fn start_span_with_kind(
+        &self,
+        _operation_name: &str,
+        _parent_span_id: Option<String>,
+        _kind: SpanKind,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    fn end_span(&self, _span_id: String) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn add_span_attribute(&self, _span_id: &str, _key: &str, _value: Value) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn get_completed_spans(&self) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    fn set_baggage(&self, _key: &str, _value: &str) {}
+
+    #[inline]
+    fn get_baggage(&self, _key: &str) -> Option<String> {
+        None
+    }
+}
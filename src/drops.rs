@@ -0,0 +1,152 @@
+//! Dropped-span diagnostics module
+//!
+//! Contains DropCounts, the spans a tracer declined to record grouped by
+//! reason (span cap overflow, tenant quota, filter directive, sampling), and
+//! the optional periodic summary (`SimpleTracer::with_drop_summary`) that
+//! logs a `tracing` warning whenever spans were dropped since the last one,
+//! so silent data loss shows up in service logs.
+//!
+//! Only the decision point is counted: children of a span that was not
+//! recorded are skipped without being counted again.
+
+use crate::sampling_audit::MatchedRule;
+use crate::span::current_timestamp;
+use crate::tracer::SimpleTracer;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Spans not recorded, by reason
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DropCounts {
+    /// `max_spans_per_trace` or `max_active_spans` was reached
+    pub overflow: u64,
+    /// The tenant exceeded its `spans_per_minute` quota
+    pub quota: u64,
+    /// A filter directive turned the operation off or sampled it out
+    pub filter: u64,
+    /// The sampling rate, a sampling priority or an unsampled remote parent
+    pub sampling: u64,
+}
+
+impl DropCounts {
+    pub fn total(&self) -> u64 {
+        self.overflow + self.quota + self.filter + self.sampling
+    }
+
+    /// Drops since an earlier reading of the same counters
+    pub fn since(&self, earlier: &DropCounts) -> DropCounts {
+        DropCounts {
+            overflow: self.overflow.saturating_sub(earlier.overflow),
+            quota: self.quota.saturating_sub(earlier.quota),
+            filter: self.filter.saturating_sub(earlier.filter),
+            sampling: self.sampling.saturating_sub(earlier.sampling),
+        }
+    }
+}
+
+/// Counters for the reasons the tracer does not track elsewhere
+#[derive(Debug, Default)]
+pub(crate) struct DropCounters {
+    filter: AtomicU64,
+    sampling: AtomicU64,
+}
+
+impl DropCounters {
+    /// Count an unsampled span under the rule that rejected it
+    pub(crate) fn unsampled(&self, rule: MatchedRule<'_>) {
+        let counter = match rule {
+            MatchedRule::Filter(_) => &self.filter,
+            _ => &self.sampling,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn filter(&self) -> u64 {
+        self.filter.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn sampling(&self) -> u64 {
+        self.sampling.load(Ordering::Relaxed)
+    }
+}
+
+/// Periodic warning summarizing the drops of the last interval
+#[derive(Debug)]
+pub(crate) struct DropSummary {
+    interval_ms: u64,
+    due_at: AtomicU64,
+    /// Counts at the previous summary
+    reported: Mutex<DropCounts>,
+}
+
+impl DropSummary {
+    fn new(interval: Duration) -> Self {
+        let interval_ms = (interval.as_millis() as u64).max(1);
+        Self {
+            interval_ms,
+            due_at: AtomicU64::new(current_timestamp() + interval_ms),
+            reported: Mutex::new(DropCounts::default()),
+        }
+    }
+
+    /// Log the drops since the previous summary once the interval is up;
+    /// silent when nothing was dropped
+    pub(crate) fn maybe_log(&self, service: &str, counts: impl FnOnce() -> DropCounts) {
+        let now = current_timestamp();
+        let due_at = self.due_at.load(Ordering::Relaxed);
+        if now < due_at
+            || self
+                .due_at
+                .compare_exchange(
+                    due_at,
+                    now + self.interval_ms,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return;
+        }
+        let counts = counts();
+        let mut reported = self.reported.lock().unwrap();
+        let dropped = counts.since(&reported);
+        *reported = counts;
+        drop(reported);
+        if dropped.total() > 0 {
+            tracing::warn!(
+                target: "tyl_tracing",
+                service,
+                overflow = dropped.overflow,
+                quota = dropped.quota,
+                filter = dropped.filter,
+                sampling = dropped.sampling,
+                "dropped {} spans in the last {}ms",
+                dropped.total(),
+                self.interval_ms
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Spans not recorded since the tracer was created, by reason
+    pub fn dropped_spans_by_reason(&self) -> DropCounts {
+        let caps = self.dropped_span_counts();
+        DropCounts {
+            overflow: caps.trace_limit + caps.active_limit,
+            quota: self.tenant_quota_drops(),
+            filter: self.drop_counters.filter(),
+            sampling: self.drop_counters.sampling(),
+        }
+    }
+
+    /// Log a warning with the dropped spans by reason at most once per
+    /// `interval`, checked as spans start
+    pub fn with_drop_summary(mut self, interval: Duration) -> Self {
+        self.drop_summary = Some(DropSummary::new(interval));
+        self
+    }
+}
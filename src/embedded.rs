@@ -0,0 +1,281 @@
+//! Embedded core module
+//!
+//! Contains a `no_std` + `alloc` span model and tracer for firmware (enabled
+//! with the `embedded` cargo feature). Time and IDs come from pluggable
+//! [`Clock`] and [`IdSource`] implementations, and all buffers are allocated
+//! once up front from [`CoreLimits`], so the tracer never grows at runtime.
+//! Devices ship the serialized [`CoreSpan`]s to a host agent, which converts
+//! them with `Span::from_core` and exports them like any other span.
+//!
+//! The module only uses `core`, `alloc`, and serde (without std); the lints
+//! below keep it that way so it can be built for targets without std.
+#![deny(
+    clippy::std_instead_of_core,
+    clippy::std_instead_of_alloc,
+    clippy::alloc_instead_of_core
+)]
+
+use alloc_crate::borrow::Cow;
+use alloc_crate::string::String;
+use alloc_crate::vec::{Drain, Vec};
+use serde::{Deserialize, Serialize};
+
+/// Monotonic time source, e.g. a hardware timer since boot
+pub trait Clock {
+    fn now_ns(&self) -> u64;
+}
+
+/// Source of span and trace IDs; must never return 0
+pub trait IdSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// xorshift64* IDs from a per-device seed (e.g. serial number mixed with a
+/// boot counter)
+#[derive(Debug, Clone)]
+pub struct XorShiftIds {
+    state: u64,
+}
+
+impl XorShiftIds {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+}
+
+impl IdSource for XorShiftIds {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D).max(1)
+    }
+}
+
+/// Fixed capacities of a [`CoreTracer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreLimits {
+    pub max_active: usize,
+    pub max_completed: usize,
+    pub max_attributes: usize,
+}
+
+impl Default for CoreLimits {
+    fn default() -> Self {
+        Self {
+            max_active: 8,
+            max_completed: 32,
+            max_attributes: 8,
+        }
+    }
+}
+
+/// Attribute value recorded on a device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CoreValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Cow<'static, str>),
+}
+
+impl From<bool> for CoreValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for CoreValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<i32> for CoreValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<u32> for CoreValue {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<f64> for CoreValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&'static str> for CoreValue {
+    fn from(value: &'static str) -> Self {
+        Self::Str(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for CoreValue {
+    fn from(value: String) -> Self {
+        Self::Str(Cow::Owned(value))
+    }
+}
+
+/// Span recorded by the embedded core; times are device clock nanoseconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreSpan {
+    pub trace_id: u128,
+    pub span_id: u64,
+    pub parent_span_id: Option<u64>,
+    pub name: Cow<'static, str>,
+    pub start_ns: u64,
+    pub end_ns: Option<u64>,
+    pub attributes: Vec<(Cow<'static, str>, CoreValue)>,
+    /// Error message, if the span failed
+    pub error: Option<Cow<'static, str>>,
+}
+
+/// Minimal tracer for devices without std
+///
+/// Spans past `max_active` are not recorded (`start_span` returns `None`), and
+/// when `max_completed` spans await export the oldest is dropped; both count
+/// towards `dropped_spans`.
+#[derive(Debug)]
+pub struct CoreTracer<C, I> {
+    clock: C,
+    ids: I,
+    limits: CoreLimits,
+    active: Vec<CoreSpan>,
+    completed: Vec<CoreSpan>,
+    dropped: u32,
+}
+
+impl<C: Clock, I: IdSource> CoreTracer<C, I> {
+    pub fn new(clock: C, ids: I) -> Self {
+        Self::with_limits(clock, ids, CoreLimits::default())
+    }
+
+    pub fn with_limits(clock: C, ids: I, limits: CoreLimits) -> Self {
+        Self {
+            clock,
+            ids,
+            limits,
+            active: Vec::with_capacity(limits.max_active),
+            completed: Vec::with_capacity(limits.max_completed),
+            dropped: 0,
+        }
+    }
+
+    /// Start a span; children of unknown parents start a new trace
+    pub fn start_span(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        parent_span_id: Option<u64>,
+    ) -> Option<u64> {
+        if self.active.len() >= self.limits.max_active {
+            self.dropped = self.dropped.saturating_add(1);
+            return None;
+        }
+        let parent_trace_id = parent_span_id.and_then(|parent| {
+            self.active
+                .iter()
+                .find(|span| span.span_id == parent)
+                .map(|span| span.trace_id)
+        });
+        let trace_id = match parent_trace_id {
+            Some(trace_id) => trace_id,
+            None => (u128::from(self.ids.next_u64()) << 64) | u128::from(self.ids.next_u64()),
+        };
+        let span_id = self.ids.next_u64();
+        self.active.push(CoreSpan {
+            trace_id,
+            span_id,
+            parent_span_id,
+            name: name.into(),
+            start_ns: self.clock.now_ns(),
+            end_ns: None,
+            attributes: Vec::with_capacity(self.limits.max_attributes),
+            error: None,
+        });
+        Some(span_id)
+    }
+
+    /// Set an attribute; false if the span is unknown or already full
+    pub fn set_attribute(
+        &mut self,
+        span_id: u64,
+        key: impl Into<Cow<'static, str>>,
+        value: impl Into<CoreValue>,
+    ) -> bool {
+        let max_attributes = self.limits.max_attributes;
+        let Some(span) = self.active.iter_mut().find(|span| span.span_id == span_id) else {
+            return false;
+        };
+        let key = key.into();
+        let value = value.into();
+        if let Some(existing) = span.attributes.iter_mut().find(|(k, _)| *k == key) {
+            existing.1 = value;
+        } else if span.attributes.len() < max_attributes {
+            span.attributes.push((key, value));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    pub fn end_span(&mut self, span_id: u64) -> bool {
+        self.finish(span_id, None)
+    }
+
+    pub fn end_span_with_error(
+        &mut self,
+        span_id: u64,
+        message: impl Into<Cow<'static, str>>,
+    ) -> bool {
+        self.finish(span_id, Some(message.into()))
+    }
+
+    fn finish(&mut self, span_id: u64, error: Option<Cow<'static, str>>) -> bool {
+        let Some(index) = self.active.iter().position(|span| span.span_id == span_id) else {
+            return false;
+        };
+        let mut span = self.active.swap_remove(index);
+        span.end_ns = Some(self.clock.now_ns());
+        span.error = error;
+        if self.limits.max_completed == 0 {
+            self.dropped = self.dropped.saturating_add(1);
+            return true;
+        }
+        if self.completed.len() >= self.limits.max_completed {
+            self.completed.remove(0);
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.completed.push(span);
+        true
+    }
+
+    /// Completed spans waiting to be shipped to the host
+    pub fn completed(&self) -> &[CoreSpan] {
+        &self.completed
+    }
+
+    /// Take the completed spans, keeping the buffer's capacity
+    pub fn drain(&mut self) -> Drain<'_, CoreSpan> {
+        self.completed.drain(..)
+    }
+
+    pub fn dropped_spans(&self) -> u32 {
+        self.dropped
+    }
+
+    pub fn limits(&self) -> CoreLimits {
+        self.limits
+    }
+}
@@ -0,0 +1,87 @@
+//! Exception recording module
+//!
+//! Records errors on spans following the OpenTelemetry exception conventions:
+//! the span gets an error status and an `exception` event carrying
+//! `exception.type`, `exception.message`, and `exception.chain`, an array with
+//! one `{type, message}` object per level of the error's `source()` chain, so
+//! deeply wrapped errors stay readable in trace UIs. TylErrors also record
+//! their category (`tyl.error.category`, e.g. `validation` or `network`).
+
+use crate::span::{Span, SpanStatus};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use tyl_errors::TylError;
+
+/// Name of the event recording an error
+pub const EXCEPTION_EVENT: &str = "exception";
+pub const EXCEPTION_TYPE_ATTRIBUTE: &str = "exception.type";
+pub const EXCEPTION_MESSAGE_ATTRIBUTE: &str = "exception.message";
+/// Array of `{type, message}` objects, outermost error first
+pub const EXCEPTION_CHAIN_ATTRIBUTE: &str = "exception.chain";
+/// TylError category, also set as a span attribute
+pub const ERROR_CATEGORY_ATTRIBUTE: &str = "tyl.error.category";
+
+impl Span {
+    /// Mark the span failed and attach an `exception` event for `error`
+    ///
+    /// The span stays open; ending it keeps the error status.
+    pub fn record_error(&mut self, error: &TylError) {
+        let category = error_category(error);
+        let mut attributes = exception_attributes(error);
+        attributes.insert(
+            ERROR_CATEGORY_ATTRIBUTE.to_string(),
+            category.clone().into(),
+        );
+        self.add_event(EXCEPTION_EVENT, attributes);
+        self.attributes
+            .insert(ERROR_CATEGORY_ATTRIBUTE.to_string(), category.into());
+        self.status = SpanStatus::Error {
+            message: error.to_string(),
+        };
+    }
+}
+
+/// `exception.*` attributes for any error, walking its `source()` chain
+pub fn exception_attributes(error: &(dyn Error + 'static)) -> HashMap<String, Value> {
+    let mut chain = Vec::new();
+    let mut level = Some(error);
+    while let Some(error) = level {
+        chain.push(json!({
+            "type": error_type(error),
+            "message": error.to_string(),
+        }));
+        level = error.source();
+    }
+    HashMap::from([
+        (
+            EXCEPTION_TYPE_ATTRIBUTE.to_string(),
+            error_type(error).into(),
+        ),
+        (
+            EXCEPTION_MESSAGE_ATTRIBUTE.to_string(),
+            error.to_string().into(),
+        ),
+        (EXCEPTION_CHAIN_ATTRIBUTE.to_string(), Value::Array(chain)),
+    ])
+}
+
+/// Lower-case TylError variant, e.g. `validation`
+pub fn error_category(error: &TylError) -> String {
+    error_type(error).to_lowercase()
+}
+
+/// Leading identifier of the Debug form: the variant or struct name for
+/// derived Debug implementations (`Validation`, `ParseIntError`, ...)
+fn error_type(error: &dyn Error) -> String {
+    let debug = format!("{:?}", error);
+    let name: String = debug
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    if name.is_empty() {
+        "Error".to_string()
+    } else {
+        name
+    }
+}
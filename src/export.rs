@@ -0,0 +1,1314 @@
+//! Span export module
+//!
+//! Contains the SpanExporter trait (port) used to ship completed spans to a
+//! backend, in-memory, console, and file adapters, and composable exporter wrappers such as
+//! retry with exponential backoff, a circuit breaker, a disk spill queue, and a
+//! dead-letter file for batches that could not be delivered at all. The
+//! BatchSpanProcessor buffers completed spans in front of an exporter.
+
+use crate::compression::Compression;
+use crate::crash::try_lock_for_crash;
+use crate::hashing::AttributeHasher;
+use crate::limits::DecodeLimits;
+use crate::otlp::to_otlp_json;
+use crate::span::{current_timestamp, Span, SpanStatus};
+use crate::tracer::TracingResult;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tyl_errors::TylError;
+use uuid::Uuid;
+
+/// Port (Interface) - Destination for batches of completed spans
+pub trait SpanExporter: Send + Sync {
+    /// Export a batch of completed spans
+    fn export(&self, batch: &[Span]) -> TracingResult<()>;
+
+    /// Flush and release resources; called once when the pipeline stops
+    fn shutdown(&self) -> TracingResult<()> {
+        Ok(())
+    }
+}
+
+/// Share one exporter between a processor and other users (e.g. its spill sink)
+impl<E: SpanExporter + ?Sized> SpanExporter for Arc<E> {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        (**self).export(batch)
+    }
+
+    fn shutdown(&self) -> TracingResult<()> {
+        (**self).shutdown()
+    }
+}
+
+/// Port (Interface) - Durable storage for spans the export queue has no room for
+///
+/// See [`BatchSpanProcessor::with_spill_sink`].
+pub trait SpillSink: Send + Sync {
+    /// Store a batch for later export
+    fn spill(&self, batch: &[Span]) -> TracingResult<()>;
+}
+
+/// Adapter - Keeps exported spans in memory (for testing and debugging)
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryExporter {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+impl InMemoryExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All spans exported so far; clones share the same storage
+    pub fn exported_spans(&self) -> Vec<Span> {
+        self.spans.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.spans.lock().unwrap().clear();
+    }
+}
+
+impl SpanExporter for InMemoryExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        self.spans.lock().unwrap().extend_from_slice(batch);
+        Ok(())
+    }
+}
+
+/// Adapter - Pretty-prints spans to stdout (or any writer) for development
+pub struct ConsoleExporter {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl ConsoleExporter {
+    pub fn new() -> Self {
+        Self::with_writer(std::io::stdout())
+    }
+
+    pub fn with_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl Default for ConsoleExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ConsoleExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsoleExporter").finish_non_exhaustive()
+    }
+}
+
+impl SpanExporter for ConsoleExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        let mut output = String::new();
+        for span in batch {
+            output.push_str(&format_console_span(span));
+        }
+        let mut writer = self.writer.lock().unwrap();
+        writer
+            .write_all(output.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| TylError::internal(format!("console export failed: {}", e)))
+    }
+}
+
+/// `operation 12ms [Completed] trace=… span=…` followed by sorted attributes
+fn format_console_span(span: &Span) -> String {
+    let duration = span
+        .duration_ms()
+        .map(|ms| format!("{}ms", ms))
+        .unwrap_or_else(|| "-".to_string());
+    let status = match &span.status {
+        SpanStatus::Active => "Active".to_string(),
+        SpanStatus::Completed => "Completed".to_string(),
+        SpanStatus::Error { message } => format!("Error: {}", message),
+    };
+    let mut line = format!(
+        "{} {} [{}] trace={} span={}",
+        span.operation_name, duration, status, span.trace_id, span.span_id
+    );
+    if let Some(parent) = &span.parent_span_id {
+        line.push_str(&format!(" parent={}", parent));
+    }
+    line.push('\n');
+    let mut attributes: Vec<_> = span.attributes.iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in attributes {
+        line.push_str(&format!("    {} = {}\n", key, value));
+    }
+    for event in &span.events {
+        let offset = event.timestamp.saturating_sub(span.start_time);
+        line.push_str(&format!("    @{}ms {}", offset, event.name));
+        let mut attributes: Vec<_> = event.attributes.iter().collect();
+        attributes.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in attributes {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+        line.push('\n');
+    }
+    line
+}
+
+/// Adapter - Appends spans to a JSON-lines file, optionally compressed
+///
+/// With compression enabled every batch is written as its own gzip member or
+/// zstd frame, so the file stays a valid stream for standard tools
+/// (`zcat`, `zstdcat`) while it keeps growing.
+#[derive(Debug)]
+pub struct FileExporter {
+    path: PathBuf,
+    compression: Compression,
+    otlp_service: Option<String>,
+    file_lock: Mutex<()>,
+}
+
+impl FileExporter {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            compression: Compression::None,
+            otlp_service: None,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    /// Write OTLP JSON instead of the native span format: one
+    /// `ExportTraceServiceRequest` per line, as read by the collector's
+    /// `otlpjsonfile` receiver
+    pub fn with_otlp_json(mut self, service_name: impl Into<String>) -> Self {
+        self.otlp_service = Some(service_name.into());
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Read back every span written to the file (native format only),
+    /// within the default `DecodeLimits`
+    pub fn read_spans(&self) -> TracingResult<Vec<Span>> {
+        self.read_spans_with_limits(&DecodeLimits::default())
+    }
+
+    /// Read back the file's spans, each line held to `limits`
+    ///
+    /// `max_input_bytes` caps each line and `max_spans` the whole file, so a
+    /// file from elsewhere cannot make the import allocate without bound.
+    pub fn read_spans_with_limits(&self, limits: &DecodeLimits) -> TracingResult<Vec<Span>> {
+        if self.otlp_service.is_some() {
+            return Err(TylError::configuration(format!(
+                "{} holds OTLP JSON; read it with OpenTelemetry tooling",
+                self.path.display()
+            )));
+        }
+        let _guard = self.file_lock.lock().unwrap();
+        let mut raw = Vec::new();
+        match File::open(&self.path) {
+            Ok(mut file) => file
+                .read_to_end(&mut raw)
+                .map_err(|e| io_error(&self.path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&self.path, e)),
+        };
+        let decoded = self.compression.decompress(&raw)?;
+        let mut spans = Vec::new();
+        for line in decoded.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            if spans.len() == limits.max_spans {
+                return Err(TylError::validation(
+                    "decode_limits",
+                    format!(
+                        "{} holds more than {} spans",
+                        self.path.display(),
+                        limits.max_spans
+                    ),
+                ));
+            }
+            limits.check_input(line)?;
+            let span = serde_json::from_slice(line)
+                .map_err(|e| TylError::serialization(format!("{}: {}", self.path.display(), e)))?;
+            limits.check_span(&span)?;
+            spans.push(span);
+        }
+        Ok(spans)
+    }
+}
+
+impl SpanExporter for FileExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let serialization = |e: serde_json::Error| {
+            TylError::serialization(format!("{}: {}", self.path.display(), e))
+        };
+        let mut payload = Vec::new();
+        match &self.otlp_service {
+            Some(service_name) => {
+                serde_json::to_writer(&mut payload, &to_otlp_json(service_name, batch))
+                    .map_err(serialization)?;
+                payload.push(b'\n');
+            }
+            None => {
+                for span in batch {
+                    serde_json::to_writer(&mut payload, span).map_err(serialization)?;
+                    payload.push(b'\n');
+                }
+            }
+        }
+        let payload = self.compression.compress(&payload)?;
+
+        let _guard = self.file_lock.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&payload))
+            .map_err(|e| io_error(&self.path, e))
+    }
+}
+
+/// Exponential backoff settings for exporter retries
+///
+/// Which errors are retried follows tyl-errors ([`is_retryable_error`]); the
+/// schedule is defined here so it can be deserialized with the exporter
+/// settings and carry jitter. The fields are public, so [`RetryPolicy::backoff`]
+/// applies the same bounds as the `with_*` builders instead of trusting them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Fraction (0.0-1.0) of each delay that is randomized
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that gives up after the first failure
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Backoff before retry number `retry` (1-based), without jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        // NaN fails the comparison and falls back to no growth
+        let multiplier = if self.multiplier >= 1.0 {
+            self.multiplier
+        } else {
+            1.0
+        };
+        let factor = multiplier.powi(retry.saturating_sub(1) as i32);
+        let delay = (self.base_delay.as_secs_f64() * factor).min(self.max_delay.as_secs_f64());
+        Duration::try_from_secs_f64(delay).unwrap_or(self.max_delay)
+    }
+
+    /// Backoff before retry number `retry` with jitter applied
+    pub fn jittered_backoff(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        let jitter = if self.jitter > 0.0 {
+            self.jitter.min(1.0)
+        } else {
+            0.0
+        };
+        backoff.mul_f64(1.0 - jitter * random_fraction())
+    }
+}
+
+/// Default classification, following tyl-errors' retry semantics
+/// ([`TylError::is_retriable`]): network and database failures are transient,
+/// validation, configuration and serialization failures are permanent
+pub fn is_retryable_error(error: &TylError) -> bool {
+    error.is_retriable()
+}
+
+type RetryClassifier = Box<dyn Fn(&TylError) -> bool + Send + Sync>;
+
+/// Exporter wrapper that retries failed exports with exponential backoff
+pub struct RetryingExporter<E> {
+    inner: E,
+    policy: RetryPolicy,
+    classifier: RetryClassifier,
+}
+
+impl<E: SpanExporter> RetryingExporter<E> {
+    pub fn new(inner: E, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            classifier: Box::new(is_retryable_error),
+        }
+    }
+
+    /// Replace the retryable error classification
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&TylError) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Box::new(classifier);
+        self
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for RetryingExporter<E> {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        let mut attempt = 1;
+        loop {
+            match self.inner.export(batch) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.policy.max_attempts && (self.classifier)(&e) => {
+                    std::thread::sleep(self.policy.jittered_backoff(attempt));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn shutdown(&self) -> TracingResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+/// Circuit breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitState {
+    /// Exports flow normally
+    Closed,
+    /// Exports are rejected without reaching the inner exporter
+    Open,
+    /// A single probe export is allowed through to test recovery
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Exporter wrapper that stops calling a failing backend
+///
+/// After `failure_threshold` consecutive failures the circuit opens and batches
+/// are rejected immediately (and counted as dropped) for `open_duration`. The
+/// next export after that is a half-open probe: success closes the circuit,
+/// failure re-opens it.
+pub struct CircuitBreakerExporter<E> {
+    inner: E,
+    failure_threshold: u32,
+    open_duration: Duration,
+    breaker: Mutex<Breaker>,
+    dropped_spans: AtomicU64,
+}
+
+impl<E: SpanExporter> CircuitBreakerExporter<E> {
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+            breaker: Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            }),
+            dropped_spans: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    pub fn with_open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.breaker.lock().unwrap().state
+    }
+
+    /// Spans rejected while the circuit was open
+    pub fn dropped_spans(&self) -> u64 {
+        self.dropped_spans.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Decide whether this export may reach the inner exporter
+    fn try_acquire(&self) -> bool {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => false, // a probe is already in flight
+            CircuitState::Open => {
+                let cooled_down = breaker
+                    .opened_at
+                    .map_or(true, |opened| opened.elapsed() >= self.open_duration);
+                if cooled_down {
+                    breaker.state = CircuitState::HalfOpen;
+                }
+                cooled_down
+            }
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if success {
+            breaker.state = CircuitState::Closed;
+            breaker.consecutive_failures = 0;
+            breaker.opened_at = None;
+            return;
+        }
+        breaker.consecutive_failures += 1;
+        if breaker.state == CircuitState::HalfOpen
+            || breaker.consecutive_failures >= self.failure_threshold
+        {
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for CircuitBreakerExporter<E> {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        if !self.try_acquire() {
+            self.dropped_spans
+                .fetch_add(batch.len() as u64, Ordering::Relaxed);
+            return Err(TylError::network(format!(
+                "exporter circuit open, dropped {} spans",
+                batch.len()
+            )));
+        }
+        let result = self.inner.export(batch);
+        self.record(result.is_ok());
+        result
+    }
+
+    fn shutdown(&self) -> TracingResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+/// Exporter wrapper that spills batches to a write-ahead file when export fails
+///
+/// Failed batches are appended to the file as one JSON line each and the export
+/// reports success, since the spans are now durable. Spilled batches are
+/// replayed after the next successful export, or explicitly via [`replay`],
+/// which also picks up files left behind by a previous process.
+///
+/// As a [`SpillSink`] it also takes the spans a [`BatchSpanProcessor`] has no
+/// queue room for; share it through an `Arc` as both the processor's exporter
+/// and its spill sink so they are replayed the same way. Lines that no longer
+/// parse (e.g. torn by a crash mid-write) are moved to `<path>.corrupt` when
+/// the file is rewritten.
+///
+/// [`replay`]: SpillingExporter::replay
+pub struct SpillingExporter<E> {
+    inner: E,
+    path: PathBuf,
+    max_bytes: u64,
+    file_lock: Mutex<()>,
+    dropped_spans: AtomicU64,
+}
+
+impl<E: SpanExporter> SpillingExporter<E> {
+    pub fn new(inner: E, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            max_bytes: 64 * 1024 * 1024,
+            file_lock: Mutex::new(()),
+            dropped_spans: AtomicU64::new(0),
+        }
+    }
+
+    /// Upper bound for the spill file; batches beyond it are dropped and counted
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Spans that could neither be exported nor spilled
+    pub fn dropped_spans(&self) -> u64 {
+        self.dropped_spans.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Number of batches currently waiting in the spill file
+    pub fn pending_batches(&self) -> usize {
+        let _guard = self.file_lock.lock().unwrap();
+        read_json_lines::<Vec<Span>>(&self.path).map_or(0, |lines| lines.values.len())
+    }
+
+    /// Re-export spilled batches in order, keeping any that still fail
+    ///
+    /// Returns the number of batches exported.
+    pub fn replay(&self) -> TracingResult<usize> {
+        let _guard = self.file_lock.lock().unwrap();
+        let lines: JsonLines<Vec<Span>> = read_json_lines(&self.path)?;
+        if lines.values.is_empty() {
+            return Ok(0);
+        }
+
+        let mut exported = 0;
+        for batch in &lines.values {
+            if self.inner.export(batch).is_err() {
+                break;
+            }
+            exported += 1;
+        }
+
+        if exported > 0 {
+            rewrite_json_lines(&self.path, &lines.values[exported..], &lines.corrupt)?;
+        }
+        Ok(exported)
+    }
+
+    /// Remove spilled spans matching `predicate`, dropping batches left empty
+    ///
+    /// [`SimpleTracer::purge_spans`](crate::SimpleTracer::purge_spans) does
+    /// not reach the spill file, so deletion requests must purge it here too.
+    /// Returns the number of spans removed.
+    pub fn purge_spans(&self, predicate: impl Fn(&Span) -> bool) -> TracingResult<usize> {
+        let _guard = self.file_lock.lock().unwrap();
+        purge_json_lines(&self.path, |batch: &mut Vec<Span>| batch, predicate)
+    }
+}
+
+impl<E: SpanExporter> SpillSink for SpillingExporter<E> {
+    fn spill(&self, batch: &[Span]) -> TracingResult<()> {
+        let _guard = self.file_lock.lock().unwrap();
+        let current_size = fs::metadata(&self.path).map_or(0, |m| m.len());
+        if current_size >= self.max_bytes {
+            return Err(TylError::internal(format!(
+                "spill file {} is full",
+                self.path.display()
+            )));
+        }
+        append_json_line(&self.path, &batch)
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for SpillingExporter<E> {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        match self.inner.export(batch) {
+            Ok(()) => {
+                // Backend is reachable again, drain what piled up; a failed replay
+                // leaves the batches on disk for the next attempt
+                let _ = self.replay();
+                Ok(())
+            }
+            Err(export_error) => self.spill(batch).map_err(|_| {
+                self.dropped_spans
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                export_error
+            }),
+        }
+    }
+
+    fn shutdown(&self) -> TracingResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+/// A batch that could not be exported, with the reason it failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Milliseconds since the Unix epoch
+    pub failed_at: u64,
+    pub reason: String,
+    pub spans: Vec<Span>,
+}
+
+/// Exporter wrapper that records permanently failed batches in a dead-letter file
+///
+/// Wrap it around the last fallible stage (usually a [`RetryingExporter`]) so
+/// that a batch only lands here once retries are exhausted. Each failure is
+/// appended as a JSON line holding the reason and the spans, and the export
+/// reports success since nothing was discarded.
+pub struct DeadLetterExporter<E> {
+    inner: E,
+    path: PathBuf,
+    file_lock: Mutex<()>,
+}
+
+impl<E: SpanExporter> DeadLetterExporter<E> {
+    pub fn new(inner: E, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Dead letters currently stored in the file
+    pub fn dead_letters(&self) -> TracingResult<Vec<DeadLetter>> {
+        let _guard = self.file_lock.lock().unwrap();
+        read_json_lines(&self.path).map(|lines| lines.values)
+    }
+
+    /// Send stored dead letters through the inner exporter again
+    ///
+    /// Returns the number of dead letters re-exported.
+    pub fn reexport_dead_letters(&self) -> TracingResult<usize> {
+        let _guard = self.file_lock.lock().unwrap();
+        reexport_dead_letters(&self.path, &self.inner)
+    }
+
+    /// Remove dead-lettered spans matching `predicate`, dropping letters left
+    /// empty
+    ///
+    /// [`SimpleTracer::purge_spans`](crate::SimpleTracer::purge_spans) does
+    /// not reach the dead-letter file, so deletion requests must purge it here
+    /// too. Returns the number of spans removed.
+    pub fn purge_spans(&self, predicate: impl Fn(&Span) -> bool) -> TracingResult<usize> {
+        let _guard = self.file_lock.lock().unwrap();
+        purge_json_lines(
+            &self.path,
+            |letter: &mut DeadLetter| &mut letter.spans,
+            predicate,
+        )
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for DeadLetterExporter<E> {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        let reason = match self.inner.export(batch) {
+            Ok(()) => return Ok(()),
+            Err(e) => e.to_string(),
+        };
+        let letter = DeadLetter {
+            failed_at: current_timestamp(),
+            reason,
+            spans: batch.to_vec(),
+        };
+        let _guard = self.file_lock.lock().unwrap();
+        append_json_line(&self.path, &letter)
+    }
+
+    fn shutdown(&self) -> TracingResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+/// Re-export the dead letters stored at `path` through any exporter
+///
+/// Letters that fail again stay in the file with their original reason, and
+/// lines that do not parse are moved to `<path>.corrupt`.
+/// Returns the number of dead letters re-exported.
+pub fn reexport_dead_letters(
+    path: impl AsRef<Path>,
+    exporter: &dyn SpanExporter,
+) -> TracingResult<usize> {
+    let path = path.as_ref();
+    let lines: JsonLines<DeadLetter> = read_json_lines(path)?;
+    let (delivered, remaining): (Vec<_>, Vec<_>) = lines
+        .values
+        .into_iter()
+        .partition(|letter| exporter.export(&letter.spans).is_ok());
+    if !delivered.is_empty() {
+        rewrite_json_lines(path, &remaining, &lines.corrupt)?;
+    }
+    Ok(delivered.len())
+}
+
+fn append_json_line<T: Serialize>(path: &Path, value: &T) -> TracingResult<()> {
+    let mut line = serde_json::to_string(value)
+        .map_err(|e| TylError::serialization(format!("{}: {}", path.display(), e)))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| io_error(path, e))
+}
+
+/// Contents of a JSON-lines file
+struct JsonLines<T> {
+    values: Vec<T>,
+    /// Raw lines that did not parse, e.g. torn by a crash mid-write
+    corrupt: Vec<String>,
+}
+
+fn read_json_lines<T: DeserializeOwned>(path: &Path) -> TracingResult<JsonLines<T>> {
+    let mut lines = JsonLines {
+        values: Vec::new(),
+        corrupt: Vec::new(),
+    };
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(lines),
+        Err(e) => return Err(io_error(path, e)),
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| io_error(path, e))?;
+        match serde_json::from_str(&line) {
+            Ok(value) => lines.values.push(value),
+            Err(_) if line.trim().is_empty() => {}
+            Err(_) => lines.corrupt.push(line),
+        }
+    }
+    Ok(lines)
+}
+
+/// Remove spans matching `predicate` from a JSON-lines file of span groups
+///
+/// Groups left empty are dropped. Lines that do not parse cannot be checked
+/// and are moved to `<path>.corrupt` with the rewrite.
+fn purge_json_lines<T: Serialize + DeserializeOwned>(
+    path: &Path,
+    spans_of: impl Fn(&mut T) -> &mut Vec<Span>,
+    predicate: impl Fn(&Span) -> bool,
+) -> TracingResult<usize> {
+    let mut lines: JsonLines<T> = read_json_lines(path)?;
+    let mut purged = 0;
+    lines.values.retain_mut(|value| {
+        let spans = spans_of(value);
+        let before = spans.len();
+        spans.retain(|span| !predicate(span));
+        purged += before - spans.len();
+        !spans.is_empty()
+    });
+    if purged > 0 {
+        rewrite_json_lines(path, &lines.values, &lines.corrupt)?;
+    }
+    Ok(purged)
+}
+
+/// Atomically replace a JSON-lines file, removing it when nothing is left
+///
+/// `corrupt` lines are appended to `<path>.corrupt` first, so they are kept
+/// for inspection rather than lost with the rewrite.
+fn rewrite_json_lines<T: Serialize>(
+    path: &Path,
+    values: &[T],
+    corrupt: &[String],
+) -> TracingResult<()> {
+    if !corrupt.is_empty() {
+        let mut corrupt_path = path.as_os_str().to_owned();
+        corrupt_path.push(".corrupt");
+        let corrupt_path = PathBuf::from(corrupt_path);
+        let contents: String = corrupt.iter().map(|line| format!("{}\n", line)).collect();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&corrupt_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(|e| io_error(&corrupt_path, e))?;
+    }
+    if values.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(path, e)),
+            _ => Ok(()),
+        };
+    }
+    let mut contents = String::new();
+    for value in values {
+        let line = serde_json::to_string(value)
+            .map_err(|e| TylError::serialization(format!("{}: {}", path.display(), e)))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| io_error(path, e))
+}
+
+pub(crate) fn io_error(path: &Path, error: std::io::Error) -> TylError {
+    TylError::internal(format!("{}: {}", path.display(), error))
+}
+
+/// Default number of spans that triggers an export
+pub const DEFAULT_BATCH_SIZE: usize = 512;
+
+/// Default number of spans buffered before new ones are dropped
+pub const DEFAULT_MAX_QUEUE_SIZE: usize = 2048;
+
+struct BatchState {
+    buffer: Vec<Span>,
+    /// A full batch is waiting for the worker
+    flush_requested: bool,
+    shutdown: bool,
+}
+
+struct BatchShared {
+    exporter: RwLock<Box<dyn SpanExporter>>,
+    batch_size: AtomicUsize,
+    max_queue_size: AtomicUsize,
+    state: Mutex<BatchState>,
+    wakeup: Condvar,
+    /// Held for a whole export, so a flush also waits for one in progress
+    export_lock: Mutex<()>,
+    exported_spans: AtomicU64,
+    failed_spans: AtomicU64,
+    /// Spans handed to the spill sink because the queue was full
+    spilled_spans: AtomicU64,
+    /// Spans turned away because the queue was full (and could not be spilled)
+    dropped_spans: AtomicU64,
+    /// Unix ms of the last successful export, 0 before the first one
+    last_export_ms: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl BatchShared {
+    fn flush(&self) -> TracingResult<()> {
+        let _exporting = self.export_lock.lock().unwrap();
+        let batch = std::mem::take(&mut self.state.lock().unwrap().buffer);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let result = self.exporter.read().unwrap().export(&batch);
+        match &result {
+            Ok(()) => {
+                self.exported_spans
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                self.last_export_ms
+                    .store(current_timestamp(), Ordering::Relaxed);
+                self.consecutive_failures.store(0, Ordering::Relaxed);
+                *self.last_error.lock().unwrap() = None;
+            }
+            Err(error) => {
+                self.failed_spans
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+                *self.last_error.lock().unwrap() = Some(error.to_string());
+            }
+        }
+        result
+    }
+}
+
+/// Buffers completed spans and hands them to an exporter in batches
+///
+/// A background worker exports a batch as soon as `batch_size` spans are
+/// buffered, so ending a span never waits on the exporter (network I/O,
+/// retry backoff). With a flush interval, the worker also exports whatever is
+/// buffered on that schedule, so low-traffic services don't hold spans back
+/// indefinitely. Remaining spans are flushed when the processor is dropped.
+///
+/// At most `max_queue_size` spans wait for export; while the exporter cannot
+/// keep up, further spans go to the spill sink if one is set, and are
+/// otherwise dropped and counted instead of growing the buffer without bound.
+pub struct BatchSpanProcessor {
+    shared: Arc<BatchShared>,
+    hasher: Option<AttributeHasher>,
+    spill_sink: Option<Arc<dyn SpillSink>>,
+    flush_interval: Option<Duration>,
+    worker: Option<JoinHandle<()>>,
+    closed: bool,
+}
+
+impl BatchSpanProcessor {
+    pub fn new(exporter: impl SpanExporter + 'static) -> Self {
+        Self::from_boxed(Box::new(exporter))
+    }
+
+    pub(crate) fn from_boxed(exporter: Box<dyn SpanExporter>) -> Self {
+        let processor = Self {
+            shared: Arc::new(BatchShared {
+                exporter: RwLock::new(exporter),
+                batch_size: AtomicUsize::new(DEFAULT_BATCH_SIZE),
+                max_queue_size: AtomicUsize::new(DEFAULT_MAX_QUEUE_SIZE),
+                state: Mutex::new(BatchState {
+                    buffer: Vec::new(),
+                    flush_requested: false,
+                    shutdown: false,
+                }),
+                wakeup: Condvar::new(),
+                export_lock: Mutex::new(()),
+                exported_spans: AtomicU64::new(0),
+                failed_spans: AtomicU64::new(0),
+                spilled_spans: AtomicU64::new(0),
+                dropped_spans: AtomicU64::new(0),
+                last_export_ms: AtomicU64::new(0),
+                consecutive_failures: AtomicU64::new(0),
+                last_error: Mutex::new(None),
+            }),
+            hasher: None,
+            spill_sink: None,
+            flush_interval: None,
+            worker: None,
+            closed: false,
+        };
+        processor.start_worker(None)
+    }
+
+    /// Hash sensitive attribute values before spans are queued for export
+    pub fn with_attribute_hasher(mut self, hasher: AttributeHasher) -> Self {
+        self.hasher = Some(hasher);
+        self
+    }
+
+    /// Number of buffered spans that triggers an export
+    pub fn with_batch_size(self, batch_size: usize) -> Self {
+        self.set_batch_size(batch_size);
+        self
+    }
+
+    /// Export buffered spans at least this often, regardless of batch size
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.stop_worker();
+        self.flush_interval = Some(interval);
+        self.start_worker(Some(interval))
+    }
+
+    fn start_worker(mut self, interval: Option<Duration>) -> Self {
+        let shared = self.shared.clone();
+        self.worker = Some(std::thread::spawn(move || {
+            run_flush_worker(shared, interval)
+        }));
+        self
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.shared.batch_size.load(Ordering::Relaxed)
+    }
+
+    /// Spans buffered before new ones are dropped; keep it above the batch size
+    pub fn with_max_queue_size(self, max_queue_size: usize) -> Self {
+        self.shared
+            .max_queue_size
+            .store(max_queue_size.max(1), Ordering::Relaxed);
+        self
+    }
+
+    pub fn max_queue_size(&self) -> usize {
+        self.shared.max_queue_size.load(Ordering::Relaxed)
+    }
+
+    /// Spill spans that find the queue full instead of dropping them
+    ///
+    /// The sink is written on the thread ending the span. Pass the
+    /// [`SpillingExporter`] this processor exports through (shared via `Arc`)
+    /// so spilled spans are replayed once exports succeed again:
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use tyl_tracing::{BatchSpanProcessor, InMemoryExporter, SpillingExporter};
+    ///
+    /// let spilling = Arc::new(SpillingExporter::new(InMemoryExporter::new(), "spans.spill"));
+    /// let processor = BatchSpanProcessor::new(spilling.clone()).with_spill_sink(spilling);
+    /// ```
+    pub fn with_spill_sink(mut self, sink: Arc<dyn SpillSink>) -> Self {
+        self.spill_sink = Some(sink);
+        self
+    }
+
+    /// Hand spans the queue has no room for to the spill sink, or drop them
+    fn overflow(&self, spans: &[Span]) {
+        let spilled = self
+            .spill_sink
+            .as_ref()
+            .is_some_and(|sink| sink.spill(spans).is_ok());
+        let counter = if spilled {
+            &self.shared.spilled_spans
+        } else {
+            &self.shared.dropped_spans
+        };
+        counter.fetch_add(spans.len() as u64, Ordering::Relaxed);
+    }
+
+    pub fn flush_interval(&self) -> Option<Duration> {
+        self.flush_interval
+    }
+
+    /// Queue a completed span; a full batch is handed to the worker
+    ///
+    /// Export failures are counted rather than returned, so ending a span
+    /// never fails (or blocks) because of the backend.
+    pub fn on_end(&self, mut span: Span) {
+        if let Some(hasher) = &self.hasher {
+            hasher.apply(&mut span);
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        if state.buffer.len() >= self.max_queue_size() {
+            drop(state);
+            self.overflow(std::slice::from_ref(&span));
+            return;
+        }
+        state.buffer.push(span);
+        self.request_flush_if_full(state);
+    }
+
+    /// Queue the spans of one trace together, so they are exported in the
+    /// same batch even if it grows past `batch_size` (or `max_queue_size`);
+    /// the whole trace overflows if the queue is already full
+    pub fn on_end_trace(&self, mut spans: Vec<Span>) {
+        if let Some(hasher) = &self.hasher {
+            spans.iter_mut().for_each(|span| hasher.apply(span));
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        if state.buffer.len() >= self.max_queue_size() {
+            drop(state);
+            self.overflow(&spans);
+            return;
+        }
+        state.buffer.append(&mut spans);
+        self.request_flush_if_full(state);
+    }
+
+    fn request_flush_if_full(&self, mut state: MutexGuard<'_, BatchState>) {
+        if state.buffer.len() < self.batch_size() {
+            return;
+        }
+        if self.worker.is_some() {
+            state.flush_requested = true;
+            self.shared.wakeup.notify_all();
+        } else {
+            // Shut down: nothing runs in the background any more
+            drop(state);
+            let _ = self.shared.flush();
+        }
+    }
+
+    /// Export everything buffered right now, after any export in progress
+    pub fn flush(&self) -> TracingResult<()> {
+        self.shared.flush()
+    }
+
+    /// Swap the exporter at runtime (e.g. after an endpoint change)
+    ///
+    /// Buffered spans are flushed to the old exporter, which is then shut down.
+    pub fn set_exporter(&self, exporter: impl SpanExporter + 'static) -> TracingResult<()> {
+        self.set_boxed_exporter(Box::new(exporter))
+    }
+
+    pub(crate) fn set_boxed_exporter(&self, exporter: Box<dyn SpanExporter>) -> TracingResult<()> {
+        let flushed = self.shared.flush();
+        let old = std::mem::replace(&mut *self.shared.exporter.write().unwrap(), exporter);
+        old.shutdown().and(flushed)
+    }
+
+    pub(crate) fn set_batch_size(&self, batch_size: usize) {
+        self.shared
+            .batch_size
+            .store(batch_size.max(1), Ordering::Relaxed);
+    }
+
+    /// Copy of the buffered spans, or None if the buffer is locked
+    pub(crate) fn try_queued_snapshot(&self) -> Option<Vec<Span>> {
+        try_lock_for_crash(&self.shared.state).map(|state| state.buffer.clone())
+    }
+
+    /// Drop buffered spans matching `predicate`; returns how many were dropped
+    pub(crate) fn purge_queued(&self, predicate: impl Fn(&Span) -> bool) -> usize {
+        let buffer = &mut self.shared.state.lock().unwrap().buffer;
+        let before = buffer.len();
+        buffer.retain(|span| !predicate(span));
+        before - buffer.len()
+    }
+
+    /// Spans waiting for the next export
+    pub fn queued_spans(&self) -> usize {
+        self.shared.state.lock().unwrap().buffer.len()
+    }
+
+    pub fn exported_spans(&self) -> u64 {
+        self.shared.exported_spans.load(Ordering::Relaxed)
+    }
+
+    /// Spans whose export returned an error
+    pub fn failed_spans(&self) -> u64 {
+        self.shared.failed_spans.load(Ordering::Relaxed)
+    }
+
+    /// Spans handed to the spill sink because the queue was full
+    pub fn spilled_spans(&self) -> u64 {
+        self.shared.spilled_spans.load(Ordering::Relaxed)
+    }
+
+    /// Spans dropped because the queue was full and could not be spilled
+    pub fn dropped_spans(&self) -> u64 {
+        self.shared.dropped_spans.load(Ordering::Relaxed)
+    }
+
+    /// Queued spans relative to `max_queue_size` (0.0 - 1.0)
+    pub fn queue_saturation(&self) -> f64 {
+        (self.queued_spans() as f64 / self.max_queue_size() as f64).min(1.0)
+    }
+
+    /// Unix ms of the last successful export
+    pub fn last_export_time(&self) -> Option<u64> {
+        match self.shared.last_export_ms.load(Ordering::Relaxed) {
+            0 => None,
+            time => Some(time),
+        }
+    }
+
+    /// Exports that failed since the last successful one
+    pub fn consecutive_failures(&self) -> u64 {
+        self.shared.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Error of the most recent export, cleared by the next success
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.last_error.lock().unwrap().clone()
+    }
+
+    /// Stop the worker, flush remaining spans, and shut the exporter down
+    pub fn shutdown(&mut self) -> TracingResult<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        self.stop_worker();
+        let flushed = self.shared.flush();
+        self.shared.exporter.read().unwrap().shutdown().and(flushed)
+    }
+
+    fn stop_worker(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.shared.state.lock().unwrap().shutdown = true;
+            self.shared.wakeup.notify_all();
+            let _ = worker.join();
+            self.shared.state.lock().unwrap().shutdown = false;
+        }
+    }
+}
+
+impl Drop for BatchSpanProcessor {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+impl std::fmt::Debug for BatchSpanProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchSpanProcessor")
+            .field("batch_size", &self.batch_size())
+            .field("max_queue_size", &self.max_queue_size())
+            .field("spill_sink", &self.spill_sink.is_some())
+            .field("flush_interval", &self.flush_interval)
+            .field("queued_spans", &self.queued_spans())
+            .finish()
+    }
+}
+
+/// Export full batches as they are handed over, and everything buffered
+/// once per `interval` if there is one
+fn run_flush_worker(shared: Arc<BatchShared>, interval: Option<Duration>) {
+    let idle = |state: &mut BatchState| !state.shutdown && !state.flush_requested;
+    loop {
+        {
+            let state = shared.state.lock().unwrap();
+            let mut state = match interval {
+                Some(interval) => {
+                    shared
+                        .wakeup
+                        .wait_timeout_while(state, interval, idle)
+                        .unwrap()
+                        .0
+                }
+                None => shared.wakeup.wait_while(state, idle).unwrap(),
+            };
+            if state.shutdown {
+                return;
+            }
+            state.flush_requested = false;
+        }
+        let _ = shared.flush();
+    }
+}
+
+/// Uniform random value in [0.0, 1.0)
+fn random_fraction() -> f64 {
+    // The low 62 bits of a v4 UUID are random; keep 53 for an exact f64
+    let bits = Uuid::new_v4().as_u128() as u64 & ((1u64 << 53) - 1);
+    bits as f64 / (1u64 << 53) as f64
+}
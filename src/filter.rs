@@ -0,0 +1,159 @@
+//! Span filtering module
+//!
+//! Parses RUST_LOG-style filter strings such as
+//! `checkout=on,health*=off,db.*=0.01` into per-operation directives that
+//! enable, disable, or sample spans by operation name.
+
+use crate::tracer::TracingResult;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use tyl_errors::TylError;
+
+/// Environment variable holding the span filter
+pub const TRACE_FILTER_ENV: &str = "TYL_TRACE_FILTER";
+
+/// What a matching directive does with a span
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterAction {
+    Enabled,
+    Disabled,
+    /// Keep this fraction of traces (0.0-1.0)
+    Sample(f64),
+}
+
+/// A single `pattern=action` entry; `*` in the pattern matches any characters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterDirective {
+    pub pattern: String,
+    pub action: FilterAction,
+}
+
+/// Ordered set of per-operation directives
+///
+/// When several patterns match, an exact match wins, then the pattern with the
+/// most literal characters, then the directive that appears last.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpanFilter {
+    pub directives: Vec<FilterDirective>,
+}
+
+impl SpanFilter {
+    /// Parse a filter string, e.g. `checkout=on,health*=off,db.*=0.01`
+    ///
+    /// A bare pattern without `=` enables matching operations.
+    pub fn parse(filter: &str) -> TracingResult<Self> {
+        let mut directives = Vec::new();
+        for entry in filter.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (pattern, value) = match entry.split_once('=') {
+                Some((pattern, value)) => (pattern.trim(), value.trim()),
+                None => (entry, "on"),
+            };
+            if pattern.is_empty() {
+                return Err(TylError::validation(
+                    "trace_filter",
+                    format!("missing operation pattern in '{}'", entry),
+                ));
+            }
+            directives.push(FilterDirective {
+                pattern: pattern.to_string(),
+                action: parse_action(value).ok_or_else(|| {
+                    TylError::validation(
+                        "trace_filter",
+                        format!(
+                            "invalid action '{}' for '{}' (expected on, off, or a rate in 0.0-1.0)",
+                            value, pattern
+                        ),
+                    )
+                })?,
+            });
+        }
+        Ok(Self { directives })
+    }
+
+    /// Read the filter from `TYL_TRACE_FILTER`, if set
+    pub fn from_env() -> TracingResult<Option<Self>> {
+        match std::env::var(TRACE_FILTER_ENV) {
+            Ok(value) => Self::parse(&value).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty()
+    }
+
+    /// The action of the most specific directive matching the operation
+    pub fn decide(&self, operation_name: &str) -> Option<FilterAction> {
+        self.matching(operation_name).map(|d| d.action)
+    }
+
+    /// The most specific directive matching the operation
+    pub fn matching(&self, operation_name: &str) -> Option<&FilterDirective> {
+        self.directives
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| glob_match(&d.pattern, operation_name))
+            .max_by_key(|(index, d)| {
+                let exact = d.pattern == operation_name;
+                let literal_chars = d.pattern.chars().filter(|c| *c != '*').count();
+                (exact, literal_chars, *index)
+            })
+            .map(|(_, d)| d)
+    }
+}
+
+impl FromStr for SpanFilter {
+    type Err = TylError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for SpanFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self
+            .directives
+            .iter()
+            .map(|d| match d.action {
+                FilterAction::Enabled => format!("{}=on", d.pattern),
+                FilterAction::Disabled => format!("{}=off", d.pattern),
+                FilterAction::Sample(rate) => format!("{}={}", d.pattern, rate),
+            })
+            .collect();
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+fn parse_action(value: &str) -> Option<FilterAction> {
+    match value.to_lowercase().as_str() {
+        "on" | "true" | "enabled" => Some(FilterAction::Enabled),
+        "off" | "false" | "disabled" => Some(FilterAction::Disabled),
+        rate => rate
+            .parse::<f64>()
+            .ok()
+            .filter(|r| (0.0..=1.0).contains(r))
+            .map(FilterAction::Sample),
+    }
+}
+
+/// Match `value` against a pattern where `*` matches any (possibly empty) run
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty(); // no '*' at all
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
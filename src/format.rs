@@ -0,0 +1,209 @@
+//! Trace formatting module
+//!
+//! Contains `format_trace`, which renders spans as an indented tree (one line
+//! per span, children under their parent), and the FormatOptions controlling
+//! depth, duration units, attribute selection, and ANSI color. Useful for test
+//! failure output and command-line tools.
+
+use crate::span::{Span, SpanStatus};
+use crate::tree::TraceTree;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Unit used to print span durations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DurationUnit {
+    #[default]
+    Millis,
+    Seconds,
+    /// Milliseconds below one second, seconds above
+    Auto,
+}
+
+/// Which attributes are printed under each span
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AttributeSelection {
+    #[default]
+    None,
+    All,
+    Only(Vec<String>),
+}
+
+/// Options for [`format_trace`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Deepest level printed (0 = roots only); deeper spans are summarized
+    pub max_depth: Option<usize>,
+    pub duration_unit: DurationUnit,
+    pub attributes: AttributeSelection,
+    /// Emit ANSI escape codes
+    pub color: bool,
+    /// Print the first 8 characters of each span ID
+    pub show_ids: bool,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn with_duration_unit(mut self, unit: DurationUnit) -> Self {
+        self.duration_unit = unit;
+        self
+    }
+
+    pub fn with_all_attributes(mut self) -> Self {
+        self.attributes = AttributeSelection::All;
+        self
+    }
+
+    pub fn with_attributes<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attributes = AttributeSelection::Only(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_ids(mut self, show_ids: bool) -> Self {
+        self.show_ids = show_ids;
+        self
+    }
+}
+
+/// Render spans as a tree
+///
+/// Spans whose parent is not in `spans` are printed as roots. Siblings are
+/// ordered by start time.
+pub fn format_trace(spans: &[Span], options: FormatOptions) -> String {
+    let printer = Printer {
+        tree: TraceTree::new(spans),
+        options,
+    };
+    let mut output = String::new();
+    for root in printer.tree.roots() {
+        printer.write_span(&mut output, root, "", "", 0);
+    }
+    output
+}
+
+struct Printer<'a> {
+    tree: TraceTree<'a>,
+    options: FormatOptions,
+}
+
+impl<'a> Printer<'a> {
+    /// `prefix` starts this span's line, `indent` starts the lines below it
+    fn write_span(
+        &self,
+        output: &mut String,
+        span: &'a Span,
+        prefix: &str,
+        indent: &str,
+        depth: usize,
+    ) {
+        output.push_str(prefix);
+        output.push_str(&self.span_line(span));
+        output.push('\n');
+
+        let children = self.tree.children(span.span_id);
+        let attributes = self.selected_attributes(span);
+        let branch_indent = if children.is_empty() { "   " } else { "│  " };
+        for (key, value) in attributes {
+            output.push_str(&format!("{}{}{} = {}\n", indent, branch_indent, key, value));
+        }
+
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            if !children.is_empty() {
+                let hidden = self.tree.descendant_count(span.span_id);
+                let line = format!(
+                    "└─ … {} more span{}",
+                    hidden,
+                    if hidden == 1 { "" } else { "s" }
+                );
+                output.push_str(indent);
+                output.push_str(&self.paint(DIM, &line));
+                output.push('\n');
+            }
+            return;
+        }
+        for (index, child) in children.iter().enumerate() {
+            let last = index + 1 == children.len();
+            let (branch, continuation) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            self.write_span(
+                output,
+                child,
+                &format!("{}{}", indent, branch),
+                &format!("{}{}", indent, continuation),
+                depth + 1,
+            );
+        }
+    }
+
+    fn span_line(&self, span: &Span) -> String {
+        let mut line = self.paint(BOLD, &span.operation_name);
+        if self.options.show_ids {
+            let id: String = span.span_id.to_string().chars().take(8).collect();
+            line.push_str(&self.paint(DIM, &format!(" [{}]", id)));
+        }
+        line.push(' ');
+        line.push_str(&match span.duration_ms() {
+            Some(ms) => self.format_duration(ms),
+            None => "(active)".to_string(),
+        });
+        if let SpanStatus::Error { message } = &span.status {
+            line.push(' ');
+            line.push_str(&self.paint(RED, &format!("error: {}", message)));
+        }
+        line
+    }
+
+    fn format_duration(&self, ms: u64) -> String {
+        match self.options.duration_unit {
+            DurationUnit::Millis => format!("{}ms", ms),
+            DurationUnit::Seconds => format!("{:.3}s", ms as f64 / 1000.0),
+            DurationUnit::Auto if ms < 1000 => format!("{}ms", ms),
+            DurationUnit::Auto => format!("{:.2}s", ms as f64 / 1000.0),
+        }
+    }
+
+    fn selected_attributes<'s>(&self, span: &'s Span) -> Vec<(&'s str, &'s serde_json::Value)> {
+        let mut attributes: Vec<_> = match &self.options.attributes {
+            AttributeSelection::None => Vec::new(),
+            AttributeSelection::All => span.attributes.iter().collect(),
+            AttributeSelection::Only(keys) => span
+                .attributes
+                .iter()
+                .filter(|(key, _)| keys.iter().any(|selected| selected == key))
+                .collect(),
+        };
+        attributes.sort_by(|a, b| a.0.cmp(b.0));
+        attributes
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.options.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Async context module
+//!
+//! Contains [`WithContext`], a future wrapper that attaches a [`Context`]
+//! every time the future is polled. It only relies on `std::future`, so the
+//! same API works on tokio, async-std, smol, or any other executor, and
+//! context follows a task across the worker threads it is polled on.
+
+use crate::context::Context;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+
+/// Future polled with a trace context attached
+#[derive(Debug, Clone)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WithContext<F> {
+    inner: F,
+    context: Context,
+}
+
+impl<F> WithContext<F> {
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: Future> Future for WithContext<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<F::Output> {
+        let _guard = self.context.attach();
+        // SAFETY: `inner` is structurally pinned; it is never moved or handed
+        // out unpinned while `self` is pinned
+        let inner = unsafe { self.map_unchecked_mut(|this| &mut this.inner) };
+        inner.poll(cx)
+    }
+}
+
+/// Attach trace context to any future
+pub trait FutureContextExt: Future + Sized {
+    /// Poll this future with `context` attached
+    fn with_context(self, context: Context) -> WithContext<Self> {
+        WithContext {
+            inner: self,
+            context,
+        }
+    }
+
+    /// Poll this future with the caller's current context, e.g. before
+    /// handing it to an executor's `spawn`
+    fn in_current_context(self) -> WithContext<Self> {
+        self.with_context(Context::current())
+    }
+}
+
+impl<F: Future> FutureContextExt for F {}
@@ -0,0 +1,207 @@
+//! Service dependency graph module
+//!
+//! Builds a service-to-service call graph from completed spans, using span kinds
+//! together with `peer.service` (or remote address) attributes to detect calls
+//! that cross a service boundary.
+
+use crate::ids::SpanId;
+use crate::span::{Span, SpanKind, SpanStatus};
+use crate::tracer::TracingResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tyl_errors::TylError;
+
+/// Attribute identifying the service that produced a span
+pub const SERVICE_NAME_ATTRIBUTE: &str = "service.name";
+
+/// Attributes identifying the remote side of a client/producer span, in priority order
+pub const PEER_ATTRIBUTES: [&str; 3] = ["peer.service", "server.address", "net.peer.name"];
+
+/// Service name used when a span carries no `service.name` attribute and was
+/// not listed under a service
+pub const UNKNOWN_SERVICE: &str = "unknown";
+
+/// Aggregated calls from one service to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEdge {
+    pub source: String,
+    pub target: String,
+    pub call_count: u64,
+    pub error_count: u64,
+    pub error_rate: f64,
+    pub latency_p50_ms: u64,
+    pub latency_p95_ms: u64,
+    pub latency_p99_ms: u64,
+}
+
+/// Service-to-service edge list computed from spans
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceGraph {
+    pub edges: Vec<ServiceEdge>,
+}
+
+#[derive(Default)]
+struct EdgeStats {
+    durations: Vec<u64>,
+    errors: u64,
+}
+
+impl ServiceGraph {
+    /// Build the graph from a set of spans (usually one or more complete traces)
+    ///
+    /// A call is recorded for every client/producer span that names its peer, and
+    /// for every server/consumer span whose parent belongs to a different service
+    /// (unless that parent already recorded the call as a client).
+    ///
+    /// Services are read from each span's `service.name` attribute; use
+    /// [`ServiceGraph::from_services`] for spans recorded by `SimpleTracer`.
+    pub fn from_spans(spans: &[Span]) -> Self {
+        Self::build(spans.iter().map(|span| (span, UNKNOWN_SERVICE)).collect())
+    }
+
+    /// Build the graph from the spans of several services, each listed under
+    /// the service that recorded it
+    ///
+    /// A tracer's service name belongs to its resource rather than its spans,
+    /// so `SimpleTracer` spans carry no `service.name` attribute. Pass each
+    /// tracer's `config().service_name` with its completed spans; a
+    /// `service.name` attribute on a span still takes precedence.
+    pub fn from_services<'a>(services: impl IntoIterator<Item = (&'a str, &'a [Span])>) -> Self {
+        Self::build(
+            services
+                .into_iter()
+                .flat_map(|(service, spans)| spans.iter().map(move |span| (span, service)))
+                .collect(),
+        )
+    }
+
+    fn build(spans: Vec<(&Span, &str)>) -> Self {
+        let by_id: HashMap<SpanId, (&Span, &str)> = spans
+            .iter()
+            .map(|&(span, service)| (span.span_id, (span, service)))
+            .collect();
+        let mut stats: BTreeMap<(String, String), EdgeStats> = BTreeMap::new();
+
+        for &(span, service) in &spans {
+            let edge = match span.kind {
+                SpanKind::Client | SpanKind::Producer => {
+                    peer_of(span).map(|peer| (service_of(span, service), peer))
+                }
+                SpanKind::Server | SpanKind::Consumer => span
+                    .parent_span_id
+                    .and_then(|id| by_id.get(&id))
+                    .filter(|(parent, _)| !is_outgoing_call(parent))
+                    .map(|&(parent, parent_service)| {
+                        (
+                            service_of(parent, parent_service),
+                            service_of(span, service),
+                        )
+                    })
+                    .filter(|(source, target)| source != target),
+                SpanKind::Internal => None,
+            };
+
+            if let Some((source, target)) = edge {
+                let entry = stats
+                    .entry((source.to_string(), target.to_string()))
+                    .or_default();
+                entry.durations.push(span.duration_ms().unwrap_or(0));
+                if matches!(span.status, SpanStatus::Error { .. }) {
+                    entry.errors += 1;
+                }
+            }
+        }
+
+        let edges = stats
+            .into_iter()
+            .map(|((source, target), mut stats)| {
+                stats.durations.sort_unstable();
+                let call_count = stats.durations.len() as u64;
+                ServiceEdge {
+                    source,
+                    target,
+                    call_count,
+                    error_count: stats.errors,
+                    error_rate: stats.errors as f64 / call_count as f64,
+                    latency_p50_ms: percentile(&stats.durations, 50.0),
+                    latency_p95_ms: percentile(&stats.durations, 95.0),
+                    latency_p99_ms: percentile(&stats.durations, 99.0),
+                }
+            })
+            .collect();
+
+        Self { edges }
+    }
+
+    /// Find the edge between two services, if any calls were observed
+    pub fn edge(&self, source: &str, target: &str) -> Option<&ServiceEdge> {
+        self.edges
+            .iter()
+            .find(|e| e.source == source && e.target == target)
+    }
+
+    /// All services appearing in the graph, sorted by name
+    pub fn services(&self) -> Vec<String> {
+        let mut services: Vec<String> = self
+            .edges
+            .iter()
+            .flat_map(|e| [e.source.clone(), e.target.clone()])
+            .collect();
+        services.sort();
+        services.dedup();
+        services
+    }
+
+    /// Serialize the edge list as pretty-printed JSON
+    pub fn to_json(&self) -> TracingResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| TylError::serialization(format!("service graph: {}", e)))
+    }
+
+    /// Render the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph services {\n");
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"calls={} errors={:.1}% p95={}ms\"];\n",
+                escape_dot(&edge.source),
+                escape_dot(&edge.target),
+                edge.call_count,
+                edge.error_rate * 100.0,
+                edge.latency_p95_ms
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn service_of<'a>(span: &'a Span, service: &'a str) -> &'a str {
+    span.attributes
+        .get(SERVICE_NAME_ATTRIBUTE)
+        .and_then(|v| v.as_str())
+        .unwrap_or(service)
+}
+
+fn peer_of(span: &Span) -> Option<&str> {
+    PEER_ATTRIBUTES
+        .iter()
+        .find_map(|key| span.attributes.get(key).and_then(|v| v.as_str()))
+}
+
+fn is_outgoing_call(span: &Span) -> bool {
+    matches!(span.kind, SpanKind::Client | SpanKind::Producer) && peer_of(span).is_some()
+}
+
+/// Nearest-rank percentile over sorted values
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
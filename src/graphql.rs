@@ -0,0 +1,238 @@
+//! GraphQL instrumentation module
+//!
+//! Contains [`GraphQLTracing`], an async-graphql extension that records one
+//! server span per executed operation (`query GetUser`) and a child span per
+//! resolved field, nested along the field path, so the time spent behind a
+//! GraphQL endpoint is broken down by resolver. Failed resolvers and
+//! operations that return errors get an error status and an `exception` event.
+//!
+//! ```ignore
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(GraphQLTracing::new(tracer.clone()))
+//!     .finish();
+//! ```
+
+use crate::exception::{EXCEPTION_EVENT, EXCEPTION_MESSAGE_ATTRIBUTE, EXCEPTION_TYPE_ATTRIBUTE};
+use crate::span::{Span, SpanKind, SpanStatus};
+use crate::tracer::{SimpleTracer, TracingManager};
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextResolve,
+    ResolveInfo,
+};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{QueryPathNode, QueryPathSegment, Response, ServerResult, Value, Variables};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub const OPERATION_TYPE_ATTRIBUTE: &str = "graphql.operation.type";
+pub const OPERATION_NAME_ATTRIBUTE: &str = "graphql.operation.name";
+/// Number of errors in the operation's response
+pub const ERROR_COUNT_ATTRIBUTE: &str = "graphql.errors";
+/// Response path of a resolved field, e.g. `user.posts.0.title`
+pub const FIELD_PATH_ATTRIBUTE: &str = "graphql.field.path";
+pub const FIELD_NAME_ATTRIBUTE: &str = "graphql.field.name";
+pub const FIELD_PARENT_TYPE_ATTRIBUTE: &str = "graphql.field.parent_type";
+pub const FIELD_TYPE_ATTRIBUTE: &str = "graphql.field.type";
+/// `exception.type` of GraphQL errors
+const GRAPHQL_ERROR_TYPE: &str = "GraphQLError";
+
+/// Adapter - async-graphql extension tracing operations and resolvers
+///
+/// The operation span's parent is the context span current when the schema
+/// executes the request (see [`crate::FutureContextExt`]).
+#[derive(Clone)]
+pub struct GraphQLTracing {
+    tracer: Arc<SimpleTracer>,
+}
+
+impl GraphQLTracing {
+    pub fn new(tracer: Arc<SimpleTracer>) -> Self {
+        Self { tracer }
+    }
+}
+
+impl ExtensionFactory for GraphQLTracing {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RequestTracing {
+            tracer: self.tracer.clone(),
+            operation_types: Mutex::new(Vec::new()),
+            operation_span: Mutex::new(None),
+            field_spans: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// Per-request extension state
+struct RequestTracing {
+    tracer: Arc<SimpleTracer>,
+    /// Operation names and types of the parsed document
+    operation_types: Mutex<Vec<(Option<String>, String)>>,
+    operation_span: Mutex<Option<String>>,
+    /// Span IDs of the fields being resolved, by response path
+    field_spans: Mutex<HashMap<String, String>>,
+}
+
+impl RequestTracing {
+    fn operation_type(&self, operation_name: Option<&str>) -> Option<String> {
+        let operations = self.operation_types.lock().unwrap();
+        match operations.as_slice() {
+            [(_, ty)] => Some(ty.clone()),
+            operations => operations
+                .iter()
+                .find(|(name, _)| name.as_deref() == operation_name)
+                .map(|(_, ty)| ty.clone()),
+        }
+    }
+
+    /// Span of the closest enclosing field, or the operation span
+    fn parent_span(&self, node: &QueryPathNode<'_>) -> Option<String> {
+        let fields = self.field_spans.lock().unwrap();
+        let mut parent = node.parent;
+        while let Some(node) = parent {
+            if matches!(node.segment, QueryPathSegment::Name(_)) {
+                if let Some(span_id) = fields.get(&node.to_string()) {
+                    return Some(span_id.clone());
+                }
+            }
+            parent = node.parent;
+        }
+        drop(fields);
+        self.operation_span.lock().unwrap().clone()
+    }
+
+    fn record_failure(&self, span_id: &str, message: &str) {
+        let _ = self.tracer.update_span(span_id, |span: &mut Span| {
+            span.add_event(
+                EXCEPTION_EVENT,
+                HashMap::from([
+                    (
+                        EXCEPTION_TYPE_ATTRIBUTE.to_string(),
+                        GRAPHQL_ERROR_TYPE.into(),
+                    ),
+                    (EXCEPTION_MESSAGE_ATTRIBUTE.to_string(), message.into()),
+                ]),
+            );
+            span.status = SpanStatus::Error {
+                message: message.to_string(),
+            };
+        });
+    }
+}
+
+#[async_graphql::async_trait::async_trait]
+impl Extension for RequestTracing {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.operation_types.lock().unwrap() = document
+            .operations
+            .iter()
+            .map(|(name, operation)| {
+                (
+                    name.map(|name| name.to_string()),
+                    operation.node.ty.to_string(),
+                )
+            })
+            .collect();
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let operation_type = self
+            .operation_type(operation_name)
+            .unwrap_or_else(|| "query".to_string());
+        let span_name = match operation_name {
+            Some(name) => format!("{} {}", operation_type, name),
+            None => operation_type.clone(),
+        };
+        let span_id = self
+            .tracer
+            .start_span_with_kind(&span_name, None, SpanKind::Server)
+            .ok();
+        if let Some(span_id) = &span_id {
+            let _ = self.tracer.add_span_attribute(
+                span_id,
+                OPERATION_TYPE_ATTRIBUTE,
+                operation_type.into(),
+            );
+            if let Some(name) = operation_name {
+                let _ =
+                    self.tracer
+                        .add_span_attribute(span_id, OPERATION_NAME_ATTRIBUTE, name.into());
+            }
+        }
+        *self.operation_span.lock().unwrap() = span_id.clone();
+
+        let response = next.run(ctx, operation_name).await;
+
+        if let Some(span_id) = span_id {
+            if let Some(error) = response.errors.first() {
+                let _ = self.tracer.add_span_attribute(
+                    &span_id,
+                    ERROR_COUNT_ATTRIBUTE,
+                    response.errors.len().into(),
+                );
+                self.record_failure(&span_id, &error.message);
+            }
+            let _ = self.tracer.end_span(span_id);
+        }
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+
+        let path = info.path_node.to_string();
+        let span_name = format!("{}.{}", info.parent_type, info.name);
+        let span_id = self
+            .tracer
+            .start_span_with_kind(
+                &span_name,
+                self.parent_span(info.path_node),
+                SpanKind::Internal,
+            )
+            .ok();
+        if let Some(span_id) = &span_id {
+            for (key, value) in [
+                (FIELD_PATH_ATTRIBUTE, path.as_str()),
+                (FIELD_NAME_ATTRIBUTE, info.name),
+                (FIELD_PARENT_TYPE_ATTRIBUTE, info.parent_type),
+                (FIELD_TYPE_ATTRIBUTE, info.return_type),
+            ] {
+                let _ = self.tracer.add_span_attribute(span_id, key, value.into());
+            }
+            self.field_spans
+                .lock()
+                .unwrap()
+                .insert(path.clone(), span_id.clone());
+        }
+
+        let result = next.run(ctx, info).await;
+
+        if let Some(span_id) = span_id {
+            self.field_spans.lock().unwrap().remove(&path);
+            if let Err(error) = &result {
+                self.record_failure(&span_id, &error.message);
+            }
+            let _ = self.tracer.end_span(span_id);
+        }
+        result
+    }
+}
@@ -0,0 +1,71 @@
+//! gRPC metadata module
+//!
+//! Contains the Injector and Extractor implementations for tonic's
+//! `MetadataMap`, so context propagates over gRPC without hand-written
+//! adapters. Text keys carry the usual `traceparent`; `-bin` keys are
+//! binary metadata, which tonic base64-encodes on the wire, and carry the
+//! binary context encoding under `grpc-trace-bin`.
+
+use crate::propagation::{Extractor, Injector, TraceContext};
+use tonic::metadata::{
+    AsciiMetadataKey, AsciiMetadataValue, BinaryMetadataKey, BinaryMetadataValue, KeyRef,
+    MetadataMap,
+};
+
+/// Binary metadata key holding the binary context (see `TraceContext::to_bytes`)
+pub const GRPC_TRACE_BIN_HEADER: &str = "grpc-trace-bin";
+
+/// Adapter - gRPC request/response metadata as a context carrier
+///
+/// Keys ending in `-bin` are stored as binary metadata with the value's raw
+/// bytes. Invalid keys or values are skipped rather than failing the call.
+impl Injector for MetadataMap {
+    fn set(&mut self, key: &str, value: String) {
+        if key.ends_with("-bin") {
+            if let Ok(key) = BinaryMetadataKey::from_bytes(key.as_bytes()) {
+                self.insert_bin(key, BinaryMetadataValue::from_bytes(value.as_bytes()));
+            }
+        } else if let (Ok(key), Ok(value)) = (
+            AsciiMetadataKey::from_bytes(key.as_bytes()),
+            AsciiMetadataValue::try_from(value),
+        ) {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// Adapter - reads text metadata; binary (`-bin`) entries are not text and
+/// are read with `TraceContext::extract_grpc_bin`
+impl Extractor for MetadataMap {
+    fn get(&self, key: &str) -> Option<&str> {
+        if key.ends_with("-bin") {
+            return None;
+        }
+        MetadataMap::get(self, key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        MetadataMap::keys(self)
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl TraceContext {
+    /// Write the binary context into `metadata` under `grpc-trace-bin`
+    pub fn inject_grpc_bin(&self, metadata: &mut MetadataMap) {
+        metadata.insert_bin(
+            GRPC_TRACE_BIN_HEADER,
+            BinaryMetadataValue::from_bytes(&self.to_bytes()),
+        );
+    }
+
+    /// Read the binary context from `metadata`, if it holds a valid one
+    pub fn extract_grpc_bin(metadata: &MetadataMap) -> Option<Self> {
+        let bytes = metadata.get_bin(GRPC_TRACE_BIN_HEADER)?.to_bytes().ok()?;
+        Self::from_bytes(&bytes).ok()
+    }
+}
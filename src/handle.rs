@@ -0,0 +1,191 @@
+//! Span handle module
+//!
+//! Contains SpanHandle, the guard returned by `SimpleTracer::start`. A handle
+//! knows whether its span is recording; on a non-recording handle (unsampled,
+//! filtered, capped, or tracing disabled) attribute and event calls return
+//! before converting their arguments or touching a lock, matching OTel's
+//! `is_recording` semantics.
+
+use crate::attributes::AttributeKey;
+use crate::propagation::TraceContext;
+use crate::span::{current_timestamp, SpanEvent, SpanKind};
+use crate::tracer::{is_recording, SimpleTracer, TracingManager, TracingResult, NOOP_SPAN_ID};
+use serde_json::Value;
+use tyl_errors::TylError;
+
+/// Started span; ends when dropped
+#[must_use = "the span ends when the handle is dropped"]
+pub struct SpanHandle<'a> {
+    tracer: &'a SimpleTracer,
+    span_id: String,
+    recording: bool,
+    ended: bool,
+}
+
+impl<'a> SpanHandle<'a> {
+    fn start(
+        tracer: &'a SimpleTracer,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> Self {
+        let span_id = tracer.start_span_with_kind(operation_name, parent_span_id, kind);
+        Self::from_started(tracer, span_id)
+    }
+
+    fn from_started(tracer: &'a SimpleTracer, span_id: TracingResult<String>) -> Self {
+        let span_id = span_id.unwrap_or_else(|_| NOOP_SPAN_ID.to_string());
+        Self {
+            tracer,
+            recording: is_recording(&span_id),
+            span_id,
+            ended: false,
+        }
+    }
+
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub(crate) fn tracer(&self) -> &'a SimpleTracer {
+        self.tracer
+    }
+
+    /// Start a child span; children of a non-recording span never record
+    pub fn child(&self, operation_name: &str) -> SpanHandle<'a> {
+        self.child_with_kind(operation_name, SpanKind::Internal)
+    }
+
+    pub fn child_with_kind(&self, operation_name: &str, kind: SpanKind) -> SpanHandle<'a> {
+        if !self.recording {
+            return Self::non_recording(self.tracer);
+        }
+        Self::start(
+            self.tracer,
+            operation_name,
+            Some(self.span_id.clone()),
+            kind,
+        )
+    }
+
+    #[inline]
+    pub fn set_attribute(&self, key: impl Into<AttributeKey>, value: impl Into<Value>) {
+        if !self.recording {
+            return;
+        }
+        let key = key.into();
+        if self.tracer.check_attribute_key(&key).is_err() {
+            return;
+        }
+        let value = value.into();
+        let _ = self.tracer.update_span(&self.span_id, |span| {
+            span.attributes.insert(key, value);
+        });
+    }
+
+    /// Set several attributes, e.g. built with the `attrs` helpers
+    pub fn set_attributes<K, V>(&self, attributes: impl IntoIterator<Item = (K, V)>)
+    where
+        K: Into<AttributeKey>,
+        V: Into<Value>,
+    {
+        if !self.recording {
+            return;
+        }
+        for (key, value) in attributes {
+            self.set_attribute(key, value);
+        }
+    }
+
+    #[inline]
+    pub fn add_event<K, V>(&self, name: &str, attributes: impl IntoIterator<Item = (K, V)>)
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        if !self.recording {
+            return;
+        }
+        let event = SpanEvent {
+            name: name.to_string(),
+            timestamp: current_timestamp(),
+            attributes: attributes
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        };
+        let _ = self.tracer.push_event(&self.span_id, event);
+    }
+
+    /// Mark the span failed with `error` (see `SimpleTracer::record_error`)
+    #[inline]
+    pub fn record_error(&self, error: &TylError) {
+        if self.recording {
+            let _ = self.tracer.record_error(&self.span_id, error);
+        }
+    }
+
+    /// Mark the span failed with `message`
+    pub fn set_error(&self, message: impl Into<String>) {
+        if self.recording {
+            let message = message.into();
+            let _ = self
+                .tracer
+                .update_span(&self.span_id, |span| span.error(message));
+        }
+    }
+
+    /// End now instead of at the end of the scope
+    pub fn end(mut self) {
+        self.finish();
+    }
+
+    fn non_recording(tracer: &'a SimpleTracer) -> Self {
+        Self {
+            tracer,
+            span_id: NOOP_SPAN_ID.to_string(),
+            recording: false,
+            ended: false,
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.recording && !self.ended {
+            let _ = self.tracer.end_span(std::mem::take(&mut self.span_id));
+        }
+        self.ended = true;
+    }
+}
+
+impl Drop for SpanHandle<'_> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl SimpleTracer {
+    /// Start a span and return its handle; the parent is the attached span, if any
+    pub fn start(&self, operation_name: &str) -> SpanHandle<'_> {
+        self.start_with_kind(operation_name, SpanKind::Internal)
+    }
+
+    pub fn start_with_kind(&self, operation_name: &str, kind: SpanKind) -> SpanHandle<'_> {
+        SpanHandle::start(self, operation_name, None, kind)
+    }
+
+    /// Start a span as the child of a context received from another process
+    pub fn start_remote(
+        &self,
+        operation_name: &str,
+        parent: &TraceContext,
+        kind: SpanKind,
+    ) -> SpanHandle<'_> {
+        let span_id = self.start_span_with_remote_parent(operation_name, parent, kind);
+        SpanHandle::from_started(self, span_id)
+    }
+}
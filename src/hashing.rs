@@ -0,0 +1,104 @@
+//! Attribute hashing module
+//!
+//! Contains AttributeHasher, which replaces the values of configured
+//! attributes (user IDs, emails, IPs, ...) with a salted HMAC-SHA256 digest
+//! before spans are exported. Equal values get equal digests, so cardinality
+//! and correlation across spans survive for debugging, while the raw value
+//! never leaves the process and cannot be recovered without the salt.
+
+use crate::span::Span;
+use crate::tracer::TracingResult;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::fmt;
+use tyl_errors::TylError;
+
+/// Minimum salt length, so digests of low-entropy values cannot be brute-forced
+pub const MIN_SALT_BYTES: usize = 16;
+/// Prefix of hashed values, marking them as digests in trace UIs
+pub const HASHED_VALUE_PREFIX: &str = "hmac:";
+/// Digest bytes kept (hex encoded): 128 bits
+const DIGEST_BYTES: usize = 16;
+
+/// Replaces configured attribute values with salted digests
+#[derive(Clone)]
+pub struct AttributeHasher {
+    salt: Vec<u8>,
+    keys: HashSet<String>,
+}
+
+impl AttributeHasher {
+    /// The salt must stay secret and stable for digests to correlate across
+    /// processes and restarts
+    pub fn new(salt: impl AsRef<[u8]>) -> TracingResult<Self> {
+        let salt = salt.as_ref();
+        if salt.len() < MIN_SALT_BYTES {
+            return Err(TylError::validation(
+                "salt",
+                format!(
+                    "salt is {} bytes, at least {} are required",
+                    salt.len(),
+                    MIN_SALT_BYTES
+                ),
+            ));
+        }
+        Ok(Self {
+            salt: salt.to_vec(),
+            keys: HashSet::new(),
+        })
+    }
+
+    /// Hash the values of these attribute keys (span and event attributes)
+    pub fn with_keys<K: Into<String>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn keys(&self) -> &HashSet<String> {
+        &self.keys
+    }
+
+    /// Digest of a value; strings are hashed by content, other values by
+    /// their JSON form
+    pub fn hash_value(&self, value: &Value) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.salt).expect("HMAC accepts keys of any length");
+        match value {
+            Value::String(text) => mac.update(text.as_bytes()),
+            other => mac.update(other.to_string().as_bytes()),
+        }
+        let digest = mac.finalize().into_bytes();
+        let hex: String = digest[..DIGEST_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("{}{}", HASHED_VALUE_PREFIX, hex)
+    }
+
+    /// Hash the configured attributes of a span and its events in place
+    pub fn apply(&self, span: &mut Span) {
+        self.apply_to(span.attributes.iter_mut());
+        for event in &mut span.events {
+            self.apply_to(event.attributes.iter_mut());
+        }
+    }
+
+    fn apply_to<'a, K: AsRef<str>>(&self, attributes: impl Iterator<Item = (K, &'a mut Value)>) {
+        for (key, value) in attributes {
+            if !value.is_null() && self.keys.contains(key.as_ref()) {
+                *value = Value::String(self.hash_value(value));
+            }
+        }
+    }
+}
+
+// The salt is never printed
+impl fmt::Debug for AttributeHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttributeHasher")
+            .field("keys", &self.keys)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,38 @@
+//! HTTP header module
+//!
+//! Contains the Injector and Extractor implementations for `http::HeaderMap`,
+//! the header type shared by hyper, tower, axum and reqwest, so any of them
+//! can carry context without a hand-written adapter.
+
+use crate::propagation::{Extractor, Injector};
+use http::header::{HeaderName, HeaderValue};
+use http::HeaderMap;
+
+/// Adapter - HTTP request/response headers as a context carrier
+///
+/// Setting a key replaces every existing value for it, so a forwarded
+/// request never carries two contexts. Invalid names or values are skipped.
+impl Injector for HeaderMap {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::try_from(value),
+        ) {
+            self.insert(name, value);
+        }
+    }
+}
+
+/// Adapter - header names are case-insensitive; when a header is repeated
+/// the first value that is valid text is returned
+impl Extractor for HeaderMap {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.get_all(key)
+            .iter()
+            .find_map(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        HeaderMap::keys(self).map(HeaderName::as_str).collect()
+    }
+}
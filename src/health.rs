@@ -0,0 +1,103 @@
+//! Health module
+//!
+//! Contains TracerHealth, a point-in-time report on the tracer and its export
+//! pipeline (exporter connectivity, last successful export, queue saturation,
+//! dropped spans) meant to back a service's readiness endpoint.
+
+use serde::{Deserialize, Serialize};
+
+/// Consecutive failed exports after which the tracer reports `Unhealthy`
+pub const UNHEALTHY_EXPORT_FAILURES: u64 = 3;
+/// Queue or active-span saturation at which the tracer reports `Degraded`
+pub const DEGRADED_SATURATION: f64 = 0.9;
+
+/// Overall verdict of a health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// Working, but exports are failing or buffers are nearly full
+    Degraded,
+    /// Exports keep failing; spans are being lost
+    Unhealthy,
+}
+
+/// State of the connection to the export backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ExporterHealth {
+    /// No exporter is configured; spans stay in memory
+    Unconfigured,
+    /// No export has been attempted yet
+    Unknown,
+    /// The last export succeeded
+    Connected,
+    /// The last `consecutive_failures` exports failed
+    Failing {
+        consecutive_failures: u64,
+        last_error: Option<String>,
+    },
+}
+
+/// Point-in-time health report of a tracer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracerHealth {
+    pub status: HealthStatus,
+    /// Whether the kill switch lets spans be recorded
+    pub enabled: bool,
+    pub exporter: ExporterHealth,
+    /// Unix ms of the last successful export
+    pub last_export_time: Option<u64>,
+    /// Spans buffered for the next export
+    pub queued_spans: usize,
+    /// `queued_spans` relative to the processor's `max_queue_size` (0.0 - 1.0)
+    pub queue_saturation: f64,
+    pub active_spans: usize,
+    /// `active_spans` relative to `max_active_spans`, when capped
+    pub active_saturation: Option<f64>,
+    /// Spans dropped by span caps, tenant quotas and a full export queue
+    pub dropped_spans: u64,
+    /// Spans lost because their export failed
+    pub failed_spans: u64,
+}
+
+impl TracerHealth {
+    /// Report for a tracer that records nothing (tracing compiled out)
+    pub fn disabled() -> Self {
+        Self {
+            status: HealthStatus::Healthy,
+            enabled: false,
+            exporter: ExporterHealth::Unconfigured,
+            last_export_time: None,
+            queued_spans: 0,
+            queue_saturation: 0.0,
+            active_spans: 0,
+            active_saturation: None,
+            dropped_spans: 0,
+            failed_spans: 0,
+        }
+    }
+
+    /// Derive `status` from the other fields
+    pub(crate) fn with_computed_status(mut self) -> Self {
+        let saturated = self.queue_saturation >= DEGRADED_SATURATION
+            || self
+                .active_saturation
+                .is_some_and(|saturation| saturation >= DEGRADED_SATURATION);
+        self.status = match &self.exporter {
+            ExporterHealth::Failing {
+                consecutive_failures,
+                ..
+            } if *consecutive_failures >= UNHEALTHY_EXPORT_FAILURES => HealthStatus::Unhealthy,
+            ExporterHealth::Failing { .. } => HealthStatus::Degraded,
+            _ if saturated => HealthStatus::Degraded,
+            _ => HealthStatus::Healthy,
+        };
+        self
+    }
+
+    /// Whether a readiness probe should pass (anything but `Unhealthy`)
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Unhealthy
+    }
+}
@@ -0,0 +1,190 @@
+//! HTTP span module
+//!
+//! Contains HttpSpan, a SpanHandle for one HTTP request that sets the
+//! `http.*`, `url.*` and `net.*` attributes of the OpenTelemetry HTTP
+//! semantic conventions and derives the span status from the response code:
+//! 5xx fails server spans, 4xx and 5xx fail client spans.
+
+use crate::handle::SpanHandle;
+use crate::propagation::{Extractor, Injector};
+use crate::span::SpanKind;
+use crate::tracer::SimpleTracer;
+
+pub const HTTP_REQUEST_METHOD: &str = "http.request.method";
+pub const HTTP_ROUTE: &str = "http.route";
+pub const HTTP_RESPONSE_STATUS_CODE: &str = "http.response.status_code";
+pub const URL_FULL: &str = "url.full";
+pub const URL_PATH: &str = "url.path";
+pub const URL_SCHEME: &str = "url.scheme";
+/// Host the server was reached as
+pub const NET_HOST_NAME: &str = "net.host.name";
+pub const NET_HOST_PORT: &str = "net.host.port";
+/// Host a client calls; picked up by the service graph
+pub const NET_PEER_NAME: &str = "net.peer.name";
+pub const NET_PEER_PORT: &str = "net.peer.port";
+pub const NET_PROTOCOL_NAME: &str = "net.protocol.name";
+pub const NET_PROTOCOL_VERSION: &str = "net.protocol.version";
+
+/// Span of one HTTP request, server or client side; ends when dropped
+#[must_use = "the span ends when the HttpSpan is dropped"]
+pub struct HttpSpan<'a> {
+    span: SpanHandle<'a>,
+    kind: SpanKind,
+}
+
+impl<'a> HttpSpan<'a> {
+    /// Span of a request handled by this service, named `"{method} {route}"`
+    ///
+    /// `route` is the matched template (`/users/{id}`), not the raw path, to
+    /// keep span names low-cardinality.
+    pub fn server(tracer: &'a SimpleTracer, method: &str, route: &str) -> Self {
+        let method = method.to_ascii_uppercase();
+        let span = tracer.start_with_kind(&format!("{} {}", method, route), SpanKind::Server);
+        span.set_attribute(HTTP_ROUTE, route);
+        Self::new(span, SpanKind::Server, method)
+    }
+
+    /// Server span continuing the caller's trace from the request `headers`
+    ///
+    /// Without context headers this is [`HttpSpan::server`]; with malformed
+    /// ones the span is a new root marked `trace.parent_malformed`.
+    pub fn server_from_headers(
+        tracer: &'a SimpleTracer,
+        method: &str,
+        route: &str,
+        headers: &dyn Extractor,
+    ) -> Self {
+        let method = method.to_ascii_uppercase();
+        let name = format!("{} {}", method, route);
+        let span = match tracer.try_extract_context(headers) {
+            Ok(Some(parent)) => tracer.start_remote(&name, &parent, SpanKind::Server),
+            Ok(None) => tracer.start_with_kind(&name, SpanKind::Server),
+            Err(error) => tracer.start_malformed_root(&name, SpanKind::Server, &error),
+        };
+        span.set_attribute(HTTP_ROUTE, route);
+        Self::new(span, SpanKind::Server, method)
+    }
+
+    /// Span of an outgoing request to `url`, named after the method
+    pub fn client(tracer: &'a SimpleTracer, method: &str, url: &str) -> Self {
+        let method = method.to_ascii_uppercase();
+        let span = tracer.start_with_kind(&method, SpanKind::Client);
+        if span.is_recording() {
+            span.set_attribute(URL_FULL, redact_credentials(url));
+            let (scheme, host, port) = split_url(url);
+            if let Some(scheme) = scheme {
+                span.set_attribute(URL_SCHEME, scheme.to_string());
+            }
+            if !host.is_empty() {
+                span.set_attribute(NET_PEER_NAME, host.to_string());
+            }
+            if let Some(port) = port {
+                span.set_attribute(NET_PEER_PORT, port);
+            }
+        }
+        Self::new(span, SpanKind::Client, method)
+    }
+
+    fn new(span: SpanHandle<'a>, kind: SpanKind, method: String) -> Self {
+        span.set_attribute(HTTP_REQUEST_METHOD, method);
+        span.set_attribute(NET_PROTOCOL_NAME, "http");
+        Self { span, kind }
+    }
+
+    /// Handle of the underlying span, e.g. to start children
+    pub fn span(&self) -> &SpanHandle<'a> {
+        &self.span
+    }
+
+    /// Write this span's context into the outgoing request's headers, in
+    /// every scheme the tracer is configured for (client side)
+    pub fn inject(&self, headers: &mut dyn Injector) {
+        self.span
+            .tracer()
+            .inject_context(self.span.span_id(), headers);
+    }
+
+    /// Request path, without the query string (server side)
+    pub fn set_path(&self, path: &str) {
+        let path = path.split(['?', '#']).next().unwrap_or(path);
+        self.span.set_attribute(URL_PATH, path.to_string());
+    }
+
+    /// Host and port the request was addressed to (server side)
+    pub fn set_host(&self, host: &str, port: u16) {
+        self.span.set_attribute(NET_HOST_NAME, host.to_string());
+        self.span.set_attribute(NET_HOST_PORT, port);
+    }
+
+    /// Protocol version, e.g. `"1.1"` or `"2"`
+    pub fn set_protocol_version(&self, version: &str) {
+        self.span
+            .set_attribute(NET_PROTOCOL_VERSION, version.to_string());
+    }
+
+    /// Record the response code and set the status it implies
+    pub fn set_status_code(&self, status_code: u16) {
+        self.span
+            .set_attribute(HTTP_RESPONSE_STATUS_CODE, status_code);
+        if is_error_status(self.kind, status_code) {
+            self.span.set_error(format!("HTTP {}", status_code));
+        }
+    }
+
+    /// End now instead of at the end of the scope
+    pub fn end(self) {
+        self.span.end();
+    }
+}
+
+/// Whether a response code fails the span, per the HTTP semantic conventions
+///
+/// Server spans fail on 5xx only, since 4xx are the client's fault; client
+/// spans fail on both. Codes outside 100-599 are always errors.
+pub fn is_error_status(kind: SpanKind, status_code: u16) -> bool {
+    match status_code {
+        100..=399 => false,
+        400..=499 => kind != SpanKind::Server,
+        500..=599 => true,
+        _ => true,
+    }
+}
+
+/// Scheme, host, and port (explicit or the scheme's default) of a URL
+fn split_url(url: &str) -> (Option<&str>, &str, Option<u16>) {
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme), rest),
+        None => (None, url),
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let (host, port) = match authority.strip_prefix('[') {
+        // IPv6 literal: [::1]:8080
+        Some(literal) => match literal.split_once(']') {
+            Some((host, rest)) => (host, rest.strip_prefix(':')),
+            None => (literal, None),
+        },
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = port.and_then(|port| port.parse().ok()).or(match scheme {
+        Some("http") => Some(80),
+        Some("https") => Some(443),
+        _ => None,
+    });
+    (scheme, host, port)
+}
+
+/// URL without the `user:password@` part, which must not be recorded
+fn redact_credentials(url: &str) -> String {
+    let start = url.find("://").map_or(0, |index| index + 3);
+    let authority_end = url[start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |index| start + index);
+    match url[start..authority_end].rfind('@') {
+        Some(at) => format!("{}{}", &url[..start], &url[start + at + 1..]),
+        None => url.to_string(),
+    }
+}
@@ -0,0 +1,276 @@
+//! Trace and span ID module
+//!
+//! Contains TraceId and SpanId, the 16- and 8-byte W3C IDs spans store.
+//! They are Copy, so passing them around never allocates; `Display` renders
+//! them as lowercase hex, the form OTLP and `traceparent` use.
+//!
+//! Also contains IdFormat, the textual form the tracer hands IDs out in:
+//! UUID strings (the default), lowercase hex, or unpadded URL-safe base64, so
+//! IDs can match an existing log-correlation scheme. IDs parse back from any
+//! format, but only at their own length: a trace ID is never taken for a
+//! span ID.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use tyl_errors::TylError;
+use uuid::Uuid;
+
+/// Bytes of a trace ID
+pub const TRACE_ID_BYTES: usize = 16;
+/// Bytes of a span ID
+pub const SPAN_ID_BYTES: usize = 8;
+
+macro_rules! id_type {
+    ($(#[$doc:meta])* $name:ident, $len:expr) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name([u8; $len]);
+
+        impl $name {
+            /// All zeros; never generated
+            pub const INVALID: Self = Self([0; $len]);
+
+            pub const fn from_bytes(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+
+            pub const fn to_bytes(self) -> [u8; $len] {
+                self.0
+            }
+
+            pub fn is_valid(&self) -> bool {
+                *self != Self::INVALID
+            }
+
+            /// Parse an ID in any IdFormat
+            ///
+            /// The decoded ID must have exactly this type's length; longer
+            /// or shorter input is rejected rather than truncated or padded.
+            pub fn parse(id: &str) -> Option<Self> {
+                let mut buffer = [0u8; 32];
+                match parse_bytes(id, &mut buffer)?.try_into() {
+                    Ok(bytes) => Some(Self(bytes)),
+                    Err(_) => Self::parse_legacy(id),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = TylError;
+
+            fn from_str(id: &str) -> Result<Self, Self::Err> {
+                Self::parse(id).ok_or_else(|| {
+                    TylError::validation(
+                        stringify!($name),
+                        format!("invalid {}: {:?}", stringify!($name), id),
+                    )
+                })
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                Self::parse(other) == Some(*self)
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                *self == **other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                *self == **other
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let id = String::deserialize(deserializer)?;
+                Self::parse(&id).ok_or_else(|| {
+                    serde::de::Error::custom(format!(
+                        "invalid {}: {:?}",
+                        stringify!($name),
+                        id
+                    ))
+                })
+            }
+        }
+    };
+}
+
+id_type!(
+    /// 16-byte trace ID
+    TraceId,
+    TRACE_ID_BYTES
+);
+
+id_type!(
+    /// 8-byte span ID
+    SpanId,
+    SPAN_ID_BYTES
+);
+
+impl TraceId {
+    /// Random, valid trace ID
+    pub fn random() -> Self {
+        let high = next_random().to_be_bytes();
+        let low = next_random().to_be_bytes();
+        let mut bytes = [0; TRACE_ID_BYTES];
+        bytes[..8].copy_from_slice(&high);
+        bytes[8..].copy_from_slice(&low);
+        Self(bytes)
+    }
+
+    /// Trace IDs have always been 16 bytes
+    fn parse_legacy(_id: &str) -> Option<Self> {
+        None
+    }
+}
+
+impl SpanId {
+    /// Random, valid span ID
+    pub fn random() -> Self {
+        Self(next_random().to_be_bytes())
+    }
+
+    /// Span IDs recorded before IDs were binary were hyphenated UUIDs; each
+    /// maps to the FNV-1a hash of its 16 bytes, so the same string always
+    /// finds the same span while two UUIDs sharing a prefix stay apart
+    fn parse_legacy(id: &str) -> Option<Self> {
+        if id.len() != 36 {
+            return None;
+        }
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let uuid = Uuid::try_parse(id).ok()?;
+        let hash = uuid.as_bytes().iter().fold(FNV_OFFSET, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        Some(Self(hash.max(1).to_be_bytes()))
+    }
+}
+
+thread_local! {
+    static RANDOM_STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish());
+}
+
+/// splitmix64 over a per-thread seed; never zero
+///
+/// IDs need uniqueness, not unpredictability, and a syscall per ID (as UUID
+/// v4 makes) is a measurable cost at high span rates.
+fn next_random() -> u64 {
+    RANDOM_STATE.with(|state| {
+        let next = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state.set(next);
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)).max(1)
+    })
+}
+
+/// Decode `id` into `buffer`, returning the decoded part
+fn parse_bytes<'b>(id: &str, buffer: &'b mut [u8; 32]) -> Option<&'b [u8]> {
+    if let Ok(uuid) = Uuid::try_parse(id) {
+        buffer[..16].copy_from_slice(uuid.as_bytes());
+        return Some(&buffer[..16]);
+    }
+    if id.len() % 2 == 0 && id.len() <= 2 * buffer.len() {
+        if let Some(len) = decode_hex(id, buffer) {
+            return Some(&buffer[..len]);
+        }
+    }
+    let len = URL_SAFE_NO_PAD.decode_slice(id, buffer).ok()?;
+    Some(&buffer[..len])
+}
+
+fn decode_hex(id: &str, buffer: &mut [u8]) -> Option<usize> {
+    let digits = id.as_bytes();
+    for (index, pair) in digits.chunks(2).enumerate() {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        buffer[index] = (high << 4 | low) as u8;
+    }
+    Some(digits.len() / 2)
+}
+
+/// Textual form of the IDs a tracer hands out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdFormat {
+    /// `8-4-4-4-12` UUID strings for trace IDs; span IDs, which are too short
+    /// for a UUID, as hex
+    #[default]
+    Uuid,
+    /// 32 hex digits for trace IDs, 16 for span IDs
+    Hex,
+    /// URL-safe base64 without padding: 22 characters for trace IDs, 11 for
+    /// span IDs
+    Base64,
+}
+
+impl IdFormat {
+    pub fn render_trace_id(self, id: TraceId) -> String {
+        self.render(&id.to_bytes())
+    }
+
+    pub fn render_span_id(self, id: SpanId) -> String {
+        self.render(&id.to_bytes())
+    }
+
+    /// Render raw ID bytes; UUID form needs 16 bytes, other lengths fall back
+    /// to hex
+    pub fn render(self, bytes: &[u8]) -> String {
+        match self {
+            IdFormat::Uuid => match Uuid::from_slice(bytes) {
+                Ok(uuid) => uuid.to_string(),
+                Err(_) => to_hex(bytes),
+            },
+            IdFormat::Hex => to_hex(bytes),
+            IdFormat::Base64 => URL_SAFE_NO_PAD.encode(bytes),
+        }
+    }
+}
+
+/// Raw bytes of an ID in any IdFormat, or `None` if it is in none of them
+pub fn parse_id(id: &str) -> Option<Vec<u8>> {
+    let mut buffer = [0u8; 32];
+    parse_bytes(id, &mut buffer).map(<[u8]>::to_vec)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,97 @@
+//! In-flight report module
+//!
+//! Contains InFlightReport, a summary of the spans that are open right now
+//! grouped by operation, with how many there are and how long the oldest has
+//! been running. Serializable so a debug endpoint can return it as JSON when
+//! the question is "what is this service stuck on?".
+
+use crate::ids::SpanId;
+use crate::span::current_timestamp;
+use crate::tracer::SimpleTracer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Open spans of one operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationInFlight {
+    pub operation_name: String,
+    pub count: usize,
+    /// Age of the oldest open span
+    pub max_age_ms: u64,
+    pub oldest_span_id: SpanId,
+}
+
+/// Open spans grouped by operation, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightReport {
+    /// When the report was taken, in milliseconds since the Unix epoch
+    pub taken_at: u64,
+    pub total: usize,
+    pub operations: Vec<OperationInFlight>,
+}
+
+impl InFlightReport {
+    pub fn get(&self, operation_name: &str) -> Option<&OperationInFlight> {
+        self.operations
+            .iter()
+            .find(|operation| operation.operation_name == operation_name)
+    }
+}
+
+impl fmt::Display for InFlightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} span(s) in flight", self.total)?;
+        for operation in &self.operations {
+            writeln!(
+                f,
+                "  {:>5} x {} (oldest {}ms, {})",
+                operation.count,
+                operation.operation_name,
+                operation.max_age_ms,
+                operation.oldest_span_id
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Active spans grouped by operation, longest-running operation first
+    pub fn in_flight_report(&self) -> InFlightReport {
+        let now = current_timestamp();
+        let mut operations: HashMap<String, OperationInFlight> = HashMap::new();
+        let active_spans = self.active_spans.lock().unwrap();
+        for span in active_spans.values() {
+            let age = now.saturating_sub(span.start_time);
+            let entry = operations
+                .entry(span.operation_name.clone())
+                .or_insert_with(|| OperationInFlight {
+                    operation_name: span.operation_name.clone(),
+                    count: 0,
+                    max_age_ms: age,
+                    oldest_span_id: span.span_id,
+                });
+            entry.count += 1;
+            if age > entry.max_age_ms {
+                entry.max_age_ms = age;
+                entry.oldest_span_id = span.span_id;
+            }
+        }
+        let total = active_spans.len();
+        drop(active_spans);
+
+        let mut operations: Vec<_> = operations.into_values().collect();
+        operations.sort_by(|a, b| {
+            b.max_age_ms
+                .cmp(&a.max_age_ms)
+                .then_with(|| a.operation_name.cmp(&b.operation_name))
+        });
+        InFlightReport {
+            taken_at: now,
+            total,
+            operations,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Batch job tracing module
+//!
+//! Contains JobTrace, a root span for non-request workloads such as cron runs
+//! and nightly batch jobs: schedule metadata on the root, `checkpoint` events
+//! for progress, sampled child spans per processed item, and a completion
+//! summary (items processed and failed, overall outcome) when the job ends.
+
+use crate::span::{current_timestamp, SpanEvent, SpanStatus};
+use crate::tracer::{SimpleTracer, TracingManager, NOOP_SPAN_ID};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const JOB_NAME_ATTRIBUTE: &str = "job.name";
+/// Schedule the run was started from, e.g. a cron expression
+pub const JOB_SCHEDULE_ATTRIBUTE: &str = "job.schedule";
+pub const JOB_RUN_ID_ATTRIBUTE: &str = "job.run_id";
+pub const JOB_ITEMS_ATTRIBUTE: &str = "job.items.processed";
+pub const JOB_FAILED_ITEMS_ATTRIBUTE: &str = "job.items.failed";
+/// [`JobOutcome`] of the run
+pub const JOB_OUTCOME_ATTRIBUTE: &str = "job.outcome";
+/// Name of checkpoint events; the checkpoint name is in `job.checkpoint`
+pub const CHECKPOINT_EVENT: &str = "checkpoint";
+pub const CHECKPOINT_ATTRIBUTE: &str = "job.checkpoint";
+
+/// Overall result of a job run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    /// Every item succeeded
+    Succeeded,
+    /// Some items failed
+    PartiallyFailed,
+    /// The job was failed explicitly, or every item failed
+    Failed,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::PartiallyFailed => "partially_failed",
+            JobOutcome::Failed => "failed",
+        }
+    }
+}
+
+/// Counts of a finished job run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSummary {
+    pub items: u64,
+    pub failed_items: u64,
+    /// Items that got their own child span
+    pub traced_items: u64,
+    pub outcome: JobOutcome,
+}
+
+/// Root span of one job run; ends with a summary when finished or dropped
+///
+/// Items may be processed from several threads through `&JobTrace`.
+#[must_use = "the job span ends when the JobTrace is dropped"]
+pub struct JobTrace<'a> {
+    tracer: &'a SimpleTracer,
+    span_id: String,
+    item_sample_rate: f64,
+    items: AtomicU64,
+    failed_items: AtomicU64,
+    traced_items: AtomicU64,
+    failure: Option<String>,
+    finished: bool,
+}
+
+impl<'a> JobTrace<'a> {
+    /// Start the root span of a run of job `name`
+    pub fn start(tracer: &'a SimpleTracer, name: &str) -> Self {
+        let span_id = tracer
+            .start_span(name, None)
+            .unwrap_or_else(|_| NOOP_SPAN_ID.to_string());
+        let job = Self {
+            tracer,
+            span_id,
+            item_sample_rate: 1.0,
+            items: AtomicU64::new(0),
+            failed_items: AtomicU64::new(0),
+            traced_items: AtomicU64::new(0),
+            failure: None,
+            finished: false,
+        };
+        job.set_attribute(JOB_NAME_ATTRIBUTE, name.into());
+        job
+    }
+
+    pub fn with_schedule(self, schedule: &str) -> Self {
+        self.set_attribute(JOB_SCHEDULE_ATTRIBUTE, schedule.into());
+        self
+    }
+
+    pub fn with_run_id(self, run_id: &str) -> Self {
+        self.set_attribute(JOB_RUN_ID_ATTRIBUTE, run_id.into());
+        self
+    }
+
+    /// Fraction of items (0.0 - 1.0) that get a child span; evenly spread,
+    /// and failed items are counted either way
+    pub fn with_item_sample_rate(mut self, rate: f64) -> Self {
+        self.item_sample_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    /// Record a `checkpoint` event with the items processed so far
+    pub fn checkpoint(&self, name: &str, mut attributes: HashMap<String, Value>) {
+        attributes.insert(CHECKPOINT_ATTRIBUTE.to_string(), name.into());
+        attributes.insert(
+            JOB_ITEMS_ATTRIBUTE.to_string(),
+            self.items.load(Ordering::Relaxed).into(),
+        );
+        let event = SpanEvent {
+            name: CHECKPOINT_EVENT.to_string(),
+            timestamp: current_timestamp(),
+            attributes,
+        };
+        let _ = self.tracer.push_event(&self.span_id, event);
+    }
+
+    /// Process one item, in a child span `name` if the item is sampled
+    pub fn item<R, E: Display>(
+        &self,
+        name: &str,
+        process: impl FnOnce() -> Result<R, E>,
+    ) -> Result<R, E> {
+        let index = self.items.fetch_add(1, Ordering::Relaxed);
+        let span_id = if self.sample_item(index) {
+            self.traced_items.fetch_add(1, Ordering::Relaxed);
+            self.tracer
+                .start_span(name, Some(self.span_id.clone()))
+                .ok()
+        } else {
+            None
+        };
+
+        let result = process();
+
+        if let Err(error) = &result {
+            self.failed_items.fetch_add(1, Ordering::Relaxed);
+            if let Some(span_id) = &span_id {
+                let message = error.to_string();
+                let _ = self.tracer.update_span(span_id, |span| {
+                    span.status = SpanStatus::Error { message };
+                });
+            }
+        }
+        if let Some(span_id) = span_id {
+            let _ = self.tracer.end_span(span_id);
+        }
+        result
+    }
+
+    /// Mark the whole run failed
+    pub fn fail(mut self, message: impl Into<String>) -> JobSummary {
+        self.failure = Some(message.into());
+        self.complete()
+    }
+
+    /// End the job span with its summary
+    pub fn finish(mut self) -> JobSummary {
+        self.complete()
+    }
+
+    /// Even spread: item `n` is sampled when `n * rate` crosses an integer
+    fn sample_item(&self, index: u64) -> bool {
+        ((index + 1) as f64 * self.item_sample_rate).floor()
+            > (index as f64 * self.item_sample_rate).floor()
+    }
+
+    fn set_attribute(&self, key: &str, value: Value) {
+        let _ = self.tracer.add_span_attribute(&self.span_id, key, value);
+    }
+
+    fn complete(&mut self) -> JobSummary {
+        self.finished = true;
+        let items = self.items.load(Ordering::Relaxed);
+        let failed_items = self.failed_items.load(Ordering::Relaxed);
+        let outcome = if self.failure.is_some() || (items > 0 && failed_items == items) {
+            JobOutcome::Failed
+        } else if failed_items > 0 {
+            JobOutcome::PartiallyFailed
+        } else {
+            JobOutcome::Succeeded
+        };
+
+        self.set_attribute(JOB_ITEMS_ATTRIBUTE, items.into());
+        self.set_attribute(JOB_FAILED_ITEMS_ATTRIBUTE, failed_items.into());
+        self.set_attribute(JOB_OUTCOME_ATTRIBUTE, outcome.as_str().into());
+        if outcome != JobOutcome::Succeeded {
+            let message = self
+                .failure
+                .take()
+                .unwrap_or_else(|| format!("{} of {} items failed", failed_items, items));
+            let _ = self.tracer.update_span(&self.span_id, |span| {
+                span.status = SpanStatus::Error { message };
+            });
+        }
+        let _ = self.tracer.end_span(std::mem::take(&mut self.span_id));
+
+        JobSummary {
+            items,
+            failed_items,
+            traced_items: self.traced_items.load(Ordering::Relaxed),
+            outcome,
+        }
+    }
+}
+
+impl Drop for JobTrace<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.complete();
+        }
+    }
+}
@@ -8,8 +8,87 @@
 //! - OpenTelemetry integration for production (optional)
 //! - Hexagonal architecture with ports and adapters
 //! - Span correlation and context propagation
-//! - Multiple output formats (JSON, pretty-print)
+//! - Multiple output formats (JSON, pretty-print, `format_trace` span trees)
 //! - Async/await support
+//! - `disabled` cargo feature that compiles tracing down to no-ops
+//! - Service dependency graphs built from completed spans
+//! - Trace comparison for catching duration regressions between runs
+//! - Online duration anomaly detection with alert callbacks
+//! - Pluggable span exporters with retry, backoff, circuit breaking, disk spill,
+//!   and dead-letter files
+//! - Optional gzip/zstd compression of export payloads
+//! - `tls` cargo feature: https OTLP endpoints with CA bundles and mTLS client certificates
+//! - Batched export with size threshold and periodic flush interval
+//! - Trace-ID ratio sampling and `TYL_TRACE_FILTER` per-operation filtering
+//! - Hot config reload from a watched config file
+//! - `Tracer::from_config` factory wiring console or OTLP export from config
+//! - Multi-tenant tracing with per-tenant sampling rates and span quotas
+//! - Span events: progress heartbeats and fine-grained section timing
+//! - `cpu-time` cargo feature recording per-span thread CPU time
+//! - Opt-in `TracingAllocator` recording allocations per span
+//! - `tokio-metrics` cargo feature attaching runtime saturation diffs to spans
+//! - Per-thread current span context, carried into `thread::spawn_traced` workers
+//!   and (with the `rayon` feature) into rayon parallel sections
+//! - `Traced<T>` channel messages that carry the sender's context to the receiver
+//! - Executor-agnostic async context (`FutureContextExt`) for tokio, async-std, and smol
+//! - `embedded` cargo feature: a `no_std` + `alloc` span core for firmware
+//! - MessagePack and CBOR span encodings (`msgpack` / `cbor` features)
+//! - Arrow record batches and Parquet export of spans for analytics (`arrow` /
+//!   `parquet` features)
+//! - Conversion to and from OpenTelemetry `SpanData` (`otel` feature)
+//! - `health()` reports on the export pipeline for readiness probes
+//! - `TracingAdmin` for live tuning (sampling, filter, flush, kill switch) from admin endpoints
+//! - SIGUSR1 dump of active spans for hung services (`unix` feature)
+//! - Crash reports of active and unexported spans on panics and fatal signals
+//! - Backpressure signal and `is_saturated()` for shedding optional spans under load
+//! - `record_error` with the full error source chain as an `exception` event
+//! - `retry_traced` recording each retry attempt as a span event
+//! - `semconv` helpers for OpenTelemetry conventions (feature-flag evaluations, cache get/set)
+//! - GraphQL operation and per-resolver spans via an async-graphql extension (`graphql` feature)
+//! - `JobTrace` root spans for batch and cron runs with checkpoints and sampled items
+//! - `trace_main` root span for CLI tools, with exit code and flush on exit
+//! - `StartupTrace` startup-phase spans under a process root span, for slow cold starts
+//! - `init(config)` picking the adapter stack for the environment as a `DynTracer`
+//! - `child_scope` tracer views stamping component/shard attributes on their spans
+//! - Salted hashing of sensitive attribute values before export (`AttributeHasher`)
+//! - `purge_spans` / `purge_by_attribute` for data-subject deletion requests
+//! - Time-based retention (`with_retention`) pruning old completed spans in the background
+//! - Time-bucketed completed-span store with `completed_spans_between` range queries
+//! - Trace-ID index behind `get_trace`
+//! - Inline attribute storage: spans with up to 8 attributes allocate no attribute map
+//! - Synthetic workload generator (`Workload`) reporting throughput, latency and drops
+//! - Sampling decided before a span is allocated; unsampled traces stay on the non-recording path
+//! - `SpanHandle` guards whose calls are free when the span is not recording
+//! - Whole-trace export on root span end (`with_export_whole_traces`)
+//! - Span-end observers (`register_on_end`) for enrichment and metrics
+//! - Optional attribute key lints against the OTel naming rules
+//! - `HttpSpan` server/client helpers following the HTTP semantic conventions
+//! - `DbSpan` database helper with statement sanitization
+//! - `MessagingSpan` producer/consumer helpers linking batches to their producers
+//! - W3C `traceparent` propagation through `Injector`/`Extractor` carriers
+//! - Unit-aware `attrs::duration`/`attrs::bytes` helpers with canonical key suffixes
+//! - `TraceTree` parent/child index with per-span self time
+//! - `in_flight_report` of open spans grouped by operation with their max age
+//! - Configurable ID format (UUID, hex, base64) via `TraceConfig::with_id_format`
+//! - Binary `TraceId`/`SpanId` storage: Copy IDs that render as hex
+//! - Single-string context tokens (`TraceContext::to_token`) for deferred work
+//! - Fixed-size binary context encoding with optional baggage (`encode_context`)
+//! - gRPC metadata carrier, including binary `grpc-trace-bin` (`grpc` feature)
+//! - `http::HeaderMap` carrier for hyper/tower/axum/reqwest (`http` feature)
+//! - Request-scoped baggage from the incoming `baggage` header (`Context::from_request`)
+//! - Composite propagators emitting and accepting W3C, B3 and baggage headers at once
+//! - Malformed incoming context reported as `PropagationError` (`try_extract`)
+//! - Remote parents (`SimpleTracer::start_remote`); see `examples/distributed/`
+//! - Local OTLP collector stub for integration tests (`testing::OtlpStub`)
+//! - proptest/quickcheck `Arbitrary` spans and trace contexts (`testing` feature)
+//! - `DecodeLimits` resource caps for spans decoded from untrusted files and payloads
+//! - Deterministic trace snapshots for golden-file tests (`testing::snapshot`)
+//! - `assert_trace!` hierarchy assertions with a line diff on mismatch
+//! - `TestTracer` on a `ManualClock` and `assert_span_duration!` latency budgets (`testing` feature)
+//! - Span-derived counters and duration histograms through a `MetricsRecorder` facade port
+//! - Tracer self-metrics in OpenMetrics text (`render_metrics`) for an existing `/metrics` route
+//! - Opt-in sampling audit log (`set_sampling_audit`) answering why a trace is missing
+//! - Dropped spans counted by reason, with an optional periodic summary warning
 //!
 //! ## Quick Start
 //!
@@ -33,6 +112,17 @@
 //! - **Port (Interface)**: `TracingManager` - defines the tracing contract
 //! - **Adapters**:
 //!   - `SimpleTracer` - In-memory tracing for development
+//!   - `NoopTracer` - Records nothing (zero-sized); with the `disabled`
+//!     feature `SimpleTracer` is zero-sized and records nothing as well
+//!   - `InMemoryExporter` - Collects exported spans (`SpanExporter` port)
+//!   - `ConsoleExporter` - Pretty-printed spans on stdout
+//!   - `FileExporter` - JSON-lines span files with optional compression
+//!   - `GraphQLTracing` - async-graphql operation and resolver spans (optional)
+//!   - `MqttExporter` - Span batches published to an MQTT topic (QoS 0-2)
+//!   - `OtlpHttpExporter` - OTLP/HTTP JSON export to a collector
+//!   - `SyslogExporter` - One-line span records to syslog or the systemd journal (Unix)
+//!   - `TempoExporter` - OTLP export to multi-tenant Grafana Tempo (`X-Scope-OrgID`)
+//!   - `UdpExporter` - Fire-and-forget span datagrams (JSON, MessagePack, CBOR)
 //!   - `OpenTelemetryTracer` - Production tracing with OTLP (optional)
 //! - **Domain Logic**: Span management and correlation
 //!
@@ -40,19 +130,224 @@
 //!
 //! See the `examples/` directory for complete usage examples.
 
+// The recording internals are unused when `disabled` swaps in the empty tracer
+#![cfg_attr(feature = "disabled", allow(dead_code, unused_imports))]
+
 // Module declarations
+// Renamed because `alloc` is the allocation tracking module
+#[cfg(feature = "embedded")]
+extern crate alloc as alloc_crate;
+
+pub mod admin;
+pub mod alloc;
+pub mod anomaly;
+#[cfg(feature = "testing")]
+mod arbitrary;
+pub mod attributes;
+pub mod attrs;
+pub mod backpressure;
+pub mod baggage;
+pub mod bench;
+pub mod binary_context;
+pub mod channel;
+pub mod cli;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+pub mod codec;
+#[cfg(feature = "arrow")]
+pub mod columnar;
+pub mod compression;
 pub mod config;
+pub mod context;
+#[cfg(feature = "cpu-time")]
+pub mod cpu;
+pub mod crash;
+pub mod db_span;
+pub mod diff;
+#[cfg(feature = "disabled")]
+mod disabled;
+pub mod drops;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+pub mod exception;
+pub mod export;
+pub mod filter;
+pub mod format;
+pub mod future;
+pub mod graph;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod handle;
+pub mod hashing;
+#[cfg(feature = "http")]
+pub mod headers;
+pub mod health;
+pub mod http_span;
+pub mod ids;
+pub mod inflight;
+pub mod job;
+pub mod limits;
+pub mod lint;
+pub mod messaging_span;
+pub mod metrics;
+pub mod mqtt;
+pub mod observer;
+pub mod openmetrics;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod otlp;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod propagation;
+pub mod propagator;
+pub mod purge;
+pub mod reload;
+mod retention;
+pub mod retry;
+#[cfg(feature = "tokio-metrics")]
+pub mod runtime_metrics;
+pub mod sampling;
+pub mod sampling_audit;
+pub mod scope;
+pub mod section;
+pub mod semconv;
+#[cfg(all(unix, feature = "unix"))]
+pub mod signal;
 pub mod span;
+pub mod startup;
+mod store;
+#[cfg(unix)]
+pub mod syslog;
+pub mod tenant;
+pub mod testing;
+pub mod thread;
+#[cfg(feature = "tls")]
+mod tls;
 pub mod tracer;
+pub mod tree;
+pub mod udp;
 
 // Re-exports for public API
-pub use config::{Environment, TraceConfig};
-pub use span::{generate_span_id, generate_trace_id, Span, SpanStatus};
-pub use tracer::{SimpleTracer, TracingManager, TracingResult};
+pub use admin::TracingAdmin;
+pub use alloc::{
+    thread_allocations, AllocationStats, TracingAllocator, ALLOC_BYTES_ATTRIBUTE,
+    ALLOC_COUNT_ATTRIBUTE,
+};
+pub use anomaly::{Anomaly, AnomalyDetector};
+pub use attributes::{AttributeKey, Attributes, INLINE_ATTRIBUTES};
+pub use backpressure::{BackpressureEvent, BackpressureSignal, BackpressureSource};
+pub use baggage::{
+    parse_baggage, validate_baggage_entry, BAGGAGE_HEADER, MAX_BAGGAGE_ENTRIES,
+    MAX_BAGGAGE_ENTRY_BYTES, MAX_BAGGAGE_TOTAL_BYTES,
+};
+pub use bench::{BenchReport, LatencySummary, Workload};
+pub use binary_context::{
+    decode_context, encode_context, BINARY_CONTEXT_LEN, BINARY_CONTEXT_VERSION,
+};
+pub use channel::{Received, Traced};
+pub use cli::{trace_main, ExitStatus, EXIT_CODE_ATTRIBUTE};
+#[cfg(feature = "cbor")]
+pub use codec::{spans_from_cbor, spans_from_cbor_with_limits, spans_to_cbor};
+#[cfg(feature = "msgpack")]
+pub use codec::{spans_from_msgpack, spans_from_msgpack_with_limits, spans_to_msgpack};
+#[cfg(feature = "arrow")]
+pub use columnar::{span_schema, to_arrow};
+#[cfg(feature = "parquet")]
+pub use columnar::{write_parquet, ParquetExporter};
+pub use compression::Compression;
+pub use config::{
+    Environment, ExporterAuth, ExporterConfig, ExporterKind, ExporterProtocol, TlsConfig,
+    TraceConfig, TraceConfigBuilder,
+};
+pub use context::{current_span_id, Context, ContextGuard};
+#[cfg(feature = "cpu-time")]
+pub use cpu::{thread_cpu_time_ns, CPU_TIME_ATTRIBUTE, CPU_UTILIZATION_ATTRIBUTE};
+pub use crash::{install_crash_handler, install_crash_handler_to, SIGNAL_SNAPSHOT_INTERVAL};
+pub use db_span::DbSpan;
+pub use diff::{SpanDelta, TraceDiff};
+pub use drops::DropCounts;
+#[cfg(feature = "embedded")]
+pub use embedded::{Clock, CoreLimits, CoreSpan, CoreTracer, CoreValue, IdSource, XorShiftIds};
+pub use exception::{
+    error_category, exception_attributes, ERROR_CATEGORY_ATTRIBUTE, EXCEPTION_CHAIN_ATTRIBUTE,
+    EXCEPTION_EVENT,
+};
+pub use export::{
+    reexport_dead_letters, BatchSpanProcessor, CircuitBreakerExporter, CircuitState,
+    ConsoleExporter, DeadLetter, DeadLetterExporter, FileExporter, InMemoryExporter, RetryPolicy,
+    RetryingExporter, SpanExporter, SpillSink, SpillingExporter,
+};
+pub use filter::{FilterAction, FilterDirective, SpanFilter};
+pub use format::{format_trace, AttributeSelection, DurationUnit, FormatOptions};
+pub use future::{FutureContextExt, WithContext};
+pub use graph::{ServiceEdge, ServiceGraph};
+#[cfg(feature = "graphql")]
+pub use graphql::GraphQLTracing;
+#[cfg(feature = "grpc")]
+pub use grpc::GRPC_TRACE_BIN_HEADER;
+pub use handle::SpanHandle;
+pub use hashing::AttributeHasher;
+pub use health::{ExporterHealth, HealthStatus, TracerHealth};
+pub use http_span::HttpSpan;
+pub use ids::{parse_id, IdFormat, SpanId, TraceId};
+pub use inflight::{InFlightReport, OperationInFlight};
+pub use job::{JobOutcome, JobSummary, JobTrace};
+pub use limits::DecodeLimits;
+pub use lint::{check_attribute_key, KeyValidation, KeyViolation};
+pub use messaging_span::MessagingSpan;
+pub use metrics::{record_span_metrics, MetricsRecorder, SPANS_ENDED_METRIC, SPAN_DURATION_METRIC};
+pub use mqtt::{MqttExporter, MqttQos};
+pub use observer::SpanObserver;
+pub use openmetrics::OPENMETRICS_CONTENT_TYPE;
+pub use otlp::{to_otlp_json, OtlpHttpExporter, TempoExporter, TEMPO_ORG_HEADER};
+#[cfg(feature = "rayon")]
+pub use parallel::{for_each_traced, in_span_scope, BATCH_ITEMS_ATTRIBUTE};
+pub use propagation::{
+    Extractor, Injector, PropagationError, TraceContext, SERVER_TIMING_HEADER, TRACEPARENT_HEADER,
+    TRACERESPONSE_HEADER,
+};
+pub use propagator::{
+    CompositePropagator, Propagator, B3_FLAGS_HEADER, B3_HEADER, B3_SAMPLED_HEADER,
+    B3_SPAN_ID_HEADER, B3_TRACE_ID_HEADER, DEFAULT_PROPAGATORS, PARENT_MALFORMED_ATTRIBUTE,
+};
+pub use reload::{ConfigWatcher, WatchHandle};
+pub use retry::{
+    RETRY_ATTEMPTS_ATTRIBUTE, RETRY_ATTEMPT_ATTRIBUTE, RETRY_DELAY_ATTRIBUTE,
+    RETRY_ERROR_ATTRIBUTE, RETRY_EVENT,
+};
+#[cfg(feature = "tokio-metrics")]
+pub use runtime_metrics::{RuntimeSnapshot, TOKIO_WORKERS_ATTRIBUTE};
+pub use sampling::{
+    parse_sampling_priority, should_sample, should_sample_trace, SAMPLING_PRIORITY_KEY,
+};
+pub use sampling_audit::{SamplingDecision, SamplingRule};
+pub use scope::ScopedTracer;
+pub use section::{SectionTimer, SECTION_DURATION_ATTRIBUTE};
+#[cfg(all(unix, feature = "unix"))]
+pub use signal::{dump_active_spans, install_dump_handler, DumpTarget};
+pub use span::{
+    generate_span_id, generate_trace_id, Span, SpanEvent, SpanKind, SpanLink, SpanStatus,
+    HEARTBEAT_EVENT,
+};
+pub use startup::StartupTrace;
+#[cfg(unix)]
+pub use syslog::{SyslogDestination, SyslogExporter};
+pub use tenant::{TenantConfig, TenantTracer, TENANT_ID_ATTRIBUTE};
+pub use tracer::{
+    init, is_recording, DroppedSpanCounts, DynTracer, NoopTracer, SimpleTracer, Tracer,
+    TracingManager, TracingResult, DROPPED_CHILDREN_ATTRIBUTE, NOOP_SPAN_ID,
+};
+pub use tree::TraceTree;
+pub use udp::{DatagramEncoding, UdpExporter};
 
+// These exercise recording: with the `disabled` feature they still compile
+// (call sites are unchanged), but only `disabled_tests` is expected to pass
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use tyl_errors::TylError;
 
     #[test]
     fn test_span_creation() {
@@ -102,6 +397,56 @@ mod tests {
         assert_eq!(config.max_spans, 500);
     }
 
+    #[test]
+    fn test_trace_config_builder_reports_all_problems() {
+        let config = TraceConfig::builder("checkout")
+            .with_environment(Environment::Testing)
+            .with_sampling_rate(0.25)
+            .build()
+            .unwrap();
+        assert_eq!(config.sampling_rate, 0.25);
+
+        let builder = TraceConfig::builder("")
+            .with_sampling_rate(1.5)
+            .with_max_spans(0)
+            .with_filter("db.*=lots");
+        let errors = builder.clone().build_unchecked().validation_errors();
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(builder.build(), Err(TylError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_trace_attributes_applied_to_all_spans() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("request", None).unwrap();
+        let child = tracer.start_span("query", Some(root.clone())).unwrap();
+
+        let trace_id = tracer.trace_id(&root).unwrap();
+        tracer
+            .set_trace_attribute(&trace_id, "customer.tier", serde_json::json!("gold"))
+            .unwrap();
+        tracer
+            .add_span_attribute(&child, "customer.tier", serde_json::json!("override"))
+            .unwrap();
+        let late_child = tracer.start_span("render", Some(root.clone())).unwrap();
+
+        tracer.end_span(child).unwrap();
+        tracer.end_span(late_child).unwrap();
+        tracer.end_span(root).unwrap();
+
+        let tiers: Vec<_> = tracer
+            .get_completed_spans()
+            .iter()
+            .map(|span| span.attributes["customer.tier"].clone())
+            .collect();
+        assert_eq!(tiers, vec!["override", "gold", "gold"]);
+
+        // Released once the trace has no active spans
+        assert!(tracer
+            .set_trace_attribute(&trace_id, "late", serde_json::json!(true))
+            .is_err());
+    }
+
     #[test]
     fn test_baggage_operations() {
         let tracer = SimpleTracer::default();
@@ -114,6 +459,1158 @@ mod tests {
         assert_eq!(tracer.get_baggage("nonexistent"), None);
     }
 
+    #[test]
+    fn test_baggage_copied_to_span_attributes() {
+        let config =
+            TraceConfig::new("test-service").with_baggage_attributes(["user_id", "region"]);
+        let tracer = SimpleTracer::new(config);
+        tracer.set_baggage("user_id", "user456");
+        tracer.set_baggage("session_token", "secret");
+
+        let span_id = tracer.start_span("checkout", None).unwrap();
+        tracer
+            .add_span_attribute(&span_id, "region", serde_json::json!("eu-west-1"))
+            .unwrap();
+        tracer.end_span(span_id).unwrap();
+
+        let span = &tracer.get_completed_spans()[0];
+        assert_eq!(span.attributes["user_id"], "user456");
+        assert_eq!(span.attributes["region"], "eu-west-1");
+        assert!(!span.attributes.contains_key("session_token"));
+    }
+
+    #[test]
+    fn test_baggage_limits() {
+        let tracer = SimpleTracer::default();
+
+        let error = tracer.try_set_baggage("user id", "42").unwrap_err();
+        assert!(error.to_string().contains("HTTP tokens"));
+        assert!(tracer
+            .try_set_baggage("note", "line\nbreak")
+            .unwrap_err()
+            .to_string()
+            .contains("control characters"));
+        assert!(tracer
+            .try_set_baggage("blob", &"x".repeat(MAX_BAGGAGE_ENTRY_BYTES))
+            .is_err());
+
+        for i in 0..MAX_BAGGAGE_ENTRIES {
+            tracer.try_set_baggage(&format!("k{}", i), "v").unwrap();
+        }
+        tracer.set_baggage("one-too-many", "v");
+        assert_eq!(tracer.get_baggage("one-too-many"), None);
+        assert_eq!(tracer.rejected_baggage_entries(), 1);
+        // Replacing an existing entry does not count against the entry limit
+        tracer.try_set_baggage("k0", "updated").unwrap();
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        let limits = DecodeLimits::new()
+            .with_max_attributes(2)
+            .with_max_string_len(16)
+            .with_max_depth(2);
+        let mut span = Span::new("op".to_string(), None);
+        span.attributes.insert("a", serde_json::json!([1, [2]]));
+        let json = serde_json::to_vec(&span).unwrap();
+        assert_eq!(limits.span_from_json(&json).unwrap(), span);
+
+        let rejects = |span: &Span, reason: &str| {
+            let json = serde_json::to_vec(span).unwrap();
+            let error = limits.span_from_json(&json).unwrap_err().to_string();
+            assert!(error.contains(reason), "{} lacks {:?}", error, reason);
+        };
+        let mut deep = span.clone();
+        deep.attributes
+            .insert("a", serde_json::json!([[[["deep"]]]]));
+        rejects(&deep, "nests deeper than 2");
+        let mut wide = span.clone();
+        wide.attributes.insert("b", serde_json::json!(1));
+        wide.attributes.insert("c", serde_json::json!(1));
+        rejects(&wide, "3 attributes exceed the limit of 2");
+        let mut long = span.clone();
+        long.operation_name = "x".repeat(17);
+        rejects(&long, "operation_name of 17 bytes");
+        let mut events = span.clone();
+        events.add_event("e".repeat(17), std::collections::HashMap::new());
+        rejects(&events, "event name");
+
+        let small = DecodeLimits::new().with_max_input_bytes(8);
+        assert!(small.span_from_json(&json).is_err());
+        let batch = serde_json::to_vec(&vec![span.clone(), span.clone()]).unwrap();
+        assert!(limits.with_max_spans(1).spans_from_json(&batch).is_err());
+        assert_eq!(limits.spans_from_json(&batch).unwrap().len(), 2);
+        assert!(limits.span_from_json(b"{not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_baggage_header() {
+        let baggage = parse_baggage("user_id=alice, note=a%20b%2Cc;prop=1,broken,bad key=x,k=%ff");
+        assert_eq!(baggage.len(), 2);
+        assert_eq!(baggage["user_id"], "alice");
+        assert_eq!(baggage["note"], "a b,c");
+        assert!(parse_baggage("").is_empty());
+    }
+
+    #[test]
+    fn test_request_scoped_baggage() {
+        let config = TraceConfig::new("test-service").with_baggage_attributes(["user_id"]);
+        let tracer = SimpleTracer::new(config);
+        tracer.set_baggage("user_id", "global");
+        let mut headers = std::collections::HashMap::new();
+        headers.insert("Baggage".to_string(), "user_id=alice".to_string());
+
+        {
+            let _request = Context::from_request(&headers).attach();
+            assert_eq!(tracer.get_baggage("user_id").as_deref(), Some("alice"));
+            let root = tracer.start_span("handle_request", None).unwrap();
+            {
+                // Nested contexts keep the request's baggage
+                let _guard = Context::with_span(root.clone()).attach();
+                assert_eq!(Context::current().baggage("user_id"), Some("alice"));
+                let child = tracer.start_span("load_user", None).unwrap();
+                tracer.end_span(child).unwrap();
+            }
+            tracer.end_span(root).unwrap();
+        }
+
+        assert_eq!(tracer.get_baggage("user_id").as_deref(), Some("global"));
+        assert!(!Context::current().has_baggage());
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans.len(), 2);
+        assert!(spans
+            .iter()
+            .all(|span| span.attributes["user_id"] == "alice"));
+    }
+
+    #[test]
+    fn test_composite_propagator_migration() {
+        let config = TraceConfig::new("test-service").with_propagators([
+            Propagator::W3c,
+            Propagator::B3,
+            Propagator::Baggage,
+        ]);
+        let tracer = SimpleTracer::new(config);
+        tracer.set_baggage("tenant", "acme corp");
+        let span_id = tracer.start_span("call_downstream", None).unwrap();
+        let context = tracer.span_context(&span_id).unwrap();
+
+        let mut headers: std::collections::HashMap<String, String> = Default::default();
+        assert!(tracer.inject_context(&span_id, &mut headers));
+        assert_eq!(headers[TRACEPARENT_HEADER], context.to_traceparent());
+        assert_eq!(
+            headers[B3_HEADER],
+            format!("{}-{}-1", context.trace_id, context.span_id)
+        );
+        assert_eq!(headers[BAGGAGE_HEADER], "tenant=acme%20corp");
+        assert!(!tracer.inject_context("not-a-span", &mut headers));
+
+        // A caller still on B3 alone is accepted
+        let mut legacy = std::collections::HashMap::new();
+        legacy.insert("X-B3-TraceId".to_string(), "a3ce929d0e0e4736".to_string());
+        legacy.insert("X-B3-SpanId".to_string(), "00f067aa0ba902b7".to_string());
+        legacy.insert("X-B3-Sampled".to_string(), "0".to_string());
+        let extracted = tracer.extract_context(&legacy).unwrap();
+        assert_eq!(
+            extracted.trace_id.to_string(),
+            "0000000000000000a3ce929d0e0e4736"
+        );
+        assert!(!extracted.sampled);
+        assert_eq!(
+            tracer.propagator().extract_baggage(&headers)["tenant"],
+            "acme corp"
+        );
+
+        let w3c_only = CompositePropagator::new([Propagator::W3c, Propagator::W3c]);
+        assert_eq!(w3c_only.propagators(), &[Propagator::W3c]);
+        assert!(w3c_only.extract(&legacy).is_none());
+        assert_eq!(
+            TraceConfig::new("test-service").propagators,
+            DEFAULT_PROPAGATORS.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_malformed_context_surfaces_error() {
+        let tracer = SimpleTracer::new(
+            TraceConfig::new("test-service").with_propagators([Propagator::W3c, Propagator::B3]),
+        );
+        let mut headers = std::collections::HashMap::new();
+        assert_eq!(tracer.try_extract_context(&headers), Ok(None));
+
+        headers.insert(
+            TRACEPARENT_HEADER.to_string(),
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01".to_string(),
+        );
+        let error = tracer.try_extract_context(&headers).unwrap_err();
+        assert_eq!(error.header, TRACEPARENT_HEADER);
+        assert_eq!(error.reason, "trace ID must be 32 lowercase hex digits");
+        assert!(tracer.extract_context(&headers).is_none());
+        let tyl_error: TylError = error.clone().into();
+        assert!(tyl_error
+            .to_string()
+            .contains("malformed traceparent header"));
+
+        // A valid context in another scheme still wins
+        headers.insert(
+            B3_HEADER.to_string(),
+            "a3ce929d0e0e4736-00f067aa0ba902b7-1".to_string(),
+        );
+        assert!(tracer.try_extract_context(&headers).unwrap().is_some());
+        headers.insert(B3_HEADER.to_string(), "a3ce929d0e0e4736-zz-1".to_string());
+        assert_eq!(tracer.try_extract_context(&headers), Err(error.clone()));
+
+        tracer
+            .start_malformed_root("handle_request", SpanKind::Server, &error)
+            .end();
+        let span = &tracer.get_completed_spans()[0];
+        assert!(span.parent_span_id.is_none());
+        assert_eq!(
+            span.attributes[PARENT_MALFORMED_ATTRIBUTE],
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_record_error_captures_source_chain() {
+        #[derive(Debug)]
+        struct ConfigLoadError(std::io::Error);
+
+        impl std::fmt::Display for ConfigLoadError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "cannot load config")
+            }
+        }
+
+        impl std::error::Error for ConfigLoadError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let wrapped = ConfigLoadError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "tracing.toml missing",
+        ));
+        let attributes = exception_attributes(&wrapped);
+        let chain = attributes[EXCEPTION_CHAIN_ATTRIBUTE].as_array().unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0]["type"], "ConfigLoadError");
+        assert_eq!(chain[1]["message"], "tracing.toml missing");
+
+        let tracer = SimpleTracer::default();
+        let span_id = tracer.start_span("load", None).unwrap();
+        tracer
+            .record_error(&span_id, &TylError::validation("port", "must be > 0"))
+            .unwrap();
+        tracer.end_span(span_id).unwrap();
+
+        let span = &tracer.get_completed_spans()[0];
+        assert!(matches!(span.status, SpanStatus::Error { .. }));
+        assert_eq!(span.attributes[ERROR_CATEGORY_ATTRIBUTE], "validation");
+        let event = &span.events[0];
+        assert_eq!(event.name, EXCEPTION_EVENT);
+        assert_eq!(
+            event.attributes[EXCEPTION_CHAIN_ATTRIBUTE][0]["type"],
+            "Validation"
+        );
+    }
+
+    #[test]
+    fn test_retry_attempts_recorded_as_events() {
+        let tracer = SimpleTracer::default();
+        let policy = RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_base_delay(std::time::Duration::from_millis(1));
+
+        let span_id = tracer.start_span("fetch_rates", None).unwrap();
+        let rate = tracer
+            .retry_traced(&span_id, &policy, |attempt| match attempt {
+                1 => Err(TylError::network("connection reset")),
+                _ => Ok(1.08),
+            })
+            .unwrap();
+        assert_eq!(rate, 1.08);
+        tracer.end_span(span_id).unwrap();
+
+        let span_id = tracer.start_span("fetch_rates", None).unwrap();
+        let result: TracingResult<()> = tracer.retry_traced(&span_id, &policy, |_| {
+            Err(TylError::network("connection refused"))
+        });
+        assert!(result.is_err());
+        tracer.end_span(span_id).unwrap();
+
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans[0].attributes[RETRY_ATTEMPTS_ATTRIBUTE], 2);
+        assert!(matches!(spans[0].status, SpanStatus::Completed));
+        assert_eq!(spans[0].events[0].name, RETRY_EVENT);
+        assert_eq!(spans[0].events[0].attributes[RETRY_ATTEMPT_ATTRIBUTE], 1);
+        assert_eq!(
+            spans[0].events[0].attributes[RETRY_ERROR_ATTRIBUTE],
+            TylError::network("connection reset").to_string()
+        );
+
+        assert_eq!(spans[1].attributes[RETRY_ATTEMPTS_ATTRIBUTE], 3);
+        assert!(matches!(spans[1].status, SpanStatus::Error { .. }));
+        let retries = spans[1].events.iter().filter(|e| e.name == RETRY_EVENT);
+        assert_eq!(retries.count(), 2);
+    }
+
+    #[test]
+    fn test_feature_flag_semconv() {
+        use crate::semconv::feature_flag;
+
+        let tracer = SimpleTracer::default();
+        let request = tracer.start_span("checkout", None).unwrap();
+        let enabled = feature_flag::evaluate(
+            &tracer,
+            Some(request.clone()),
+            "new-checkout",
+            "tyl-flags",
+            || true,
+        );
+        assert!(enabled);
+        tracer.end_span(request).unwrap();
+
+        let flag_span = &tracer.get_completed_spans()[0];
+        assert_eq!(flag_span.operation_name, feature_flag::SPAN_NAME);
+        assert_eq!(flag_span.attributes[feature_flag::KEY], "new-checkout");
+        assert_eq!(flag_span.attributes[feature_flag::VARIANT], "true");
+        assert_eq!(
+            flag_span.attributes[feature_flag::PROVIDER_NAME],
+            "tyl-flags"
+        );
+
+        let mut span = Span::new("render".to_string(), None);
+        feature_flag::record(&mut span, "theme", "dark", "tyl-flags");
+        assert_eq!(span.events[0].name, feature_flag::EVENT_NAME);
+        assert_eq!(span.events[0].attributes[feature_flag::VARIANT], "dark");
+    }
+
+    #[test]
+    fn test_cache_semconv() {
+        use crate::semconv::cache;
+        use std::time::Duration;
+
+        let tracer = SimpleTracer::default();
+        let stored = cache::set(
+            &tracer,
+            None,
+            "user:42",
+            Some(Duration::from_secs(60)),
+            || true,
+        );
+        assert!(stored);
+        assert_eq!(cache::get(&tracer, None, "user:42", || Some(42)), Some(42));
+        assert_eq!(cache::get(&tracer, None, "user:7", || None::<u32>), None);
+
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans[0].operation_name, cache::SET_SPAN_NAME);
+        assert_eq!(spans[0].attributes[cache::TTL], 60_000);
+        assert_eq!(spans[1].attributes[cache::HIT], true);
+        assert_eq!(spans[2].attributes[cache::HIT], false);
+        assert_eq!(
+            spans[1].attributes[cache::KEY_HASH],
+            cache::key_hash("user:42")
+        );
+        assert_ne!(cache::key_hash("user:42"), cache::key_hash("user:7"));
+        assert!(!spans[1].attributes.contains_key(cache::TTL));
+    }
+
+    #[test]
+    fn test_job_trace_summary() {
+        let tracer = SimpleTracer::default();
+        let job = JobTrace::start(&tracer, "nightly-reconciliation")
+            .with_schedule("0 2 * * *")
+            .with_item_sample_rate(0.5);
+        for item in 0..4 {
+            let _ = job.item("reconcile_account", || {
+                if item == 3 {
+                    Err("ledger mismatch")
+                } else {
+                    Ok(item)
+                }
+            });
+            if item == 1 {
+                job.checkpoint("halfway", std::collections::HashMap::new());
+            }
+        }
+        let summary = job.finish();
+        assert_eq!(summary.items, 4);
+        assert_eq!(summary.failed_items, 1);
+        assert_eq!(summary.traced_items, 2);
+        assert_eq!(summary.outcome, JobOutcome::PartiallyFailed);
+
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans.len(), 3);
+        let root = spans.last().unwrap();
+        assert_eq!(root.attributes[job::JOB_SCHEDULE_ATTRIBUTE], "0 2 * * *");
+        assert_eq!(
+            root.attributes[job::JOB_OUTCOME_ATTRIBUTE],
+            "partially_failed"
+        );
+        assert_eq!(root.events[0].attributes[job::JOB_ITEMS_ATTRIBUTE], 2);
+        assert!(matches!(root.status, SpanStatus::Error { .. }));
+        assert!(matches!(spans[1].status, SpanStatus::Error { .. }));
+    }
+
+    #[test]
+    fn test_init_picks_adapter_per_environment() {
+        let testing = init(TraceConfig::new("svc").with_environment(Environment::Testing)).unwrap();
+        let span = testing.start_span("op", None).unwrap();
+        assert_eq!(span, NOOP_SPAN_ID);
+
+        let overridden = init(
+            TraceConfig::new("svc")
+                .with_environment(Environment::Testing)
+                .with_exporter(ExporterConfig::console()),
+        )
+        .unwrap();
+        let span = overridden.start_span("op", None).unwrap();
+        overridden.end_span(span).unwrap();
+        assert_eq!(overridden.get_completed_spans().len(), 1);
+
+        let development =
+            init(TraceConfig::new("svc").with_environment(Environment::Development)).unwrap();
+        assert_ne!(development.start_span("op", None).unwrap(), NOOP_SPAN_ID);
+
+        assert!(init(TraceConfig::new("").with_environment(Environment::Testing)).is_err());
+    }
+
+    #[test]
+    fn test_child_scope_stamps_attributes() {
+        let tracer = SimpleTracer::default();
+        tracer.set_baggage("request.id", "r-1");
+        let billing = tracer.child_scope([("component", "billing")]);
+        let shard = billing.child_scope([("shard.id", serde_json::json!(3))]);
+        assert_eq!(shard.get_baggage("request.id").as_deref(), Some("r-1"));
+
+        let charge = shard.start_span("charge", None).unwrap();
+        shard.end_span(charge).unwrap();
+        let plain = tracer.start_span("plain", None).unwrap();
+        tracer.end_span(plain).unwrap();
+
+        let spans = shard.get_completed_spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].attributes["component"], "billing");
+        assert_eq!(spans[0].attributes["shard.id"], 3);
+        assert!(!spans[1].attributes.contains_key("component"));
+    }
+
+    #[test]
+    fn test_purge_by_attribute() {
+        let processor = BatchSpanProcessor::new(InMemoryExporter::new()).with_batch_size(100);
+        let tracer = SimpleTracer::default().with_batch_processor(processor);
+        for user in ["42", "7", "42"] {
+            let span = tracer.start_span("checkout", None).unwrap();
+            tracer
+                .add_span_attribute(&span, "user_id", serde_json::json!(user))
+                .unwrap();
+            tracer.end_span(span).unwrap();
+        }
+
+        // Two completed and two queued copies
+        assert_eq!(tracer.purge_by_attribute("user_id", "42"), 4);
+        assert_eq!(tracer.purge_by_attribute("user_id", "42"), 0);
+        let remaining = tracer.get_completed_spans();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].attributes["user_id"], "7");
+        assert_eq!(tracer.batch_processor().unwrap().queued_spans(), 1);
+    }
+
+    #[test]
+    fn test_retention_prunes_old_spans() {
+        use std::time::Duration;
+
+        let tracer =
+            SimpleTracer::new(TraceConfig::new("svc").with_retention(Duration::from_millis(50)));
+        let span = tracer.start_span("old", None).unwrap();
+        tracer.end_span(span).unwrap();
+        assert_eq!(tracer.get_completed_spans().len(), 1);
+
+        std::thread::sleep(Duration::from_millis(150));
+        let span = tracer.start_span("fresh", None).unwrap();
+        tracer.end_span(span).unwrap();
+        let names: Vec<_> = tracer
+            .get_completed_spans()
+            .into_iter()
+            .map(|span| span.operation_name)
+            .collect();
+        assert_eq!(names, ["fresh"]);
+
+        assert!(!TraceConfig::new("svc")
+            .with_retention(Duration::ZERO)
+            .validation_errors()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_completed_spans_between() {
+        let tracer = SimpleTracer::new(TraceConfig::new("svc").with_max_spans(3));
+        let before = crate::span::current_timestamp();
+        for name in ["a", "b", "c", "d"] {
+            let span = tracer.start_span(name, None).unwrap();
+            tracer.end_span(span).unwrap();
+        }
+        let after = crate::span::current_timestamp();
+
+        // max_spans evicts the oldest span across buckets
+        let names: Vec<_> = tracer
+            .completed_spans_between(before, after)
+            .into_iter()
+            .map(|span| span.operation_name)
+            .collect();
+        assert_eq!(names, ["b", "c", "d"]);
+        assert!(tracer.completed_spans_between(0, before - 1).is_empty());
+        assert!(tracer
+            .completed_spans_between(after + 1, after + 60_000)
+            .is_empty());
+
+        let mut store = crate::store::SpanStore::default();
+        for end_time in [1_000, 59_000, 61_000, 130_000, 131_000] {
+            let mut span = Span::new(end_time.to_string(), None);
+            span.end_time = Some(end_time);
+            store.push(span);
+        }
+        assert_eq!(store.prune_before(61_000), 2);
+        assert_eq!(store.prune_before(130_500), 2);
+        let left: Vec<_> = store.iter().map(|span| span.end_time).collect();
+        assert_eq!(left, [Some(131_000)]);
+    }
+
+    #[test]
+    fn test_get_trace_uses_index() {
+        let tracer = SimpleTracer::new(TraceConfig::new("svc").with_max_spans(4));
+        let mut trace_ids = Vec::new();
+        for _ in 0..3 {
+            let root = tracer.start_span("request", None).unwrap();
+            let child = tracer.start_span("db", Some(root.clone())).unwrap();
+            trace_ids.push(tracer.trace_id(&root).unwrap());
+            tracer.end_span(child).unwrap();
+            tracer.end_span(root).unwrap();
+        }
+
+        // The first trace was evicted by max_spans
+        assert!(tracer.get_trace(&trace_ids[0]).is_empty());
+        let names: Vec<_> = tracer
+            .get_trace(&trace_ids[2])
+            .into_iter()
+            .map(|span| span.operation_name)
+            .collect();
+        assert_eq!(names, ["db", "request"]);
+
+        tracer.purge_spans(|span| span.operation_name == "db");
+        assert_eq!(tracer.get_trace(&trace_ids[1]).len(), 1);
+        tracer.clear_completed_spans();
+        assert!(tracer.get_trace(&trace_ids[2]).is_empty());
+    }
+
+    #[test]
+    fn test_attributes_stay_inline() {
+        let mut span = Span::new("op".to_string(), None);
+        for i in 0..INLINE_ATTRIBUTES {
+            span.attributes
+                .insert(format!("key.{}", i), serde_json::json!(i));
+        }
+        assert!(!span.attributes.spilled());
+        assert_eq!(
+            span.attributes
+                .insert("key.0", serde_json::json!("replaced")),
+            Some(serde_json::json!(0))
+        );
+        assert_eq!(span.attributes.len(), INLINE_ATTRIBUTES);
+        span.attributes.insert("one.more", serde_json::json!(true));
+        assert!(span.attributes.spilled());
+
+        let json = serde_json::to_string(&span).unwrap();
+        let decoded: Span = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.attributes, span.attributes);
+        assert_eq!(decoded.attributes["key.0"], "replaced");
+    }
+
+    #[test]
+    fn test_workload_bench_report() {
+        let tracer = SimpleTracer::new(TraceConfig::new("bench"));
+        let report = Workload::new()
+            .with_traces(10)
+            .with_depth(3)
+            .with_attributes(2)
+            .with_concurrency(2)
+            .run(&tracer)
+            .unwrap();
+
+        assert_eq!(report.spans, 30);
+        assert_eq!((report.errors, report.dropped), (0, 0));
+        assert!(report.latency.p50 <= report.latency.max);
+        let spans = tracer.get_completed_spans();
+        let roots = spans.iter().filter(|s| s.parent_span_id.is_none()).count();
+        assert_eq!(roots, 10);
+        assert!(spans
+            .iter()
+            .all(|s| s.attributes.contains_key("bench.attr0")
+                && s.attributes.contains_key("bench.attr1")));
+
+        assert!(Workload::new().with_depth(0).run(&tracer).is_err());
+    }
+
+    #[test]
+    fn test_unsampled_trace_is_non_recording() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service").with_sampling_rate(0.0));
+        let root = tracer.start_span("request", None).unwrap();
+        assert!(!is_recording(&root));
+
+        let child = tracer.start_span("db.query", Some(root.clone())).unwrap();
+        assert!(!is_recording(&child));
+        tracer
+            .add_span_attribute(&child, "db.rows", serde_json::json!(3))
+            .unwrap();
+        tracer
+            .span_heartbeat(&child, serde_json::json!(50))
+            .unwrap();
+        assert_eq!(tracer.trace_id(&child), None);
+        tracer.end_span(child).unwrap();
+        tracer.end_span(root).unwrap();
+
+        assert!(tracer.active_spans().is_empty());
+        assert!(tracer.get_completed_spans().is_empty());
+
+        // Children share their root's trace and decision
+        let sampled = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = sampled.start_span("request", None).unwrap();
+        let child = sampled.start_span("db.query", Some(root.clone())).unwrap();
+        assert!(is_recording(&child));
+        assert_eq!(sampled.trace_id(&child), sampled.trace_id(&root));
+    }
+
+    #[test]
+    fn test_span_handle_recording() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        {
+            let request = tracer.start("request");
+            assert!(request.is_recording());
+            request.set_attribute("http.method", "GET");
+            let query = request.child("db.query");
+            query.add_event("rows", [("count", 3)]);
+            query.end();
+        }
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].operation_name, "db.query");
+        assert_eq!(spans[0].events[0].attributes["count"], 3);
+        assert_eq!(spans[0].parent_span_id, Some(spans[1].span_id));
+        assert_eq!(spans[1].attributes["http.method"], "GET");
+
+        let unsampled = SimpleTracer::new(TraceConfig::new("test-service").with_sampling_rate(0.0));
+        let request = unsampled.start("request");
+        assert!(!request.is_recording());
+        assert_eq!(request.span_id(), NOOP_SPAN_ID);
+        request.set_attribute("http.method", "GET");
+        assert!(!request.child("db.query").is_recording());
+        drop(request);
+        assert!(unsampled.get_completed_spans().is_empty());
+    }
+
+    #[test]
+    fn test_span_end_observers() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let ended = Arc::new(AtomicUsize::new(0));
+        let counter = ended.clone();
+        tracer.register_on_end(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        tracer.register_on_end(|span| {
+            span.attributes
+                .insert("region", serde_json::json!("eu-west-1"));
+        });
+
+        let root = tracer.start_span("request", None).unwrap();
+        let child = tracer.start_span("db.query", Some(root.clone())).unwrap();
+        tracer.end_span(child).unwrap();
+        tracer.end_span(root).unwrap();
+
+        assert_eq!(ended.load(Ordering::Relaxed), 2);
+        assert!(tracer
+            .get_completed_spans()
+            .iter()
+            .all(|span| span.attributes["region"] == "eu-west-1"));
+    }
+
+    #[test]
+    fn test_publish_span_metrics() {
+        #[derive(Default)]
+        struct Recorder {
+            counters: std::sync::Mutex<Vec<(String, Vec<(String, String)>, u64)>>,
+            histograms: std::sync::Mutex<Vec<(String, f64)>>,
+        }
+        impl MetricsRecorder for Recorder {
+            fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+                let labels = labels
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                self.counters
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), labels, value));
+            }
+
+            fn record_histogram(&self, name: &str, _labels: &[(&str, &str)], value: f64) {
+                self.histograms
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), value));
+            }
+        }
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service").with_sampling_rate(1.0));
+        let recorder = Arc::new(Recorder::default());
+        tracer.publish_span_metrics(recorder.clone());
+        let ok = tracer.start_span("checkout", None).unwrap();
+        tracer.end_span(ok).unwrap();
+        let failed = tracer
+            .start_span_with_kind("charge", None, SpanKind::Client)
+            .unwrap();
+        tracer
+            .record_error(&failed, &TylError::validation("card", "declined"))
+            .unwrap();
+        tracer.end_span(failed).unwrap();
+
+        let counters = recorder.counters.lock().unwrap();
+        assert_eq!(counters.len(), 2);
+        assert!(counters
+            .iter()
+            .all(|(name, _, value)| name == SPANS_ENDED_METRIC && *value == 1));
+        let label = |index: usize, key: &str| {
+            counters[index]
+                .1
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+        assert_eq!(label(0, "operation").as_deref(), Some("checkout"));
+        assert_eq!(label(0, "status").as_deref(), Some("ok"));
+        assert_eq!(label(1, "kind").as_deref(), Some("client"));
+        assert_eq!(label(1, "status").as_deref(), Some("error"));
+        let histograms = recorder.histograms.lock().unwrap();
+        assert_eq!(histograms.len(), 2);
+        assert!(histograms
+            .iter()
+            .all(|(name, seconds)| name == SPAN_DURATION_METRIC && *seconds >= 0.0));
+    }
+
+    #[test]
+    fn test_attribute_key_lints() {
+        assert_eq!(check_attribute_key("http.request.method"), Ok(()));
+        assert_eq!(check_attribute_key("userId"), Err(KeyViolation::Uppercase));
+        assert_eq!(
+            check_attribute_key("order id"),
+            Err(KeyViolation::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            check_attribute_key("db..table"),
+            Err(KeyViolation::MalformedSegment)
+        );
+        assert_eq!(
+            check_attribute_key("retries"),
+            Err(KeyViolation::Unnamespaced)
+        );
+        assert_eq!(
+            check_attribute_key("http_status"),
+            Err(KeyViolation::UnderscoreNamespace("http.status".to_string()))
+        );
+
+        let config = |mode| TraceConfig::new("test-service").with_attribute_key_validation(mode);
+        let warn = SimpleTracer::new(config(KeyValidation::Warn));
+        let span = warn.start_span("op", None).unwrap();
+        warn.add_span_attribute(&span, "http_status", serde_json::json!(200))
+            .unwrap();
+        warn.add_span_attribute(&span, "http.status", serde_json::json!(200))
+            .unwrap();
+        assert_eq!(warn.attribute_key_violations(), vec!["http_status"]);
+
+        let deny = SimpleTracer::new(config(KeyValidation::Deny));
+        let span = deny.start_span("op", None).unwrap();
+        assert!(deny
+            .add_span_attribute(&span, "Order ID", serde_json::json!(1))
+            .is_err());
+        assert!(deny
+            .add_span_attribute(&span, "order.id", serde_json::json!(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_db_span_sanitizes_statement() {
+        use crate::db_span::sanitize_statement;
+
+        assert_eq!(
+            sanitize_statement("SELECT * FROM users2 WHERE name = 'O''Brien' AND age > 42"),
+            "SELECT * FROM users2 WHERE name = ? AND age > ?"
+        );
+        assert_eq!(
+            sanitize_statement(r#"UPDATE "t1" SET x = $1 WHERE id IN (1, 2.5)"#),
+            r#"UPDATE "t1" SET x = $1 WHERE id IN (?, ?)"#
+        );
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        {
+            let query = DbSpan::query(
+                &tracer,
+                "postgresql",
+                "/* report */ select id from orders where total > 100",
+            );
+            query.set_database("shop");
+            query.set_rows_returned(7);
+        }
+        let span = &tracer.get_completed_spans()[0];
+        assert_eq!(span.operation_name, "SELECT orders");
+        assert_eq!(span.kind, SpanKind::Client);
+        assert_eq!(span.attributes["db.operation"], "SELECT");
+        assert_eq!(span.attributes["db.sql.table"], "orders");
+        assert_eq!(
+            span.attributes["db.statement"],
+            "/* report */ select id from orders where total > ?"
+        );
+        assert_eq!(span.attributes["db.response.returned_rows"], 7);
+    }
+
+    #[test]
+    fn test_messaging_consume_links_producers() {
+        let tracer = SimpleTracer::new(TraceConfig::new("messaging-service"));
+        let mut headers = std::collections::HashMap::new();
+        let producer_trace = {
+            let publish = MessagingSpan::publish(&tracer, "kafka", "orders");
+            publish.inject(&mut headers);
+            tracer.trace_id(publish.span().span_id()).unwrap()
+        };
+        let unlinked = std::collections::HashMap::new();
+
+        MessagingSpan::consume(&tracer, "kafka", "orders", [&headers, &unlinked]).end();
+
+        let spans = tracer.get_completed_spans();
+        let consumer = spans
+            .iter()
+            .find(|span| span.operation_name == "orders process")
+            .unwrap();
+        assert_eq!(consumer.kind, SpanKind::Consumer);
+        assert_eq!(consumer.attributes["messaging.batch.message_count"], 2);
+        assert_eq!(consumer.links.len(), 1);
+        assert_eq!(
+            consumer.links[0].trace_id,
+            producer_trace.parse::<TraceId>().unwrap()
+        );
+        assert!(TraceContext::from_traceparent(
+            "00-00000000000000000000000000000000-0000000000000001-01"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_unit_aware_attributes() {
+        let tracer = SimpleTracer::new(TraceConfig::new("attrs-service"));
+        let span = tracer.start("upload");
+        span.set_attributes([
+            attrs::duration("db.lock_wait", std::time::Duration::from_micros(1500)),
+            attrs::bytes("payload.size", 2048),
+            attrs::bytes("response_bytes", 10),
+        ]);
+        let span_id = span.span_id().to_string();
+        span.end();
+
+        let spans = tracer.get_completed_spans();
+        let span = spans.iter().find(|span| span.span_id == span_id).unwrap();
+        assert_eq!(span.attributes["db.lock_wait_ms"], 1.5);
+        assert_eq!(span.attributes["payload.size_bytes"], 2048);
+        assert_eq!(span.attributes["response_bytes"], 10);
+    }
+
+    #[test]
+    fn test_self_time_excludes_overlapping_children() {
+        let mut root = Span::new("request".to_string(), None);
+        root.start_time = 1_000;
+        root.end_time = Some(1_100);
+        let child = |name: &str, start: u64, end: Option<u64>| {
+            let mut span = Span::new(name.to_string(), Some(root.span_id));
+            span.trace_id = root.trace_id;
+            span.start_time = start;
+            span.end_time = end;
+            span
+        };
+        let spans = vec![
+            root.clone(),
+            child("db", 1_010, Some(1_040)),
+            child("cache", 1_030, Some(1_050)),
+            child("late", 1_090, None),
+        ];
+
+        let tree = TraceTree::new(&spans);
+        assert_eq!(tree.roots().len(), 1);
+        assert_eq!(tree.children(root.span_id).len(), 3);
+        // 100ms minus db/cache (1010..1050) and the open child (1090..1100)
+        assert_eq!(tree.self_time(root.span_id), Some(50));
+        assert_eq!(tree.self_time(spans[1].span_id), Some(30));
+    }
+
+    #[test]
+    fn test_in_flight_report_groups_by_operation() {
+        let tracer = SimpleTracer::new(TraceConfig::new("inflight-service"));
+        let first = tracer.start_span("db_query", None).unwrap();
+        let _second = tracer.start_span("db_query", None).unwrap();
+        let _other = tracer.start_span("render", None).unwrap();
+        tracer
+            .update_span(&first, |span| span.start_time -= 5_000)
+            .unwrap();
+
+        let report = tracer.in_flight_report();
+        assert_eq!(report.total, 3);
+        assert_eq!(report.operations[0].operation_name, "db_query");
+        assert_eq!(report.operations[0].count, 2);
+        assert_eq!(report.operations[0].oldest_span_id, first);
+        assert!(report.operations[0].max_age_ms >= 5_000);
+        assert_eq!(report.get("render").unwrap().count, 1);
+    }
+
+    #[test]
+    fn test_configurable_id_format() {
+        let tracer =
+            SimpleTracer::new(TraceConfig::new("ids-service").with_id_format(IdFormat::Base64));
+        let span_id = tracer.start_span("op", None).unwrap();
+        let trace_id = tracer.trace_id(&span_id).unwrap();
+        assert_eq!(span_id.len(), 11);
+        assert_eq!(trace_id.len(), 22);
+
+        let hex = IdFormat::Hex.render(&parse_id(&trace_id).unwrap());
+        assert_eq!(hex.len(), 32);
+        assert_eq!(tracer.span_context(&span_id).unwrap().trace_id, hex);
+
+        let hex_tracer =
+            SimpleTracer::new(TraceConfig::new("ids-service").with_id_format(IdFormat::Hex));
+        let span_id = hex_tracer.start_span("op", None).unwrap();
+        assert_eq!(span_id.len(), 16);
+        assert!(span_id.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_span_ids_are_binary_and_copy() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("root", None).unwrap();
+        let child = tracer.start_span("child", Some(root.clone())).unwrap();
+        tracer.end_span(child.clone()).unwrap();
+        tracer.end_span(root.clone()).unwrap();
+
+        let spans = tracer.get_completed_spans();
+        let child_span = spans.iter().find(|span| span.span_id == child).unwrap();
+        let parent: SpanId = child_span.parent_span_id.unwrap();
+        assert_eq!(parent, root);
+        assert_eq!(parent.to_string().len(), 16);
+        assert_eq!(parent.to_string().parse::<SpanId>().unwrap(), parent);
+
+        let trace_id = child_span.trace_id;
+        assert_eq!(TraceId::from_bytes(trace_id.to_bytes()), trace_id);
+        let json = serde_json::to_value(child_span).unwrap();
+        assert_eq!(json["trace_id"], trace_id.to_string());
+        assert!("not-an-id".parse::<TraceId>().is_err());
+        assert!(!TraceId::INVALID.is_valid());
+    }
+
+    #[test]
+    fn test_ids_parse_only_at_their_own_length() {
+        let trace_id = TraceId::random();
+        for format in [IdFormat::Uuid, IdFormat::Hex, IdFormat::Base64] {
+            let rendered = format.render_trace_id(trace_id);
+            assert_eq!(TraceId::parse(&rendered), Some(trace_id));
+            assert_eq!(SpanId::parse(&rendered), None, "{:?}", format);
+            let span_id = SpanId::random();
+            assert_eq!(TraceId::parse(&format.render_span_id(span_id)), None);
+        }
+        let hex = trace_id.to_string();
+        assert!(SpanId::parse(&hex[..15]).is_none());
+        assert!(SpanId::parse(&hex[..18]).is_none());
+        let prefix = SpanId::from_bytes(trace_id.to_bytes()[..8].try_into().unwrap());
+        assert!(prefix != hex.as_str());
+
+        // A trace ID handed to span operations is an unknown span, not a
+        // lookup of its first 8 bytes
+        let tracer =
+            SimpleTracer::new(TraceConfig::new("test-service").with_id_format(IdFormat::Hex));
+        let span_id = tracer.start_span("op", None).unwrap();
+        let trace_id = tracer.trace_id(&span_id).unwrap();
+        assert!(tracer.end_span(trace_id).is_err());
+        tracer.end_span(span_id).unwrap();
+
+        // Legacy UUID span IDs map by hash: stable, and a shared prefix
+        // does not collide
+        let legacy = "6f1c2a9e-4b7d-4e2a-9c3b-1d2e3f405162";
+        let sibling = "6f1c2a9e-4b7d-4e2a-9c3b-000000000000";
+        let mapped = SpanId::parse(legacy).unwrap();
+        assert_eq!(SpanId::parse(legacy), Some(mapped));
+        assert_ne!(SpanId::parse(sibling), Some(mapped));
+        assert_ne!(
+            mapped.to_bytes(),
+            [0x6f, 0x1c, 0x2a, 0x9e, 0x4b, 0x7d, 0x4e, 0x2a]
+        );
+        assert!(mapped == legacy);
+        assert!(SpanId::parse(&legacy.replace('-', "")).is_none());
+    }
+
+    #[test]
+    fn test_trace_context_token_round_trip() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let span_id = tracer.start_span("enqueue_job", None).unwrap();
+        let context = tracer.span_context(&span_id).unwrap();
+
+        let token = context.to_token();
+        assert_eq!(token.len(), 35);
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(TraceContext::from_token(&token), Some(context));
+
+        assert!(TraceContext::from_token("").is_none());
+        assert!(TraceContext::from_token("not a token").is_none());
+        assert!(TraceContext::from_token(&token[..34]).is_none());
+        let zero = TraceContext::new(TraceId::INVALID, SpanId::INVALID, true);
+        assert!(TraceContext::from_token(&zero.to_token()).is_none());
+    }
+
+    #[test]
+    fn test_trace_context_response_helpers() {
+        let context = TraceContext::new(TraceId::random(), SpanId::random(), true);
+        let mut headers: std::collections::HashMap<String, String> = Default::default();
+        context.inject_response(&mut headers);
+
+        let traceparent = context.to_traceparent();
+        assert_eq!(headers[TRACERESPONSE_HEADER], traceparent);
+        assert_eq!(
+            headers[SERVER_TIMING_HEADER],
+            format!("traceparent;desc=\"{}\"", traceparent)
+        );
+
+        let json = context.to_json();
+        assert_eq!(json["traceId"], context.trace_id.to_string());
+        assert_eq!(json["spanId"], context.span_id.to_string());
+        assert_eq!(json["sampled"], true);
+        assert_eq!(json["traceparent"], traceparent);
+    }
+
+    #[test]
+    fn test_binary_context_round_trip_and_hardening() {
+        let context = TraceContext::new(TraceId::random(), SpanId::random(), true);
+        let header = context.to_bytes();
+        assert_eq!(header.len(), BINARY_CONTEXT_LEN);
+        assert_eq!(TraceContext::from_bytes(&header).unwrap(), context);
+
+        let mut baggage = std::collections::HashMap::new();
+        baggage.insert("tenant".to_string(), "acme".to_string());
+        baggage.insert("region".to_string(), "eu-west-1".to_string());
+        let encoded = encode_context(&context, &baggage).unwrap();
+        assert_eq!(
+            decode_context(&encoded).unwrap(),
+            (context.clone(), baggage)
+        );
+        assert!(TraceContext::from_bytes(&encoded).is_err());
+
+        // Every truncation and single-byte corruption fails cleanly or decodes
+        for len in 0..encoded.len() {
+            assert!(decode_context(&encoded[..len]).is_err() || len == BINARY_CONTEXT_LEN);
+        }
+        for index in 0..encoded.len() {
+            for byte in [0x00, 0x7f, 0x80, 0xff] {
+                let mut corrupted = encoded.clone();
+                corrupted[index] = byte;
+                let _ = decode_context(&corrupted);
+            }
+        }
+        let mut future = header;
+        future[0] = BINARY_CONTEXT_VERSION + 1;
+        assert!(TraceContext::from_bytes(&future).is_err());
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn test_grpc_metadata_carrier() {
+        use tonic::metadata::MetadataMap;
+
+        let context = TraceContext::new(TraceId::random(), SpanId::random(), true);
+        let mut metadata = MetadataMap::new();
+        context.inject(&mut metadata);
+        context.inject_grpc_bin(&mut metadata);
+        Injector::set(&mut metadata, "bad key", "dropped".to_string());
+
+        assert_eq!(TraceContext::extract(&metadata), Some(context.clone()));
+        assert_eq!(TraceContext::extract_grpc_bin(&metadata), Some(context));
+        assert_eq!(Extractor::keys(&metadata), vec![TRACEPARENT_HEADER]);
+        assert_eq!(Extractor::get(&metadata, GRPC_TRACE_BIN_HEADER), None);
+
+        metadata.insert_bin(
+            GRPC_TRACE_BIN_HEADER,
+            tonic::metadata::BinaryMetadataValue::from_bytes(b"short"),
+        );
+        assert!(TraceContext::extract_grpc_bin(&metadata).is_none());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_header_map_carrier() {
+        use http::header::{HeaderMap, HeaderValue};
+
+        let context = TraceContext::new(TraceId::random(), SpanId::random(), true);
+        let mut headers = HeaderMap::new();
+        headers.append(TRACEPARENT_HEADER, HeaderValue::from_static("stale"));
+        headers.append(TRACEPARENT_HEADER, HeaderValue::from_static("stale"));
+        context.inject(&mut headers);
+        Injector::set(&mut headers, "bad key", "dropped".to_string());
+
+        assert_eq!(headers.get_all(TRACEPARENT_HEADER).iter().count(), 1);
+        assert_eq!(TraceContext::extract(&headers), Some(context.clone()));
+        assert_eq!(
+            Extractor::get(&headers, "TraceParent"),
+            Some(context.to_traceparent().as_str())
+        );
+        assert_eq!(Extractor::keys(&headers), vec![TRACEPARENT_HEADER]);
+
+        let mut repeated = HeaderMap::new();
+        repeated.append("x-tag", HeaderValue::from_bytes(b"\xff").unwrap());
+        repeated.append("x-tag", HeaderValue::from_static("b"));
+        assert_eq!(Extractor::get(&repeated, "x-tag"), Some("b"));
+    }
+
+    #[cfg(feature = "testing")]
+    mod arbitrary {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn test_arbitrary_span_round_trips(span in any::<Span>()) {
+                prop_assert_eq!(span.end_time.is_none(), matches!(span.status, SpanStatus::Active));
+                prop_assert!(span.end_time.unwrap_or(span.start_time) >= span.start_time);
+                let json = serde_json::to_string(&span).unwrap();
+                prop_assert_eq!(serde_json::from_str::<Span>(&json).unwrap(), span);
+            }
+
+            #[test]
+            fn test_arbitrary_trace_context_round_trips(context in any::<TraceContext>()) {
+                prop_assert_eq!(
+                    TraceContext::from_traceparent(&context.to_traceparent()),
+                    Some(context.clone())
+                );
+                prop_assert_eq!(TraceContext::from_bytes(&context.to_bytes()).ok(), Some(context));
+            }
+        }
+
+        #[test]
+        fn test_quickcheck_spans_are_well_formed() {
+            fn well_formed(span: Span) -> bool {
+                span.trace_id.is_valid()
+                    && span.span_id.is_valid()
+                    && span.end_time.is_none() == matches!(span.status, SpanStatus::Active)
+            }
+            quickcheck::quickcheck(well_formed as fn(Span) -> bool);
+        }
+    }
+
     #[test]
     fn test_invalid_span_operations() {
         let tracer = SimpleTracer::default();
@@ -170,4 +1667,684 @@ mod tests {
             Environment::Development | Environment::Production | Environment::Testing
         ));
     }
+
+    #[test]
+    fn test_environment_defaults() {
+        let production = TraceConfig::new("svc").with_environment(Environment::Production);
+        assert_eq!(production.sampling_rate, 0.05);
+        assert_eq!(
+            production.exporter.as_ref().unwrap().kind,
+            ExporterKind::Otlp
+        );
+        assert!(production.flush_interval.is_some());
+
+        let development = TraceConfig::new("svc").with_environment(Environment::Development);
+        assert_eq!(development.sampling_rate, 1.0);
+        assert_eq!(development.exporter.unwrap().kind, ExporterKind::Console);
+
+        // Explicit settings win whichever side of the environment they are on
+        let exporter = ExporterConfig::new("http://collector:4318");
+        let interval = std::time::Duration::from_millis(250);
+        let after = TraceConfig::new("svc")
+            .with_environment(Environment::Staging)
+            .with_sampling_rate(0.5)
+            .with_exporter(exporter.clone())
+            .with_flush_interval(interval);
+        let before = TraceConfig::new("svc")
+            .with_sampling_rate(0.5)
+            .with_exporter(exporter.clone())
+            .with_flush_interval(interval)
+            .with_environment(Environment::Staging);
+        for staging in [after, before] {
+            assert_eq!(staging.environment, Environment::Staging);
+            assert_eq!(staging.sampling_rate, 0.5);
+            assert_eq!(staging.exporter.as_ref(), Some(&exporter));
+            assert_eq!(staging.flush_interval, Some(interval));
+        }
+        // Unset settings still follow the environment
+        let partial = TraceConfig::new("svc")
+            .with_sampling_rate(0.5)
+            .with_environment(Environment::Production);
+        assert_eq!(partial.sampling_rate, 0.5);
+        assert_eq!(
+            partial.exporter.unwrap().endpoint,
+            config::DEFAULT_OTLP_ENDPOINT
+        );
+
+        assert_eq!(Environment::parse("stage"), Environment::Staging);
+        let custom = Environment::parse("qa-eu");
+        assert_eq!(custom, Environment::Custom("qa-eu".to_string()));
+        assert_eq!(serde_json::to_string(&custom).unwrap(), "\"qa-eu\"");
+        assert_eq!(
+            serde_json::from_str::<Environment>("\"prod\"").unwrap(),
+            Environment::Production
+        );
+    }
+
+    #[test]
+    fn test_trace_diff_compare() {
+        let span = |name: &str, parent: Option<&Span>, start: u64, duration: u64| {
+            let mut span = Span::new(name.to_string(), parent.map(|p| p.span_id));
+            span.start_time = start;
+            span.end_time = Some(start + duration);
+            span
+        };
+
+        let root_a = span("http_request", None, 0, 100);
+        let trace_a = vec![
+            span("db_query", Some(&root_a), 10, 20),
+            span("validate", Some(&root_a), 40, 5),
+            root_a,
+        ];
+
+        let root_b = span("http_request", None, 0, 180);
+        let trace_b = vec![
+            span("db_query", Some(&root_b), 10, 90),
+            span("cache_lookup", Some(&root_b), 5, 2),
+            root_b,
+        ];
+
+        let diff = TraceDiff::compare(&trace_a, &trace_b);
+        assert_eq!(diff.added, vec!["http_request > cache_lookup"]);
+        assert_eq!(diff.removed, vec!["http_request > validate"]);
+        assert!(!diff.same_structure());
+        assert_eq!(diff.delta("http_request > db_query").unwrap().delta_ms, 70);
+        assert_eq!(diff.regressions(50).len(), 2);
+        assert_eq!(diff.max_regression().unwrap().path, "http_request");
+    }
+
+    #[test]
+    fn test_exporter_tls_and_auth_validation() {
+        use tyl_config::ConfigPlugin;
+
+        let cert = std::env::temp_dir().join(format!("tyl-client-{}.pem", generate_span_id()));
+        std::fs::write(&cert, "-----BEGIN CERTIFICATE-----").unwrap();
+
+        let exporter = ExporterConfig::new("https://collector:4318")
+            .with_header("x-tenant", "acme")
+            .with_auth(ExporterAuth::bearer("s3cr3t"))
+            .with_tls(
+                TlsConfig::new()
+                    .with_ca_bundle(&cert)
+                    .with_client_identity(&cert, &cert),
+            );
+        // https endpoints are only accepted when they can be served
+        assert_eq!(exporter.validate().is_ok(), cfg!(feature = "tls"));
+        assert!(exporter.tls.as_ref().unwrap().is_mutual());
+        assert!(exporter
+            .request_headers()
+            .contains(&("Authorization".to_string(), "Bearer s3cr3t".to_string())));
+        assert!(!format!("{:?}", exporter).contains("s3cr3t"));
+
+        // mTLS needs both halves, TLS needs https, files must exist
+        let half_mtls = TlsConfig {
+            client_cert: Some(cert.clone()),
+            ..TlsConfig::default()
+        };
+        assert!(half_mtls.validate().is_err());
+        assert!(ExporterConfig::new("http://collector:4318")
+            .with_tls(TlsConfig::new())
+            .validate()
+            .is_err());
+        assert!(TlsConfig::new()
+            .with_ca_bundle("/nonexistent/ca.pem")
+            .validate()
+            .is_err());
+        assert!(ExporterConfig::new("https://collector:4318")
+            .with_header("bad header", "x")
+            .validate()
+            .is_err());
+
+        // Checked as part of the tracing config at startup
+        let config = TraceConfig::new("svc")
+            .with_exporter(ExporterConfig::new("https://c").with_auth(ExporterAuth::bearer("")));
+        assert!(config.validate().is_err());
+
+        // Every exporter problem is reported, not just the first
+        let broken = ExporterConfig::new("ftp://collector")
+            .with_batch_size(0)
+            .with_header("bad header", "x")
+            .with_auth(ExporterAuth::bearer(""));
+        assert_eq!(broken.validation_errors().len(), 4);
+        let errors = TraceConfig::new("")
+            .with_exporter(broken)
+            .validation_errors();
+        assert_eq!(errors.len(), 5);
+
+        std::fs::remove_file(&cert).unwrap();
+    }
+
+    #[test]
+    fn test_env_sampling_rate_out_of_range_is_rejected() {
+        use tyl_config::ConfigPlugin;
+
+        // No other unit test reads the tracing environment variables
+        std::env::set_var("TYL_TRACE_SAMPLING_RATE", "1.5");
+        let mut config = TraceConfig::new("svc").with_sampling_rate(0.5);
+        let out_of_range = config.merge_env();
+        std::env::set_var("TYL_TRACE_SAMPLING_RATE", "0.25");
+        let mut accepted = config.clone();
+        let in_range = accepted.merge_env();
+        std::env::remove_var("TYL_TRACE_SAMPLING_RATE");
+
+        assert!(out_of_range
+            .unwrap_err()
+            .to_string()
+            .contains("between 0.0 and 1.0"));
+        assert_eq!(config.sampling_rate, 0.5);
+        in_range.unwrap();
+        assert_eq!(accepted.sampling_rate, 0.25);
+    }
+
+    #[test]
+    fn test_span_filter_directives() {
+        let filter = SpanFilter::parse("checkout=on, health*=off, db.*=0.01, *=0.5").unwrap();
+        assert_eq!(filter.decide("checkout"), Some(FilterAction::Enabled));
+        assert_eq!(filter.decide("healthz"), Some(FilterAction::Disabled));
+        assert_eq!(filter.decide("db.query"), Some(FilterAction::Sample(0.01)));
+        assert_eq!(filter.decide("other"), Some(FilterAction::Sample(0.5)));
+        assert_eq!(SpanFilter::parse("").unwrap().decide("other"), None);
+        assert!(SpanFilter::parse("db=2.0").is_err());
+        assert!(SpanFilter::parse("=on").is_err());
+
+        let tracer = SimpleTracer::new(TraceConfig::new("svc").with_filter("health*=off"));
+        let health = tracer.start_span("health_check", None).unwrap();
+        assert_eq!(health, NOOP_SPAN_ID);
+        // Children of a filtered span are dropped too
+        assert_eq!(
+            tracer.start_span("ping_db", Some(health)).unwrap(),
+            NOOP_SPAN_ID
+        );
+
+        tracer.set_filter_str("health*=on").unwrap();
+        let parent = tracer.start_span("health_check", None).unwrap();
+        let child = tracer.start_span("ping_db", Some(parent.clone())).unwrap();
+        tracer.end_span(child).unwrap();
+        tracer.end_span(parent).unwrap();
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].trace_id, spans[1].trace_id);
+    }
+
+    #[test]
+    fn test_trace_id_ratio_sampling() {
+        assert!(should_sample("any-trace", 1.0));
+        assert!(!should_sample("any-trace", 0.0));
+
+        let kept = (0..2000)
+            .filter(|_| should_sample_trace(generate_trace_id(), 0.25))
+            .count();
+        assert!((350..650).contains(&kept), "kept {} of 2000", kept);
+    }
+
+    #[test]
+    fn test_sampling_priority_from_baggage() {
+        let tracer = SimpleTracer::new(
+            TraceConfig::new("test-service")
+                .with_sampling_rate(0.0)
+                .with_filter("db.*=0.0"),
+        );
+        assert_eq!(tracer.start_span("request", None).unwrap(), NOOP_SPAN_ID);
+
+        tracer.set_baggage(SAMPLING_PRIORITY_KEY, "1");
+        let root = tracer.start_span("request", None).unwrap();
+        assert_ne!(root, NOOP_SPAN_ID);
+        // Children of a forced trace skip rate-based filter directives too
+        let query = tracer.start_span("db.query", Some(root.clone())).unwrap();
+        assert_ne!(query, NOOP_SPAN_ID);
+
+        let sampled_rate = SimpleTracer::new(TraceConfig::new("test-service"));
+        sampled_rate.set_baggage(SAMPLING_PRIORITY_KEY, "0");
+        assert_eq!(
+            sampled_rate.start_span("request", None).unwrap(),
+            NOOP_SPAN_ID
+        );
+        assert_eq!(parse_sampling_priority("debug"), Some(1));
+    }
+
+    #[test]
+    fn test_sampling_priority_overrides_unsampled_remote_parent() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let parent = TraceContext::new(TraceId::random(), SpanId::random(), false);
+        let dropped = tracer
+            .start_span_with_remote_parent("handle", &parent, SpanKind::Server)
+            .unwrap();
+        assert_eq!(dropped, NOOP_SPAN_ID);
+
+        tracer.set_baggage(SAMPLING_PRIORITY_KEY, "1");
+        let forced = tracer
+            .start_span_with_remote_parent("handle", &parent, SpanKind::Server)
+            .unwrap();
+        assert_ne!(forced, NOOP_SPAN_ID);
+        let child = tracer.start_span("query", Some(forced.clone())).unwrap();
+        assert_ne!(child, NOOP_SPAN_ID);
+        tracer.end_span(child).unwrap();
+        tracer.end_span(forced).unwrap();
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans.len(), 2);
+        assert!(spans.iter().all(|span| span.trace_id == parent.trace_id));
+        let handle = spans.iter().find(|span| span.operation_name == "handle");
+        assert_eq!(handle.unwrap().parent_span_id, Some(parent.span_id));
+
+        // A priority of 0 drops the trace even when the caller sampled it
+        tracer.set_baggage(SAMPLING_PRIORITY_KEY, "0");
+        let sampled = TraceContext::new(TraceId::random(), SpanId::random(), true);
+        assert_eq!(
+            tracer
+                .start_span_with_remote_parent("handle", &sampled, SpanKind::Server)
+                .unwrap(),
+            NOOP_SPAN_ID
+        );
+    }
+
+    #[test]
+    fn test_format_trace_tree() {
+        let span = |name: &str, parent: Option<&Span>, start: u64, end: u64| {
+            let mut span = Span::new(name.to_string(), parent.map(|p| p.span_id));
+            span.start_time = start;
+            span.end_time = Some(end);
+            span.status = SpanStatus::Completed;
+            span
+        };
+        let root = span("http_request", None, 0, 1500);
+        let mut query = span("db_query", Some(&root), 10, 40);
+        query
+            .attributes
+            .insert("db.rows".to_string(), serde_json::json!(3));
+        let mut render = span("render", Some(&root), 50, 1400);
+        render.status = SpanStatus::Error {
+            message: "template missing".to_string(),
+        };
+        let partial = span("partial", Some(&render), 60, 70);
+        let spans = vec![render.clone(), partial, root, query];
+
+        let tree = format_trace(
+            &spans,
+            FormatOptions::new()
+                .with_duration_unit(DurationUnit::Auto)
+                .with_attributes(["db.rows"]),
+        );
+        assert_eq!(
+            tree,
+            concat!(
+                "http_request 1.50s\n",
+                "├─ db_query 30ms\n",
+                "│     db.rows = 3\n",
+                "└─ render 1.35s error: template missing\n",
+                "   └─ partial 10ms\n",
+            )
+        );
+
+        let shallow = format_trace(&spans, FormatOptions::new().with_max_depth(0));
+        assert_eq!(shallow, "http_request 1500ms\n└─ … 3 more spans\n");
+        assert!(format_trace(&spans, FormatOptions::new().with_color(true)).contains("\x1b[31m"));
+    }
+
+    #[test]
+    fn test_dyn_tracer_is_shareable() {
+        fn record(tracer: impl TracingManager, name: &str) -> String {
+            let span_id = tracer.start_span(name, None).unwrap();
+            tracer.end_span(span_id.clone()).unwrap();
+            span_id
+        }
+
+        let tracer: DynTracer = Arc::new(SimpleTracer::new(TraceConfig::new("test-service")));
+        record(&tracer, "by_ref");
+        record(tracer.clone(), "by_arc");
+        let worker = tracer.clone();
+        std::thread::spawn(move || record(worker, "worker"))
+            .join()
+            .unwrap();
+
+        let names: Vec<_> = tracer
+            .get_completed_spans()
+            .into_iter()
+            .map(|span| span.operation_name)
+            .collect();
+        assert_eq!(names, ["by_ref", "by_arc", "worker"]);
+    }
+
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    #[test]
+    fn test_binary_encodings_round_trip() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("publish", None).unwrap();
+        tracer
+            .add_span_attribute(
+                &root,
+                "payload",
+                serde_json::json!({"bytes": 512, "ratio": 0.5}),
+            )
+            .unwrap();
+        tracer
+            .span_heartbeat(&root, serde_json::json!("halfway"))
+            .unwrap();
+        tracer.end_span(root).unwrap();
+        let spans = tracer.get_completed_spans();
+        let json = serde_json::to_value(&spans).unwrap();
+
+        let from_msgpack = Span::from_msgpack(&spans[0].to_msgpack().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(from_msgpack).unwrap(), json[0]);
+        let from_cbor = Span::from_cbor(&spans[0].to_cbor().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(from_cbor).unwrap(), json[0]);
+
+        let msgpack = spans_to_msgpack(&spans).unwrap();
+        let cbor = spans_to_cbor(&spans).unwrap();
+        assert!(msgpack.len() < serde_json::to_vec(&spans).unwrap().len());
+        assert_eq!(
+            serde_json::to_value(spans_from_msgpack(&msgpack).unwrap()).unwrap(),
+            json
+        );
+        assert_eq!(
+            serde_json::to_value(spans_from_cbor(&cbor).unwrap()).unwrap(),
+            json
+        );
+        assert!(spans_from_cbor(&msgpack).is_err());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_spans_to_arrow_record_batch() {
+        use arrow_array::{Array, StringArray};
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("job", None).unwrap();
+        let step = tracer.start_span("step", Some(root.clone())).unwrap();
+        tracer.end_span(step).unwrap();
+        tracer.end_span(root).unwrap();
+        let spans = tracer.get_completed_spans();
+
+        let batch = to_arrow(&spans);
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), span_schema());
+        let parents = batch.column_by_name("parent_span_id").unwrap();
+        assert!(parents.is_valid(0));
+        assert!(parents.is_null(1));
+        let status = batch.column_by_name("status").unwrap();
+        let status = status.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(status.value(1), "ok");
+        assert_eq!(to_arrow(&[]).num_rows(), 0);
+    }
+
+    #[cfg(feature = "otel")]
+    #[test]
+    fn test_span_data_round_trip() {
+        use opentelemetry::trace::{SpanId, Status};
+        use opentelemetry_sdk::export::trace::SpanData;
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("checkout", None).unwrap();
+        let charge = tracer
+            .start_span_with_kind("charge", Some(root.clone()), SpanKind::Client)
+            .unwrap();
+        tracer
+            .add_span_attribute(&charge, "items", serde_json::json!(["a", "b"]))
+            .unwrap();
+        tracer
+            .add_span_attribute(&charge, "card", serde_json::json!({"brand": "visa"}))
+            .unwrap();
+        tracer
+            .span_heartbeat(&charge, serde_json::json!(50))
+            .unwrap();
+        tracer.end_span(charge).unwrap();
+        tracer.end_span(root).unwrap();
+        let spans = tracer.get_completed_spans();
+
+        let data = SpanData::from(spans[0].clone());
+        let root_data = SpanData::from(spans[1].clone());
+        assert_eq!(data.parent_span_id, root_data.span_context.span_id());
+        assert_eq!(
+            data.span_context.trace_id(),
+            root_data.span_context.trace_id()
+        );
+        assert_ne!(data.parent_span_id, SpanId::INVALID);
+        assert_eq!(data.status, Status::Ok);
+        assert_eq!(data.events.len(), 1);
+
+        let back = Span::from(data);
+        assert_eq!(back.operation_name, "charge");
+        assert_eq!(back.kind, SpanKind::Client);
+        assert_eq!(back.start_time, spans[0].start_time);
+        assert_eq!(back.attributes["items"], serde_json::json!(["a", "b"]));
+        assert_eq!(back.attributes["card"], r#"{"brand":"visa"}"#);
+        assert_eq!(back.events[0].name, HEARTBEAT_EVENT);
+        assert!(Span::from(root_data).parent_span_id.is_none());
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded_core_spans_convert_on_host() {
+        struct TickClock(std::cell::Cell<u64>);
+        impl Clock for TickClock {
+            fn now_ns(&self) -> u64 {
+                self.0.set(self.0.get() + 1_000_000);
+                self.0.get()
+            }
+        }
+
+        let limits = CoreLimits {
+            max_active: 2,
+            max_completed: 2,
+            max_attributes: 1,
+        };
+        let mut tracer =
+            CoreTracer::with_limits(TickClock(Default::default()), XorShiftIds::new(7), limits);
+        let root = tracer.start_span("sensor_cycle", None).unwrap();
+        let read = tracer.start_span("adc_read", Some(root)).unwrap();
+        assert!(tracer.start_span("over_cap", Some(root)).is_none());
+        assert!(tracer.set_attribute(read, "channel", 3));
+        assert!(!tracer.set_attribute(read, "gain", 2));
+        assert!(tracer.end_span_with_error(read, "timeout"));
+        assert!(tracer.end_span(root));
+        assert_eq!(tracer.dropped_spans(), 1);
+
+        let payload = serde_json::to_string(&tracer.drain().collect::<Vec<_>>()).unwrap();
+        assert!(tracer.completed().is_empty());
+        let shipped: Vec<CoreSpan> = serde_json::from_str(&payload).unwrap();
+        let spans: Vec<Span> = shipped
+            .into_iter()
+            .map(|span| Span::from_core(span, 1_700_000_000_000_000_000))
+            .collect();
+
+        assert_eq!(spans[0].operation_name, "adc_read");
+        assert_eq!(spans[0].trace_id, spans[1].trace_id);
+        assert_eq!(spans[0].parent_span_id, Some(spans[1].span_id));
+        assert_eq!(spans[0].attributes["channel"], 3);
+        assert!(matches!(spans[0].status, SpanStatus::Error { .. }));
+        assert_eq!(spans[1].start_time, 1_700_000_000_001);
+        assert_eq!(spans[1].duration_ms(), Some(3));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_rayon_sections_keep_parentage() {
+        use rayon::prelude::*;
+
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("import", None).unwrap();
+        let _guard = Context::with_span(root.clone()).attach();
+
+        in_span_scope(|| {
+            (0..6).into_par_iter().for_each(|_| {
+                let child = tracer.start_span("row", None).unwrap();
+                tracer.end_span(child).unwrap();
+            })
+        });
+        for_each_traced(&tracer, "chunk", &[1, 2, 3, 4, 5], 2, |_| {
+            let item = tracer.start_span("item", None).unwrap();
+            tracer.end_span(item).unwrap();
+        });
+        tracer.end_span(root.clone()).unwrap();
+
+        let spans = tracer.get_completed_spans();
+        let named = |name: &str| -> Vec<&Span> {
+            spans
+                .iter()
+                .filter(|span| span.operation_name == name)
+                .collect()
+        };
+        let child_of = |span: &Span, parent: SpanId| span.parent_span_id == Some(parent);
+        assert!(named("row")
+            .iter()
+            .all(|span| child_of(span, root.parse().unwrap())));
+        let chunks = named("chunk");
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|span| child_of(span, root.parse().unwrap())));
+        let items = named("item");
+        assert_eq!(items.len(), 5);
+        assert!(items
+            .iter()
+            .all(|item| chunks.iter().any(|chunk| child_of(item, chunk.span_id))));
+        let batched: u64 = chunks
+            .iter()
+            .map(|span| span.attributes[BATCH_ITEMS_ATTRIBUTE].as_u64().unwrap())
+            .sum();
+        assert_eq!(batched, 5);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_rayon_section_detaches_on_panic() {
+        use rayon::prelude::*;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let root = tracer.start_span("import", None).unwrap();
+        let _guard = Context::with_span(root.clone()).attach();
+
+        let current = Context::current();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.install(|| {
+                let _guard = current.attach();
+                in_span_scope(|| {
+                    (0..4).into_par_iter().for_each(|row| {
+                        assert_ne!(row, 3, "bad row");
+                    })
+                })
+            })
+        }));
+        assert!(panicked.is_err());
+        let leftover = pool.broadcast(|_| Context::current());
+        assert!(leftover.iter().all(|context| context.span_id().is_none()));
+        tracer.end_span(root).unwrap();
+    }
+
+    #[cfg(feature = "cpu-time")]
+    #[test]
+    fn test_cpu_time_attributes() {
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service"));
+        let span_id = tracer.start_span("compute", None).unwrap();
+        let mut acc = 0u64;
+        for i in 0..2_000_000u64 {
+            acc = std::hint::black_box(acc.wrapping_add(i * i));
+        }
+        tracer.end_span(span_id).unwrap();
+
+        let span = &tracer.get_completed_spans()[0];
+        assert!(span.attributes[CPU_TIME_ATTRIBUTE].as_u64().unwrap() > 0);
+        let utilization = span.attributes[CPU_UTILIZATION_ATTRIBUTE].as_f64().unwrap();
+        assert!((0.0..=1.0).contains(&utilization));
+    }
+
+    #[cfg(feature = "tokio-metrics")]
+    #[test]
+    fn test_tokio_runtime_metrics_attributes() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .unwrap();
+        let tracer = SimpleTracer::new(TraceConfig::new("test-service")).with_runtime_metrics();
+
+        let span_id = runtime.block_on(async { tracer.start_span("handler", None).unwrap() });
+        runtime.block_on(async { tracer.end_span(span_id).unwrap() });
+        let outside = tracer.start_span("outside_runtime", None).unwrap();
+        tracer.end_span(outside).unwrap();
+
+        let spans = tracer.get_completed_spans();
+        assert_eq!(spans[0].attributes[TOKIO_WORKERS_ATTRIBUTE], 2);
+        assert!(!spans[1].attributes.contains_key(TOKIO_WORKERS_ATTRIBUTE));
+    }
+
+    #[test]
+    fn test_runtime_kill_switch() {
+        let tracer = SimpleTracer::default();
+        assert!(tracer.is_enabled());
+
+        tracer.set_enabled(false);
+        let span_id = tracer.start_span("under_incident_load", None).unwrap();
+        assert_eq!(span_id, NOOP_SPAN_ID);
+        tracer
+            .add_span_attribute(&span_id, "ignored", serde_json::json!(true))
+            .unwrap();
+        tracer.end_span(span_id).unwrap();
+        assert!(tracer.get_completed_spans().is_empty());
+
+        tracer.set_enabled(true);
+        let span_id = tracer.start_span("recovered", None).unwrap();
+        assert_ne!(span_id, NOOP_SPAN_ID);
+        tracer.end_span(span_id).unwrap();
+        assert_eq!(tracer.get_completed_spans().len(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "disabled"))]
+mod disabled_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tyl_errors::TylError;
+
+    #[test]
+    fn test_disabled_feature_records_nothing() {
+        let tracer = SimpleTracer::default();
+        tracer.set_enabled(true);
+        assert!(!tracer.is_enabled());
+
+        let span_id = tracer.start_span("compiled_out", None).unwrap();
+        assert_eq!(span_id, NOOP_SPAN_ID);
+        tracer
+            .add_span_attribute(&span_id, "key", serde_json::json!("value"))
+            .unwrap();
+        tracer.end_span(span_id).unwrap();
+        assert!(tracer.get_completed_spans().is_empty());
+        assert_eq!(std::mem::size_of::<NoopTracer>(), 0);
+        assert_eq!(std::mem::size_of::<SimpleTracer>(), 0);
+    }
+
+    #[test]
+    fn test_disabled_span_ids_do_not_allocate() {
+        let tracer = SimpleTracer::new(TraceConfig::new("disabled-service"));
+        let span_id = tracer.start_span("compiled_out", None).unwrap();
+        assert_eq!(span_id.capacity(), 0);
+        assert!(!is_recording(&span_id));
+
+        let handle = tracer.start("handle");
+        assert!(!handle.is_recording());
+        assert_eq!(handle.span_id().len(), 0);
+        let timer = tracer.section_timer(&span_id, "render");
+        assert_eq!(timer.name(), "");
+    }
+
+    #[test]
+    fn test_disabled_baggage_and_events_record_nothing() {
+        let tracer = SimpleTracer::default();
+        tracer.set_baggage("user.id", "42");
+        tracer.try_set_baggage("tenant", "acme").unwrap();
+        assert_eq!(tracer.get_baggage("user.id"), None);
+
+        let span_id = tracer.start_span("compiled_out", None).unwrap();
+        tracer
+            .add_span_event(&span_id, "event", HashMap::new())
+            .unwrap();
+        tracer
+            .record_error(&span_id, &TylError::network("down"))
+            .unwrap();
+        tracer.time_section(&span_id, "phase", || ());
+        assert!(tracer.active_spans().is_empty());
+        assert_eq!(tracer.health(), TracerHealth::disabled());
+    }
 }
@@ -0,0 +1,209 @@
+//! Decode limits module
+//!
+//! Contains DecodeLimits, resource caps for spans read from untrusted
+//! sources (files handed in for import, payloads received from other
+//! services). The encoded size is checked before anything is decoded, which
+//! bounds what decoding can allocate; decoded spans are then held to
+//! per-span caps on attributes, events, links, string lengths and value
+//! nesting. Anything over a cap is a validation error naming the field.
+
+use crate::span::{Span, SpanStatus};
+use crate::tracer::TracingResult;
+use serde_json::Value;
+use std::collections::HashMap;
+use tyl_errors::TylError;
+
+/// Resource caps applied when decoding spans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Largest encoded payload (one span, one batch or one file line)
+    pub max_input_bytes: usize,
+    /// Most spans in one batch
+    pub max_spans: usize,
+    /// Most attributes on a span, event or link
+    pub max_attributes: usize,
+    /// Longest string anywhere in a span, in bytes
+    pub max_string_len: usize,
+    pub max_events: usize,
+    pub max_links: usize,
+    /// Deepest nesting of arrays/objects in an attribute value
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: 16 * 1024 * 1024,
+            max_spans: 65_536,
+            max_attributes: 1024,
+            max_string_len: 64 * 1024,
+            max_events: 1024,
+            max_links: 1024,
+            max_depth: 16,
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_input_bytes(mut self, max: usize) -> Self {
+        self.max_input_bytes = max;
+        self
+    }
+
+    pub fn with_max_spans(mut self, max: usize) -> Self {
+        self.max_spans = max;
+        self
+    }
+
+    pub fn with_max_attributes(mut self, max: usize) -> Self {
+        self.max_attributes = max;
+        self
+    }
+
+    pub fn with_max_string_len(mut self, max: usize) -> Self {
+        self.max_string_len = max;
+        self
+    }
+
+    pub fn with_max_events(mut self, max: usize) -> Self {
+        self.max_events = max;
+        self
+    }
+
+    pub fn with_max_links(mut self, max: usize) -> Self {
+        self.max_links = max;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Reject an encoded payload larger than `max_input_bytes`
+    pub fn check_input(&self, bytes: &[u8]) -> TracingResult<()> {
+        if bytes.len() > self.max_input_bytes {
+            return Err(exceeded(format!(
+                "input of {} bytes exceeds the {} byte limit",
+                bytes.len(),
+                self.max_input_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check a decoded span against every per-span cap
+    pub fn check_span(&self, span: &Span) -> TracingResult<()> {
+        self.check_str("operation_name", &span.operation_name)?;
+        if let SpanStatus::Error { message } = &span.status {
+            self.check_str("status message", message)?;
+        }
+        self.check_count("attributes", span.attributes.len(), self.max_attributes)?;
+        for (key, value) in span.attributes.iter() {
+            self.check_str("attribute key", key)?;
+            self.check_value(key, value)?;
+        }
+        self.check_count("events", span.events.len(), self.max_events)?;
+        for event in &span.events {
+            self.check_str("event name", &event.name)?;
+            self.check_attributes(&event.attributes)?;
+        }
+        self.check_count("links", span.links.len(), self.max_links)?;
+        for link in &span.links {
+            self.check_attributes(&link.attributes)?;
+        }
+        Ok(())
+    }
+
+    /// Check a decoded batch: its size, then each span
+    pub fn check_spans(&self, spans: &[Span]) -> TracingResult<()> {
+        self.check_count("spans", spans.len(), self.max_spans)?;
+        spans.iter().try_for_each(|span| self.check_span(span))
+    }
+
+    /// Decode one JSON span within the limits
+    pub fn span_from_json(&self, bytes: &[u8]) -> TracingResult<Span> {
+        self.check_input(bytes)?;
+        let span = serde_json::from_slice(bytes).map_err(decoding)?;
+        self.check_span(&span)?;
+        Ok(span)
+    }
+
+    /// Decode a JSON array of spans within the limits
+    pub fn spans_from_json(&self, bytes: &[u8]) -> TracingResult<Vec<Span>> {
+        self.check_input(bytes)?;
+        let spans: Vec<Span> = serde_json::from_slice(bytes).map_err(decoding)?;
+        self.check_spans(&spans)?;
+        Ok(spans)
+    }
+
+    fn check_attributes(&self, attributes: &HashMap<String, Value>) -> TracingResult<()> {
+        self.check_count("attributes", attributes.len(), self.max_attributes)?;
+        attributes.iter().try_for_each(|(key, value)| {
+            self.check_str("attribute key", key)?;
+            self.check_value(key, value)
+        })
+    }
+
+    fn check_value(&self, key: &str, value: &Value) -> TracingResult<()> {
+        let mut pending = vec![(value, 0)];
+        while let Some((value, depth)) = pending.pop() {
+            if depth > self.max_depth {
+                return Err(exceeded(format!(
+                    "attribute '{}' nests deeper than {} levels",
+                    key, self.max_depth
+                )));
+            }
+            match value {
+                Value::String(string) => self.check_str("attribute value", string)?,
+                Value::Array(values) => {
+                    self.check_count("array elements", values.len(), self.max_attributes)?;
+                    pending.extend(values.iter().map(|value| (value, depth + 1)));
+                }
+                Value::Object(fields) => {
+                    self.check_count("object fields", fields.len(), self.max_attributes)?;
+                    for (field, value) in fields {
+                        self.check_str("attribute key", field)?;
+                        pending.push((value, depth + 1));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn check_str(&self, what: &str, value: &str) -> TracingResult<()> {
+        if value.len() > self.max_string_len {
+            return Err(exceeded(format!(
+                "{} of {} bytes exceeds the {} byte limit",
+                what,
+                value.len(),
+                self.max_string_len
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_count(&self, what: &str, count: usize, max: usize) -> TracingResult<()> {
+        if count > max {
+            return Err(exceeded(format!(
+                "{} {} exceed the limit of {}",
+                count, what, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn exceeded(message: String) -> TylError {
+    TylError::validation("decode_limits", message)
+}
+
+fn decoding(e: serde_json::Error) -> TylError {
+    TylError::serialization(format!("JSON span decoding failed: {}", e))
+}
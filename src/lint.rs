@@ -0,0 +1,167 @@
+//! Attribute key lint module
+//!
+//! Contains the optional check of attribute keys against the OpenTelemetry
+//! naming rules: lowercase, dot-separated namespaces, letters, digits and
+//! underscores only. Keys that spell a semantic-convention namespace with an
+//! underscore (`http_status`) are flagged with the dotted form. Enabled with
+//! `TraceConfig::with_attribute_key_validation`, so teams converge on
+//! consistent keys before dashboards depend on them.
+
+use crate::tracer::TracingResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Mutex;
+use tyl_errors::TylError;
+
+/// Namespaces of the OpenTelemetry semantic conventions (and this crate's)
+pub const SEMCONV_NAMESPACES: &[&str] = &[
+    "cache",
+    "client",
+    "cloud",
+    "code",
+    "container",
+    "db",
+    "deployment",
+    "enduser",
+    "error",
+    "exception",
+    "faas",
+    "feature_flag",
+    "graphql",
+    "host",
+    "http",
+    "job",
+    "k8s",
+    "messaging",
+    "net",
+    "network",
+    "os",
+    "peer",
+    "process",
+    "rpc",
+    "server",
+    "service",
+    "session",
+    "telemetry",
+    "thread",
+    "url",
+    "user",
+    "user_agent",
+];
+
+/// What happens when an attribute key breaks the naming rules
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyValidation {
+    /// Keys are not checked
+    #[default]
+    Off,
+    /// The attribute is kept; each offending key is reported once as a `tracing`
+    /// warning and listed by `SimpleTracer::attribute_key_violations`
+    Warn,
+    /// The attribute is rejected with a validation error
+    Deny,
+}
+
+/// Why an attribute key breaks the naming rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyViolation {
+    Empty,
+    Uppercase,
+    /// A character other than a lowercase letter, digit, `_` or `.`
+    InvalidCharacter(char),
+    /// Leading, trailing, or doubled dot, or a segment not starting with a letter
+    MalformedSegment,
+    /// No `namespace.` prefix
+    Unnamespaced,
+    /// A semantic-convention namespace joined with `_`; holds the dotted key
+    UnderscoreNamespace(String),
+}
+
+impl fmt::Display for KeyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyViolation::Empty => write!(f, "key is empty"),
+            KeyViolation::Uppercase => write!(f, "key must be lowercase"),
+            KeyViolation::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+            KeyViolation::MalformedSegment => {
+                write!(f, "segments must be non-empty and start with a letter")
+            }
+            KeyViolation::Unnamespaced => write!(f, "key needs a dot-separated namespace"),
+            KeyViolation::UnderscoreNamespace(dotted) => write!(f, "use `{}`", dotted),
+        }
+    }
+}
+
+/// Check a key against the naming rules, returning the first violation
+pub fn check_attribute_key(key: &str) -> Result<(), KeyViolation> {
+    if key.is_empty() {
+        return Err(KeyViolation::Empty);
+    }
+    if key.chars().any(|c| c.is_uppercase()) {
+        return Err(KeyViolation::Uppercase);
+    }
+    if let Some(c) = key
+        .chars()
+        .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '_' || *c == '.'))
+    {
+        return Err(KeyViolation::InvalidCharacter(c));
+    }
+    if key
+        .split('.')
+        .any(|segment| !segment.starts_with(|c: char| c.is_ascii_lowercase()))
+    {
+        return Err(KeyViolation::MalformedSegment);
+    }
+    if !key.contains('.') {
+        // `user_agent` and `feature_flag` are namespaces themselves
+        let known = SEMCONV_NAMESPACES
+            .iter()
+            .filter(|namespace| !namespace.contains('_'))
+            .find(|namespace| {
+                key.strip_prefix(**namespace)
+                    .is_some_and(|rest| rest.len() > 1 && rest.starts_with('_'))
+            });
+        return Err(match known {
+            Some(namespace) => KeyViolation::UnderscoreNamespace(format!(
+                "{}.{}",
+                namespace,
+                &key[namespace.len() + 1..]
+            )),
+            None => KeyViolation::Unnamespaced,
+        });
+    }
+    Ok(())
+}
+
+/// Applies a KeyValidation mode, remembering which keys were reported
+#[derive(Debug, Default)]
+pub(crate) struct KeyLinter {
+    reported: Mutex<BTreeSet<String>>,
+}
+
+impl KeyLinter {
+    pub(crate) fn check(&self, mode: KeyValidation, key: &str) -> TracingResult<()> {
+        if mode == KeyValidation::Off {
+            return Ok(());
+        }
+        let Err(violation) = check_attribute_key(key) else {
+            return Ok(());
+        };
+        if mode == KeyValidation::Deny {
+            return Err(TylError::validation(
+                "key",
+                format!("attribute key {:?}: {}", key, violation),
+            ));
+        }
+        if self.reported.lock().unwrap().insert(key.to_string()) {
+            tracing::warn!(target: "tyl_tracing", key = %key, "{}", violation);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn reported(&self) -> Vec<String> {
+        self.reported.lock().unwrap().iter().cloned().collect()
+    }
+}
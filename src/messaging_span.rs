@@ -0,0 +1,112 @@
+//! Messaging span module
+//!
+//! Contains MessagingSpan, a SpanHandle for publishing or consuming messages
+//! that sets the `messaging.*` attributes of the OpenTelemetry messaging
+//! semantic conventions. A consumer processing a batch cannot have every
+//! producer as its parent, so it links to the context extracted from each
+//! message's headers instead.
+
+use crate::handle::SpanHandle;
+use crate::propagation::{Extractor, Injector, TraceContext};
+use crate::span::{SpanKind, SpanLink};
+use crate::tracer::SimpleTracer;
+use std::collections::HashMap;
+
+/// Broker product, e.g. `kafka`, `rabbitmq`, `aws_sqs`
+pub const MESSAGING_SYSTEM: &str = "messaging.system";
+/// Topic or queue name
+pub const MESSAGING_DESTINATION_NAME: &str = "messaging.destination.name";
+/// `publish` or `process`
+pub const MESSAGING_OPERATION: &str = "messaging.operation";
+pub const MESSAGING_MESSAGE_ID: &str = "messaging.message.id";
+pub const MESSAGING_BATCH_MESSAGE_COUNT: &str = "messaging.batch.message_count";
+
+/// Span of publishing or processing messages; ends when dropped
+#[must_use = "the span ends when the MessagingSpan is dropped"]
+pub struct MessagingSpan<'a> {
+    span: SpanHandle<'a>,
+}
+
+impl<'a> MessagingSpan<'a> {
+    /// Producer span for sending to `destination`, named `"{destination} publish"`
+    pub fn publish(tracer: &'a SimpleTracer, system: &str, destination: &str) -> Self {
+        Self::new(tracer, system, destination, "publish", SpanKind::Producer)
+    }
+
+    /// Consumer span for processing `messages`, named `"{destination} process"`
+    ///
+    /// Each message whose headers carry a valid trace context becomes a link;
+    /// messages without one are still counted.
+    pub fn consume<'m, E>(
+        tracer: &'a SimpleTracer,
+        system: &str,
+        destination: &str,
+        messages: impl IntoIterator<Item = &'m E>,
+    ) -> Self
+    where
+        E: Extractor + 'm,
+    {
+        let this = Self::new(tracer, system, destination, "process", SpanKind::Consumer);
+        if !this.span.is_recording() {
+            return this;
+        }
+        let mut count = 0u64;
+        let links: Vec<SpanLink> = messages
+            .into_iter()
+            .inspect(|_| count += 1)
+            .filter_map(|headers| TraceContext::extract(headers))
+            .map(|context| SpanLink {
+                trace_id: context.trace_id,
+                span_id: context.span_id,
+                attributes: HashMap::new(),
+            })
+            .collect();
+        this.span
+            .set_attribute(MESSAGING_BATCH_MESSAGE_COUNT, count);
+        let _ = tracer.update_span(this.span.span_id(), |span| span.links.extend(links));
+        this
+    }
+
+    fn new(
+        tracer: &'a SimpleTracer,
+        system: &str,
+        destination: &str,
+        operation: &'static str,
+        kind: SpanKind,
+    ) -> Self {
+        let span = tracer.start_with_kind(&format!("{} {}", destination, operation), kind);
+        if span.is_recording() {
+            span.set_attribute(MESSAGING_SYSTEM, system.to_string());
+            span.set_attribute(MESSAGING_DESTINATION_NAME, destination.to_string());
+            span.set_attribute(MESSAGING_OPERATION, operation);
+        }
+        Self { span }
+    }
+
+    /// Handle of the underlying span
+    pub fn span(&self) -> &SpanHandle<'a> {
+        &self.span
+    }
+
+    /// Write this span's context into the outgoing message's headers
+    pub fn inject(&self, headers: &mut dyn Injector) {
+        if let Some(context) = self.span.tracer().span_context(self.span.span_id()) {
+            context.inject(headers);
+        }
+    }
+
+    pub fn set_message_id(&self, id: &str) {
+        self.span
+            .set_attribute(MESSAGING_MESSAGE_ID, id.to_string());
+    }
+
+    /// Mark the operation failed with `message`
+    pub fn set_error(&self, message: impl Into<String>) {
+        self.span.set_error(message);
+    }
+
+    /// End now instead of at the end of the scope
+    pub fn end(self) {
+        self.span.end();
+    }
+}
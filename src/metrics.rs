@@ -0,0 +1,62 @@
+//! Span metrics module
+//!
+//! Contains MetricsRecorder, the port through which span-derived counters
+//! and histograms leave the tracer, and `SimpleTracer::publish_span_metrics`
+//! wiring it to span ends. A metrics facade (such as a future tyl-metrics
+//! crate) implements the port once, so metrics and tracing share one
+//! emission path instead of each service counting spans by hand.
+
+use crate::span::{Span, SpanStatus};
+use crate::tracer::SimpleTracer;
+use std::sync::Arc;
+
+/// Counter of ended spans, labeled by operation, kind and status
+pub const SPANS_ENDED_METRIC: &str = "tyl_tracing_spans_ended_total";
+/// Histogram of span durations in seconds, labeled like the counter
+pub const SPAN_DURATION_METRIC: &str = "tyl_tracing_span_duration_seconds";
+
+/// Port to a metrics facade (adapter side implements it)
+pub trait MetricsRecorder: Send + Sync {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64);
+
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64);
+}
+
+impl<T: MetricsRecorder + ?Sized> MetricsRecorder for Arc<T> {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        (**self).increment_counter(name, labels, value)
+    }
+
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        (**self).record_histogram(name, labels, value)
+    }
+}
+
+/// Record `span` on `recorder`: one count and, if ended, its duration
+pub fn record_span_metrics(recorder: &dyn MetricsRecorder, span: &Span) {
+    let status = match span.status {
+        SpanStatus::Active => "active",
+        SpanStatus::Completed => "ok",
+        SpanStatus::Error { .. } => "error",
+    };
+    let kind = format!("{:?}", span.kind).to_lowercase();
+    let labels = [
+        ("operation", span.operation_name.as_str()),
+        ("kind", kind.as_str()),
+        ("status", status),
+    ];
+    recorder.increment_counter(SPANS_ENDED_METRIC, &labels, 1);
+    if let Some(ms) = span.duration_ms() {
+        recorder.record_histogram(SPAN_DURATION_METRIC, &labels, ms as f64 / 1000.0);
+    }
+}
+
+impl SimpleTracer {
+    /// Publish a counter and a duration histogram for every recorded span
+    /// as it ends (see `SPANS_ENDED_METRIC` and `SPAN_DURATION_METRIC`)
+    ///
+    /// Runs as a span-end observer, so unsampled spans are not counted.
+    pub fn publish_span_metrics(&self, recorder: impl MetricsRecorder + 'static) {
+        self.register_on_end(move |span| record_span_metrics(&recorder, span));
+    }
+}
@@ -0,0 +1,325 @@
+//! MQTT export module
+//!
+//! Contains MqttExporter, which publishes serialized span batches to a topic on
+//! an MQTT 3.1.1 broker so edge devices can forward traces through existing IoT
+//! infrastructure. Each batch opens a short-lived connection (clean session),
+//! publishes one message with the configured QoS, and disconnects.
+
+use crate::export::SpanExporter;
+use crate::otlp::to_otlp_json;
+use crate::span::Span;
+use crate::tracer::TracingResult;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
+use tyl_errors::TylError;
+use uuid::Uuid;
+
+/// Default port of unencrypted MQTT brokers
+pub const MQTT_DEFAULT_PORT: u16 = 1883;
+
+/// Largest remaining length an MQTT packet can declare
+const MAX_REMAINING_LENGTH: usize = 268_435_455;
+const KEEP_ALIVE_SECS: u16 = 60;
+
+/// MQTT delivery guarantee for published batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MqttQos {
+    /// Fire and forget (QoS 0)
+    AtMostOnce,
+    /// Acknowledged by the broker, may be duplicated (QoS 1)
+    #[default]
+    AtLeastOnce,
+    /// Four-way handshake, delivered once (QoS 2)
+    ExactlyOnce,
+}
+
+impl MqttQos {
+    fn level(self) -> u8 {
+        match self {
+            MqttQos::AtMostOnce => 0,
+            MqttQos::AtLeastOnce => 1,
+            MqttQos::ExactlyOnce => 2,
+        }
+    }
+}
+
+/// Adapter - Publishes span batches to an MQTT topic
+///
+/// Payloads are a JSON array of spans, or an OTLP JSON request with
+/// [`MqttExporter::with_otlp_json`] for brokers bridged to a collector.
+#[derive(Debug)]
+pub struct MqttExporter {
+    host: String,
+    port: u16,
+    topic: String,
+    client_id: String,
+    qos: MqttQos,
+    credentials: Option<(String, String)>,
+    timeout: Duration,
+    otlp_service: Option<String>,
+    next_packet_id: AtomicU16,
+}
+
+impl MqttExporter {
+    /// Publish to `topic` on `broker` (`host[:port]`, optionally `mqtt://` or `tcp://`)
+    pub fn new(broker: &str, topic: impl Into<String>) -> TracingResult<Self> {
+        let topic = topic.into();
+        if topic.is_empty() || topic.contains(['+', '#']) {
+            return Err(TylError::validation(
+                "topic",
+                format!("'{}' is not a valid MQTT topic name", topic),
+            ));
+        }
+        let authority = broker
+            .strip_prefix("mqtt://")
+            .or_else(|| broker.strip_prefix("tcp://"))
+            .unwrap_or(broker)
+            .trim_end_matches('/');
+        if authority.contains("://") {
+            return Err(TylError::configuration(format!(
+                "MQTT broker '{}' must be a plain mqtt:// address",
+                broker
+            )));
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>().map_err(|_| {
+                    TylError::configuration(format!("invalid port in '{}'", broker))
+                })?,
+            ),
+            None => (authority, MQTT_DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(TylError::configuration(format!(
+                "missing host in '{}'",
+                broker
+            )));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            topic,
+            client_id: format!("tyl-{}", &Uuid::new_v4().simple().to_string()[..16]),
+            qos: MqttQos::default(),
+            credentials: None,
+            timeout: Duration::from_secs(10),
+            otlp_service: None,
+            next_packet_id: AtomicU16::new(1),
+        })
+    }
+
+    pub fn with_qos(mut self, qos: MqttQos) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Connect, read, and write timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Publish OTLP JSON requests for `service_name` instead of span arrays
+    pub fn with_otlp_json(mut self, service_name: impl Into<String>) -> Self {
+        self.otlp_service = Some(service_name.into());
+        self
+    }
+
+    /// `host:port` of the broker
+    pub fn broker(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn qos(&self) -> MqttQos {
+        self.qos
+    }
+
+    fn network(&self, e: std::io::Error) -> TylError {
+        TylError::network(format!("MQTT export to {} failed: {}", self.broker(), e))
+    }
+
+    fn connect(&self) -> TracingResult<TcpStream> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| self.network(e))?
+            .next()
+            .ok_or_else(|| TylError::network(format!("cannot resolve {}", self.host)))?;
+        let mut stream =
+            TcpStream::connect_timeout(&address, self.timeout).map_err(|e| self.network(e))?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.timeout)))
+            .map_err(|e| self.network(e))?;
+
+        let mut body = Vec::new();
+        put_str(&mut body, "MQTT");
+        body.push(4); // protocol level 3.1.1
+        let mut flags = 0x02; // clean session
+        if self.credentials.is_some() {
+            flags |= 0xC0;
+        }
+        body.push(flags);
+        body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+        put_str(&mut body, &self.client_id);
+        if let Some((username, password)) = &self.credentials {
+            put_str(&mut body, username);
+            put_str(&mut body, password);
+        }
+        self.write_packet(&mut stream, 0x10, &body)?;
+
+        let (kind, body) = self.read_packet(&mut stream)?;
+        match (kind >> 4, body.get(1)) {
+            (2, Some(0)) => Ok(stream),
+            (2, Some(3)) => Err(TylError::network(format!(
+                "MQTT broker {} is unavailable",
+                self.broker()
+            ))),
+            (2, Some(code)) => Err(TylError::configuration(format!(
+                "MQTT broker {} refused the connection (return code {})",
+                self.broker(),
+                code
+            ))),
+            _ => Err(self.protocol_error("CONNACK")),
+        }
+    }
+
+    fn publish(&self, stream: &mut TcpStream, payload: &[u8]) -> TracingResult<()> {
+        let qos = self.qos.level();
+        let packet_id = match self.next_packet_id.fetch_add(1, Ordering::Relaxed) {
+            // Packet identifiers must be non-zero
+            0 => self.next_packet_id.fetch_add(1, Ordering::Relaxed),
+            id => id,
+        };
+        let mut body = Vec::with_capacity(self.topic.len() + payload.len() + 4);
+        put_str(&mut body, &self.topic);
+        if qos > 0 {
+            body.extend_from_slice(&packet_id.to_be_bytes());
+        }
+        body.extend_from_slice(payload);
+        self.write_packet(stream, 0x30 | (qos << 1), &body)?;
+
+        match self.qos {
+            MqttQos::AtMostOnce => Ok(()),
+            MqttQos::AtLeastOnce => self.expect_ack(stream, 4, packet_id, "PUBACK"),
+            MqttQos::ExactlyOnce => {
+                self.expect_ack(stream, 5, packet_id, "PUBREC")?;
+                self.write_packet(stream, 0x62, &packet_id.to_be_bytes())?;
+                self.expect_ack(stream, 7, packet_id, "PUBCOMP")
+            }
+        }
+    }
+
+    fn expect_ack(
+        &self,
+        stream: &mut TcpStream,
+        kind: u8,
+        packet_id: u16,
+        name: &str,
+    ) -> TracingResult<()> {
+        let (header, body) = self.read_packet(stream)?;
+        if header >> 4 == kind && body == packet_id.to_be_bytes() {
+            Ok(())
+        } else {
+            Err(self.protocol_error(name))
+        }
+    }
+
+    fn protocol_error(&self, expected: &str) -> TylError {
+        TylError::network(format!(
+            "MQTT broker {} sent an unexpected packet (expected {})",
+            self.broker(),
+            expected
+        ))
+    }
+
+    fn write_packet(&self, stream: &mut TcpStream, header: u8, body: &[u8]) -> TracingResult<()> {
+        let mut packet = Vec::with_capacity(body.len() + 5);
+        packet.push(header);
+        let mut remaining = body.len();
+        loop {
+            let mut byte = (remaining % 128) as u8;
+            remaining /= 128;
+            if remaining > 0 {
+                byte |= 0x80;
+            }
+            packet.push(byte);
+            if remaining == 0 {
+                break;
+            }
+        }
+        packet.extend_from_slice(body);
+        stream.write_all(&packet).map_err(|e| self.network(e))
+    }
+
+    fn read_packet(&self, stream: &mut TcpStream) -> TracingResult<(u8, Vec<u8>)> {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).map_err(|e| self.network(e))?;
+        let header = byte[0];
+        let mut length = 0usize;
+        for shift in (0..28).step_by(7) {
+            stream.read_exact(&mut byte).map_err(|e| self.network(e))?;
+            length |= ((byte[0] & 0x7F) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).map_err(|e| self.network(e))?;
+                return Ok((header, body));
+            }
+        }
+        Err(self.protocol_error("a valid remaining length"))
+    }
+}
+
+impl SpanExporter for MqttExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let payload = match &self.otlp_service {
+            Some(service) => serde_json::to_vec(&to_otlp_json(service, batch)),
+            None => serde_json::to_vec(batch),
+        }
+        .map_err(|e| TylError::serialization(format!("MQTT payload encoding failed: {}", e)))?;
+        if payload.len() + self.topic.len() + 4 > MAX_REMAINING_LENGTH {
+            return Err(TylError::validation(
+                "batch",
+                format!(
+                    "{} byte payload exceeds the MQTT packet limit",
+                    payload.len()
+                ),
+            ));
+        }
+
+        let mut stream = self.connect()?;
+        self.publish(&mut stream, &payload)?;
+        // The message is delivered once acknowledged; a failed DISCONNECT is harmless
+        let _ = self.write_packet(&mut stream, 0xE0, &[]);
+        Ok(())
+    }
+}
+
+/// MQTT UTF-8 string: big-endian length prefix followed by the bytes
+fn put_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value.as_bytes());
+}
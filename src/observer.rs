@@ -0,0 +1,52 @@
+//! Span observer module
+//!
+//! Contains the span-end observers of SimpleTracer: closures registered with
+//! `register_on_end` run synchronously on every span as it completes, before
+//! it is stored or exported, for custom enrichment or metrics without
+//! implementing an exporter or processor.
+
+use crate::span::Span;
+use crate::tracer::SimpleTracer;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Closure called with each completed span; may add attributes or events
+pub type SpanObserver = Box<dyn FnMut(&mut Span) + Send>;
+
+/// Registered observers, called in registration order
+#[derive(Default)]
+pub(crate) struct SpanObservers {
+    observers: Mutex<Vec<SpanObserver>>,
+}
+
+impl SpanObservers {
+    pub(crate) fn notify(&self, span: &mut Span) {
+        for observer in self.observers.lock().unwrap().iter_mut() {
+            observer(span);
+        }
+    }
+}
+
+impl fmt::Debug for SpanObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.observers.lock().map_or(0, |observers| observers.len());
+        f.debug_struct("SpanObservers")
+            .field("count", &count)
+            .finish()
+    }
+}
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Call `observer` on every span as it ends, before storage and export
+    ///
+    /// Observers run on the thread ending the span, after the tracer's own
+    /// attributes are set; they must not register further observers.
+    pub fn register_on_end(&self, observer: impl FnMut(&mut Span) + Send + 'static) {
+        self.observers
+            .observers
+            .lock()
+            .unwrap()
+            .push(Box::new(observer));
+    }
+}
@@ -0,0 +1,143 @@
+//! OpenMetrics module
+//!
+//! Contains `render_metrics`, the tracer's own counters (spans started,
+//! ended, dropped by reason, exported, failed) and gauges (active spans, export queue
+//! depth) in the OpenMetrics text format, for services to serve from the
+//! `/metrics` route they already have. Every sample carries a `service`
+//! label so several tracers can share one scrape.
+
+use crate::tracer::{SimpleTracer, Tracer};
+
+/// Content type to answer scrapes of `render_metrics` output with
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Prefix of every metric name
+const PREFIX: &str = "tyl_tracing";
+
+impl SimpleTracer {
+    /// Self-observability metrics as a complete OpenMetrics exposition,
+    /// ending with `# EOF`
+    pub fn render_metrics(&self) -> String {
+        let mut output = String::new();
+        self.write_metric_families(&mut output);
+        output.push_str("# EOF\n");
+        output
+    }
+
+    /// Append the metric families without the closing `# EOF`, to combine
+    /// them with a service's own metrics in one exposition
+    pub fn write_metric_families(&self, output: &mut String) {
+        let service = self.config().service_name.clone();
+        let mut families = Families { output, service };
+        let dropped = self.dropped_span_counts();
+        let by_reason = self.dropped_spans_by_reason();
+        let processor = self.batch_processor();
+
+        families.counter(
+            "spans_started",
+            "Spans recorded (sampled and within span caps)",
+            &[(None, self.started_spans())],
+        );
+        families.counter(
+            "spans_ended",
+            "Recorded spans that ended",
+            &[(None, self.ended_spans())],
+        );
+        families.counter(
+            "spans_dropped",
+            "Spans not recorded, by reason",
+            &[
+                (Some(("reason", "trace_limit")), dropped.trace_limit),
+                (Some(("reason", "active_limit")), dropped.active_limit),
+                (Some(("reason", "tenant_quota")), by_reason.quota),
+                (Some(("reason", "filter")), by_reason.filter),
+                (Some(("reason", "sampling")), by_reason.sampling),
+            ],
+        );
+        families.counter(
+            "spans_exported",
+            "Spans delivered to the exporter",
+            &[(
+                None,
+                processor.map_or(0, |processor| processor.exported_spans()),
+            )],
+        );
+        families.counter(
+            "spans_export_failed",
+            "Spans lost because their export failed",
+            &[(
+                None,
+                processor.map_or(0, |processor| processor.failed_spans()),
+            )],
+        );
+        families.counter(
+            "baggage_rejected",
+            "Baggage entries rejected for breaking the W3C limits",
+            &[(None, self.rejected_baggage_entries())],
+        );
+        families.gauge(
+            "active_spans",
+            "Spans started and not yet ended",
+            self.active_span_count() as u64,
+        );
+        families.gauge(
+            "export_queue_depth",
+            "Ended spans waiting for the next export",
+            processor.map_or(0, |processor| processor.queued_spans() as u64),
+        );
+    }
+}
+
+impl Tracer {
+    /// See `SimpleTracer::render_metrics`; empty when tracing is compiled out
+    pub fn render_metrics(&self) -> String {
+        match self.as_simple() {
+            Some(tracer) => tracer.render_metrics(),
+            None => "# EOF\n".to_string(),
+        }
+    }
+}
+
+struct Families<'a> {
+    output: &'a mut String,
+    service: String,
+}
+
+impl Families<'_> {
+    fn counter(&mut self, name: &str, help: &str, samples: &[(Option<(&str, &str)>, u64)]) {
+        self.header(name, "counter", help);
+        for (label, value) in samples {
+            self.sample(&format!("{}_{}_total", PREFIX, name), *label, *value);
+        }
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: u64) {
+        self.header(name, "gauge", help);
+        self.sample(&format!("{}_{}", PREFIX, name), None, value);
+    }
+
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        self.output
+            .push_str(&format!("# TYPE {}_{} {}\n", PREFIX, name, kind));
+        self.output
+            .push_str(&format!("# HELP {}_{} {}\n", PREFIX, name, help));
+    }
+
+    fn sample(&mut self, name: &str, label: Option<(&str, &str)>, value: u64) {
+        let mut labels = format!("service=\"{}\"", escape_label(&self.service));
+        if let Some((key, label_value)) = label {
+            labels.push_str(&format!(",{}=\"{}\"", key, escape_label(label_value)));
+        }
+        self.output
+            .push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+    }
+}
+
+/// Escape a label value (backslash, double quote, newline)
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,219 @@
+//! OpenTelemetry interop module
+//!
+//! Contains conversions between Span and `opentelemetry_sdk`'s SpanData
+//! (enabled with the `otel` cargo feature), so tyl-tracing spans can be fed
+//! to existing OpenTelemetry processors and exporters, and spans produced by
+//! them can be inspected with this crate's tooling.
+//!
+//! IDs are converted to OpenTelemetry's hex form (UUID-style IDs lose their
+//! dashes and are truncated to 16/32 digits). Attribute values without an
+//! OpenTelemetry equivalent (objects, mixed arrays) are stored as JSON text.
+
+use crate::ids;
+use crate::span::{Span, SpanEvent, SpanKind, SpanLink, SpanStatus};
+use opentelemetry::trace::{
+    Event, Link, SpanContext, SpanId, SpanKind as OtelSpanKind, Status, TraceFlags, TraceId,
+    TraceState,
+};
+use opentelemetry::{Array, InstrumentationLibrary, Key, KeyValue, StringValue, Value};
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl From<Span> for SpanData {
+    fn from(span: Span) -> Self {
+        let span_id = SpanId::from_bytes(span.span_id.to_bytes());
+        let trace_id = TraceId::from_bytes(span.trace_id.to_bytes());
+        let parent_span_id = span.parent_span_id.map_or(SpanId::INVALID, |parent| {
+            SpanId::from_bytes(parent.to_bytes())
+        });
+        let mut events = SpanEvents::default();
+        events.events = span
+            .events
+            .into_iter()
+            .map(|event| {
+                Event::new(
+                    event.name,
+                    to_system_time(event.timestamp),
+                    to_key_values(event.attributes),
+                    0,
+                )
+            })
+            .collect();
+        let mut links = SpanLinks::default();
+        links.links = span
+            .links
+            .into_iter()
+            .map(|link| {
+                let context = SpanContext::new(
+                    TraceId::from_bytes(link.trace_id.to_bytes()),
+                    SpanId::from_bytes(link.span_id.to_bytes()),
+                    TraceFlags::SAMPLED,
+                    true,
+                    TraceState::default(),
+                );
+                Link::new(context, to_key_values(link.attributes), 0)
+            })
+            .collect();
+
+        SpanData {
+            span_context: SpanContext::new(
+                trace_id,
+                span_id,
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id,
+            span_kind: match span.kind {
+                SpanKind::Internal => OtelSpanKind::Internal,
+                SpanKind::Server => OtelSpanKind::Server,
+                SpanKind::Client => OtelSpanKind::Client,
+                SpanKind::Producer => OtelSpanKind::Producer,
+                SpanKind::Consumer => OtelSpanKind::Consumer,
+            },
+            name: Cow::Owned(span.operation_name),
+            start_time: to_system_time(span.start_time),
+            end_time: to_system_time(span.end_time.unwrap_or(span.start_time)),
+            attributes: to_key_values(span.attributes),
+            dropped_attributes_count: 0,
+            events,
+            links,
+            status: match span.status {
+                SpanStatus::Active => Status::Unset,
+                SpanStatus::Completed => Status::Ok,
+                SpanStatus::Error { message } => Status::error(message),
+            },
+            instrumentation_lib: InstrumentationLibrary::builder("tyl-tracing")
+                .with_version(env!("CARGO_PKG_VERSION"))
+                .build(),
+        }
+    }
+}
+
+impl From<SpanData> for Span {
+    fn from(data: SpanData) -> Self {
+        let parent_span_id = (data.parent_span_id != SpanId::INVALID)
+            .then(|| ids::SpanId::from_bytes(data.parent_span_id.to_bytes()));
+        Span {
+            span_id: ids::SpanId::from_bytes(data.span_context.span_id().to_bytes()),
+            trace_id: ids::TraceId::from_bytes(data.span_context.trace_id().to_bytes()),
+            parent_span_id,
+            operation_name: data.name.into_owned(),
+            start_time: to_unix_ms(data.start_time),
+            end_time: Some(to_unix_ms(data.end_time)),
+            attributes: from_key_values(data.attributes),
+            status: match data.status {
+                Status::Error { description } => SpanStatus::Error {
+                    message: description.into_owned(),
+                },
+                _ => SpanStatus::Completed,
+            },
+            kind: match data.span_kind {
+                OtelSpanKind::Server => SpanKind::Server,
+                OtelSpanKind::Client => SpanKind::Client,
+                OtelSpanKind::Producer => SpanKind::Producer,
+                OtelSpanKind::Consumer => SpanKind::Consumer,
+                OtelSpanKind::Internal => SpanKind::Internal,
+            },
+            events: data
+                .events
+                .events
+                .into_iter()
+                .map(|event| SpanEvent {
+                    name: event.name.into_owned(),
+                    timestamp: to_unix_ms(event.timestamp),
+                    attributes: from_key_values(event.attributes),
+                })
+                .collect(),
+            links: data
+                .links
+                .links
+                .into_iter()
+                .map(|link| SpanLink {
+                    trace_id: ids::TraceId::from_bytes(link.span_context.trace_id().to_bytes()),
+                    span_id: ids::SpanId::from_bytes(link.span_context.span_id().to_bytes()),
+                    attributes: from_key_values(link.attributes),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn to_system_time(unix_ms: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(unix_ms)
+}
+
+fn to_unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+fn to_key_values<K: Into<Key>>(
+    attributes: impl IntoIterator<Item = (K, serde_json::Value)>,
+) -> Vec<KeyValue> {
+    let mut attributes: Vec<_> = attributes
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| KeyValue::new(key, to_otel_value(value)))
+        .collect();
+    attributes.sort_by(|a, b| a.key.as_str().cmp(b.key.as_str()));
+    attributes
+}
+
+fn to_otel_value(value: serde_json::Value) -> Value {
+    use serde_json::Value as Json;
+
+    match value {
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::I64(i),
+            None => Value::F64(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Json::String(s) => Value::String(s.into()),
+        Json::Array(values) => {
+            if let Some(values) = values.iter().map(Json::as_bool).collect::<Option<Vec<_>>>() {
+                Value::Array(Array::Bool(values))
+            } else if let Some(values) = values.iter().map(Json::as_i64).collect::<Option<Vec<_>>>()
+            {
+                Value::Array(Array::I64(values))
+            } else if let Some(values) = values.iter().map(Json::as_f64).collect::<Option<Vec<_>>>()
+            {
+                Value::Array(Array::F64(values))
+            } else if let Some(values) = values
+                .iter()
+                .map(|v| v.as_str().map(|s| StringValue::from(s.to_string())))
+                .collect::<Option<Vec<_>>>()
+            {
+                Value::Array(Array::String(values))
+            } else {
+                Value::String(Json::Array(values).to_string().into())
+            }
+        }
+        other => Value::String(other.to_string().into()),
+    }
+}
+
+fn from_key_values<T: FromIterator<(String, serde_json::Value)>>(attributes: Vec<KeyValue>) -> T {
+    attributes
+        .into_iter()
+        .map(|kv| (kv.key.as_str().to_string(), from_otel_value(kv.value)))
+        .collect()
+}
+
+fn from_otel_value(value: Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => b.into(),
+        Value::I64(i) => i.into(),
+        Value::F64(f) => f.into(),
+        Value::String(s) => s.as_str().into(),
+        Value::Array(Array::Bool(values)) => values.into(),
+        Value::Array(Array::I64(values)) => values.into(),
+        Value::Array(Array::F64(values)) => values.into(),
+        Value::Array(Array::String(values)) => values
+            .iter()
+            .map(|s| serde_json::Value::from(s.as_str()))
+            .collect(),
+    }
+}
@@ -0,0 +1,424 @@
+//! OTLP export module
+//!
+//! Contains OtlpHttpExporter, which posts spans to an OpenTelemetry collector
+//! using OTLP/HTTP with JSON encoding, its Grafana Tempo variant, and the
+//! span-to-OTLP JSON encoding. Request bodies are compressed with the
+//! exporter's `compression` setting.
+//! `https://` endpoints, with the exporter's CA bundle and client identity,
+//! need the `tls` feature; without it only plain `http://` is supported.
+
+use crate::compression::Compression;
+use crate::config::{ExporterConfig, ExporterProtocol};
+use crate::export::SpanExporter;
+use crate::span::{Span, SpanKind, SpanStatus};
+use crate::tenant::{span_tenant, TENANT_ID_ATTRIBUTE};
+use crate::tracer::TracingResult;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::time::Duration;
+use tyl_errors::TylError;
+
+/// Path appended to endpoints that do not name one
+pub const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+/// Header Tempo (and other Grafana backends) use to pick the tenant
+pub const TEMPO_ORG_HEADER: &str = "X-Scope-OrgID";
+
+/// Adapter - Sends span batches to an OTLP/HTTP collector
+#[derive(Debug, Clone)]
+pub struct OtlpHttpExporter {
+    service_name: String,
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    compression: Compression,
+    transport: Transport,
+}
+
+/// How requests reach the collector
+#[derive(Debug, Clone)]
+enum Transport {
+    Plain,
+    #[cfg(feature = "tls")]
+    Tls {
+        config: Arc<rustls::ClientConfig>,
+        server_name: rustls::pki_types::ServerName<'static>,
+    },
+}
+
+impl OtlpHttpExporter {
+    /// Build from exporter settings; fails for unsupported protocols or URLs
+    pub fn from_config(
+        service_name: impl Into<String>,
+        config: &ExporterConfig,
+    ) -> TracingResult<Self> {
+        if config.protocol != ExporterProtocol::HttpJson {
+            return Err(TylError::configuration(format!(
+                "OTLP protocol {:?} is not supported by the built-in exporter (use http/json)",
+                config.protocol
+            )));
+        }
+        let (rest, secure) = match config.endpoint.split_once("://") {
+            Some(("http", rest)) => (rest, false),
+            Some(("https", rest)) if cfg!(feature = "tls") => (rest, true),
+            Some(("https", _)) => {
+                return Err(TylError::configuration(format!(
+                    "OTLP endpoint '{}' needs the `tls` cargo feature",
+                    config.endpoint
+                )))
+            }
+            _ => {
+                return Err(TylError::configuration(format!(
+                    "OTLP endpoint '{}' must be an http:// or https:// URL",
+                    config.endpoint
+                )))
+            }
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>().map_err(|_| {
+                    TylError::configuration(format!("invalid port in '{}'", config.endpoint))
+                })?,
+            ),
+            None => (authority, if secure { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(TylError::configuration(format!(
+                "missing host in '{}'",
+                config.endpoint
+            )));
+        }
+        let path = match path.trim_end_matches('/') {
+            "" => OTLP_TRACES_PATH.to_string(),
+            path => path.to_string(),
+        };
+        #[cfg(feature = "tls")]
+        let transport = if secure {
+            Transport::Tls {
+                config: crate::tls::client_config(config.tls.as_ref())?,
+                server_name: crate::tls::server_name(config.tls.as_ref(), host)?,
+            }
+        } else {
+            Transport::Plain
+        };
+        #[cfg(not(feature = "tls"))]
+        let transport = Transport::Plain;
+        Ok(Self {
+            service_name: service_name.into(),
+            host: host.to_string(),
+            port,
+            path,
+            headers: config.request_headers(),
+            timeout: config.timeout(),
+            compression: config.compression,
+            transport,
+        })
+    }
+
+    /// Full URL spans are posted to
+    pub fn url(&self) -> String {
+        let scheme = match self.transport {
+            Transport::Plain => "http",
+            #[cfg(feature = "tls")]
+            Transport::Tls { .. } => "https",
+        };
+        format!("{}://{}:{}{}", scheme, self.host, self.port, self.path)
+    }
+
+    fn post(&self, body: &[u8], extra_headers: &[(&str, &str)]) -> TracingResult<u16> {
+        let network = |e: std::io::Error| {
+            TylError::network(format!("OTLP export to {} failed: {}", self.url(), e))
+        };
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(network)?
+            .next()
+            .ok_or_else(|| TylError::network(format!("cannot resolve {}", self.host)))?;
+        let stream = TcpStream::connect_timeout(&address, self.timeout).map_err(network)?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.timeout)))
+            .map_err(network)?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            self.port,
+            body.len()
+        );
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()));
+        for (name, value) in headers.chain(extra_headers.iter().copied()) {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(encoding) = self.compression.content_encoding() {
+            request.push_str(&format!("Content-Encoding: {}\r\n", encoding));
+        }
+        request.push_str("\r\n");
+        let response = match &self.transport {
+            Transport::Plain => exchange(stream, request.as_bytes(), body),
+            #[cfg(feature = "tls")]
+            Transport::Tls {
+                config,
+                server_name,
+            } => crate::tls::connect(config, server_name, stream)
+                .and_then(|stream| exchange(stream, request.as_bytes(), body)),
+        }
+        .map_err(network)?;
+        let status_line = String::from_utf8_lossy(&response);
+        status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| TylError::network(format!("malformed response from {}", self.url())))
+    }
+}
+
+/// Send one request and read the response until the server closes
+fn exchange(mut stream: impl Read + Write, head: &[u8], body: &[u8]) -> std::io::Result<Vec<u8>> {
+    stream.write_all(head)?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    let mut response = Vec::new();
+    match stream.read_to_end(&mut response) {
+        // TLS servers may close without close_notify after a complete response
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !response.is_empty() => {}
+        result => {
+            result?;
+        }
+    }
+    Ok(response)
+}
+
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        self.send(batch, &[])
+    }
+}
+
+impl OtlpHttpExporter {
+    fn send(&self, batch: &[Span], extra_headers: &[(&str, &str)]) -> TracingResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let body = serde_json::to_vec(&to_otlp_json(&self.service_name, batch))
+            .map_err(|e| TylError::serialization(format!("OTLP encoding failed: {}", e)))?;
+        let body = match self.compression {
+            Compression::None => body,
+            compression => compression.compress(&body)?,
+        };
+        match self.post(&body, extra_headers)? {
+            200..=299 => Ok(()),
+            // Throttling and gateway errors are worth retrying
+            status @ (429 | 502 | 503 | 504) => Err(TylError::network(format!(
+                "collector {} returned HTTP {}",
+                self.url(),
+                status
+            ))),
+            status => Err(TylError::validation(
+                "exporter",
+                format!("collector {} rejected spans: HTTP {}", self.url(), status),
+            )),
+        }
+    }
+}
+
+/// Adapter - OTLP/HTTP exporter for multi-tenant Grafana Tempo
+///
+/// Each batch is split by the spans' `tenant.id`; every part is posted with
+/// that tenant as `X-Scope-OrgID`, falling back to the configured `org_id`.
+/// Spans with neither are sent without the header (single-tenant Tempo).
+#[derive(Debug, Clone)]
+pub struct TempoExporter {
+    otlp: OtlpHttpExporter,
+    org_id: Option<String>,
+}
+
+impl TempoExporter {
+    pub fn from_config(
+        service_name: impl Into<String>,
+        config: &ExporterConfig,
+    ) -> TracingResult<Self> {
+        Ok(Self {
+            otlp: OtlpHttpExporter::from_config(service_name, config)?,
+            org_id: config.org_id.clone(),
+        })
+    }
+
+    pub fn url(&self) -> String {
+        self.otlp.url()
+    }
+}
+
+impl SpanExporter for TempoExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        let mut by_org: BTreeMap<Option<&str>, Vec<Span>> = BTreeMap::new();
+        for span in batch {
+            let org_id = span_tenant(span).or(self.org_id.as_deref());
+            by_org.entry(org_id).or_default().push(span.clone());
+        }
+        // Try every tenant so one rejected org does not hold back the others
+        let mut first_error = None;
+        for (org_id, spans) in by_org {
+            let header: Vec<_> = org_id
+                .map(|org_id| (TEMPO_ORG_HEADER, org_id))
+                .into_iter()
+                .collect();
+            if let Err(error) = self.otlp.send(&spans, &header) {
+                first_error.get_or_insert(error);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// OTLP JSON `ExportTraceServiceRequest` for a batch of spans
+///
+/// Uses the OTLP field names (`traceId`, `spanId`, `parentSpanId`, nanosecond
+/// `startTimeUnixNano`, ...) so the output can be ingested by OpenTelemetry
+/// tooling. Spans are grouped into one resource per tenant, carrying
+/// `tenant.id`.
+pub fn to_otlp_json(service_name: &str, spans: &[Span]) -> Value {
+    let mut by_tenant: BTreeMap<Option<&str>, Vec<Value>> = BTreeMap::new();
+    for span in spans {
+        by_tenant
+            .entry(span_tenant(span))
+            .or_default()
+            .push(encode_span(span));
+    }
+    let resource_spans: Vec<Value> = by_tenant
+        .into_iter()
+        .map(|(tenant, spans)| {
+            let mut attributes = vec![key_value("service.name", &json!(service_name))];
+            if let Some(tenant) = tenant {
+                attributes.push(key_value(TENANT_ID_ATTRIBUTE, &json!(tenant)));
+            }
+            json!({
+                "resource": { "attributes": attributes },
+                "scopeSpans": [{
+                    "scope": { "name": "tyl-tracing", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            })
+        })
+        .collect();
+    json!({ "resourceSpans": resource_spans })
+}
+
+impl Span {
+    /// This span as an OTLP JSON `Span` object
+    pub fn to_otlp_json(&self) -> Value {
+        encode_span(self)
+    }
+}
+
+fn encode_span(span: &Span) -> Value {
+    let mut attributes: Vec<_> = span.attributes.iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(b.0));
+    let (code, message) = match &span.status {
+        SpanStatus::Active => (0, None),
+        SpanStatus::Completed => (1, None),
+        SpanStatus::Error { message } => (2, Some(message.as_str())),
+    };
+    let mut encoded = json!({
+        "traceId": span.trace_id.to_string(),
+        "spanId": span.span_id.to_string(),
+        "name": span.operation_name,
+        "kind": match span.kind {
+            SpanKind::Internal => 1,
+            SpanKind::Server => 2,
+            SpanKind::Client => 3,
+            SpanKind::Producer => 4,
+            SpanKind::Consumer => 5,
+        },
+        "startTimeUnixNano": (span.start_time as u128 * 1_000_000).to_string(),
+        "endTimeUnixNano": (span.end_time.unwrap_or(span.start_time) as u128 * 1_000_000).to_string(),
+        "attributes": attributes
+            .into_iter()
+            .map(|(key, value)| key_value(key, value))
+            .collect::<Vec<_>>(),
+        "status": { "code": code },
+    });
+    if !span.events.is_empty() {
+        encoded["events"] = span
+            .events
+            .iter()
+            .map(|event| {
+                let mut attributes: Vec<_> = event.attributes.iter().collect();
+                attributes.sort_by(|a, b| a.0.cmp(b.0));
+                json!({
+                    "timeUnixNano": (event.timestamp as u128 * 1_000_000).to_string(),
+                    "name": event.name,
+                    "attributes": attributes
+                        .into_iter()
+                        .map(|(key, value)| key_value(key, value))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+    }
+    if !span.links.is_empty() {
+        encoded["links"] = span
+            .links
+            .iter()
+            .map(|link| {
+                let mut attributes: Vec<_> = link.attributes.iter().collect();
+                attributes.sort_by(|a, b| a.0.cmp(b.0));
+                json!({
+                    "traceId": link.trace_id.to_string(),
+                    "spanId": link.span_id.to_string(),
+                    "attributes": attributes
+                        .into_iter()
+                        .map(|(key, value)| key_value(key, value))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+    }
+    if let Some(parent) = &span.parent_span_id {
+        encoded["parentSpanId"] = json!(parent.to_string());
+    }
+    if let Some(message) = message {
+        encoded["status"]["message"] = json!(message);
+    }
+    encoded
+}
+
+fn key_value(key: &str, value: &Value) -> Value {
+    json!({ "key": key, "value": any_value(value) })
+}
+
+/// OTLP `AnyValue`; 64-bit integers are strings in OTLP JSON
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => json!({ "intValue": i.to_string() }),
+            None => json!({ "doubleValue": n.as_f64() }),
+        },
+        Value::String(s) => json!({ "stringValue": s }),
+        Value::Array(values) => json!({
+            "arrayValue": { "values": values.iter().map(any_value).collect::<Vec<_>>() }
+        }),
+        Value::Object(map) => json!({
+            "kvlistValue": {
+                "values": map.iter().map(|(k, v)| key_value(k, v)).collect::<Vec<_>>()
+            }
+        }),
+    }
+}
@@ -0,0 +1,79 @@
+//! Parallel section module
+//!
+//! Contains rayon helpers (enabled with the `rayon` cargo feature) that carry
+//! the caller's [`Context`] into the worker threads of a parallel section, and
+//! batch per-item child spans so a large `par_iter` does not produce one span
+//! per element.
+
+use crate::context::{self, Context};
+use crate::tracer::TracingManager;
+use rayon::prelude::*;
+
+/// Span attribute: number of items processed under a batch span
+pub const BATCH_ITEMS_ATTRIBUTE: &str = "batch.items";
+
+/// Run `f` with the caller's context attached on every worker of the current
+/// rayon pool (the global pool unless called from inside another pool)
+///
+/// Spans started inside parallel iterators in `f` become children of the
+/// caller's current span. Workers pick up the context before `f` starts, so
+/// the section waits for busy workers. The context is detached again when
+/// `f` returns or panics. Workers cannot tell which section a task belongs
+/// to, so concurrent sections on the same pool should use
+/// [`for_each_traced`] instead.
+pub fn in_span_scope<R, F>(f: F) -> R
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    let current = Context::current();
+    rayon::broadcast(|_| context::push(current.clone()));
+    let _scope = PoolScope(&current);
+    f()
+}
+
+/// Detaches a section's context from every worker when dropped
+struct PoolScope<'a>(&'a Context);
+
+impl Drop for PoolScope<'_> {
+    fn drop(&mut self) {
+        // By span rather than from the top, so a section that ends first
+        // does not take another's context with it
+        rayon::broadcast(|_| context::remove(self.0));
+    }
+}
+
+/// Process `items` in parallel with one child span per batch of `batch_size`
+///
+/// Batch spans are children of the caller's current span, carry
+/// [`BATCH_ITEMS_ATTRIBUTE`], and are attached while `f` runs so spans
+/// started by `f` nest under them. A `batch_size` of 1 gives a span per item.
+pub fn for_each_traced<T, M, F>(
+    tracer: &M,
+    operation_name: &str,
+    items: &[T],
+    batch_size: usize,
+    f: F,
+) where
+    T: Sync,
+    M: TracingManager + ?Sized,
+    F: Fn(&T) + Send + Sync,
+{
+    let parent = Context::current();
+    items.par_chunks(batch_size.max(1)).for_each(|batch| {
+        let span_id = {
+            let _guard = parent.attach();
+            tracer.start_span(operation_name, None)
+        };
+        let Ok(span_id) = span_id else {
+            batch.iter().for_each(&f);
+            return;
+        };
+        let _ = tracer.add_span_attribute(&span_id, BATCH_ITEMS_ATTRIBUTE, batch.len().into());
+        {
+            let _guard = Context::with_span(span_id.clone()).attach();
+            batch.iter().for_each(&f);
+        }
+        let _ = tracer.end_span(span_id);
+    });
+}
@@ -0,0 +1,254 @@
+//! Context propagation module
+//!
+//! Contains TraceContext, the part of a span that crosses process boundaries,
+//! and its W3C `traceparent` encoding. Carriers (HTTP headers, message
+//! headers, ...) are reached through the Injector and Extractor traits, so the
+//! encoding does not depend on any transport crate.
+//!
+//! `try_extract` reports a malformed header as a PropagationError instead of
+//! treating it like a missing one, so interop bugs with other stacks show up.
+//!
+//! Responses can echo the context back (`inject_response`, `to_json`) so
+//! browser RUM tools can tie client-side timings to the backend trace.
+//!
+//! For context that waits outside any request (job payloads, database rows,
+//! links in emails), `to_token` packs it into one URL-safe string.
+
+use crate::binary_context::BINARY_CONTEXT_LEN;
+use crate::ids::{SpanId, TraceId};
+use crate::tracer::SimpleTracer;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use std::collections::HashMap;
+use std::fmt;
+use tyl_errors::TylError;
+
+/// Header holding the W3C trace context
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Response header echoing the server span (W3C Trace Context Level 2)
+pub const TRACERESPONSE_HEADER: &str = "traceresponse";
+
+/// Response header readable from the browser's Resource Timing API
+pub const SERVER_TIMING_HEADER: &str = "server-timing";
+
+/// Longest header value kept in a PropagationError
+const MAX_ERROR_VALUE_CHARS: usize = 128;
+
+/// A context header that was present but could not be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropagationError {
+    /// Header holding the bad value
+    pub header: String,
+    /// The value as received, cut to 128 characters
+    pub value: String,
+    /// What is wrong with it
+    pub reason: &'static str,
+}
+
+impl PropagationError {
+    pub fn new(header: impl Into<String>, value: &str, reason: &'static str) -> Self {
+        Self {
+            header: header.into(),
+            value: value.chars().take(MAX_ERROR_VALUE_CHARS).collect(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for PropagationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "malformed {} header {:?}: {}",
+            self.header, self.value, self.reason
+        )
+    }
+}
+
+impl std::error::Error for PropagationError {}
+
+impl From<PropagationError> for TylError {
+    fn from(error: PropagationError) -> Self {
+        TylError::validation(error.header.clone(), error.to_string())
+    }
+}
+
+/// Carrier that context is written into
+pub trait Injector {
+    fn set(&mut self, key: &str, value: String);
+}
+
+/// Carrier that context is read from; lookups ignore ASCII case
+pub trait Extractor {
+    fn get(&self, key: &str) -> Option<&str>;
+    fn keys(&self) -> Vec<&str>;
+}
+
+impl Injector for HashMap<String, String> {
+    fn set(&mut self, key: &str, value: String) {
+        self.insert(key.to_ascii_lowercase(), value);
+    }
+}
+
+impl Extractor for HashMap<String, String> {
+    fn get(&self, key: &str) -> Option<&str> {
+        HashMap::get(self, key)
+            .or_else(|| {
+                self.iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, value)| value)
+            })
+            .map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        HashMap::keys(self).map(String::as_str).collect()
+    }
+}
+
+/// Trace and span ID of a span, as sent to another process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: TraceId,
+    pub span_id: SpanId,
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    pub fn new(trace_id: TraceId, span_id: SpanId, sampled: bool) -> Self {
+        Self {
+            trace_id,
+            span_id,
+            sampled,
+        }
+    }
+
+    /// `traceparent` value: `00-{trace_id}-{span_id}-{flags}`
+    pub fn to_traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// Parse a `traceparent` value; `None` if it is malformed or all-zero
+    pub fn from_traceparent(value: &str) -> Option<Self> {
+        Self::parse_traceparent(value).ok()
+    }
+
+    /// Parse a `traceparent` value, explaining what is wrong with a bad one
+    pub fn parse_traceparent(value: &str) -> Result<Self, PropagationError> {
+        let error = |reason| PropagationError::new(TRACEPARENT_HEADER, value, reason);
+        let mut parts = value.trim().split('-');
+        let (Some(version), Some(trace_id), Some(span_id), Some(flags)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(error("expected version-traceid-spanid-flags"));
+        };
+        let is_hex = |field: &str, len: usize| {
+            field.len() == len
+                && field
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        };
+        if !is_hex(version, 2) {
+            return Err(error("version must be 2 lowercase hex digits"));
+        }
+        if version == "ff" {
+            return Err(error("version ff is invalid"));
+        }
+        // Versions after 00 may append fields
+        if version == "00" && parts.next().is_some() {
+            return Err(error("version 00 has exactly 4 fields"));
+        }
+        if !is_hex(trace_id, 32) {
+            return Err(error("trace ID must be 32 lowercase hex digits"));
+        }
+        if !is_hex(span_id, 16) {
+            return Err(error("span ID must be 16 lowercase hex digits"));
+        }
+        if !is_hex(flags, 2) {
+            return Err(error("flags must be 2 lowercase hex digits"));
+        }
+        let trace_id = TraceId::parse(trace_id)
+            .filter(TraceId::is_valid)
+            .ok_or_else(|| error("trace ID is all zeros"))?;
+        let span_id = SpanId::parse(span_id)
+            .filter(SpanId::is_valid)
+            .ok_or_else(|| error("span ID is all zeros"))?;
+        let flags = u8::from_str_radix(flags, 16).map_err(|_| error("invalid flags"))?;
+        Ok(Self::new(trace_id, span_id, flags & 0x01 != 0))
+    }
+
+    /// Compact URL-safe token (35 characters) to store with deferred work
+    ///
+    /// The token is the binary form (see `to_bytes`) in unpadded base64.
+    pub fn to_token(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    /// Parse a token from `to_token`; `None` if it is malformed or all-zero
+    pub fn from_token(token: &str) -> Option<Self> {
+        let mut bytes = [0u8; BINARY_CONTEXT_LEN + 1];
+        let len = URL_SAFE_NO_PAD
+            .decode_slice(token.trim(), &mut bytes)
+            .ok()?;
+        Self::from_bytes(&bytes[..len]).ok()
+    }
+
+    /// Write the context into `carrier`
+    pub fn inject(&self, carrier: &mut dyn Injector) {
+        carrier.set(TRACEPARENT_HEADER, self.to_traceparent());
+    }
+
+    /// Read a context from `carrier`, if it holds a valid one
+    pub fn extract(carrier: &dyn Extractor) -> Option<Self> {
+        Self::try_extract(carrier).ok().flatten()
+    }
+
+    /// Read a context from `carrier`: `Ok(None)` when it has no
+    /// `traceparent`, an error when the one it has is malformed
+    pub fn try_extract(carrier: &dyn Extractor) -> Result<Option<Self>, PropagationError> {
+        carrier
+            .get(TRACEPARENT_HEADER)
+            .map(Self::parse_traceparent)
+            .transpose()
+    }
+
+    /// `Server-Timing` value: `traceparent;desc="{traceparent}"`
+    pub fn to_server_timing(&self) -> String {
+        format!("traceparent;desc=\"{}\"", self.to_traceparent())
+    }
+
+    /// Write `traceresponse` and `Server-Timing` into response headers
+    ///
+    /// Cross-origin pages only see `Server-Timing` when the response also
+    /// sends `Timing-Allow-Origin`; that policy is left to the application.
+    pub fn inject_response(&self, carrier: &mut dyn Injector) {
+        carrier.set(TRACERESPONSE_HEADER, self.to_traceparent());
+        carrier.set(SERVER_TIMING_HEADER, self.to_server_timing());
+    }
+
+    /// JSON snippet for frontends, e.g. embedded in a server-rendered page
+    ///
+    /// `{"traceId", "spanId", "sampled", "traceparent"}` with IDs in hex.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "traceId": self.trace_id.to_string(),
+            "spanId": self.span_id.to_string(),
+            "sampled": self.sampled,
+            "traceparent": self.to_traceparent(),
+        })
+    }
+}
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Propagation context of an active span; only recording spans have one
+    pub fn span_context(&self, span_id: &str) -> Option<TraceContext> {
+        let span_id = SpanId::parse(span_id)?;
+        let trace_id = self.active_spans.lock().unwrap().get(&span_id)?.trace_id;
+        Some(TraceContext::new(trace_id, span_id, true))
+    }
+}
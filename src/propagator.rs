@@ -0,0 +1,319 @@
+//! Propagator module
+//!
+//! Contains Propagator, the header schemes context can travel in (W3C
+//! `traceparent`, Zipkin B3 and W3C `baggage`), and CompositePropagator,
+//! which runs several at once. A service migrating between schemes
+//! configures both with `TraceConfig::with_propagators`: it then emits every
+//! scheme and accepts whichever one a caller sent.
+//!
+//! `try_extract` tells a missing context from a malformed one. A server that
+//! gets a malformed one can still start its span with `start_malformed_root`,
+//! which marks the new root with the reason the caller's context was lost.
+
+use crate::baggage::{format_baggage, parse_baggage, BAGGAGE_HEADER};
+use crate::context::Context;
+use crate::handle::SpanHandle;
+use crate::ids::{SpanId, TraceId};
+use crate::propagation::{Extractor, Injector, PropagationError, TraceContext};
+use crate::span::SpanKind;
+use crate::tracer::SimpleTracer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Single-header B3: `{trace_id}-{span_id}-{sampled}`
+pub const B3_HEADER: &str = "b3";
+/// Multi-header B3 trace ID
+pub const B3_TRACE_ID_HEADER: &str = "x-b3-traceid";
+/// Multi-header B3 span ID
+pub const B3_SPAN_ID_HEADER: &str = "x-b3-spanid";
+/// Multi-header B3 sampling decision (`1` or `0`)
+pub const B3_SAMPLED_HEADER: &str = "x-b3-sampled";
+/// Multi-header B3 debug flag, which implies sampling
+pub const B3_FLAGS_HEADER: &str = "x-b3-flags";
+
+/// Set on a root span started because the incoming context was malformed;
+/// the value is the PropagationError message
+pub const PARENT_MALFORMED_ATTRIBUTE: &str = "trace.parent_malformed";
+
+/// A header scheme context is propagated in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Propagator {
+    /// W3C `traceparent`
+    W3c,
+    /// Zipkin B3, emitted as the single `b3` header
+    B3,
+    /// Zipkin B3, emitted as the `X-B3-*` headers
+    B3Multi,
+    /// W3C `baggage`
+    Baggage,
+}
+
+/// Propagators used when none are configured
+pub const DEFAULT_PROPAGATORS: [Propagator; 2] = [Propagator::W3c, Propagator::Baggage];
+
+impl Propagator {
+    /// Write `context` (or, for `Baggage`, `baggage`) into `carrier`
+    pub fn inject(
+        &self,
+        context: &TraceContext,
+        baggage: &HashMap<String, String>,
+        carrier: &mut dyn Injector,
+    ) {
+        match self {
+            Self::W3c => context.inject(carrier),
+            Self::B3 => carrier.set(
+                B3_HEADER,
+                format!(
+                    "{}-{}-{}",
+                    context.trace_id, context.span_id, context.sampled as u8
+                ),
+            ),
+            Self::B3Multi => {
+                carrier.set(B3_TRACE_ID_HEADER, context.trace_id.to_string());
+                carrier.set(B3_SPAN_ID_HEADER, context.span_id.to_string());
+                carrier.set(B3_SAMPLED_HEADER, (context.sampled as u8).to_string());
+            }
+            Self::Baggage if !baggage.is_empty() => carrier.set(
+                BAGGAGE_HEADER,
+                format_baggage(baggage.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+            ),
+            Self::Baggage => {}
+        }
+    }
+
+    /// Read a context from `carrier`; always `None` for `Baggage`
+    ///
+    /// Both B3 variants accept the single and the multi-header form.
+    pub fn extract(&self, carrier: &dyn Extractor) -> Option<TraceContext> {
+        self.try_extract(carrier).ok().flatten()
+    }
+
+    /// Like `extract`, but a malformed header is an error rather than `None`
+    pub fn try_extract(
+        &self,
+        carrier: &dyn Extractor,
+    ) -> Result<Option<TraceContext>, PropagationError> {
+        match self {
+            Self::W3c => TraceContext::try_extract(carrier),
+            Self::B3 | Self::B3Multi => extract_b3(carrier),
+            Self::Baggage => Ok(None),
+        }
+    }
+}
+
+/// Several propagators run as one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompositePropagator {
+    propagators: Vec<Propagator>,
+}
+
+impl CompositePropagator {
+    /// Propagators in priority order; duplicates are ignored
+    pub fn new(propagators: impl IntoIterator<Item = Propagator>) -> Self {
+        let mut unique = Vec::new();
+        for propagator in propagators {
+            if !unique.contains(&propagator) {
+                unique.push(propagator);
+            }
+        }
+        Self {
+            propagators: unique,
+        }
+    }
+
+    pub fn propagators(&self) -> &[Propagator] {
+        &self.propagators
+    }
+
+    /// Write the context in every configured scheme
+    pub fn inject(
+        &self,
+        context: &TraceContext,
+        baggage: &HashMap<String, String>,
+        carrier: &mut dyn Injector,
+    ) {
+        for propagator in &self.propagators {
+            propagator.inject(context, baggage, carrier);
+        }
+    }
+
+    /// Context from the first propagator, in configured order, that finds one
+    pub fn extract(&self, carrier: &dyn Extractor) -> Option<TraceContext> {
+        self.propagators
+            .iter()
+            .find_map(|propagator| propagator.extract(carrier))
+    }
+
+    /// Like `extract`; when no scheme yields a context but one of them found
+    /// a malformed header, that error is returned
+    pub fn try_extract(
+        &self,
+        carrier: &dyn Extractor,
+    ) -> Result<Option<TraceContext>, PropagationError> {
+        let mut malformed = None;
+        for propagator in &self.propagators {
+            match propagator.try_extract(carrier) {
+                Ok(Some(context)) => return Ok(Some(context)),
+                Ok(None) => {}
+                Err(error) => {
+                    malformed.get_or_insert(error);
+                }
+            }
+        }
+        malformed.map_or(Ok(None), Err)
+    }
+
+    /// Incoming baggage, empty unless `Baggage` is configured
+    pub fn extract_baggage(&self, carrier: &dyn Extractor) -> HashMap<String, String> {
+        if !self.propagators.contains(&Propagator::Baggage) {
+            return HashMap::new();
+        }
+        carrier
+            .get(BAGGAGE_HEADER)
+            .map(parse_baggage)
+            .unwrap_or_default()
+    }
+}
+
+impl Default for CompositePropagator {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROPAGATORS)
+    }
+}
+
+fn extract_b3(carrier: &dyn Extractor) -> Result<Option<TraceContext>, PropagationError> {
+    if let Some(value) = carrier.get(B3_HEADER) {
+        let error = |reason| PropagationError::new(B3_HEADER, value, reason);
+        let mut parts = value.trim().split('-');
+        let (Some(trace_id), Some(span_id)) = (parts.next(), parts.next()) else {
+            return Err(error("expected traceid-spanid[-sampled]"));
+        };
+        let sampled = match parts.next() {
+            Some(sampled) => {
+                parse_b3_sampled(sampled).ok_or_else(|| error("sampled must be 1, 0 or d"))?
+            }
+            None => true,
+        };
+        return b3_context(trace_id, span_id, sampled)
+            .map(Some)
+            .map_err(error);
+    }
+    let (trace_id, span_id) = match (
+        carrier.get(B3_TRACE_ID_HEADER),
+        carrier.get(B3_SPAN_ID_HEADER),
+    ) {
+        (Some(trace_id), Some(span_id)) => (trace_id, span_id),
+        (None, None) => return Ok(None),
+        (Some(trace_id), None) => {
+            return Err(PropagationError::new(
+                B3_TRACE_ID_HEADER,
+                trace_id,
+                "sent without X-B3-SpanId",
+            ))
+        }
+        (None, Some(span_id)) => {
+            return Err(PropagationError::new(
+                B3_SPAN_ID_HEADER,
+                span_id,
+                "sent without X-B3-TraceId",
+            ))
+        }
+    };
+    let debug = carrier.get(B3_FLAGS_HEADER).map(str::trim) == Some("1");
+    let sampled = match carrier.get(B3_SAMPLED_HEADER) {
+        Some(value) => parse_b3_sampled(value.trim())
+            .ok_or_else(|| PropagationError::new(B3_SAMPLED_HEADER, value, "must be 1 or 0"))?,
+        None => true,
+    };
+    b3_context(trace_id.trim(), span_id.trim(), sampled || debug)
+        .map(Some)
+        .map_err(|reason| {
+            let (header, value) = if reason.starts_with("trace") {
+                (B3_TRACE_ID_HEADER, trace_id)
+            } else {
+                (B3_SPAN_ID_HEADER, span_id)
+            };
+            PropagationError::new(header, value, reason)
+        })
+}
+
+/// `1`/`d` sample, `0` does not; older senders use `true`/`false`
+fn parse_b3_sampled(value: &str) -> Option<bool> {
+    match value {
+        "1" | "d" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Context from B3 hex IDs; 64-bit trace IDs are left-padded to 128 bits
+fn b3_context(trace_id: &str, span_id: &str, sampled: bool) -> Result<TraceContext, &'static str> {
+    let is_hex = |id: &str| id.chars().all(|c| c.is_ascii_hexdigit());
+    if !matches!(trace_id.len(), 16 | 32) || !is_hex(trace_id) {
+        return Err("trace ID must be 16 or 32 hex digits");
+    }
+    if span_id.len() != 16 || !is_hex(span_id) {
+        return Err("span ID must be 16 hex digits");
+    }
+    let trace_id = format!("{:0>32}", trace_id.to_ascii_lowercase());
+    let trace_id = TraceId::parse(&trace_id)
+        .filter(TraceId::is_valid)
+        .ok_or("trace ID is all zeros")?;
+    let span_id = SpanId::parse(&span_id.to_ascii_lowercase())
+        .filter(SpanId::is_valid)
+        .ok_or("span ID is all zeros")?;
+    Ok(TraceContext::new(trace_id, span_id, sampled))
+}
+
+impl SimpleTracer {
+    /// Propagators configured with `TraceConfig::with_propagators`
+    pub fn propagator(&self) -> CompositePropagator {
+        CompositePropagator::new(self.config().propagators.iter().copied())
+    }
+
+    /// Write an active span's context in every configured scheme
+    ///
+    /// Baggage is the tracer's own merged with the attached context's
+    /// request-scoped entries, which win. Returns `false` when the span is
+    /// not active, leaving `carrier` untouched.
+    pub fn inject_context(&self, span_id: &str, carrier: &mut dyn Injector) -> bool {
+        let Some(context) = self.span_context(span_id) else {
+            return false;
+        };
+        let mut baggage = self.baggage_snapshot();
+        baggage.extend(
+            Context::current()
+                .baggage_entries()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        self.propagator().inject(&context, &baggage, carrier);
+        true
+    }
+
+    /// Read an incoming context in any configured scheme
+    pub fn extract_context(&self, carrier: &dyn Extractor) -> Option<TraceContext> {
+        self.propagator().extract(carrier)
+    }
+
+    /// Like `extract_context`, reporting a malformed header as an error
+    pub fn try_extract_context(
+        &self,
+        carrier: &dyn Extractor,
+    ) -> Result<Option<TraceContext>, PropagationError> {
+        self.propagator().try_extract(carrier)
+    }
+
+    /// Start a root span in place of a caller's unusable context, with
+    /// `trace.parent_malformed` set to what was wrong with it
+    pub fn start_malformed_root(
+        &self,
+        operation_name: &str,
+        kind: SpanKind,
+        error: &PropagationError,
+    ) -> SpanHandle<'_> {
+        let span = self.start_with_kind(operation_name, kind);
+        span.set_attribute(PARENT_MALFORMED_ATTRIBUTE, error.to_string());
+        span
+    }
+}
@@ -0,0 +1,44 @@
+//! Span purge module
+//!
+//! Deletes locally retained spans on request, e.g. to honor GDPR data-subject
+//! deletion requests: completed spans kept by the tracer and spans still
+//! queued or held back for export are removed. Spans that are still active are left alone,
+//! and data that already reached an exporter's backend is out of reach.
+//!
+//! Files written by exporters are not covered either: the spill file of a
+//! `SpillingExporter` and the dead-letter file of a `DeadLetterExporter` must
+//! be purged through their own `purge_spans` methods.
+
+use crate::span::Span;
+use crate::tracer::SimpleTracer;
+use serde_json::Value;
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Remove every completed or queued span matching `predicate`; returns
+    /// how many were removed
+    ///
+    /// Spill and dead-letter files are not covered; call
+    /// [`SpillingExporter::purge_spans`](crate::SpillingExporter::purge_spans)
+    /// and [`DeadLetterExporter::purge_spans`](crate::DeadLetterExporter::purge_spans)
+    /// with the same predicate for those.
+    pub fn purge_spans(&self, predicate: impl Fn(&Span) -> bool) -> usize {
+        let mut purged = self
+            .completed_spans
+            .lock()
+            .unwrap()
+            .retain(|span| !predicate(span));
+        purged += self.purge_trace_buffers(&predicate);
+        if let Some(processor) = self.batch_processor() {
+            purged += processor.purge_queued(&predicate);
+        }
+        purged
+    }
+
+    /// Remove spans whose attribute `key` equals `value`, e.g.
+    /// `purge_by_attribute("user_id", "42")`
+    pub fn purge_by_attribute(&self, key: &str, value: impl Into<Value>) -> usize {
+        let value = value.into();
+        self.purge_spans(|span| span.attributes.get(key) == Some(&value))
+    }
+}
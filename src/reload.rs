@@ -0,0 +1,146 @@
+//! Config reload module
+//!
+//! Contains ConfigWatcher, which polls a config file and applies changes to a
+//! running SimpleTracer so sampling rate, filters, and exporter settings
+//! (endpoint, credentials, batch size) can change without a restart.
+
+use crate::config::TraceConfig;
+use crate::tracer::{SimpleTracer, TracingResult};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tyl_errors::TylError;
+
+/// Default interval between config file checks
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+type ReloadHook = Box<dyn Fn(&TraceConfig) + Send + Sync>;
+type ErrorHook = Box<dyn Fn(&TylError) + Send + Sync>;
+
+/// Watches a config file loaded with [`TraceConfig::from_file`]
+///
+/// The file contents are compared on every poll; when they change the new
+/// config is validated and swapped into the tracer in one step. An invalid
+/// file leaves the previous config in place and is reported to `on_error`.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    tracer: Arc<SimpleTracer>,
+    poll_interval: Duration,
+    last_contents: Mutex<Option<String>>,
+    on_reload: Option<ReloadHook>,
+    on_error: Option<ErrorHook>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`; its current contents count as already applied
+    pub fn new(path: impl AsRef<Path>, tracer: Arc<SimpleTracer>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let last_contents = std::fs::read_to_string(&path).ok();
+        Self {
+            path,
+            tracer,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            last_contents: Mutex::new(last_contents),
+            on_reload: None,
+            on_error: None,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Called after a new config was applied, exporter included (see
+    /// [`SimpleTracer::reload_config`])
+    pub fn on_reload(mut self, hook: impl Fn(&TraceConfig) + Send + Sync + 'static) -> Self {
+        self.on_reload = Some(Box::new(hook));
+        self
+    }
+
+    /// Called when the changed file cannot be loaded or fails validation
+    pub fn on_error(mut self, hook: impl Fn(&TylError) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Check the file once; returns whether a new config was applied
+    pub fn check_now(&self) -> TracingResult<bool> {
+        let contents = std::fs::read_to_string(&self.path).map_err(|e| {
+            TylError::configuration(format!("cannot read {}: {}", self.path.display(), e))
+        })?;
+        let mut last_contents = self.last_contents.lock().unwrap();
+        if last_contents.as_deref() == Some(contents.as_str()) {
+            return Ok(false);
+        }
+        // Remember the contents even if invalid so the error is reported once
+        *last_contents = Some(contents);
+        drop(last_contents);
+
+        let config = TraceConfig::from_file(&self.path)?;
+        self.tracer.reload_config(config.clone())?;
+        if let Some(hook) = &self.on_reload {
+            hook(&config);
+        }
+        Ok(true)
+    }
+
+    /// Poll in a background thread until the returned handle is dropped
+    pub fn start(self) -> WatchHandle {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let worker_stop = stop.clone();
+        let worker = std::thread::Builder::new()
+            .name("tyl-tracing-config-watch".to_string())
+            .spawn(move || self.run(&worker_stop))
+            .ok();
+        WatchHandle { stop, worker }
+    }
+
+    fn run(&self, stop: &(Mutex<bool>, Condvar)) {
+        let (stopped, wakeup) = stop;
+        loop {
+            {
+                let stopped = stopped.lock().unwrap();
+                let (stopped, _) = wakeup
+                    .wait_timeout_while(stopped, self.poll_interval, |stopped| !*stopped)
+                    .unwrap();
+                if *stopped {
+                    return;
+                }
+            }
+            if let Err(error) = self.check_now() {
+                if let Some(hook) = &self.on_error {
+                    hook(&error);
+                }
+            }
+        }
+    }
+}
+
+/// Stops the background watcher when dropped
+pub struct WatchHandle {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let (stopped, wakeup) = &*self.stop;
+        *stopped.lock().unwrap() = true;
+        wakeup.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
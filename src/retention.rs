@@ -0,0 +1,96 @@
+//! Retention module
+//!
+//! Contains the background pruning of completed spans older than the
+//! configured `retention`. The `max_spans` cap bounds memory by count only,
+//! so a burst followed by quiet hours would otherwise keep stale spans around
+//! indefinitely.
+
+use crate::span::current_timestamp;
+use crate::store::SpanStore;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Bounds of the pruning tick, a tenth of the retention
+const MIN_TICK: Duration = Duration::from_millis(10);
+const MAX_TICK: Duration = Duration::from_secs(1);
+
+struct PrunerShared {
+    /// Current retention in ms, 0 when disabled
+    retention_ms: AtomicU64,
+    stopped: Mutex<bool>,
+    wakeup: Condvar,
+}
+
+/// Background worker pruning expired completed spans
+pub(crate) struct RetentionPruner {
+    shared: Arc<PrunerShared>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl RetentionPruner {
+    pub(crate) fn new() -> Self {
+        Self {
+            shared: Arc::new(PrunerShared {
+                retention_ms: AtomicU64::new(0),
+                stopped: Mutex::new(false),
+                wakeup: Condvar::new(),
+            }),
+            worker: Mutex::new(None),
+        }
+    }
+
+    /// Apply a retention setting; the worker starts the first time one is set
+    pub(crate) fn configure(&self, retention: Option<Duration>, spans: &Arc<Mutex<SpanStore>>) {
+        let retention_ms = retention.map_or(0, |retention| retention.as_millis().max(1) as u64);
+        self.shared
+            .retention_ms
+            .store(retention_ms, Ordering::Relaxed);
+        let mut worker = self.worker.lock().unwrap();
+        if retention_ms > 0 && worker.is_none() {
+            let shared = self.shared.clone();
+            let spans = Arc::downgrade(spans);
+            *worker = Some(std::thread::spawn(move || run_pruner(shared, spans)));
+        }
+        self.shared.wakeup.notify_all();
+    }
+}
+
+impl Drop for RetentionPruner {
+    fn drop(&mut self) {
+        *self.shared.stopped.lock().unwrap() = true;
+        self.shared.wakeup.notify_all();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn run_pruner(shared: Arc<PrunerShared>, spans: Weak<Mutex<SpanStore>>) {
+    loop {
+        let retention_ms = shared.retention_ms.load(Ordering::Relaxed);
+        let tick = match retention_ms {
+            0 => MAX_TICK,
+            retention_ms => (Duration::from_millis(retention_ms) / 10).clamp(MIN_TICK, MAX_TICK),
+        };
+        {
+            let stopped = shared.stopped.lock().unwrap();
+            let (stopped, _) = shared
+                .wakeup
+                .wait_timeout_while(stopped, tick, |stopped| !*stopped)
+                .unwrap();
+            if *stopped {
+                return;
+            }
+        }
+        let Some(spans) = spans.upgrade() else {
+            return;
+        };
+        let retention_ms = shared.retention_ms.load(Ordering::Relaxed);
+        if retention_ms > 0 {
+            let cutoff = current_timestamp().saturating_sub(retention_ms);
+            spans.lock().unwrap().prune_before(cutoff);
+        }
+    }
+}
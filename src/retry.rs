@@ -0,0 +1,78 @@
+//! Retry recording module
+//!
+//! Runs an operation under a [`RetryPolicy`] and records every retry on a
+//! span: each failed attempt that is retried becomes a `retry` event (attempt
+//! number, backoff delay, error), the span gets the total `retry.attempts`,
+//! and a final failure is recorded with [`SimpleTracer::record_error`], which
+//! makes flaky dependencies visible in traces.
+
+use crate::export::{is_retryable_error, RetryPolicy};
+use crate::span::{current_timestamp, SpanEvent};
+use crate::tracer::{SimpleTracer, TracingManager, TracingResult};
+use std::collections::HashMap;
+use tyl_errors::TylError;
+
+/// Event recorded for each failed attempt that is retried
+pub const RETRY_EVENT: &str = "retry";
+/// Event attribute: 1-based number of the attempt that failed
+pub const RETRY_ATTEMPT_ATTRIBUTE: &str = "retry.attempt";
+/// Event attribute: backoff before the next attempt, in milliseconds
+pub const RETRY_DELAY_ATTRIBUTE: &str = "retry.delay_ms";
+/// Event attribute: error of the failed attempt
+pub const RETRY_ERROR_ATTRIBUTE: &str = "retry.error";
+/// Span attribute: attempts made in total
+pub const RETRY_ATTEMPTS_ATTRIBUTE: &str = "retry.attempts";
+
+impl SimpleTracer {
+    /// Run `operation` (given the 1-based attempt number) until it succeeds,
+    /// fails permanently ([`is_retryable_error`]), or `policy` gives up
+    pub fn retry_traced<T>(
+        &self,
+        span_id: &str,
+        policy: &RetryPolicy,
+        operation: impl FnMut(u32) -> TracingResult<T>,
+    ) -> TracingResult<T> {
+        self.retry_traced_with(span_id, policy, is_retryable_error, operation)
+    }
+
+    /// [`SimpleTracer::retry_traced`] with a custom retryable-error classifier
+    pub fn retry_traced_with<T>(
+        &self,
+        span_id: &str,
+        policy: &RetryPolicy,
+        is_retryable: impl Fn(&TylError) -> bool,
+        mut operation: impl FnMut(u32) -> TracingResult<T>,
+    ) -> TracingResult<T> {
+        let mut attempt = 1;
+        let result = loop {
+            match operation(attempt) {
+                Err(error) if attempt < policy.max_attempts && is_retryable(&error) => {
+                    let delay = policy.jittered_backoff(attempt);
+                    let event = SpanEvent {
+                        name: RETRY_EVENT.to_string(),
+                        timestamp: current_timestamp(),
+                        attributes: HashMap::from([
+                            (RETRY_ATTEMPT_ATTRIBUTE.to_string(), attempt.into()),
+                            (
+                                RETRY_DELAY_ATTRIBUTE.to_string(),
+                                (delay.as_millis() as u64).into(),
+                            ),
+                            (RETRY_ERROR_ATTRIBUTE.to_string(), error.to_string().into()),
+                        ]),
+                    };
+                    // Recording is best-effort; the retry loop must not fail on it
+                    let _ = self.push_event(span_id, event);
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        let _ = self.add_span_attribute(span_id, RETRY_ATTEMPTS_ATTRIBUTE, attempt.into());
+        if let Err(error) = &result {
+            let _ = self.record_error(span_id, error);
+        }
+        result
+    }
+}
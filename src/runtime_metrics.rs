@@ -0,0 +1,107 @@
+//! Tokio runtime metrics module
+//!
+//! Captures a snapshot of the current tokio runtime at span start and end
+//! (enabled with the `tokio-metrics` cargo feature and
+//! `SimpleTracer::with_runtime_metrics`) and records the differences as span
+//! attributes, so latency caused by a saturated runtime shows up in the trace.
+//!
+//! Task counts, queue depths, and forced yields are only exposed by tokio when
+//! built with `RUSTFLAGS="--cfg tokio_unstable"`; otherwise just the worker
+//! count is recorded.
+
+use crate::ids::SpanId;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Span attribute: runtime worker threads
+pub const TOKIO_WORKERS_ATTRIBUTE: &str = "tokio.workers";
+/// Span attribute: alive tasks at span end
+pub const TOKIO_ACTIVE_TASKS_ATTRIBUTE: &str = "tokio.active_tasks";
+/// Span attribute: change in alive tasks over the span
+pub const TOKIO_ACTIVE_TASKS_DELTA_ATTRIBUTE: &str = "tokio.active_tasks.delta";
+/// Span attribute: queued tasks (global and worker-local) at span end
+pub const TOKIO_QUEUE_DEPTH_ATTRIBUTE: &str = "tokio.queue_depth";
+/// Span attribute: change in queued tasks over the span
+pub const TOKIO_QUEUE_DEPTH_DELTA_ATTRIBUTE: &str = "tokio.queue_depth.delta";
+/// Span attribute: tasks forced to yield by the coop budget during the span
+pub const TOKIO_BUDGET_FORCED_YIELDS_ATTRIBUTE: &str = "tokio.budget_forced_yields";
+
+/// Point-in-time metrics of a tokio runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeSnapshot {
+    pub workers: usize,
+    /// `None` without `tokio_unstable`
+    pub active_tasks: Option<usize>,
+    pub queue_depth: Option<usize>,
+    pub budget_forced_yields: Option<u64>,
+}
+
+impl RuntimeSnapshot {
+    /// Snapshot of the runtime the caller runs on, if any
+    pub fn capture() -> Option<Self> {
+        let handle = tokio::runtime::Handle::try_current().ok()?;
+        let metrics = handle.metrics();
+        #[allow(unused_mut)]
+        let mut snapshot = Self {
+            workers: metrics.num_workers(),
+            ..Self::default()
+        };
+        #[cfg(tokio_unstable)]
+        {
+            snapshot.active_tasks = Some(metrics.active_tasks_count());
+            let local: usize = (0..snapshot.workers)
+                .map(|worker| metrics.worker_local_queue_depth(worker))
+                .sum();
+            snapshot.queue_depth = Some(metrics.injection_queue_depth() + local);
+            snapshot.budget_forced_yields = Some(metrics.budget_forced_yield_count());
+        }
+        Some(snapshot)
+    }
+
+    /// Attributes describing the change from `start` to this snapshot
+    pub fn diff_attributes(&self, start: &RuntimeSnapshot) -> HashMap<String, Value> {
+        let mut attributes = HashMap::new();
+        attributes.insert(TOKIO_WORKERS_ATTRIBUTE.to_string(), self.workers.into());
+        if let (Some(end), Some(begin)) = (self.active_tasks, start.active_tasks) {
+            attributes.insert(TOKIO_ACTIVE_TASKS_ATTRIBUTE.to_string(), end.into());
+            attributes.insert(
+                TOKIO_ACTIVE_TASKS_DELTA_ATTRIBUTE.to_string(),
+                (end as i64 - begin as i64).into(),
+            );
+        }
+        if let (Some(end), Some(begin)) = (self.queue_depth, start.queue_depth) {
+            attributes.insert(TOKIO_QUEUE_DEPTH_ATTRIBUTE.to_string(), end.into());
+            attributes.insert(
+                TOKIO_QUEUE_DEPTH_DELTA_ATTRIBUTE.to_string(),
+                (end as i64 - begin as i64).into(),
+            );
+        }
+        if let (Some(end), Some(begin)) = (self.budget_forced_yields, start.budget_forced_yields) {
+            attributes.insert(
+                TOKIO_BUDGET_FORCED_YIELDS_ATTRIBUTE.to_string(),
+                end.saturating_sub(begin).into(),
+            );
+        }
+        attributes
+    }
+}
+
+/// Runtime snapshots taken when active spans started
+#[derive(Debug, Default)]
+pub(crate) struct RuntimeMetricsTracker {
+    starts: Mutex<HashMap<SpanId, RuntimeSnapshot>>,
+}
+
+impl RuntimeMetricsTracker {
+    pub(crate) fn start(&self, span_id: SpanId) {
+        if let Some(snapshot) = RuntimeSnapshot::capture() {
+            self.starts.lock().unwrap().insert(span_id, snapshot);
+        }
+    }
+
+    pub(crate) fn finish(&self, span_id: SpanId) -> Option<HashMap<String, Value>> {
+        let start = self.starts.lock().unwrap().remove(&span_id)?;
+        RuntimeSnapshot::capture().map(|end| end.diff_attributes(&start))
+    }
+}
@@ -0,0 +1,63 @@
+//! Sampling module
+//!
+//! Trace-ID based ratio sampling: the decision is a pure function of the trace
+//! ID and the rate, so every span of a trace (and every service seeing that
+//! trace) agrees on it without coordination.
+//!
+//! A `sampling.priority` baggage entry overrides the rate for a whole trace:
+//! `1` (or higher) forces it to be recorded, `0` drops it.
+
+use crate::ids::TraceId;
+
+/// Baggage key (and span attribute) carrying the sampling priority
+pub const SAMPLING_PRIORITY_KEY: &str = "sampling.priority";
+
+/// Parse a sampling priority value; `true`/`debug` count as priority 1
+pub fn parse_sampling_priority(value: &str) -> Option<i64> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "debug" => Some(1),
+        "false" => Some(0),
+        other => other.parse().ok(),
+    }
+}
+
+/// Decide whether a trace is kept at the given sampling rate (0.0-1.0)
+///
+/// IDs in any `IdFormat` decide like their binary form, so services rendering
+/// IDs differently still agree.
+pub fn should_sample(trace_id: &str, rate: f64) -> bool {
+    match TraceId::parse(trace_id) {
+        Some(trace_id) => should_sample_trace(trace_id, rate),
+        None => sample_hash(trace_id.as_bytes(), rate),
+    }
+}
+
+/// [`should_sample`] for a binary trace ID
+pub fn should_sample_trace(trace_id: TraceId, rate: f64) -> bool {
+    sample_hash(&trace_id.to_bytes(), rate)
+}
+
+fn sample_hash(trace_id: &[u8], rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate <= 0.0 || rate.is_nan() {
+        return false;
+    }
+    let threshold = (rate * u64::MAX as f64) as u64;
+    trace_id_hash(trace_id) < threshold
+}
+
+/// FNV-1a hash of the trace ID, spread uniformly over u64
+fn trace_id_hash(trace_id: &[u8]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = trace_id.iter().fold(FNV_OFFSET, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    // FNV's low bits mix poorly for short inputs; finalize like splitmix64
+    let mut z = hash;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
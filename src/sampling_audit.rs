@@ -0,0 +1,149 @@
+//! Sampling audit module
+//!
+//! Contains the opt-in sampling audit log: while enabled, every sampling
+//! decision (operation, trace, outcome, effective rate and the rule that
+//! made it) is kept in a bounded in-memory log, oldest entries evicted
+//! first. Admin endpoints serve it through `TracingAdmin::sampling_decisions`
+//! to answer "why is this trace missing?" without redeploying.
+
+use crate::ids::TraceId;
+use crate::span::current_timestamp;
+use crate::tracer::SimpleTracer;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The rule that decided whether a trace is recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum SamplingRule {
+    /// The most specific span filter directive for the operation
+    Filter { pattern: String },
+    /// A `sampling.priority` forcing the trace in (above 0) or out
+    Priority { priority: i64 },
+    /// The tenant's own root sampling rate
+    Tenant { tenant_id: String },
+    /// The configured `sampling_rate`
+    Rate,
+    /// A child following its parent; remote parents carry their sampled flag
+    Parent,
+}
+
+/// One audited sampling decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingDecision {
+    /// When the decision was made (milliseconds since the epoch)
+    pub timestamp: u64,
+    pub operation: String,
+    pub trace_id: TraceId,
+    pub sampled: bool,
+    /// Rate in effect (0.0-1.0); forced decisions report 0.0 or 1.0
+    pub rate: f64,
+    pub rule: SamplingRule,
+}
+
+/// Borrowed form of `SamplingRule`, so deciding allocates nothing while the
+/// audit log is off
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MatchedRule<'a> {
+    Filter(&'a str),
+    Priority(i64),
+    Tenant(&'a str),
+    Rate,
+    Parent,
+}
+
+impl MatchedRule<'_> {
+    fn to_rule(self) -> SamplingRule {
+        match self {
+            MatchedRule::Filter(pattern) => SamplingRule::Filter {
+                pattern: pattern.to_string(),
+            },
+            MatchedRule::Priority(priority) => SamplingRule::Priority { priority },
+            MatchedRule::Tenant(tenant_id) => SamplingRule::Tenant {
+                tenant_id: tenant_id.to_string(),
+            },
+            MatchedRule::Rate => SamplingRule::Rate,
+            MatchedRule::Parent => SamplingRule::Parent,
+        }
+    }
+}
+
+/// Bounded log of sampling decisions; a capacity of 0 turns it off
+#[derive(Debug, Default)]
+pub(crate) struct SamplingAudit {
+    capacity: AtomicUsize,
+    decisions: Mutex<VecDeque<SamplingDecision>>,
+}
+
+impl SamplingAudit {
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut decisions = self.decisions.lock().unwrap();
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let excess = decisions.len().saturating_sub(capacity);
+        decisions.drain(..excess);
+        if capacity == 0 {
+            decisions.shrink_to_fit();
+        }
+    }
+
+    pub(crate) fn record(
+        &self,
+        operation: &str,
+        trace_id: TraceId,
+        sampled: bool,
+        rate: f64,
+        rule: MatchedRule<'_>,
+    ) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return;
+        }
+        let decision = SamplingDecision {
+            timestamp: current_timestamp(),
+            operation: operation.to_string(),
+            trace_id,
+            sampled,
+            rate,
+            rule: rule.to_rule(),
+        };
+        let mut decisions = self.decisions.lock().unwrap();
+        while decisions.len() >= capacity {
+            decisions.pop_front();
+        }
+        decisions.push_back(decision);
+    }
+
+    pub(crate) fn decisions(&self) -> Vec<SamplingDecision> {
+        self.decisions.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+#[cfg(not(feature = "disabled"))]
+impl SimpleTracer {
+    /// Keep the last `capacity` sampling decisions for inspection; 0 turns
+    /// the audit log off and discards it
+    ///
+    /// Meant as a debug mode: each audited decision allocates, so leave it
+    /// off outside investigations.
+    pub fn set_sampling_audit(&self, capacity: usize) {
+        self.sampling_audit.set_capacity(capacity);
+    }
+
+    /// Audited sampling decisions, oldest first
+    pub fn sampling_decisions(&self) -> Vec<SamplingDecision> {
+        self.sampling_audit.decisions()
+    }
+
+    /// Audited sampling decisions for one trace, in any `IdFormat`
+    pub fn sampling_decisions_for_trace(&self, trace_id: &str) -> Vec<SamplingDecision> {
+        let Some(trace_id) = TraceId::parse(trace_id) else {
+            return Vec::new();
+        };
+        self.sampling_decisions()
+            .into_iter()
+            .filter(|decision| decision.trace_id == trace_id)
+            .collect()
+    }
+}
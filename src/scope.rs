@@ -0,0 +1,114 @@
+//! Scoped tracer module
+//!
+//! Contains ScopedTracer, the view returned by `SimpleTracer::child_scope`.
+//! Spans started through it are stamped with the scope's attributes (e.g.
+//! `component`, `shard.id`); storage, export, and baggage stay shared with the
+//! parent tracer, so a scope is cheap to create per component or shard.
+
+use crate::span::{Span, SpanKind};
+use crate::tracer::{SimpleTracer, TracingManager, TracingResult};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Tracer view adding fixed attributes to every span it starts
+#[derive(Clone)]
+pub struct ScopedTracer<'a> {
+    tracer: &'a SimpleTracer,
+    attributes: Arc<HashMap<String, Value>>,
+}
+
+impl<'a> ScopedTracer<'a> {
+    pub(crate) fn new(tracer: &'a SimpleTracer, attributes: HashMap<String, Value>) -> Self {
+        Self {
+            tracer,
+            attributes: Arc::new(attributes),
+        }
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, Value> {
+        &self.attributes
+    }
+
+    /// Nested scope; its attributes override this scope's on conflict
+    pub fn child_scope<K, V>(&self, attributes: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let mut merged = (*self.attributes).clone();
+        merged.extend(
+            attributes
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        Self::new(self.tracer, merged)
+    }
+}
+
+impl SimpleTracer {
+    /// View of this tracer that stamps `attributes` on the spans it starts
+    pub fn child_scope<K, V>(
+        &self,
+        attributes: impl IntoIterator<Item = (K, V)>,
+    ) -> ScopedTracer<'_>
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        ScopedTracer::new(
+            self,
+            attributes
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl TracingManager for ScopedTracer<'_> {
+    fn start_span(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+    ) -> TracingResult<String> {
+        self.start_span_with_kind(operation_name, parent_span_id, SpanKind::Internal)
+    }
+
+    fn start_span_with_kind(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        let span_id = self
+            .tracer
+            .start_span_with_kind(operation_name, parent_span_id, kind)?;
+        self.tracer.update_span(&span_id, |span| {
+            for (key, value) in self.attributes.iter() {
+                span.attributes.insert_if_absent(key.clone(), value.clone());
+            }
+        })?;
+        Ok(span_id)
+    }
+
+    fn end_span(&self, span_id: String) -> TracingResult<()> {
+        self.tracer.end_span(span_id)
+    }
+
+    fn add_span_attribute(&self, span_id: &str, key: &str, value: Value) -> TracingResult<()> {
+        self.tracer.add_span_attribute(span_id, key, value)
+    }
+
+    fn get_completed_spans(&self) -> Vec<Span> {
+        self.tracer.get_completed_spans()
+    }
+
+    fn set_baggage(&self, key: &str, value: &str) {
+        self.tracer.set_baggage(key, value)
+    }
+
+    fn get_baggage(&self, key: &str) -> Option<String> {
+        self.tracer.get_baggage(key)
+    }
+}
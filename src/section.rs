@@ -0,0 +1,72 @@
+//! Section timing module
+//!
+//! Contains SectionTimer, a guard that records how long a named phase of a
+//! span took (e.g. `parse`, `validate`, `render`) as a span event, for phase
+//! timing without the ceremony of a child span.
+
+use crate::span::{current_timestamp, SpanEvent};
+use crate::tracer::{is_recording, SimpleTracer};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Event attribute holding a section's duration in (fractional) milliseconds
+pub const SECTION_DURATION_ATTRIBUTE: &str = "duration_ms";
+
+/// Records a `name` event with its duration on the span when dropped
+///
+/// The event is stamped with the section's start time. Sections of spans
+/// that already ended, or were not sampled, are ignored; for spans that are
+/// not recording the timer holds nothing and never reads the clock.
+#[must_use = "the section is recorded when the timer is dropped"]
+pub struct SectionTimer<'a> {
+    tracer: &'a SimpleTracer,
+    span_id: String,
+    name: String,
+    /// Wall-clock and monotonic start, `None` when the span is not recording
+    started: Option<(u64, Instant)>,
+}
+
+impl<'a> SectionTimer<'a> {
+    pub(crate) fn new(tracer: &'a SimpleTracer, span_id: &str, name: &str) -> Self {
+        if !is_recording(span_id) {
+            return Self {
+                tracer,
+                span_id: String::new(),
+                name: String::new(),
+                started: None,
+            };
+        }
+        Self {
+            tracer,
+            span_id: span_id.to_string(),
+            name: name.to_string(),
+            started: Some((current_timestamp(), Instant::now())),
+        }
+    }
+
+    /// Section name; empty when the span is not recording
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Record now instead of at the end of the scope
+    pub fn finish(self) {}
+}
+
+impl Drop for SectionTimer<'_> {
+    fn drop(&mut self) {
+        let Some((started_at, started)) = self.started else {
+            return;
+        };
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let event = SpanEvent {
+            name: std::mem::take(&mut self.name),
+            timestamp: started_at,
+            attributes: HashMap::from([(
+                SECTION_DURATION_ATTRIBUTE.to_string(),
+                serde_json::json!(duration_ms),
+            )]),
+        };
+        let _ = self.tracer.push_event(&self.span_id, event);
+    }
+}
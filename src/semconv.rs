@@ -0,0 +1,154 @@
+//! Semantic conventions module
+//!
+//! Contains helpers that record well-known operations with OpenTelemetry
+//! semantic-convention attribute names, so backends can recognize them.
+
+/// Cache operation conventions
+///
+/// [`get`] and [`set`] wrap a cache call in a `cache.get` / `cache.set` span
+/// recording whether it hit, a hash of the key (keys may hold user data), and
+/// the TTL written, so hit ratio and cache latency can be read from traces.
+pub mod cache {
+    use crate::span::SpanKind;
+    use crate::tracer::TracingManager;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    pub const GET_SPAN_NAME: &str = "cache.get";
+    pub const SET_SPAN_NAME: &str = "cache.set";
+    /// Whether a get found the key
+    pub const HIT: &str = "cache.hit";
+    /// [`key_hash`] of the key, as 16 hex digits
+    pub const KEY_HASH: &str = "cache.key_hash";
+    /// TTL of a set, in milliseconds; absent when the entry does not expire
+    pub const TTL: &str = "cache.ttl";
+
+    /// FNV-1a hash of a cache key, stable across processes and releases
+    pub fn key_hash(key: &str) -> String {
+        let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Run a cache lookup inside a `cache.get` span; `Some` counts as a hit
+    pub fn get<T, M>(
+        tracer: &M,
+        parent_span_id: Option<String>,
+        key: &str,
+        lookup: impl FnOnce() -> Option<T>,
+    ) -> Option<T>
+    where
+        M: TracingManager + ?Sized,
+    {
+        traced(tracer, parent_span_id, GET_SPAN_NAME, key, || {
+            let value = lookup();
+            let hit = value.is_some();
+            (value, vec![(HIT, hit.into())])
+        })
+    }
+
+    /// Run a cache write inside a `cache.set` span
+    pub fn set<R, M>(
+        tracer: &M,
+        parent_span_id: Option<String>,
+        key: &str,
+        ttl: Option<Duration>,
+        write: impl FnOnce() -> R,
+    ) -> R
+    where
+        M: TracingManager + ?Sized,
+    {
+        traced(tracer, parent_span_id, SET_SPAN_NAME, key, || {
+            let attributes = ttl
+                .map(|ttl| (TTL, Value::from(ttl.as_millis() as u64)))
+                .into_iter()
+                .collect();
+            (write(), attributes)
+        })
+    }
+
+    fn traced<R, M>(
+        tracer: &M,
+        parent_span_id: Option<String>,
+        name: &str,
+        key: &str,
+        operation: impl FnOnce() -> (R, Vec<(&'static str, Value)>),
+    ) -> R
+    where
+        M: TracingManager + ?Sized,
+    {
+        let span_id = tracer
+            .start_span_with_kind(name, parent_span_id, SpanKind::Internal)
+            .ok();
+        let (result, attributes) = operation();
+        if let Some(span_id) = span_id {
+            let _ = tracer.add_span_attribute(&span_id, KEY_HASH, key_hash(key).into());
+            for (key, attribute) in attributes {
+                let _ = tracer.add_span_attribute(&span_id, key, attribute);
+            }
+            let _ = tracer.end_span(span_id);
+        }
+        result
+    }
+}
+
+/// OpenTelemetry feature-flag conventions
+///
+/// A flag evaluation is recorded as a `feature_flag` event on the span that
+/// evaluated it, or as its own internal span with [`evaluate`].
+pub mod feature_flag {
+    use crate::span::{Span, SpanKind};
+    use crate::tracer::TracingManager;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::fmt::Display;
+
+    /// Name of the evaluation event
+    pub const EVENT_NAME: &str = "feature_flag";
+    /// Operation name of spans created by [`evaluate`]
+    pub const SPAN_NAME: &str = "feature_flag.evaluate";
+    pub const KEY: &str = "feature_flag.key";
+    pub const VARIANT: &str = "feature_flag.variant";
+    pub const PROVIDER_NAME: &str = "feature_flag.provider_name";
+
+    /// Convention attributes of one evaluation
+    pub fn attributes(flag_key: &str, variant: &str, provider: &str) -> HashMap<String, Value> {
+        HashMap::from([
+            (KEY.to_string(), flag_key.into()),
+            (VARIANT.to_string(), variant.into()),
+            (PROVIDER_NAME.to_string(), provider.into()),
+        ])
+    }
+
+    /// Add a `feature_flag` event for an evaluation to `span`
+    pub fn record(span: &mut Span, flag_key: &str, variant: &str, provider: &str) {
+        span.add_event(EVENT_NAME, attributes(flag_key, variant, provider));
+    }
+
+    /// Run a flag evaluation inside an internal `feature_flag.evaluate` span;
+    /// the returned value's `Display` form is recorded as the variant
+    pub fn evaluate<T, M>(
+        tracer: &M,
+        parent_span_id: Option<String>,
+        flag_key: &str,
+        provider: &str,
+        evaluation: impl FnOnce() -> T,
+    ) -> T
+    where
+        T: Display,
+        M: TracingManager + ?Sized,
+    {
+        let span_id = tracer
+            .start_span_with_kind(SPAN_NAME, parent_span_id, SpanKind::Internal)
+            .ok();
+        let value = evaluation();
+        if let Some(span_id) = span_id {
+            for (key, attribute) in attributes(flag_key, &value.to_string(), provider) {
+                let _ = tracer.add_span_attribute(&span_id, &key, attribute);
+            }
+            let _ = tracer.end_span(span_id);
+        }
+        value
+    }
+}
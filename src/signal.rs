@@ -0,0 +1,128 @@
+//! Signal dump module
+//!
+//! Contains an opt-in SIGUSR1 handler (`unix` cargo feature) that writes every
+//! active span, with its age and attributes, to stderr or a file, for
+//! diagnosing hung services in production:
+//!
+//! ```text
+//! kill -USR1 <pid>
+//! ```
+//!
+//! The handler itself only wakes a background thread through a pipe; the
+//! spans are serialized on that thread, outside of signal context.
+
+use crate::span::{current_timestamp, Span};
+use crate::tracer::{SimpleTracer, TracingResult};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Weak};
+use tyl_errors::TylError;
+
+/// Write end of the wake-up pipe, -1 until a handler is installed
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Where a span dump is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpTarget {
+    Stderr,
+    /// Appended to, so repeated dumps accumulate
+    File(PathBuf),
+}
+
+/// Write one JSON line per active span (`age_ms` plus the span fields),
+/// oldest first; returns how many spans were written
+pub fn dump_active_spans(tracer: &SimpleTracer, writer: &mut impl Write) -> std::io::Result<usize> {
+    let mut spans = tracer.active_spans();
+    spans.sort_by_key(|span| span.start_time);
+    let now = current_timestamp();
+    for span in &spans {
+        writer.write_all(dump_line(span, now).as_bytes())?;
+    }
+    writer.flush()?;
+    Ok(spans.len())
+}
+
+fn dump_line(span: &Span, now: u64) -> String {
+    let mut record = serde_json::to_value(span).unwrap_or_default();
+    if let Some(fields) = record.as_object_mut() {
+        fields.insert(
+            "age_ms".to_string(),
+            now.saturating_sub(span.start_time).into(),
+        );
+    }
+    format!("{}\n", record)
+}
+
+/// Dump the tracer's active spans to `target` on every SIGUSR1
+///
+/// Only one handler can be installed per process. The dump thread holds a
+/// weak reference and stops once the tracer is dropped.
+pub fn install_dump_handler(tracer: &Arc<SimpleTracer>, target: DumpTarget) -> TracingResult<()> {
+    let os_error =
+        |what: &str| TylError::internal(format!("{}: {}", what, std::io::Error::last_os_error()));
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors pipe() writes
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(os_error("cannot create span dump pipe"));
+    }
+    // SAFETY: both descriptors were just created and are owned by these files
+    let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+    if WAKE_FD
+        .compare_exchange(-1, fds[1], Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return Err(TylError::configuration(
+            "a SIGUSR1 span dump handler is already installed",
+        ));
+    }
+    // The signal handler writes to this descriptor for the rest of the process
+    std::mem::forget(writer);
+
+    // SAFETY: the sigaction struct is fully initialized before use, and the
+    // handler only performs an async-signal-safe write()
+    let installed = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_sigusr1 as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut()) == 0
+    };
+    if !installed {
+        return Err(os_error("cannot install SIGUSR1 handler"));
+    }
+
+    let tracer = Arc::downgrade(tracer);
+    std::thread::Builder::new()
+        .name("tyl-span-dump".to_string())
+        .spawn(move || run_dump_thread(reader, tracer, target))
+        .map_err(|e| TylError::internal(format!("cannot start span dump thread: {}", e)))?;
+    Ok(())
+}
+
+extern "C" fn on_sigusr1(_signal: libc::c_int) {
+    let fd = WAKE_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        // SAFETY: write() is async-signal-safe and the buffer outlives the call
+        unsafe { libc::write(fd, [1u8].as_ptr().cast(), 1) };
+    }
+}
+
+fn run_dump_thread(mut reader: File, tracer: Weak<SimpleTracer>, target: DumpTarget) {
+    let mut wake = [0u8; 1];
+    while matches!(reader.read(&mut wake), Ok(1)) {
+        let Some(tracer) = tracer.upgrade() else {
+            return;
+        };
+        let _ = match &target {
+            DumpTarget::Stderr => dump_active_spans(&tracer, &mut std::io::stderr().lock()),
+            DumpTarget::File(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| dump_active_spans(&tracer, &mut file)),
+        };
+    }
+}
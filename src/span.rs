@@ -1,42 +1,100 @@
 //! Span management module
 //!
-//! Contains the Span struct, SpanStatus enum, and related functionality for
+//! Contains the Span struct, SpanStatus enum, SpanEvent, and related functionality for
 //! managing distributed tracing spans.
 
+use crate::attributes::Attributes;
+use crate::ids::{SpanId, TraceId};
 use serde::{Deserialize, Serialize};
+#[cfg(any(test, feature = "testing"))]
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
+
+/// Name of the progress events added by `SimpleTracer::span_heartbeat`
+pub const HEARTBEAT_EVENT: &str = "heartbeat";
 
 /// Core span data structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Span {
-    pub span_id: String,
-    pub trace_id: String,
-    pub parent_span_id: Option<String>,
+    pub span_id: SpanId,
+    pub trace_id: TraceId,
+    pub parent_span_id: Option<SpanId>,
     pub operation_name: String,
     pub start_time: u64,
     pub end_time: Option<u64>,
-    pub attributes: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub attributes: Attributes,
     pub status: SpanStatus,
+    #[serde(default)]
+    pub kind: SpanKind,
+    /// Timestamped events recorded while the span was active
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<SpanEvent>,
+    /// Causally related spans that are not the parent (e.g. the producers
+    /// of a consumed batch)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<SpanLink>,
+}
+
+/// Reference to a span in this or another trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanLink {
+    pub trace_id: TraceId,
+    pub span_id: SpanId,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Point-in-time occurrence within a span (progress, retries, errors)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanEvent {
+    pub name: String,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, serde_json::Value>,
 }
 
 /// Span execution status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SpanStatus {
     Active,
     Completed,
     Error { message: String },
 }
 
+/// Role of a span in a distributed call (OpenTelemetry span kind)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpanKind {
+    #[default]
+    Internal,
+    Server,
+    Client,
+    Producer,
+    Consumer,
+}
+
 impl Span {
-    pub fn new(operation_name: String, parent_span_id: Option<String>) -> Self {
-        let span_id = generate_span_id();
-        let trace_id = parent_span_id
-            .as_ref()
-            .map(|_| generate_trace_id()) // In real implementation, inherit from parent
-            .unwrap_or_else(generate_trace_id);
+    pub fn new(operation_name: String, parent_span_id: Option<SpanId>) -> Self {
+        Self::in_trace(generate_trace_id(), operation_name, parent_span_id)
+    }
+
+    /// New span in an existing trace
+    pub fn in_trace(
+        trace_id: TraceId,
+        operation_name: String,
+        parent_span_id: Option<SpanId>,
+    ) -> Self {
+        Self::with_ids(generate_span_id(), trace_id, operation_name, parent_span_id)
+    }
 
+    pub(crate) fn with_ids(
+        span_id: SpanId,
+        trace_id: TraceId,
+        operation_name: String,
+        parent_span_id: Option<SpanId>,
+    ) -> Self {
         Self {
             span_id,
             trace_id,
@@ -44,15 +102,73 @@ impl Span {
             operation_name,
             start_time: current_timestamp(),
             end_time: None,
-            attributes: HashMap::new(),
+            attributes: Attributes::new(),
             status: SpanStatus::Active,
+            kind: SpanKind::Internal,
+            events: Vec::new(),
+            links: Vec::new(),
         }
     }
 
+    pub fn with_kind(mut self, kind: SpanKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Append an event stamped with the current time
+    pub fn add_event(
+        &mut self,
+        name: impl Into<String>,
+        attributes: HashMap<String, serde_json::Value>,
+    ) {
+        self.events.push(SpanEvent {
+            name: name.into(),
+            timestamp: current_timestamp(),
+            attributes,
+        });
+    }
+
+    /// Time of the most recent sign of life: the last heartbeat, else the start
+    pub fn last_activity(&self) -> u64 {
+        self.events
+            .iter()
+            .rev()
+            .find(|event| event.name == HEARTBEAT_EVENT)
+            .map_or(self.start_time, |event| event.timestamp)
+    }
+
     pub fn duration_ms(&self) -> Option<u64> {
         self.end_time.map(|end| end.saturating_sub(self.start_time))
     }
 
+    /// Duration not covered by any of `children`, in milliseconds
+    ///
+    /// Overlapping (concurrent) children count once, and child time outside
+    /// this span is ignored. A child still open counts until this span's end.
+    /// `None` while this span is active.
+    pub fn self_time(&self, children: &[&Span]) -> Option<u64> {
+        let end = self.end_time?;
+        let mut intervals: Vec<(u64, u64)> = children
+            .iter()
+            .map(|child| {
+                let start = child.start_time.clamp(self.start_time, end);
+                (start, child.end_time.unwrap_or(end).clamp(start, end))
+            })
+            .filter(|(start, end)| end > start)
+            .collect();
+        intervals.sort_unstable();
+        let mut covered = 0;
+        let mut reached = self.start_time;
+        for (start, end) in intervals {
+            let start = start.max(reached);
+            if end > start {
+                covered += end - start;
+                reached = end;
+            }
+        }
+        Some((end - self.start_time).saturating_sub(covered))
+    }
+
     pub fn is_active(&self) -> bool {
         matches!(self.status, SpanStatus::Active)
     }
@@ -62,22 +178,96 @@ impl Span {
         self.status = SpanStatus::Completed;
     }
 
+    /// End the span, keeping an error status recorded while it was open
+    pub fn finish(&mut self) {
+        match self.status {
+            SpanStatus::Error { .. } => self.end_time = Some(current_timestamp()),
+            _ => self.complete(),
+        }
+    }
+
     pub fn error(&mut self, message: String) {
         self.end_time = Some(current_timestamp());
         self.status = SpanStatus::Error { message };
     }
 }
 
+#[cfg(feature = "embedded")]
+impl Span {
+    /// Host-side conversion of a span recorded by the embedded core
+    ///
+    /// `epoch_offset_ns` maps the device clock to Unix time (nanoseconds since
+    /// the epoch at device clock zero).
+    pub fn from_core(span: crate::embedded::CoreSpan, epoch_offset_ns: u64) -> Self {
+        use crate::embedded::CoreValue;
+
+        let to_ms = |ns: u64| ns.saturating_add(epoch_offset_ns) / 1_000_000;
+        let attributes = span
+            .attributes
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    CoreValue::Bool(b) => serde_json::Value::from(b),
+                    CoreValue::Int(i) => i.into(),
+                    CoreValue::Float(f) => f.into(),
+                    CoreValue::Str(s) => s.into_owned().into(),
+                };
+                (key.into_owned(), value)
+            })
+            .collect();
+        let end_time = span.end_ns.map(to_ms);
+        Self {
+            span_id: SpanId::from_bytes(span.span_id.to_be_bytes()),
+            trace_id: TraceId::from_bytes(span.trace_id.to_be_bytes()),
+            parent_span_id: span
+                .parent_span_id
+                .map(|id| SpanId::from_bytes(id.to_be_bytes())),
+            operation_name: span.name.into_owned(),
+            start_time: to_ms(span.start_ns),
+            end_time,
+            attributes,
+            status: match (span.error, end_time) {
+                (Some(message), _) => SpanStatus::Error {
+                    message: message.into_owned(),
+                },
+                (None, Some(_)) => SpanStatus::Completed,
+                (None, None) => SpanStatus::Active,
+            },
+            kind: SpanKind::Internal,
+            events: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+}
+
 // Utility functions
-pub fn generate_span_id() -> String {
-    Uuid::new_v4().to_string()
+pub fn generate_span_id() -> SpanId {
+    SpanId::random()
 }
 
-pub fn generate_trace_id() -> String {
-    Uuid::new_v4().to_string()
+pub fn generate_trace_id() -> TraceId {
+    TraceId::random()
 }
 
-fn current_timestamp() -> u64 {
+// Only test builds can override the clock, so production builds read the
+// system time without a thread-local lookup
+#[cfg(any(test, feature = "testing"))]
+thread_local! {
+    /// Time of this thread's `testing::ManualClock`, if one is installed
+    static MANUAL_TIME: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Replace this thread's manual time, returning the previous one
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn set_manual_time(time: Option<u64>) -> Option<u64> {
+    MANUAL_TIME.with(|manual| manual.replace(time))
+}
+
+pub(crate) fn current_timestamp() -> u64 {
+    #[cfg(any(test, feature = "testing"))]
+    if let Some(now) = MANUAL_TIME.with(Cell::get) {
+        return now;
+    }
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
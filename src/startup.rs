@@ -0,0 +1,131 @@
+//! Startup tracing module
+//!
+//! Contains StartupTrace, a process-level root span emitted once on boot with
+//! one child span per startup phase (config load, migrations, warmup, ...),
+//! to find out which phase makes a cold start slow, e.g. when a Kubernetes
+//! pod takes long to become ready.
+
+use crate::context::Context;
+use crate::span::SpanStatus;
+use crate::tracer::{SimpleTracer, TracingManager, TracingResult, NOOP_SPAN_ID};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tyl_errors::TylError;
+
+/// Operation name of the root span
+pub const STARTUP_SPAN_NAME: &str = "process.startup";
+/// Root span attribute: process ID
+pub const PROCESS_PID_ATTRIBUTE: &str = "process.pid";
+/// Phase span attribute: phase name
+pub const STARTUP_PHASE_ATTRIBUTE: &str = "startup.phase";
+/// Root span attribute: total startup time in milliseconds
+pub const STARTUP_DURATION_ATTRIBUTE: &str = "startup.duration_ms";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Root span of the process startup; ends when finished or dropped
+#[must_use = "the startup span ends when the StartupTrace is dropped"]
+pub struct StartupTrace<'a> {
+    tracer: &'a SimpleTracer,
+    span_id: String,
+    started: Instant,
+    finished: bool,
+}
+
+impl<'a> StartupTrace<'a> {
+    /// Start the startup span; fails if startup was already traced in this
+    /// process
+    pub fn begin(tracer: &'a SimpleTracer) -> TracingResult<Self> {
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return Err(TylError::validation(
+                "startup",
+                "startup is traced once per process",
+            ));
+        }
+        let span_id = tracer.start_span(STARTUP_SPAN_NAME, None)?;
+        let _ =
+            tracer.add_span_attribute(&span_id, PROCESS_PID_ATTRIBUTE, std::process::id().into());
+        Ok(Self {
+            tracer,
+            span_id,
+            started: Instant::now(),
+            finished: false,
+        })
+    }
+
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    /// Run a startup phase in a child span; spans started inside it without
+    /// an explicit parent become its children
+    pub fn phase<R>(&self, name: &str, run: impl FnOnce() -> R) -> R {
+        self.run_phase(name, run, |_| None)
+    }
+
+    /// [`StartupTrace::phase`] for fallible phases; errors mark the phase
+    /// span failed
+    pub fn try_phase<R, E: Display>(
+        &self,
+        name: &str,
+        run: impl FnOnce() -> Result<R, E>,
+    ) -> Result<R, E> {
+        self.run_phase(name, run, |result| {
+            result.as_ref().err().map(|error| error.to_string())
+        })
+    }
+
+    /// End the startup span; returns the total startup time
+    pub fn finish(mut self) -> Duration {
+        self.complete()
+    }
+
+    fn run_phase<R>(
+        &self,
+        name: &str,
+        run: impl FnOnce() -> R,
+        failure: impl FnOnce(&R) -> Option<String>,
+    ) -> R {
+        let span_id = self
+            .tracer
+            .start_span(name, Some(self.span_id.clone()))
+            .unwrap_or_else(|_| NOOP_SPAN_ID.to_string());
+        let _ = self
+            .tracer
+            .add_span_attribute(&span_id, STARTUP_PHASE_ATTRIBUTE, name.into());
+
+        let result = {
+            let _guard = Context::with_span(span_id.clone()).attach();
+            run()
+        };
+
+        if let Some(message) = failure(&result) {
+            let _ = self.tracer.update_span(&span_id, |span| {
+                span.status = SpanStatus::Error { message };
+            });
+        }
+        let _ = self.tracer.end_span(span_id);
+        result
+    }
+
+    fn complete(&mut self) -> Duration {
+        self.finished = true;
+        let elapsed = self.started.elapsed();
+        let _ = self.tracer.add_span_attribute(
+            &self.span_id,
+            STARTUP_DURATION_ATTRIBUTE,
+            (elapsed.as_millis() as u64).into(),
+        );
+        let _ = self.tracer.end_span(std::mem::take(&mut self.span_id));
+        elapsed
+    }
+}
+
+impl Drop for StartupTrace<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.complete();
+        }
+    }
+}
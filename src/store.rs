@@ -0,0 +1,185 @@
+//! Span store module
+//!
+//! Contains SpanStore, the completed-span storage of SimpleTracer. Spans are
+//! kept in end order, grouped into one-minute buckets by end time, so
+//! retention pruning drops whole buckets (O(buckets) rather than O(spans))
+//! and time-range queries only visit the buckets that overlap the range.
+//!
+//! Every span gets an increasing sequence number, and a trace-ID index maps
+//! each trace to the sequence numbers of its spans, so fetching one trace
+//! does not scan (or clone) the whole store.
+
+use crate::ids::TraceId;
+use crate::span::Span;
+use std::collections::{HashMap, VecDeque};
+
+/// Time window covered by one bucket
+pub(crate) const BUCKET_WIDTH_MS: u64 = 60_000;
+
+/// Spans that ended in `[start, start + BUCKET_WIDTH_MS)`, never empty
+#[derive(Debug)]
+struct Bucket {
+    start: u64,
+    /// Spans with their sequence numbers, in increasing order
+    spans: VecDeque<(u64, Span)>,
+}
+
+impl Bucket {
+    fn last_seq(&self) -> u64 {
+        self.spans.back().map_or(0, |(seq, _)| *seq)
+    }
+}
+
+/// Time when a completed span ended
+fn end_time(span: &Span) -> u64 {
+    span.end_time.unwrap_or(span.start_time)
+}
+
+/// Completed spans in end order, bucketed by end time and indexed by trace
+#[derive(Debug, Default)]
+pub(crate) struct SpanStore {
+    buckets: VecDeque<Bucket>,
+    len: usize,
+    next_seq: u64,
+    /// Sequence numbers of each trace's spans, in increasing order
+    by_trace: HashMap<TraceId, Vec<u64>>,
+}
+
+impl SpanStore {
+    pub(crate) fn push(&mut self, span: Span) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.by_trace.entry(span.trace_id).or_default().push(seq);
+
+        let start = end_time(&span) - end_time(&span) % BUCKET_WIDTH_MS;
+        match self.buckets.back_mut() {
+            // A clock step backwards lands in the newest bucket, keeping order
+            Some(bucket) if bucket.start >= start => bucket.spans.push_back((seq, span)),
+            _ => self.buckets.push_back(Bucket {
+                start,
+                spans: VecDeque::from([(seq, span)]),
+            }),
+        }
+        self.len += 1;
+    }
+
+    /// Drop the oldest spans until at most `max_spans` remain
+    pub(crate) fn truncate_oldest(&mut self, max_spans: usize) {
+        while self.len > max_spans {
+            let Some(bucket) = self.buckets.front_mut() else {
+                break;
+            };
+            let excess = (self.len - max_spans).min(bucket.spans.len());
+            for (seq, span) in bucket.spans.drain(..excess) {
+                unindex(&mut self.by_trace, span.trace_id, seq);
+            }
+            self.len -= excess;
+            if bucket.spans.is_empty() {
+                self.buckets.pop_front();
+            }
+        }
+    }
+
+    /// Remove spans that ended before `cutoff` (Unix ms); returns how many
+    pub(crate) fn prune_before(&mut self, cutoff: u64) -> usize {
+        let mut pruned = 0;
+        while let Some(bucket) = self.buckets.front_mut() {
+            let whole_bucket = bucket.start + BUCKET_WIDTH_MS <= cutoff;
+            let expired = if whole_bucket {
+                bucket.spans.len()
+            } else {
+                // Only the oldest remaining bucket can straddle the cutoff
+                bucket
+                    .spans
+                    .iter()
+                    .take_while(|(_, span)| end_time(span) < cutoff)
+                    .count()
+            };
+            for (seq, span) in bucket.spans.drain(..expired) {
+                unindex(&mut self.by_trace, span.trace_id, seq);
+            }
+            pruned += expired;
+            if bucket.spans.is_empty() {
+                self.buckets.pop_front();
+            }
+            if !whole_bucket {
+                break;
+            }
+        }
+        self.len -= pruned;
+        pruned
+    }
+
+    /// Keep only spans matching `keep`; returns how many were removed
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&Span) -> bool) -> usize {
+        let before = self.len;
+        let by_trace = &mut self.by_trace;
+        for bucket in &mut self.buckets {
+            bucket.spans.retain(|(seq, span)| {
+                let kept = keep(span);
+                if !kept {
+                    unindex(by_trace, span.trace_id, *seq);
+                }
+                kept
+            });
+        }
+        self.buckets.retain(|bucket| !bucket.spans.is_empty());
+        self.len = self.buckets.iter().map(|bucket| bucket.spans.len()).sum();
+        before - self.len
+    }
+
+    /// Remove every span; returns how many were removed
+    pub(crate) fn clear(&mut self) -> usize {
+        self.buckets.clear();
+        self.by_trace.clear();
+        std::mem::take(&mut self.len)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Span> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.spans.iter().map(|(_, span)| span))
+    }
+
+    /// Spans that ended within `[from, to]` (Unix ms)
+    pub(crate) fn range(&self, from: u64, to: u64) -> impl Iterator<Item = &Span> {
+        self.buckets
+            .iter()
+            .filter(move |bucket| bucket.start + BUCKET_WIDTH_MS > from && bucket.start <= to)
+            .flat_map(|bucket| bucket.spans.iter().map(|(_, span)| span))
+            .filter(move |span| (from..=to).contains(&end_time(span)))
+    }
+
+    /// Spans of one trace, in end order
+    pub(crate) fn trace(&self, trace_id: TraceId) -> impl Iterator<Item = &Span> {
+        self.by_trace
+            .get(&trace_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|seq| self.get(*seq))
+    }
+
+    fn get(&self, seq: u64) -> Option<&Span> {
+        let bucket = self.buckets.get(
+            self.buckets
+                .partition_point(|bucket| bucket.last_seq() < seq),
+        )?;
+        let index = bucket
+            .spans
+            .binary_search_by_key(&seq, |(seq, _)| *seq)
+            .ok()?;
+        Some(&bucket.spans[index].1)
+    }
+}
+
+/// Forget one span of a trace; spans usually leave oldest first
+fn unindex(by_trace: &mut HashMap<TraceId, Vec<u64>>, trace_id: TraceId, seq: u64) {
+    if let Some(seqs) = by_trace.get_mut(&trace_id) {
+        if let Some(position) = seqs.iter().position(|indexed| *indexed == seq) {
+            seqs.remove(position);
+        }
+        if seqs.is_empty() {
+            by_trace.remove(&trace_id);
+        }
+    }
+}
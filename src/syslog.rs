@@ -0,0 +1,222 @@
+//! Host log sink module
+//!
+//! Contains SyslogExporter, which writes one compact record per completed span
+//! to the local syslog socket (`/dev/log`) or natively to the systemd journal,
+//! for single-host deployments without a tracing backend. Journal records
+//! carry the span as structured fields (`TRACE_ID`, `SPAN_ID`, `TYL_ATTR_*`,
+//! ...); syslog records append them to the message as `key=value` pairs.
+//! Unix only.
+
+use crate::export::SpanExporter;
+use crate::span::{Span, SpanStatus};
+use crate::tracer::TracingResult;
+use serde_json::Value;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use tyl_errors::TylError;
+
+/// Default socket of the local syslog daemon
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+/// Default socket of systemd-journald's native protocol
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// `user` facility (RFC 5424 code 1)
+const DEFAULT_FACILITY: u8 = 1;
+/// Syslog severities for completed and failed spans
+const SEVERITY_INFO: u8 = 6;
+const SEVERITY_ERROR: u8 = 3;
+
+/// Where a SyslogExporter writes its records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogDestination {
+    /// BSD syslog lines (`<PRI>ident[pid]: message`)
+    Syslog,
+    /// systemd journal native protocol with one field per span property
+    Journal,
+}
+
+/// Adapter - Writes completed spans to syslog or the systemd journal
+#[derive(Debug)]
+pub struct SyslogExporter {
+    socket: UnixDatagram,
+    destination: SyslogDestination,
+    path: PathBuf,
+    identifier: String,
+    facility: u8,
+}
+
+impl SyslogExporter {
+    /// Syslog records tagged with `identifier`, sent to `/dev/log`
+    pub fn syslog(identifier: impl Into<String>) -> TracingResult<Self> {
+        Self::new(SyslogDestination::Syslog, SYSLOG_SOCKET, identifier)
+    }
+
+    /// Journal records with `SYSLOG_IDENTIFIER=identifier`
+    pub fn journal(identifier: impl Into<String>) -> TracingResult<Self> {
+        Self::new(SyslogDestination::Journal, JOURNAL_SOCKET, identifier)
+    }
+
+    fn new(
+        destination: SyslogDestination,
+        path: &str,
+        identifier: impl Into<String>,
+    ) -> TracingResult<Self> {
+        let socket = UnixDatagram::unbound()
+            .map_err(|e| TylError::network(format!("cannot open log socket: {}", e)))?;
+        Ok(Self {
+            socket,
+            destination,
+            path: PathBuf::from(path),
+            identifier: identifier.into(),
+            facility: DEFAULT_FACILITY,
+        })
+    }
+
+    /// Send to another socket (e.g. a container-mounted `/dev/log`)
+    pub fn with_socket_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Syslog facility code, e.g. 16 for `local0` (clamped to 0..=23)
+    pub fn with_facility(mut self, facility: u8) -> Self {
+        self.facility = facility.min(23);
+        self
+    }
+
+    pub fn destination(&self) -> SyslogDestination {
+        self.destination
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn severity(span: &Span) -> u8 {
+        match span.status {
+            SpanStatus::Error { .. } => SEVERITY_ERROR,
+            _ => SEVERITY_INFO,
+        }
+    }
+
+    fn syslog_record(&self, span: &Span) -> Vec<u8> {
+        let priority = self.facility * 8 + Self::severity(span);
+        let mut line = format!(
+            "<{}>{}[{}]: {}",
+            priority,
+            self.identifier,
+            std::process::id(),
+            summary(span)
+        );
+        for (key, value) in fields(span) {
+            line.push(' ');
+            line.push_str(&key);
+            line.push('=');
+            line.push_str(&logfmt_value(&value));
+        }
+        line.into_bytes()
+    }
+
+    fn journal_record(&self, span: &Span) -> Vec<u8> {
+        let mut record = Vec::new();
+        let mut push = |name: &str, value: &str| {
+            record.extend_from_slice(name.as_bytes());
+            if value.contains('\n') {
+                // Binary-safe form: name, newline, little-endian length, value
+                record.push(b'\n');
+                record.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            } else {
+                record.push(b'=');
+            }
+            record.extend_from_slice(value.as_bytes());
+            record.push(b'\n');
+        };
+        push("MESSAGE", &summary(span));
+        push("PRIORITY", &Self::severity(span).to_string());
+        push("SYSLOG_FACILITY", &self.facility.to_string());
+        push("SYSLOG_IDENTIFIER", &self.identifier);
+        for (key, value) in fields(span) {
+            let name = match key.strip_prefix("attr.") {
+                Some(attribute) => format!("TYL_ATTR_{}", journal_field_name(attribute)),
+                None => journal_field_name(&key),
+            };
+            push(&name, &value);
+        }
+        record
+    }
+}
+
+impl SpanExporter for SyslogExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        for span in batch {
+            let record = match self.destination {
+                SyslogDestination::Syslog => self.syslog_record(span),
+                SyslogDestination::Journal => self.journal_record(span),
+            };
+            self.socket
+                .send_to(&record, &self.path)
+                .map_err(|e| TylError::network(format!("{}: {}", self.path.display(), e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// `operation 12ms ok` / `operation 12ms error: message`
+fn summary(span: &Span) -> String {
+    let duration = span
+        .duration_ms()
+        .map(|ms| format!("{}ms", ms))
+        .unwrap_or_else(|| "-".to_string());
+    match &span.status {
+        SpanStatus::Error { message } => {
+            format!("{} {} error: {}", span.operation_name, duration, message)
+        }
+        SpanStatus::Active => format!("{} {} active", span.operation_name, duration),
+        SpanStatus::Completed => format!("{} {} ok", span.operation_name, duration),
+    }
+}
+
+/// Span identity and timing, then sorted attributes prefixed with `attr.`
+fn fields(span: &Span) -> Vec<(String, String)> {
+    let mut fields = vec![
+        ("trace_id".to_string(), span.trace_id.to_string()),
+        ("span_id".to_string(), span.span_id.to_string()),
+    ];
+    if let Some(parent) = &span.parent_span_id {
+        fields.push(("parent_span_id".to_string(), parent.to_string()));
+    }
+    fields.push(("operation".to_string(), span.operation_name.clone()));
+    fields.push(("start_ms".to_string(), span.start_time.to_string()));
+    if let Some(duration) = span.duration_ms() {
+        fields.push(("duration_ms".to_string(), duration.to_string()));
+    }
+    let mut attributes: Vec<_> = span.attributes.iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in attributes {
+        let value = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        fields.push((format!("attr.{}", key), value));
+    }
+    fields
+}
+
+/// Quote values that would break `key=value` parsing
+fn logfmt_value(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        Value::String(value.to_string()).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Journal field names are upper-case ASCII letters, digits and `_`
+fn journal_field_name(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect()
+}
@@ -0,0 +1,177 @@
+//! Multi-tenant tracing module
+//!
+//! Contains per-tenant settings (sampling rate, span quota), the TenantTracer
+//! view returned by `SimpleTracer::with_tenant`, and quota bookkeeping. Spans
+//! started through a tenant view, and their children, carry a `tenant.id`
+//! attribute that exporters also surface as a resource attribute.
+
+use crate::span::{current_timestamp, Span, SpanKind};
+use crate::tracer::{SimpleTracer, TracingManager, TracingResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Span attribute holding the tenant a span belongs to
+pub const TENANT_ID_ATTRIBUTE: &str = "tenant.id";
+
+/// Length of the span quota window
+const QUOTA_WINDOW_MS: u64 = 60_000;
+
+/// Per-tenant overrides of the tracer configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Root span sampling rate for this tenant (defaults to the global rate)
+    #[serde(default)]
+    pub sampling_rate: Option<f64>,
+    /// Maximum spans recorded per minute; further spans are dropped
+    #[serde(default)]
+    pub spans_per_minute: Option<u64>,
+}
+
+impl TenantConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sampling_rate(mut self, rate: f64) -> Self {
+        self.sampling_rate = Some(rate);
+        self
+    }
+
+    pub fn with_spans_per_minute(mut self, quota: u64) -> Self {
+        self.spans_per_minute = Some(quota);
+        self
+    }
+}
+
+/// The tenant recorded on a span, if any
+pub fn span_tenant(span: &Span) -> Option<&str> {
+    span.attributes
+        .get(TENANT_ID_ATTRIBUTE)
+        .and_then(|tenant| tenant.as_str())
+}
+
+#[derive(Debug, Default)]
+struct TenantUsage {
+    window_start: u64,
+    recorded: u64,
+    dropped: u64,
+}
+
+/// Fixed-window span counters per tenant
+#[derive(Debug, Default)]
+pub(crate) struct TenantQuotas {
+    usage: Mutex<HashMap<String, TenantUsage>>,
+}
+
+impl TenantQuotas {
+    /// Count a span against the tenant's quota; false when it must be dropped
+    pub(crate) fn try_acquire(&self, tenant: &str, spans_per_minute: Option<u64>) -> bool {
+        let Some(limit) = spans_per_minute else {
+            return true;
+        };
+        let now = current_timestamp();
+        let mut usage = self.usage.lock().unwrap();
+        let usage = usage.entry(tenant.to_string()).or_default();
+        if now.saturating_sub(usage.window_start) >= QUOTA_WINDOW_MS {
+            usage.window_start = now;
+            usage.recorded = 0;
+        }
+        if usage.recorded < limit {
+            usage.recorded += 1;
+            true
+        } else {
+            usage.dropped += 1;
+            false
+        }
+    }
+
+    /// Spans dropped by quotas across all tenants
+    pub(crate) fn total_dropped(&self) -> u64 {
+        self.usage
+            .lock()
+            .unwrap()
+            .values()
+            .map(|usage| usage.dropped)
+            .sum()
+    }
+
+    pub(crate) fn dropped(&self, tenant: &str) -> u64 {
+        self.usage
+            .lock()
+            .unwrap()
+            .get(tenant)
+            .map_or(0, |usage| usage.dropped)
+    }
+}
+
+/// Tracer view scoped to one tenant
+///
+/// Root spans started here are stamped with the tenant, sampled at its rate,
+/// and counted against its quota; `get_completed_spans` only returns the
+/// tenant's spans. Baggage is shared with the underlying tracer.
+#[derive(Clone, Copy)]
+pub struct TenantTracer<'a> {
+    tracer: &'a SimpleTracer,
+    tenant_id: &'a str,
+}
+
+impl<'a> TenantTracer<'a> {
+    pub(crate) fn new(tracer: &'a SimpleTracer, tenant_id: &'a str) -> Self {
+        Self { tracer, tenant_id }
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        self.tenant_id
+    }
+
+    /// Spans dropped because the tenant exceeded its quota
+    pub fn dropped_spans(&self) -> u64 {
+        self.tracer.tenant_dropped_spans(self.tenant_id)
+    }
+}
+
+impl TracingManager for TenantTracer<'_> {
+    fn start_span(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+    ) -> TracingResult<String> {
+        self.start_span_with_kind(operation_name, parent_span_id, SpanKind::Internal)
+    }
+
+    fn start_span_with_kind(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        self.tracer
+            .start_tenant_span(operation_name, parent_span_id, kind, Some(self.tenant_id))
+    }
+
+    fn end_span(&self, span_id: String) -> TracingResult<()> {
+        self.tracer.end_span(span_id)
+    }
+
+    fn add_span_attribute(
+        &self,
+        span_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> TracingResult<()> {
+        self.tracer.add_span_attribute(span_id, key, value)
+    }
+
+    fn get_completed_spans(&self) -> Vec<Span> {
+        self.tracer.completed_spans_for_tenant(self.tenant_id)
+    }
+
+    fn set_baggage(&self, key: &str, value: &str) {
+        self.tracer.set_baggage(key, value)
+    }
+
+    fn get_baggage(&self, key: &str) -> Option<String> {
+        self.tracer.get_baggage(key)
+    }
+}
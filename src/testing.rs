@@ -0,0 +1,1092 @@
+//! Testing module
+//!
+//! Contains OtlpStub, a minimal OTLP/HTTP collector for integration tests.
+//! It binds a local port, accepts OTLP/JSON posts (gzip or zstd encoded
+//! when those features are on), answers with a chosen
+//! status, and keeps every request and span it received for assertions, so
+//! an exporter configuration can be tested end to end without docker.
+//!
+//! Also contains `snapshot`, a deterministic text rendering of traces for
+//! insta or golden-file comparisons of instrumentation output, and
+//! TraceShape, the span hierarchy `assert_trace!` checks captured spans
+//! against. TestTracer records every span on a ManualClock, so latency
+//! budgets can be asserted with `assert_span_duration!`; both need the
+//! `testing` feature, which keeps the clock override out of production builds.
+//!
+//! ```no_run
+//! use tyl_tracing::testing::OtlpStub;
+//!
+//! let stub = OtlpStub::start().unwrap();
+//! let exporter = stub.exporter_config(); // point the tracer here
+//! // ... run the code under test, flush the tracer ...
+//! let spans = stub.wait_for_spans(1, std::time::Duration::from_secs(5));
+//! assert_eq!(spans[0].service_name, "checkout");
+//! ```
+
+use crate::compression::Compression;
+use crate::config::ExporterConfig;
+#[cfg(any(test, feature = "testing"))]
+use crate::config::TraceConfig;
+use crate::ids::{SpanId, TraceId};
+#[cfg(any(test, feature = "testing"))]
+use crate::span::{current_timestamp, set_manual_time};
+use crate::span::{Span, SpanKind, SpanStatus};
+#[cfg(any(test, feature = "testing"))]
+use crate::tracer::SimpleTracer;
+use crate::tree::TraceTree;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(any(test, feature = "testing"))]
+use std::marker::PhantomData;
+use std::net::{TcpListener, TcpStream};
+#[cfg(any(test, feature = "testing"))]
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Largest request body the stub reads
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// One HTTP request the stub received
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedRequest {
+    pub path: String,
+    /// Header names lowercased
+    pub headers: BTreeMap<String, String>,
+    /// Parsed JSON body, decoded per `Content-Encoding` (`Null` if it was
+    /// not JSON)
+    pub body: Value,
+}
+
+/// One span decoded from a received OTLP payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedSpan {
+    /// `service.name` of the span's resource
+    pub service_name: String,
+    /// Resource attributes, `service.name` included
+    pub resource: HashMap<String, Value>,
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    /// OTLP kind number (1 internal, 2 server, 3 client, 4 producer, 5 consumer)
+    pub kind: i64,
+    /// Attributes with OTLP `AnyValue`s turned back into JSON values
+    pub attributes: HashMap<String, Value>,
+    /// The span object as received
+    pub raw: Value,
+}
+
+impl ReceivedSpan {
+    pub fn attribute(&self, key: &str) -> Option<&Value> {
+        self.attributes.get(key)
+    }
+}
+
+#[derive(Default)]
+struct Received {
+    requests: Vec<ReceivedRequest>,
+    spans: Vec<ReceivedSpan>,
+}
+
+/// Local OTLP/HTTP collector; stops when dropped
+pub struct OtlpStub {
+    endpoint: String,
+    received: Arc<Mutex<Received>>,
+    status: Arc<AtomicU16>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl OtlpStub {
+    /// Listen on a free port of 127.0.0.1, answering `200 OK`
+    pub fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        let received = Arc::new(Mutex::new(Received::default()));
+        let status = Arc::new(AtomicU16::new(200));
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let (received, status, stop) = (received.clone(), status.clone(), stop.clone());
+            std::thread::Builder::new()
+                .name("tyl-tracing-otlp-stub".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if stop.load(Ordering::Acquire) {
+                            break;
+                        }
+                        if let Ok(stream) = stream {
+                            serve(stream, &received, status.load(Ordering::Relaxed));
+                        }
+                    }
+                })?
+        };
+        Ok(Self {
+            endpoint,
+            received,
+            status,
+            stop,
+            worker: Some(worker),
+        })
+    }
+
+    /// Base URL, e.g. `http://127.0.0.1:41234`
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// OTLP exporter settings pointing at this stub
+    pub fn exporter_config(&self) -> ExporterConfig {
+        ExporterConfig::new(self.endpoint.clone())
+    }
+
+    /// Answer later requests with `status`, e.g. 503 to exercise retries
+    ///
+    /// Requests are recorded whatever the status; spans only on 2xx.
+    pub fn respond_with(&self, status: u16) {
+        self.status.store(status, Ordering::Relaxed);
+    }
+
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.received.lock().unwrap().requests.clone()
+    }
+
+    /// Spans accepted so far, in arrival order
+    pub fn spans(&self) -> Vec<ReceivedSpan> {
+        self.received.lock().unwrap().spans.clone()
+    }
+
+    /// Accepted spans with the given name
+    pub fn spans_named(&self, name: &str) -> Vec<ReceivedSpan> {
+        self.spans()
+            .into_iter()
+            .filter(|span| span.name == name)
+            .collect()
+    }
+
+    /// Wait until at least `count` spans arrived or `timeout` passed, then
+    /// return what arrived
+    pub fn wait_for_spans(&self, count: usize, timeout: Duration) -> Vec<ReceivedSpan> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let spans = self.spans();
+            if spans.len() >= count || Instant::now() >= deadline {
+                return spans;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Forget everything received so far
+    pub fn clear(&self) {
+        *self.received.lock().unwrap() = Received::default();
+    }
+}
+
+impl Drop for OtlpStub {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wake the blocked accept so the worker sees the flag
+        let _ = TcpStream::connect(self.endpoint.trim_start_matches("http://"));
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, received: &Mutex<Received>, status: u16) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let Some(request) = read_request(&stream) else {
+        return;
+    };
+    let accepted = (200..300).contains(&status);
+    {
+        let mut received = received.lock().unwrap();
+        if accepted {
+            received.spans.extend(decode_spans(&request.body));
+        }
+        received.requests.push(request);
+    }
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+        status,
+        if accepted { "OK" } else { "Error" }
+    );
+    let _ = (&stream).write_all(response.as_bytes());
+}
+
+fn read_request(stream: &TcpStream) -> Option<ReceivedRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let path = request_line.split_whitespace().nth(1)?.to_string();
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return None;
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    let encoding = match headers.get("content-encoding").map(String::as_str) {
+        Some("gzip") => Compression::gzip(0),
+        Some("zstd") => Compression::zstd(1),
+        _ => Compression::None,
+    };
+    let body = encoding.decompress(&body).unwrap_or_default();
+    Some(ReceivedRequest {
+        path,
+        headers,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    })
+}
+
+fn decode_spans(payload: &Value) -> Vec<ReceivedSpan> {
+    let mut spans = Vec::new();
+    for resource_spans in array(&payload["resourceSpans"]) {
+        let resource = attributes(&resource_spans["resource"]["attributes"]);
+        let service_name = resource
+            .get("service.name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        for scope_spans in array(&resource_spans["scopeSpans"]) {
+            for span in array(&scope_spans["spans"]) {
+                let text = |key: &str| span[key].as_str().unwrap_or_default().to_string();
+                spans.push(ReceivedSpan {
+                    service_name: service_name.clone(),
+                    resource: resource.clone(),
+                    trace_id: text("traceId"),
+                    span_id: text("spanId"),
+                    parent_span_id: span["parentSpanId"]
+                        .as_str()
+                        .filter(|id| !id.is_empty())
+                        .map(str::to_string),
+                    name: text("name"),
+                    kind: span["kind"].as_i64().unwrap_or(0),
+                    attributes: attributes(&span["attributes"]),
+                    raw: span.clone(),
+                });
+            }
+        }
+    }
+    spans
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// OTLP `KeyValue` list as a map
+fn attributes(key_values: &Value) -> HashMap<String, Value> {
+    array(key_values)
+        .iter()
+        .filter_map(|key_value| {
+            let key = key_value["key"].as_str()?;
+            Some((key.to_string(), from_any_value(&key_value["value"])))
+        })
+        .collect()
+}
+
+/// JSON value of an OTLP `AnyValue`; integers arrive as strings
+fn from_any_value(value: &Value) -> Value {
+    if let Some(string) = value.get("stringValue") {
+        return string.clone();
+    }
+    if let Some(int) = value.get("intValue") {
+        return match int {
+            Value::String(int) => int.parse::<i64>().map_or(Value::Null, Value::from),
+            int => int.clone(),
+        };
+    }
+    if let Some(double) = value.get("doubleValue") {
+        return double.clone();
+    }
+    if let Some(boolean) = value.get("boolValue") {
+        return boolean.clone();
+    }
+    if let Some(values) = value.get("arrayValue") {
+        return array(&values["values"])
+            .iter()
+            .map(from_any_value)
+            .collect();
+    }
+    if let Some(values) = value.get("kvlistValue") {
+        return attributes(&values["values"]).into_iter().collect();
+    }
+    Value::Null
+}
+
+/// What `snapshot_with` renders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotOptions {
+    timings: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self { timings: true }
+    }
+}
+
+impl SnapshotOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out start offsets, durations and event times, for code whose
+    /// timing is not under the test's control
+    pub fn without_timings(mut self) -> Self {
+        self.timings = false;
+        self
+    }
+}
+
+/// Deterministic text rendering of `spans` for golden-file comparisons
+///
+/// Traces and spans get stable labels (`trace#1`, `span#1`, in depth-first
+/// order) in place of their random IDs, also where an ID appears in an
+/// attribute or link; times are milliseconds from the start of the trace;
+/// siblings are ordered by start time, then name, and attributes by key.
+///
+/// ```
+/// use tyl_tracing::testing::snapshot;
+/// use tyl_tracing::{SimpleTracer, TraceConfig, TracingManager};
+///
+/// let tracer = SimpleTracer::new(TraceConfig::new("checkout").with_sampling_rate(1.0));
+/// let root = tracer.start_span("http_request", None).unwrap();
+/// let child = tracer.start_span("db_query", Some(root.clone())).unwrap();
+/// tracer.end_span(child).unwrap();
+/// tracer.end_span(root).unwrap();
+///
+/// let text = snapshot(&tracer.get_completed_spans());
+/// assert!(text.starts_with("trace#1\n- http_request [span#1]"));
+/// assert!(text.contains("\n  - db_query [span#2]"));
+/// ```
+pub fn snapshot(spans: &[Span]) -> String {
+    snapshot_with(spans, SnapshotOptions::default())
+}
+
+/// `snapshot` with options
+pub fn snapshot_with(spans: &[Span], options: SnapshotOptions) -> String {
+    let tree = TraceTree::new(spans);
+    let mut roots = tree.roots().to_vec();
+    sort_siblings(&mut roots);
+
+    // Traces in order of their first root; labels follow traversal order
+    let mut labels = Labels::default();
+    let mut traces: Vec<(TraceId, Vec<&Span>)> = Vec::new();
+    for root in roots {
+        match traces
+            .iter_mut()
+            .find(|(trace_id, _)| *trace_id == root.trace_id)
+        {
+            Some((_, trace_roots)) => trace_roots.push(root),
+            None => traces.push((root.trace_id, vec![root])),
+        }
+    }
+    for (trace_id, trace_roots) in &traces {
+        labels.trace(*trace_id);
+        for root in trace_roots {
+            visit(&tree, root, &mut |span, _| {
+                labels.span(span.span_id);
+            });
+        }
+    }
+
+    let mut output = String::new();
+    for (trace_id, trace_roots) in &traces {
+        let epoch = spans
+            .iter()
+            .filter(|span| span.trace_id == *trace_id)
+            .map(|span| span.start_time)
+            .min()
+            .unwrap_or_default();
+        output.push_str(&labels.trace(*trace_id));
+        output.push('\n');
+        for root in trace_roots {
+            visit(&tree, root, &mut |span, depth| {
+                write_snapshot_span(&mut output, span, depth, epoch, options, &mut labels);
+            });
+        }
+    }
+    output
+}
+
+fn visit<'a>(tree: &TraceTree<'a>, span: &'a Span, f: &mut dyn FnMut(&'a Span, usize)) {
+    fn walk<'a>(
+        tree: &TraceTree<'a>,
+        span: &'a Span,
+        depth: usize,
+        f: &mut dyn FnMut(&'a Span, usize),
+    ) {
+        f(span, depth);
+        let mut children = tree.children(span.span_id).to_vec();
+        sort_siblings(&mut children);
+        for child in children {
+            walk(tree, child, depth + 1, f);
+        }
+    }
+    walk(tree, span, 0, f)
+}
+
+fn sort_siblings(spans: &mut [&Span]) {
+    spans.sort_by(|a, b| (a.start_time, &a.operation_name).cmp(&(b.start_time, &b.operation_name)));
+}
+
+fn write_snapshot_span(
+    output: &mut String,
+    span: &Span,
+    depth: usize,
+    epoch: u64,
+    options: SnapshotOptions,
+    labels: &mut Labels,
+) {
+    let indent = "  ".repeat(depth);
+    let mut line = format!(
+        "{}- {} [{}]",
+        indent,
+        span.operation_name,
+        labels.span(span.span_id)
+    );
+    if span.kind != SpanKind::Internal {
+        line.push_str(&format!(" kind={:?}", span.kind).to_lowercase());
+    }
+    if depth == 0 {
+        if let Some(parent) = span.parent_span_id {
+            line.push_str(&format!(" parent={}", labels.span(parent)));
+        }
+    }
+    if options.timings {
+        line.push_str(&format!(
+            " start=+{}ms",
+            span.start_time.saturating_sub(epoch)
+        ));
+        if let Some(duration) = span.duration_ms() {
+            line.push_str(&format!(" duration={}ms", duration));
+        }
+    }
+    line.push_str(&match &span.status {
+        SpanStatus::Active => " status=active".to_string(),
+        SpanStatus::Completed => " status=ok".to_string(),
+        SpanStatus::Error { message } => format!(" status=error({:?})", message),
+    });
+    output.push_str(&line);
+    output.push('\n');
+
+    let mut attributes: Vec<_> = span.attributes.iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in attributes {
+        output.push_str(&format!(
+            "{}    {} = {}\n",
+            indent,
+            key,
+            labels.relabel(value)
+        ));
+    }
+    for event in &span.events {
+        let mut line = format!("{}    event {:?}", indent, event.name);
+        if options.timings {
+            line.push_str(&format!(" at +{}ms", event.timestamp.saturating_sub(epoch)));
+        }
+        if !event.attributes.is_empty() {
+            line.push_str(&format!(" {}", labels.relabel_map(&event.attributes)));
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    for link in &span.links {
+        let mut line = format!(
+            "{}    link -> {}/{}",
+            indent,
+            labels.trace(link.trace_id),
+            labels.span(link.span_id)
+        );
+        if !link.attributes.is_empty() {
+            line.push_str(&format!(" {}", labels.relabel_map(&link.attributes)));
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+}
+
+/// Stable labels for IDs, numbered in order of first use
+#[derive(Default)]
+struct Labels {
+    traces: HashMap<TraceId, String>,
+    spans: HashMap<SpanId, String>,
+    /// Rendered ID to label, for IDs quoted in attribute values
+    by_text: HashMap<String, String>,
+}
+
+impl Labels {
+    fn trace(&mut self, trace_id: TraceId) -> String {
+        if let Some(label) = self.traces.get(&trace_id) {
+            return label.clone();
+        }
+        let label = format!("trace#{}", self.traces.len() + 1);
+        self.traces.insert(trace_id, label.clone());
+        self.by_text.insert(trace_id.to_string(), label.clone());
+        label
+    }
+
+    fn span(&mut self, span_id: SpanId) -> String {
+        if let Some(label) = self.spans.get(&span_id) {
+            return label.clone();
+        }
+        let label = format!("span#{}", self.spans.len() + 1);
+        self.spans.insert(span_id, label.clone());
+        self.by_text.insert(span_id.to_string(), label.clone());
+        label
+    }
+
+    /// `value` as JSON with known IDs replaced by their labels
+    fn relabel(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => match self.by_text.get(text) {
+                Some(label) => Value::String(label.clone()),
+                None => value.clone(),
+            },
+            Value::Array(values) => values.iter().map(|value| self.relabel(value)).collect(),
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(key, value)| (key.clone(), self.relabel(value)))
+                .collect(),
+            _ => value.clone(),
+        }
+    }
+
+    /// Attribute map as JSON with sorted keys and known IDs relabeled
+    fn relabel_map(&self, attributes: &HashMap<String, Value>) -> Value {
+        attributes
+            .iter()
+            .map(|(key, value)| (key.clone(), self.relabel(value)))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+/// Expected span hierarchy: an operation name and the shapes of its
+/// children, usually built with `trace_shape!` or `assert_trace!`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceShape {
+    pub name: String,
+    pub children: Vec<TraceShape>,
+}
+
+impl TraceShape {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_children(mut self, children: Vec<TraceShape>) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Shape of the spans under `span`
+    fn of(tree: &TraceTree<'_>, span: &Span) -> Self {
+        Self::new(span.operation_name.clone()).with_children(
+            tree.children(span.span_id)
+                .iter()
+                .map(|child| Self::of(tree, child))
+                .collect(),
+        )
+    }
+
+    /// One line per span, indented by depth, siblings sorted so that
+    /// concurrent children started in any order compare equal
+    fn canonical_lines(&self) -> Vec<String> {
+        fn subtree(shape: &TraceShape, depth: usize) -> Vec<String> {
+            let mut children: Vec<Vec<String>> = shape
+                .children
+                .iter()
+                .map(|child| subtree(child, depth + 1))
+                .collect();
+            children.sort();
+            let mut lines = vec![format!("{}{}", "  ".repeat(depth), shape.name)];
+            lines.extend(children.into_iter().flatten());
+            lines
+        }
+        subtree(self, 0)
+    }
+}
+
+/// Why captured spans did not match a TraceShape; displays as a line diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceMismatch {
+    /// Expected tree, one span per line
+    pub expected: Vec<String>,
+    /// Closest captured tree, or the captured root names if no root had the
+    /// expected name
+    pub actual: Vec<String>,
+    /// Whether a root with the expected name was captured at all
+    pub root_found: bool,
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.root_found {
+            writeln!(
+                f,
+                "no completed root span named {:?}; captured roots:",
+                self.expected
+                    .first()
+                    .map(String::as_str)
+                    .unwrap_or_default()
+            )?;
+            for root in &self.actual {
+                writeln!(f, "  {}", root)?;
+            }
+            return Ok(());
+        }
+        writeln!(
+            f,
+            "trace does not match the expected shape (- expected, + actual):"
+        )?;
+        for (marker, line) in diff_lines(&self.expected, &self.actual) {
+            writeln!(f, "{} {}", marker, line)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TraceMismatch {}
+
+/// Check that a root span of `spans` has exactly the hierarchy of `expected`
+///
+/// Only completed spans are matched; children match in any order. When
+/// several roots share the expected name, any one matching is enough and a
+/// failure reports the closest.
+pub fn match_trace(spans: &[Span], expected: &TraceShape) -> Result<(), TraceMismatch> {
+    let tree = TraceTree::new(spans);
+    let expected_lines = expected.canonical_lines();
+    let candidates: Vec<Vec<String>> = tree
+        .roots()
+        .iter()
+        .filter(|root| root.operation_name == expected.name)
+        .map(|root| TraceShape::of(&tree, root).canonical_lines())
+        .collect();
+    if candidates.iter().any(|lines| *lines == expected_lines) {
+        return Ok(());
+    }
+    let closest = candidates.into_iter().min_by_key(|lines| {
+        diff_lines(&expected_lines, lines)
+            .iter()
+            .filter(|(marker, _)| *marker != ' ')
+            .count()
+    });
+    Err(match closest {
+        Some(actual) => TraceMismatch {
+            expected: expected_lines,
+            actual,
+            root_found: true,
+        },
+        None => {
+            let mut roots: Vec<String> = tree
+                .roots()
+                .iter()
+                .map(|root| root.operation_name.clone())
+                .collect();
+            roots.sort();
+            roots.dedup();
+            TraceMismatch {
+                expected: expected_lines,
+                actual: roots,
+                root_found: false,
+            }
+        }
+    })
+}
+
+/// Longest-common-subsequence line diff: `' '` kept, `'-'` only in
+/// `expected`, `'+'` only in `actual`
+fn diff_lines<'a>(expected: &'a [String], actual: &'a [String]) -> Vec<(char, &'a str)> {
+    let (n, m) = (expected.len(), actual.len());
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            lines.push((' ', expected[i].as_str()));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', expected[i].as_str()));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j].as_str()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Build a TraceShape from `"name" => [children...]` notation
+///
+/// ```
+/// use tyl_tracing::trace_shape;
+///
+/// let shape = trace_shape!("http_request" => ["validate", "db_query" => ["acquire_conn"]]);
+/// assert_eq!(shape.children[1].children[0].name, "acquire_conn");
+/// ```
+#[macro_export]
+macro_rules! trace_shape {
+    ($name:literal => [$($children:tt)*]) => {
+        $crate::testing::TraceShape::new($name)
+            .with_children($crate::__trace_shapes!(@shapes [] $($children)*))
+    };
+    ($name:literal) => {
+        $crate::testing::TraceShape::new($name)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_shapes {
+    (@shapes [$($shapes:expr),*] $(,)?) => {
+        vec![$($shapes),*]
+    };
+    (@shapes [$($shapes:expr),*] $name:literal => [$($children:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::__trace_shapes!(
+            @shapes [$($shapes,)* $crate::trace_shape!($name => [$($children)*])] $($($rest)*)?
+        )
+    };
+    (@shapes [$($shapes:expr),*] $name:literal $(, $($rest:tt)*)?) => {
+        $crate::__trace_shapes!(@shapes [$($shapes,)* $crate::trace_shape!($name)] $($($rest)*)?)
+    };
+}
+
+/// Assert that a tracer's completed spans contain a trace of the given shape
+///
+/// Panics with a line diff between the expected tree and the closest
+/// captured one. Children match in any order; see `testing::match_trace`.
+///
+/// ```
+/// use tyl_tracing::{assert_trace, SimpleTracer, TraceConfig, TracingManager};
+///
+/// let tracer = SimpleTracer::new(TraceConfig::new("checkout").with_sampling_rate(1.0));
+/// let request = tracer.start_span("http_request", None).unwrap();
+/// let query = tracer.start_span("db_query", Some(request.clone())).unwrap();
+/// let conn = tracer.start_span("acquire_conn", Some(query.clone())).unwrap();
+/// tracer.end_span(conn).unwrap();
+/// tracer.end_span(query).unwrap();
+/// let validate = tracer.start_span("validate", Some(request.clone())).unwrap();
+/// tracer.end_span(validate).unwrap();
+/// tracer.end_span(request).unwrap();
+///
+/// assert_trace!(tracer, "http_request" => ["validate", "db_query" => ["acquire_conn"]]);
+/// ```
+#[macro_export]
+macro_rules! assert_trace {
+    ($tracer:expr, $($shape:tt)+) => {{
+        use $crate::TracingManager as _;
+        if let Err(mismatch) = $crate::testing::match_trace(
+            &($tracer).get_completed_spans(),
+            &$crate::trace_shape!($($shape)+),
+        ) {
+            panic!("{}", mismatch);
+        }
+    }};
+}
+
+#[cfg(any(test, feature = "testing"))]
+/// Manual time for span timestamps taken on the installing thread
+///
+/// While the clock is alive, spans started, ended or given events on this
+/// thread read it instead of the system time; dropping it restores the
+/// previous time source. Other threads keep the system time, so the clock is
+/// neither `Send` nor `Sync`. Requires the `testing` feature.
+pub struct ManualClock {
+    previous: Option<u64>,
+    _thread: PhantomData<*const ()>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl ManualClock {
+    /// Install a clock reading the current system time until advanced
+    pub fn start() -> Self {
+        Self::start_at(current_timestamp())
+    }
+
+    /// Install a clock reading `now_ms` (milliseconds since the epoch)
+    pub fn start_at(now_ms: u64) -> Self {
+        Self {
+            previous: set_manual_time(Some(now_ms)),
+            _thread: PhantomData,
+        }
+    }
+
+    /// Milliseconds since the epoch
+    pub fn now(&self) -> u64 {
+        current_timestamp()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.set(self.now() + by.as_millis() as u64);
+    }
+
+    pub fn set(&self, now_ms: u64) {
+        set_manual_time(Some(now_ms));
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Drop for ManualClock {
+    fn drop(&mut self) {
+        set_manual_time(self.previous);
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+/// SimpleTracer that samples every span and times them on a ManualClock
+///
+/// Dereferences to the SimpleTracer. Span times only move with `advance`,
+/// so durations are exact on the creating thread. Requires the `testing`
+/// feature.
+///
+/// ```
+/// use std::time::Duration;
+/// use tyl_tracing::testing::TestTracer;
+/// use tyl_tracing::{assert_span_duration, TracingManager};
+///
+/// let tracer = TestTracer::new();
+/// let query = tracer.start_span("db_query", None).unwrap();
+/// tracer.advance(Duration::from_millis(20));
+/// tracer.end_span(query).unwrap();
+///
+/// assert_span_duration!(tracer, "db_query", < 50ms);
+/// assert_span_duration!(tracer, "db_query", == 25ms +- 5ms);
+/// ```
+pub struct TestTracer {
+    tracer: SimpleTracer,
+    clock: ManualClock,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl TestTracer {
+    pub fn new() -> Self {
+        Self::with_config(TraceConfig::new("test").with_sampling_rate(1.0))
+    }
+
+    pub fn with_config(config: TraceConfig) -> Self {
+        Self {
+            clock: ManualClock::start(),
+            tracer: SimpleTracer::new(config),
+        }
+    }
+
+    pub fn tracer(&self) -> &SimpleTracer {
+        &self.tracer
+    }
+
+    pub fn clock(&self) -> &ManualClock {
+        &self.clock
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.clock.advance(by);
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Default for TestTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Deref for TestTracer {
+    type Target = SimpleTracer;
+
+    fn deref(&self) -> &SimpleTracer {
+        &self.tracer
+    }
+}
+
+/// Allowed span duration, as written in `assert_span_duration!`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationBound {
+    /// `< limit`
+    Below(Duration),
+    /// `<= limit`
+    AtMost(Duration),
+    /// `> limit`
+    Above(Duration),
+    /// `>= limit`
+    AtLeast(Duration),
+    /// `== expected +- tolerance`
+    Within {
+        expected: Duration,
+        tolerance: Duration,
+    },
+}
+
+impl DurationBound {
+    pub fn contains(&self, duration: Duration) -> bool {
+        match *self {
+            Self::Below(limit) => duration < limit,
+            Self::AtMost(limit) => duration <= limit,
+            Self::Above(limit) => duration > limit,
+            Self::AtLeast(limit) => duration >= limit,
+            Self::Within {
+                expected,
+                tolerance,
+            } => {
+                let difference = if duration > expected {
+                    duration - expected
+                } else {
+                    expected - duration
+                };
+                difference <= tolerance
+            }
+        }
+    }
+
+    /// Bound from the macro's tokens, e.g. `("<", "50ms", None)`
+    ///
+    /// # Panics
+    ///
+    /// On an unknown operator, a tolerance without `==`, or a duration that
+    /// is not a number followed by `ns`, `us`, `ms` or `s`.
+    #[doc(hidden)]
+    pub fn __parse(op: &str, value: &str, tolerance: Option<&str>) -> Self {
+        let value = parse_duration(value);
+        match (op, tolerance) {
+            ("<", None) => Self::Below(value),
+            ("<=", None) => Self::AtMost(value),
+            (">", None) => Self::Above(value),
+            (">=", None) => Self::AtLeast(value),
+            ("==", tolerance) => Self::Within {
+                expected: value,
+                tolerance: tolerance.map_or(Duration::ZERO, parse_duration),
+            },
+            _ => panic!(
+                "unsupported duration bound {:?}; use <, <=, >, >= or == [+- tolerance]",
+                op
+            ),
+        }
+    }
+}
+
+impl fmt::Display for DurationBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Below(limit) => write!(f, "< {:?}", limit),
+            Self::AtMost(limit) => write!(f, "<= {:?}", limit),
+            Self::Above(limit) => write!(f, "> {:?}", limit),
+            Self::AtLeast(limit) => write!(f, ">= {:?}", limit),
+            Self::Within {
+                expected,
+                tolerance,
+            } if tolerance.is_zero() => write!(f, "== {:?}", expected),
+            Self::Within {
+                expected,
+                tolerance,
+            } => write!(f, "== {:?} +- {:?}", expected, tolerance),
+        }
+    }
+}
+
+fn parse_duration(text: &str) -> Duration {
+    let split = text
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let seconds_per_unit = match unit {
+        "ns" => 1e-9,
+        "us" => 1e-6,
+        "ms" => 1e-3,
+        "s" => 1.0,
+        _ => panic!("duration {:?} needs a unit: ns, us, ms or s", text),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number >= 0.0 => Duration::from_secs_f64(number * seconds_per_unit),
+        _ => panic!("invalid duration {:?}", text),
+    }
+}
+
+/// Check that every completed span named `name` took a duration within
+/// `bound`
+///
+/// Span times have millisecond resolution. Fails if no such span completed,
+/// listing the offending durations otherwise.
+pub fn check_span_duration(spans: &[Span], name: &str, bound: DurationBound) -> Result<(), String> {
+    let durations: Vec<Duration> = spans
+        .iter()
+        .filter(|span| span.operation_name == name)
+        .filter_map(Span::duration_ms)
+        .map(Duration::from_millis)
+        .collect();
+    if durations.is_empty() {
+        return Err(format!("no completed span named {:?}", name));
+    }
+    let outside: Vec<String> = durations
+        .iter()
+        .filter(|duration| !bound.contains(**duration))
+        .map(|duration| format!("{:?}", duration))
+        .collect();
+    if outside.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} of {} {:?} spans took a duration not {}: {}",
+        outside.len(),
+        durations.len(),
+        name,
+        bound,
+        outside.join(", ")
+    ))
+}
+
+/// Assert that every completed span of an operation took a duration within
+/// a bound: `< 50ms`, `<= 1s`, `> 10ms`, `>= 2ms` or `== 100ms +- 5ms`
+///
+/// Works on any tracer; with a `TestTracer` durations follow its manual
+/// clock. See `testing::check_span_duration`.
+#[macro_export]
+macro_rules! assert_span_duration {
+    ($tracer:expr, $name:expr, $op:tt $value:tt $(+- $tolerance:tt)?) => {{
+        use $crate::TracingManager as _;
+        let bound = $crate::testing::DurationBound::__parse(
+            stringify!($op),
+            stringify!($value),
+            None $(.or(Some(stringify!($tolerance))))?,
+        );
+        if let Err(message) =
+            $crate::testing::check_span_duration(&($tracer).get_completed_spans(), $name, bound)
+        {
+            panic!("{}", message);
+        }
+    }};
+}
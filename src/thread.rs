@@ -0,0 +1,36 @@
+//! Thread helpers module
+//!
+//! Contains `std::thread` spawn wrappers that carry the caller's [`Context`]
+//! into the new thread, so spans started there parent correctly.
+
+use crate::context::Context;
+use std::thread::{JoinHandle, Scope, ScopedJoinHandle};
+
+/// [`std::thread::spawn`] running `f` under the caller's context
+pub fn spawn_traced<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let context = Context::current();
+    std::thread::spawn(move || {
+        let _guard = context.attach();
+        f()
+    })
+}
+
+/// [`Scope::spawn`] running `f` under the caller's context
+pub fn spawn_scoped_traced<'scope, 'env, F, T>(
+    scope: &'scope Scope<'scope, 'env>,
+    f: F,
+) -> ScopedJoinHandle<'scope, T>
+where
+    F: FnOnce() -> T + Send + 'scope,
+    T: Send + 'scope,
+{
+    let context = Context::current();
+    scope.spawn(move || {
+        let _guard = context.attach();
+        f()
+    })
+}
@@ -0,0 +1,87 @@
+//! TLS module
+//!
+//! Contains the rustls client setup behind `https://` exporter endpoints
+//! (`tls` feature). The trust store is the PEM `ca_bundle` when one is
+//! configured and the Mozilla roots otherwise; a client certificate and key
+//! enable mutual TLS.
+
+use crate::config::TlsConfig;
+use crate::tracer::TracingResult;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+use tyl_errors::TylError;
+
+/// Client settings for an https endpoint, loading every configured file
+pub(crate) fn client_config(tls: Option<&TlsConfig>) -> TracingResult<Arc<ClientConfig>> {
+    let mut roots = RootCertStore::empty();
+    match tls.and_then(|tls| tls.ca_bundle.as_deref()) {
+        Some(path) => {
+            for cert in read_certs(path)? {
+                roots.add(cert).map_err(|e| invalid(path, e))?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    let builder =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| TylError::configuration(format!("TLS setup failed: {}", e)))?
+            .with_root_certificates(roots);
+    let identity = tls.and_then(|tls| tls.client_cert.as_deref().zip(tls.client_key.as_deref()));
+    let config = match identity {
+        Some((cert, key)) => builder
+            .with_client_auth_cert(read_certs(cert)?, read_key(key)?)
+            .map_err(|e| invalid(cert, e))?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(config))
+}
+
+/// Name checked against the collector certificate: the configured override,
+/// else the endpoint host
+pub(crate) fn server_name(
+    tls: Option<&TlsConfig>,
+    host: &str,
+) -> TracingResult<ServerName<'static>> {
+    let name = tls
+        .and_then(|tls| tls.server_name.as_deref())
+        .unwrap_or(host);
+    ServerName::try_from(name.to_string())
+        .map_err(|_| TylError::configuration(format!("invalid TLS server name '{}'", name)))
+}
+
+/// Start a TLS session over a connected socket; the handshake runs on first use
+pub(crate) fn connect(
+    config: &Arc<ClientConfig>,
+    server_name: &ServerName<'static>,
+    stream: TcpStream,
+) -> std::io::Result<StreamOwned<ClientConnection, TcpStream>> {
+    let connection = ClientConnection::new(config.clone(), server_name.clone())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+fn read_certs(path: &Path) -> TracingResult<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path).map_err(|e| invalid(path, e))?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(path, e))?;
+    if certs.is_empty() {
+        return Err(invalid(path, "no PEM certificates found"));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> TracingResult<PrivateKeyDer<'static>> {
+    let pem = std::fs::read(path).map_err(|e| invalid(path, e))?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|e| invalid(path, e))?
+        .ok_or_else(|| invalid(path, "no PEM private key found"))
+}
+
+fn invalid(path: &Path, error: impl std::fmt::Display) -> TylError {
+    TylError::configuration(format!("TLS file {}: {}", path.display(), error))
+}
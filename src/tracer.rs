@@ -3,16 +3,137 @@
 //! Contains the TracingManager trait (port) and SimpleTracer implementation (adapter)
 //! following hexagonal architecture principles.
 
-use crate::config::TraceConfig;
-use crate::span::Span;
+use crate::alloc::{AllocationTracker, ALLOC_BYTES_ATTRIBUTE, ALLOC_COUNT_ATTRIBUTE};
+use crate::anomaly::{AnomalyDetector, ANOMALY_EXPECTED_MS_ATTRIBUTE, ANOMALY_Z_SCORE_ATTRIBUTE};
+use crate::backpressure::{
+    export_level, BackpressureMonitor, BackpressureSignal, BackpressureSource, DEFAULT_HIGH_WATER,
+};
+use crate::baggage::validate_baggage_insert;
+use crate::config::{Environment, ExporterConfig, ExporterKind, TraceConfig};
+use crate::context::{current_span_id, Context};
+#[cfg(feature = "cpu-time")]
+use crate::cpu::{CpuClock, CPU_TIME_ATTRIBUTE, CPU_UTILIZATION_ATTRIBUTE};
+use crate::crash::try_lock_for_crash;
+use crate::drops::{DropCounters, DropSummary};
+use crate::export::{BatchSpanProcessor, ConsoleExporter, SpanExporter};
+use crate::filter::{FilterAction, SpanFilter, TRACE_FILTER_ENV};
+use crate::health::{ExporterHealth, TracerHealth};
+use crate::ids::{SpanId, TraceId};
+use crate::lint::KeyLinter;
+use crate::observer::SpanObservers;
+use crate::otlp::{OtlpHttpExporter, TempoExporter};
+use crate::propagation::TraceContext;
+use crate::retention::RetentionPruner;
+#[cfg(feature = "tokio-metrics")]
+use crate::runtime_metrics::RuntimeMetricsTracker;
+use crate::sampling::{parse_sampling_priority, should_sample_trace, SAMPLING_PRIORITY_KEY};
+use crate::sampling_audit::{MatchedRule, SamplingAudit};
+use crate::section::SectionTimer;
+use crate::span::{current_timestamp, Span, SpanEvent, SpanKind, HEARTBEAT_EVENT};
+use crate::store::SpanStore;
+use crate::tenant::{span_tenant, TenantQuotas, TenantTracer, TENANT_ID_ATTRIBUTE};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tyl_config::ConfigPlugin;
 use tyl_errors::{TylError, TylResult};
 
 /// Result type for tracing operations using unified TYL error handling
 pub type TracingResult<T> = TylResult<T>;
 
+/// Span ID handed out while tracing is disabled; every operation on it is a no-op
+#[cfg(not(feature = "disabled"))]
+pub const NOOP_SPAN_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// Span ID handed out while tracing is disabled; empty with the `disabled`
+/// feature, so returning it as a `String` never allocates
+#[cfg(feature = "disabled")]
+pub const NOOP_SPAN_ID: &str = "";
+
+/// Whether a span ID returned by `start_span` belongs to a recorded span
+///
+/// Unsampled, filtered, and capped spans get [`NOOP_SPAN_ID`]; every span
+/// operation accepts it and returns immediately without taking a lock. With
+/// the `disabled` feature nothing is recording.
+#[inline]
+pub fn is_recording(span_id: &str) -> bool {
+    !cfg!(feature = "disabled") && span_id != NOOP_SPAN_ID
+}
+
+/// Binary ID of a span ID handed out by `start_span`
+fn parse_span_id(span_id: &str) -> TracingResult<SpanId> {
+    SpanId::parse(span_id).ok_or_else(|| invalid_span_id(span_id))
+}
+
+fn invalid_span_id(span_id: &str) -> TylError {
+    TylError::validation("span_id", format!("invalid span ID: {}", span_id))
+}
+
+/// Environment variable that force-enables or disables tracing at runtime
+pub const TRACING_ENABLED_ENV: &str = "TYL_TRACING_ENABLED";
+
+/// How often the environment override is re-read
+const ENV_REFRESH_INTERVAL_MS: u64 = 1000;
+
+const ENV_UNSET: u8 = 0;
+const ENV_ENABLED: u8 = 1;
+const ENV_DISABLED: u8 = 2;
+
+/// Runtime on/off switch combining `set_enabled` with the env override
+#[derive(Debug)]
+struct KillSwitch {
+    enabled: AtomicBool,
+    env_override: AtomicU8,
+    env_checked_at: AtomicU64,
+}
+
+impl KillSwitch {
+    fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            env_override: AtomicU8::new(ENV_UNSET),
+            env_checked_at: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        let now = current_timestamp();
+        let checked_at = self.env_checked_at.load(Ordering::Relaxed);
+        if now.saturating_sub(checked_at) >= ENV_REFRESH_INTERVAL_MS
+            && self
+                .env_checked_at
+                .compare_exchange(checked_at, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.env_override
+                .store(read_env_override(), Ordering::Relaxed);
+        }
+
+        match self.env_override.load(Ordering::Relaxed) {
+            ENV_ENABLED => true,
+            ENV_DISABLED => false,
+            _ => self.enabled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn read_env_override() -> u8 {
+    match std::env::var(TRACING_ENABLED_ENV)
+        .map(|v| v.trim().to_lowercase())
+        .as_deref()
+    {
+        Ok("1" | "true" | "on" | "yes") => ENV_ENABLED,
+        Ok("0" | "false" | "off" | "no") => ENV_DISABLED,
+        _ => ENV_UNSET,
+    }
+}
+
 /// Port (Interface) - Main tracing contract
-pub trait TracingManager {
+///
+/// The trait is object safe and requires `Send + Sync`, so a tracer can be
+/// injected as a [`DynTracer`] and shared across threads and tasks.
+pub trait TracingManager: Send + Sync {
     /// Start a new span with optional parent span ID
     fn start_span(
         &self,
@@ -20,6 +141,21 @@ pub trait TracingManager {
         parent_span_id: Option<String>,
     ) -> TracingResult<String>;
 
+    /// Start a new span with an explicit span kind (client, server, ...)
+    ///
+    /// The default implementation ignores `kind` and starts a
+    /// `SpanKind::Internal` span through `start_span`; tracers that record
+    /// span kinds override it.
+    fn start_span_with_kind(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        let _ = kind;
+        self.start_span(operation_name, parent_span_id)
+    }
+
     /// End a span by its ID
     fn end_span(&self, span_id: String) -> TracingResult<()>;
 
@@ -41,70 +177,1124 @@ pub trait TracingManager {
     fn get_baggage(&self, key: &str) -> Option<String>;
 }
 
+/// Shared, type-erased tracer for dependency injection
+pub type DynTracer = Arc<dyn TracingManager>;
+
+/// Implement TracingManager for a pointer type by forwarding to its target
+macro_rules! forward_tracing_manager {
+    ($($pointer:ty),+ $(,)?) => {$(
+        impl<T: TracingManager + ?Sized> TracingManager for $pointer {
+            fn start_span(
+                &self,
+                operation_name: &str,
+                parent_span_id: Option<String>,
+            ) -> TracingResult<String> {
+                (**self).start_span(operation_name, parent_span_id)
+            }
+
+            fn start_span_with_kind(
+                &self,
+                operation_name: &str,
+                parent_span_id: Option<String>,
+                kind: SpanKind,
+            ) -> TracingResult<String> {
+                (**self).start_span_with_kind(operation_name, parent_span_id, kind)
+            }
+
+            fn end_span(&self, span_id: String) -> TracingResult<()> {
+                (**self).end_span(span_id)
+            }
+
+            fn add_span_attribute(
+                &self,
+                span_id: &str,
+                key: &str,
+                value: serde_json::Value,
+            ) -> TracingResult<()> {
+                (**self).add_span_attribute(span_id, key, value)
+            }
+
+            fn get_completed_spans(&self) -> Vec<Span> {
+                (**self).get_completed_spans()
+            }
+
+            fn set_baggage(&self, key: &str, value: &str) {
+                (**self).set_baggage(key, value)
+            }
+
+            fn get_baggage(&self, key: &str) -> Option<String> {
+                (**self).get_baggage(key)
+            }
+        }
+    )+};
+}
+
+forward_tracing_manager!(&T, &mut T, Box<T>, Arc<T>);
+
+/// Configuration snapshot, replaced as a whole so readers never see a mix of
+/// old and new values
+#[derive(Debug)]
+struct Settings {
+    config: Arc<TraceConfig>,
+    filter: SpanFilter,
+}
+
 /// Adapter - Simple in-memory tracer for development
+///
+/// With the `disabled` feature this is the empty tracer from the `disabled`
+/// module instead, with the same methods.
+#[cfg(not(feature = "disabled"))]
 pub struct SimpleTracer {
-    config: TraceConfig,
-    active_spans: std::sync::Mutex<HashMap<String, Span>>,
-    completed_spans: std::sync::Mutex<Vec<Span>>,
-    baggage: std::sync::Mutex<HashMap<String, String>>,
+    settings: RwLock<Arc<Settings>>,
+    pub(crate) active_spans: std::sync::Mutex<HashMap<SpanId, Span>>,
+    pub(crate) completed_spans: Arc<std::sync::Mutex<SpanStore>>,
+    retention: RetentionPruner,
+    pub(crate) baggage: std::sync::Mutex<HashMap<String, String>>,
+    anomaly_detector: Option<AnomalyDetector>,
+    pub(crate) observers: SpanObservers,
+    pub(crate) key_linter: KeyLinter,
+    processor: Option<BatchSpanProcessor>,
+    kill_switch: KillSwitch,
+    filter_env: Mutex<Option<String>>,
+    filter_env_checked_at: AtomicU64,
+    tenant_quotas: TenantQuotas,
+    pub(crate) sampling_audit: SamplingAudit,
+    traces: Mutex<HashMap<TraceId, TraceState>>,
+    /// Ended spans held back until their trace is exported as a whole
+    trace_buffers: Mutex<HashMap<TraceId, Vec<Span>>>,
+    dropped_trace_limit: AtomicU64,
+    dropped_active_limit: AtomicU64,
+    rejected_baggage: AtomicU64,
+    spans_started: AtomicU64,
+    spans_ended: AtomicU64,
+    pub(crate) drop_counters: DropCounters,
+    pub(crate) drop_summary: Option<DropSummary>,
+    #[cfg(feature = "cpu-time")]
+    cpu_clock: CpuClock,
+    allocations: AllocationTracker,
+    #[cfg(feature = "tokio-metrics")]
+    runtime_metrics: Option<RuntimeMetricsTracker>,
+    backpressure: Option<BackpressureMonitor>,
+}
+
+/// Bookkeeping for a trace with active spans
+#[derive(Debug, Default)]
+struct TraceState {
+    spans_started: usize,
+    attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Spans replaced by placeholders because a span cap was reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DroppedSpanCounts {
+    /// Dropped by `max_spans_per_trace`
+    pub trace_limit: u64,
+    /// Dropped by `max_active_spans`
+    pub active_limit: u64,
 }
 
+/// Attribute counting a span's children that were dropped by a span cap
+pub const DROPPED_CHILDREN_ATTRIBUTE: &str = "tracing.dropped_children";
+
+#[cfg(feature = "disabled")]
+pub use crate::disabled::SimpleTracer;
+
+#[cfg(not(feature = "disabled"))]
 impl SimpleTracer {
     pub fn new(config: TraceConfig) -> Self {
+        // The config is validated at load time; an invalid filter records everything
+        let filter = config
+            .filter
+            .as_deref()
+            .and_then(|f| SpanFilter::parse(f).ok())
+            .unwrap_or_default();
+        let completed_spans = Arc::new(std::sync::Mutex::new(SpanStore::default()));
+        let retention = RetentionPruner::new();
+        retention.configure(config.retention, &completed_spans);
         Self {
-            config,
+            settings: RwLock::new(Arc::new(Settings {
+                config: Arc::new(config),
+                filter,
+            })),
             active_spans: std::sync::Mutex::new(HashMap::new()),
-            completed_spans: std::sync::Mutex::new(Vec::new()),
+            completed_spans,
+            retention,
             baggage: std::sync::Mutex::new(HashMap::new()),
+            anomaly_detector: None,
+            observers: SpanObservers::default(),
+            key_linter: KeyLinter::default(),
+            processor: None,
+            kill_switch: KillSwitch::new(),
+            filter_env: Mutex::new(None),
+            filter_env_checked_at: AtomicU64::new(0),
+            tenant_quotas: TenantQuotas::default(),
+            sampling_audit: SamplingAudit::default(),
+            traces: Mutex::new(HashMap::new()),
+            trace_buffers: Mutex::new(HashMap::new()),
+            dropped_trace_limit: AtomicU64::new(0),
+            dropped_active_limit: AtomicU64::new(0),
+            rejected_baggage: AtomicU64::new(0),
+            spans_started: AtomicU64::new(0),
+            spans_ended: AtomicU64::new(0),
+            drop_counters: DropCounters::default(),
+            drop_summary: None,
+            #[cfg(feature = "cpu-time")]
+            cpu_clock: CpuClock::default(),
+            allocations: AllocationTracker::default(),
+            #[cfg(feature = "tokio-metrics")]
+            runtime_metrics: None,
+            backpressure: None,
+        }
+    }
+
+    /// Replace the per-operation span filter at runtime
+    ///
+    /// A later change of the `TYL_TRACE_FILTER` environment variable (re-read
+    /// every second) replaces it again.
+    pub fn set_filter(&self, filter: SpanFilter) {
+        let mut settings = self.settings.write().unwrap();
+        *settings = Arc::new(Settings {
+            config: settings.config.clone(),
+            filter,
+        });
+    }
+
+    /// Parse and apply a filter string such as `checkout=on,health*=off,db.*=0.01`
+    pub fn set_filter_str(&self, filter: &str) -> TracingResult<()> {
+        self.set_filter(SpanFilter::parse(filter)?);
+        Ok(())
+    }
+
+    pub fn filter(&self) -> SpanFilter {
+        self.settings().filter.clone()
+    }
+
+    /// Change the sampling rate for spans started afterwards, keeping the filter
+    pub fn set_sampling_rate(&self, rate: f64) -> TracingResult<()> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(TylError::validation(
+                "sampling_rate",
+                "must be between 0.0 and 1.0",
+            ));
+        }
+        let mut settings = self.settings.write().unwrap();
+        let mut config = (*settings.config).clone();
+        config.sampling_rate = rate;
+        *settings = Arc::new(Settings {
+            config: Arc::new(config),
+            filter: settings.filter.clone(),
+        });
+        Ok(())
+    }
+
+    /// Apply a new configuration without restarting the tracer
+    ///
+    /// The config is validated first; sampling rate, filter, and `max_spans`
+    /// take effect atomically for spans started afterwards, `retention` on the
+    /// next pruning tick. A changed `exporter` section rebuilds the exporter
+    /// behind the batch processor (buffered spans go to the old one first) and
+    /// applies its batch size; adding or removing the section needs a restart.
+    pub fn reload_config(&self, config: TraceConfig) -> TracingResult<()> {
+        config.validate()?;
+        let filter = match config.filter.as_deref() {
+            Some(filter) => SpanFilter::parse(filter)?,
+            None => SpanFilter::default(),
+        };
+        // Built before anything is swapped, so a bad endpoint changes nothing
+        let current = self.settings();
+        let new_exporter = match (&self.processor, &config.exporter) {
+            (Some(_), Some(exporter)) if current.config.exporter.as_ref() != Some(exporter) => {
+                Some((
+                    build_exporter(&config.service_name, exporter)?,
+                    exporter.batch_size,
+                ))
+            }
+            _ => None,
+        };
+        self.retention
+            .configure(config.retention, &self.completed_spans);
+        *self.settings.write().unwrap() = Arc::new(Settings {
+            config: Arc::new(config),
+            filter,
+        });
+        match (&self.processor, new_exporter) {
+            (Some(processor), Some((exporter, batch_size))) => {
+                processor.set_batch_size(batch_size);
+                // Errors here come from flushing or shutting down the old exporter
+                processor.set_boxed_exporter(exporter)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn settings(&self) -> Arc<Settings> {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Pick up changes to `TYL_TRACE_FILTER`, at most once per refresh interval
+    fn refresh_filter_from_env(&self) {
+        let now = current_timestamp();
+        let checked_at = self.filter_env_checked_at.load(Ordering::Relaxed);
+        if now.saturating_sub(checked_at) < ENV_REFRESH_INTERVAL_MS
+            || self
+                .filter_env_checked_at
+                .compare_exchange(checked_at, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+
+        let current = std::env::var(TRACE_FILTER_ENV).ok();
+        let mut last_seen = self.filter_env.lock().unwrap();
+        if *last_seen == current {
+            return;
+        }
+        if let Some(filter) = current.as_deref().and_then(|f| SpanFilter::parse(f).ok()) {
+            self.set_filter(filter);
+        }
+        *last_seen = current;
+    }
+
+    /// Filter directives first (a sampling priority overrides their rates);
+    /// otherwise root spans follow the tenant's or the configured rate and
+    /// children follow their (recorded) parent
+    ///
+    /// Returns the decision with the effective rate and the rule that made it.
+    fn is_sampled<'a>(
+        settings: &'a Settings,
+        operation_name: &str,
+        trace_id: TraceId,
+        is_root: bool,
+        tenant_rate: Option<(&'a str, f64)>,
+        priority: Option<i64>,
+    ) -> (bool, f64, MatchedRule<'a>) {
+        let directive = settings
+            .filter
+            .matching(operation_name)
+            .map(|d| (d.action, d.pattern.as_str()));
+        match (directive, priority) {
+            (Some((FilterAction::Enabled, pattern)), _) => {
+                (true, 1.0, MatchedRule::Filter(pattern))
+            }
+            (Some((FilterAction::Disabled, pattern)), _) => {
+                (false, 0.0, MatchedRule::Filter(pattern))
+            }
+            (_, Some(priority)) => (
+                priority > 0,
+                if priority > 0 { 1.0 } else { 0.0 },
+                MatchedRule::Priority(priority),
+            ),
+            (Some((FilterAction::Sample(rate), pattern)), None) => (
+                should_sample_trace(trace_id, rate),
+                rate,
+                MatchedRule::Filter(pattern),
+            ),
+            (None, None) if is_root => {
+                let (rate, rule) = match tenant_rate {
+                    Some((tenant_id, rate)) => (rate, MatchedRule::Tenant(tenant_id)),
+                    None => (settings.config.sampling_rate, MatchedRule::Rate),
+                };
+                (should_sample_trace(trace_id, rate), rate, rule)
+            }
+            (None, None) => (true, 1.0, MatchedRule::Parent),
+        }
+    }
+
+    /// View of this tracer scoped to a tenant
+    pub fn with_tenant<'a>(&'a self, tenant_id: &'a str) -> TenantTracer<'a> {
+        TenantTracer::new(self, tenant_id)
+    }
+
+    /// Completed spans stamped with the given tenant
+    pub fn completed_spans_for_tenant(&self, tenant_id: &str) -> Vec<Span> {
+        self.completed_spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|span| span_tenant(span) == Some(tenant_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Spans dropped because the tenant exceeded its `spans_per_minute` quota
+    pub fn tenant_dropped_spans(&self, tenant_id: &str) -> u64 {
+        self.tenant_quotas.dropped(tenant_id)
+    }
+
+    /// Start a span, optionally for a tenant; children inherit the parent's tenant
+    pub(crate) fn start_tenant_span(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+        tenant_id: Option<&str>,
+    ) -> TracingResult<String> {
+        self.start_span_from(operation_name, parent_span_id, None, kind, tenant_id)
+    }
+
+    /// Start a span continuing a trace from another process
+    ///
+    /// The span joins `parent`'s trace as its child. When the caller did not
+    /// sample the trace nothing is recorded and the NOOP ID is returned,
+    /// unless a `sampling.priority` in baggage forces the trace in.
+    pub fn start_span_with_remote_parent(
+        &self,
+        operation_name: &str,
+        parent: &TraceContext,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        self.start_span_from(operation_name, None, Some(parent), kind, None)
+    }
+
+    fn start_span_from(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        remote_parent: Option<&TraceContext>,
+        kind: SpanKind,
+        tenant_id: Option<&str>,
+    ) -> TracingResult<String> {
+        // Without an explicit parent, the span attached to this thread is used
+        let (parent_span_id, implicit_parent) = match (parent_span_id, remote_parent) {
+            (_, Some(_)) => (None, false),
+            (Some(parent_span_id), None) => (Some(parent_span_id), false),
+            (None, None) => (current_span_id(), true),
+        };
+        // Children of unrecorded spans are not recorded either
+        if !self.is_enabled()
+            || parent_span_id
+                .as_deref()
+                .is_some_and(|id| !is_recording(id))
+        {
+            return Ok(NOOP_SPAN_ID.to_string());
+        }
+        if let Some(summary) = &self.drop_summary {
+            summary.maybe_log(&self.config().service_name, || {
+                self.dropped_spans_by_reason()
+            });
         }
+        // A sampling priority in baggage outranks the caller's sampled flag
+        let remote_priority = remote_parent.and_then(|_| {
+            self.get_baggage(SAMPLING_PRIORITY_KEY)
+                .and_then(|priority| parse_sampling_priority(&priority))
+        });
+        if let Some(parent) =
+            remote_parent.filter(|parent| !parent.sampled && remote_priority.is_none())
+        {
+            self.drop_counters.unsampled(MatchedRule::Parent);
+            self.sampling_audit.record(
+                operation_name,
+                parent.trace_id,
+                false,
+                0.0,
+                MatchedRule::Parent,
+            );
+            return Ok(NOOP_SPAN_ID.to_string());
+        }
+        self.refresh_filter_from_env();
+        let settings = self.settings();
+
+        // What the parent passes down: trace, tenant, and forced priority.
+        // Roots never take the active-span lock before being sampled in.
+        let parent_span_id = parent_span_id.as_deref().and_then(SpanId::parse);
+        let parent = parent_span_id.and_then(|id| {
+            let active_spans = self.active_spans.lock().unwrap();
+            let parent = active_spans.get(&id)?;
+            Some((
+                parent.trace_id,
+                span_tenant(parent).map(str::to_string),
+                parent
+                    .attributes
+                    .get(SAMPLING_PRIORITY_KEY)
+                    .and_then(|priority| priority.as_i64()),
+            ))
+        });
+        // An attached span that ended or belongs to another tracer is ignored
+        let parent_span_id = parent_span_id
+            .filter(|_| !implicit_parent || parent.is_some())
+            .or_else(|| remote_parent.map(|parent| parent.span_id));
+        let parent =
+            parent.or_else(|| remote_parent.map(|parent| (parent.trace_id, None, remote_priority)));
+        let (trace_id, parent_tenant, priority) = match parent {
+            Some(parent) => parent,
+            // Forced traces keep their priority on every span; roots take it from baggage
+            None => (
+                TraceId::random(),
+                None,
+                self.get_baggage(SAMPLING_PRIORITY_KEY)
+                    .and_then(|priority| parse_sampling_priority(&priority)),
+            ),
+        };
+        let tenant_id = tenant_id.map(str::to_string).or(parent_tenant);
+        let tenant_config = tenant_id
+            .as_deref()
+            .and_then(|tenant| settings.config.tenants.get(tenant));
+        // Decided before anything is allocated for the span; children of an
+        // unsampled span get the NOOP ID back and return at the top
+        let (sampled, rate, rule) = Self::is_sampled(
+            &settings,
+            operation_name,
+            trace_id,
+            parent_span_id.is_none(),
+            tenant_id
+                .as_deref()
+                .zip(tenant_config.and_then(|tenant| tenant.sampling_rate)),
+            priority,
+        );
+        self.sampling_audit
+            .record(operation_name, trace_id, sampled, rate, rule);
+        if !sampled {
+            self.drop_counters.unsampled(rule);
+            return Ok(NOOP_SPAN_ID.to_string());
+        }
+        let mut span = Span::with_ids(
+            SpanId::random(),
+            trace_id,
+            operation_name.to_string(),
+            parent_span_id,
+        )
+        .with_kind(kind);
+        if let Some(tenant_id) = &tenant_id {
+            span.attributes.insert(
+                TENANT_ID_ATTRIBUTE.to_string(),
+                serde_json::Value::String(tenant_id.clone()),
+            );
+        }
+
+        if let Some(priority) = priority {
+            span.attributes
+                .insert(SAMPLING_PRIORITY_KEY.to_string(), priority.into());
+        }
+
+        // Span caps: beyond them a placeholder (NOOP) ID is handed out
+        let mut active_spans = self.active_spans.lock().unwrap();
+        let config = &settings.config;
+        if config
+            .max_active_spans
+            .is_some_and(|max| active_spans.len() >= max)
+        {
+            self.dropped_active_limit.fetch_add(1, Ordering::Relaxed);
+            Self::count_dropped_child(&mut active_spans, span.parent_span_id);
+            return Ok(NOOP_SPAN_ID.to_string());
+        }
+        {
+            let mut traces = self.traces.lock().unwrap();
+            let spans_started = traces
+                .get(&span.trace_id)
+                .map_or(0, |trace| trace.spans_started);
+            if config
+                .max_spans_per_trace
+                .is_some_and(|max| spans_started >= max)
+            {
+                drop(traces);
+                self.dropped_trace_limit.fetch_add(1, Ordering::Relaxed);
+                Self::count_dropped_child(&mut active_spans, span.parent_span_id);
+                return Ok(NOOP_SPAN_ID.to_string());
+            }
+            // Quota last, so spans dropped by a cap do not use it up
+            if let Some(tenant_id) = &tenant_id {
+                let quota = tenant_config.and_then(|tenant| tenant.spans_per_minute);
+                if !self.tenant_quotas.try_acquire(tenant_id, quota) {
+                    return Ok(NOOP_SPAN_ID.to_string());
+                }
+            }
+            traces.entry(span.trace_id).or_default().spans_started += 1;
+        }
+
+        let span_id = span.span_id;
+        active_spans.insert(span_id, span);
+        drop(active_spans);
+        self.spans_started.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "cpu-time")]
+        self.cpu_clock.start(span_id);
+        self.allocations.start(span_id);
+        #[cfg(feature = "tokio-metrics")]
+        if let Some(tracker) = &self.runtime_metrics {
+            tracker.start(span_id);
+        }
+        self.observe_backpressure();
+
+        Ok(settings.config.id_format.render_span_id(span_id))
+    }
+
+    /// Hold a span back until its root ends (or, for traces whose root is
+    /// remote, until no span of the trace is active), then export the trace
+    ///
+    /// Spans ending after their root are exported together once the rest of
+    /// the trace has ended.
+    fn export_with_trace(&self, processor: &BatchSpanProcessor, span: Span, trace_ended: bool) {
+        let mut buffers = self.trace_buffers.lock().unwrap();
+        if span.parent_span_id.is_none() || trace_ended {
+            let mut trace = buffers.remove(&span.trace_id).unwrap_or_default();
+            drop(buffers);
+            trace.push(span);
+            processor.on_end_trace(trace);
+        } else {
+            buffers.entry(span.trace_id).or_default().push(span);
+        }
+    }
+
+    /// Apply the configured `attribute_key_validation` to a key
+    pub(crate) fn check_attribute_key(&self, key: &str) -> TracingResult<()> {
+        let mode = self.settings().config.attribute_key_validation;
+        self.key_linter.check(mode, key)
     }
 
-    pub fn config(&self) -> &TraceConfig {
-        &self.config
+    /// Attribute keys reported by `KeyValidation::Warn` so far, sorted
+    pub fn attribute_key_violations(&self) -> Vec<String> {
+        self.key_linter.reported()
+    }
+
+    /// Drop held-back spans matching `predicate`; returns how many were dropped
+    pub(crate) fn purge_trace_buffers(&self, predicate: impl Fn(&Span) -> bool) -> usize {
+        let mut buffers = self.trace_buffers.lock().unwrap();
+        let mut purged = 0;
+        for spans in buffers.values_mut() {
+            let before = spans.len();
+            spans.retain(|span| !predicate(span));
+            purged += before - spans.len();
+        }
+        buffers.retain(|_, spans| !spans.is_empty());
+        purged
+    }
+
+    /// Record a dropped child on its parent so trace viewers can see the gap
+    fn count_dropped_child(
+        active_spans: &mut HashMap<SpanId, Span>,
+        parent_span_id: Option<SpanId>,
+    ) {
+        if let Some(parent) = parent_span_id.and_then(|id| active_spans.get_mut(&id)) {
+            let dropped = parent
+                .attributes
+                .get(DROPPED_CHILDREN_ATTRIBUTE)
+                .and_then(|count| count.as_u64())
+                .unwrap_or(0);
+            parent
+                .attributes
+                .insert(DROPPED_CHILDREN_ATTRIBUTE.to_string(), (dropped + 1).into());
+        }
+    }
+
+    /// Turn tracing on or off at runtime
+    ///
+    /// While disabled, `start_span` returns [`NOOP_SPAN_ID`] without recording
+    /// anything. The `TYL_TRACING_ENABLED` environment variable, re-read every
+    /// second, takes precedence over this flag when set. With the `disabled`
+    /// cargo feature tracing is always off and this has no effect.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.kill_switch.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.kill_switch.is_enabled()
+    }
+
+    /// Export completed spans in batches, flushing on the configured interval
+    pub fn with_exporter(self, exporter: impl SpanExporter + 'static) -> Self {
+        let mut processor = BatchSpanProcessor::new(exporter);
+        if let Some(interval) = self.config().flush_interval {
+            processor = processor.with_flush_interval(interval);
+        }
+        self.with_batch_processor(processor)
+    }
+
+    /// Export completed spans through a custom-configured batch processor
+    pub fn with_batch_processor(mut self, processor: BatchSpanProcessor) -> Self {
+        self.processor = Some(processor);
+        self
+    }
+
+    pub fn batch_processor(&self) -> Option<&BatchSpanProcessor> {
+        self.processor.as_ref()
+    }
+
+    /// Export all buffered spans now (no-op without an exporter)
+    pub fn flush(&self) -> TracingResult<()> {
+        match &self.processor {
+            Some(processor) => processor.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Record tokio runtime metric changes (`tokio.*` attributes) on spans
+    /// that start and end inside a runtime
+    #[cfg(feature = "tokio-metrics")]
+    pub fn with_runtime_metrics(mut self) -> Self {
+        self.runtime_metrics = Some(RuntimeMetricsTracker::default());
+        self
+    }
+
+    /// Call `signal` when the active-span table or the export pipeline crosses
+    /// its high-water mark, and again once it drains
+    pub fn with_backpressure_signal(mut self, signal: BackpressureSignal) -> Self {
+        self.backpressure = Some(BackpressureMonitor::new(signal));
+        self
+    }
+
+    /// Whether any queue is at or above its high-water mark (the signal's, or
+    /// [`DEFAULT_HIGH_WATER`] without one); shed optional spans while true
+    pub fn is_saturated(&self) -> bool {
+        let high_water = self
+            .backpressure
+            .as_ref()
+            .map_or(DEFAULT_HIGH_WATER, BackpressureMonitor::high_water);
+        self.backpressure_levels()
+            .iter()
+            .any(|(_, level)| *level >= high_water)
+    }
+
+    fn backpressure_levels(&self) -> [(BackpressureSource, f64); 2] {
+        let active_level = self.config().max_active_spans.map_or(0.0, |max| {
+            self.active_spans.lock().unwrap().len() as f64 / max.max(1) as f64
+        });
+        let export_level = self.processor.as_ref().map_or(0.0, |processor| {
+            export_level(processor.consecutive_failures())
+        });
+        [
+            (BackpressureSource::ActiveSpans, active_level),
+            (BackpressureSource::Export, export_level),
+        ]
+    }
+
+    /// Report watermark crossings; called without any store lock held so the
+    /// callback may use the tracer
+    fn observe_backpressure(&self) {
+        if let Some(monitor) = &self.backpressure {
+            for (source, level) in self.backpressure_levels() {
+                monitor.observe(source, level);
+            }
+        }
+    }
+
+    /// Check every completed span against an anomaly detector
+    ///
+    /// Flagged spans get `anomaly.z_score` and `anomaly.expected_ms` attributes.
+    pub fn with_anomaly_detector(mut self, detector: AnomalyDetector) -> Self {
+        self.anomaly_detector = Some(detector);
+        self
+    }
+
+    /// Trace ID of an active span
+    pub fn trace_id(&self, span_id: &str) -> Option<String> {
+        if !is_recording(span_id) {
+            return None;
+        }
+        let span_id = SpanId::parse(span_id)?;
+        let trace_id = self.active_spans.lock().unwrap().get(&span_id)?.trace_id;
+        Some(self.settings().config.id_format.render_trace_id(trace_id))
+    }
+
+    /// Set an attribute on every span of a trace, applied when each span ends
+    ///
+    /// Covers spans already active and those started later; attributes set on
+    /// the span itself take precedence. Kept until the trace's last active span
+    /// ends.
+    pub fn set_trace_attribute(
+        &self,
+        trace_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> TracingResult<()> {
+        let no_active_spans = || {
+            TylError::validation(
+                "trace_id",
+                format!("no active spans in trace: {}", trace_id),
+            )
+        };
+        let trace_id = TraceId::parse(trace_id).ok_or_else(no_active_spans)?;
+        let active_spans = self.active_spans.lock().unwrap();
+        if !active_spans.values().any(|span| span.trace_id == trace_id) {
+            return Err(no_active_spans());
+        }
+        self.traces
+            .lock()
+            .unwrap()
+            .entry(trace_id)
+            .or_default()
+            .attributes
+            .insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Record progress on a long-running span and mark it as alive
+    ///
+    /// Appends a timestamped `heartbeat` event; an object value becomes the
+    /// event's attributes, any other value is stored as `progress`.
+    pub fn span_heartbeat(&self, span_id: &str, progress: serde_json::Value) -> TracingResult<()> {
+        if !is_recording(span_id) {
+            return Ok(());
+        }
+        let attributes = match progress {
+            serde_json::Value::Object(map) => map.into_iter().collect(),
+            serde_json::Value::Null => HashMap::new(),
+            value => HashMap::from([("progress".to_string(), value)]),
+        };
+        self.add_span_event(span_id, HEARTBEAT_EVENT, attributes)
+    }
+
+    /// Append a timestamped event to an active span
+    pub fn add_span_event(
+        &self,
+        span_id: &str,
+        name: &str,
+        attributes: HashMap<String, serde_json::Value>,
+    ) -> TracingResult<()> {
+        self.push_event(
+            span_id,
+            SpanEvent {
+                name: name.to_string(),
+                timestamp: current_timestamp(),
+                attributes,
+            },
+        )
+    }
+
+    /// Mark an active span failed with `error`, attaching its full source
+    /// chain as an `exception` event (see [`Span::record_error`])
+    pub fn record_error(&self, span_id: &str, error: &TylError) -> TracingResult<()> {
+        self.update_span(span_id, |span| span.record_error(error))
+    }
+
+    pub(crate) fn push_event(&self, span_id: &str, event: SpanEvent) -> TracingResult<()> {
+        self.update_span(span_id, |span| span.events.push(event))
+    }
+
+    /// Apply `update` to an active span
+    pub(crate) fn update_span(
+        &self,
+        span_id: &str,
+        update: impl FnOnce(&mut Span),
+    ) -> TracingResult<()> {
+        if !is_recording(span_id) {
+            return Ok(());
+        }
+        let id = parse_span_id(span_id)?;
+        match self.active_spans.lock().unwrap().get_mut(&id) {
+            Some(span) => {
+                update(span);
+                Ok(())
+            }
+            None => Err(invalid_span_id(span_id)),
+        }
+    }
+
+    /// Time a named phase of a span; recorded as an event on drop of the guard
+    pub fn section_timer<'a>(&'a self, span_id: &str, name: &str) -> SectionTimer<'a> {
+        SectionTimer::new(self, span_id, name)
+    }
+
+    /// Run `f` and record its duration as a `name` event on the span
+    ///
+    /// Lighter than a child span: no IDs, sampling, or export of its own.
+    pub fn time_section<R>(&self, span_id: &str, name: &str, f: impl FnOnce() -> R) -> R {
+        let _timer = self.section_timer(span_id, name);
+        f()
+    }
+
+    /// Run `f` inside a child span of `parent_span_id`, ended when `f` returns
+    pub fn time_child_span<R>(
+        &self,
+        parent_span_id: &str,
+        operation_name: &str,
+        f: impl FnOnce() -> R,
+    ) -> TracingResult<R> {
+        let span_id = self.start_span(operation_name, Some(parent_span_id.to_string()))?;
+        let result = f();
+        self.end_span(span_id)?;
+        Ok(result)
+    }
+
+    /// Store a baggage entry, or explain why it breaks the W3C baggage limits
+    /// (key characters, entry and total size, entry count)
+    pub fn try_set_baggage(&self, key: &str, value: &str) -> TracingResult<()> {
+        let mut baggage = self.baggage.lock().unwrap();
+        validate_baggage_insert(&baggage, key, value)?;
+        baggage.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Baggage entries dropped by `set_baggage` for breaking the limits
+    pub fn rejected_baggage_entries(&self) -> u64 {
+        self.rejected_baggage.load(Ordering::Relaxed)
+    }
+
+    /// Copy of the tracer-wide baggage
+    pub(crate) fn baggage_snapshot(&self) -> HashMap<String, String> {
+        self.baggage.lock().unwrap().clone()
+    }
+
+    /// Active, queued, and kept completed spans for a crash report, tagged with
+    /// their state; skips any store whose lock is held, so it never blocks
+    pub(crate) fn crash_snapshot(&self) -> Vec<(&'static str, Span)> {
+        let mut spans = Vec::new();
+        if let Some(active) = try_lock_for_crash(&self.active_spans) {
+            spans.extend(active.values().map(|span| ("active", span.clone())));
+        }
+        if let Some(queued) = self
+            .processor
+            .as_ref()
+            .and_then(BatchSpanProcessor::try_queued_snapshot)
+        {
+            spans.extend(queued.into_iter().map(|span| ("queued", span)));
+        }
+        if let Some(completed) = try_lock_for_crash(&self.completed_spans) {
+            spans.extend(completed.iter().map(|span| ("completed", span.clone())));
+        }
+        spans
+    }
+
+    /// Snapshot of all spans that have started but not ended
+    pub fn active_spans(&self) -> Vec<Span> {
+        self.active_spans
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Number of spans that have started but not ended
+    pub(crate) fn active_span_count(&self) -> usize {
+        self.active_spans.lock().unwrap().len()
+    }
+
+    /// Active spans without a heartbeat (or start) within `max_idle`, for
+    /// timeout watchdogs
+    pub fn stale_spans(&self, max_idle: std::time::Duration) -> Vec<Span> {
+        let cutoff = current_timestamp().saturating_sub(max_idle.as_millis() as u64);
+        self.active_spans
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|span| span.last_activity() < cutoff)
+            .cloned()
+            .collect()
+    }
+
+    /// Spans not recorded because `max_spans_per_trace` or `max_active_spans`
+    /// was reached
+    pub fn dropped_span_counts(&self) -> DroppedSpanCounts {
+        DroppedSpanCounts {
+            trace_limit: self.dropped_trace_limit.load(Ordering::Relaxed),
+            active_limit: self.dropped_active_limit.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Spans recorded since the tracer was created (sampled, within caps)
+    pub fn started_spans(&self) -> u64 {
+        self.spans_started.load(Ordering::Relaxed)
+    }
+
+    /// Recorded spans that have ended
+    pub fn ended_spans(&self) -> u64 {
+        self.spans_ended.load(Ordering::Relaxed)
+    }
+
+    /// Spans dropped by tenant quotas, across tenants
+    pub(crate) fn tenant_quota_drops(&self) -> u64 {
+        self.tenant_quotas.total_dropped()
+    }
+
+    /// Current configuration (reflects the latest reload)
+    pub fn config(&self) -> Arc<TraceConfig> {
+        self.settings().config.clone()
+    }
+
+    /// Drop all completed spans kept in memory, returning how many there were
+    ///
+    /// Active spans and spans already handed to the batch processor are kept.
+    pub fn clear_completed_spans(&self) -> usize {
+        self.completed_spans.lock().unwrap().clear()
+    }
+
+    /// Completed spans of one trace, in end order (served from an index)
+    pub fn get_trace(&self, trace_id: &str) -> Vec<Span> {
+        let Some(trace_id) = TraceId::parse(trace_id) else {
+            return Vec::new();
+        };
+        self.completed_spans
+            .lock()
+            .unwrap()
+            .trace(trace_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Completed spans that ended between `from` and `to` (Unix ms, inclusive)
+    pub fn completed_spans_between(&self, from: u64, to: u64) -> Vec<Span> {
+        self.completed_spans
+            .lock()
+            .unwrap()
+            .range(from, to)
+            .cloned()
+            .collect()
+    }
+
+    /// Health of the tracer and its export pipeline, e.g. for readiness probes
+    pub fn health(&self) -> TracerHealth {
+        let active_spans = self.active_spans.lock().unwrap().len();
+        let dropped = self.dropped_span_counts();
+        let mut health = TracerHealth {
+            enabled: self.is_enabled(),
+            active_spans,
+            active_saturation: self
+                .config()
+                .max_active_spans
+                .map(|max| active_spans as f64 / max.max(1) as f64),
+            dropped_spans: dropped.trace_limit
+                + dropped.active_limit
+                + self.tenant_quotas.total_dropped(),
+            ..TracerHealth::disabled()
+        };
+        if let Some(processor) = &self.processor {
+            let failures = processor.consecutive_failures();
+            health.exporter = match (failures, processor.last_export_time()) {
+                (0, None) => ExporterHealth::Unknown,
+                (0, Some(_)) => ExporterHealth::Connected,
+                (consecutive_failures, _) => ExporterHealth::Failing {
+                    consecutive_failures,
+                    last_error: processor.last_error(),
+                },
+            };
+            health.last_export_time = processor.last_export_time();
+            health.queued_spans = processor.queued_spans();
+            health.queue_saturation = processor.queue_saturation();
+            health.dropped_spans += processor.dropped_spans();
+            health.failed_spans = processor.failed_spans();
+        }
+        health.with_computed_status()
     }
 }
 
+#[cfg(not(feature = "disabled"))]
 impl Default for SimpleTracer {
     fn default() -> Self {
         Self::new(TraceConfig::new("default-service"))
     }
 }
 
+#[cfg(not(feature = "disabled"))]
 impl TracingManager for SimpleTracer {
     fn start_span(
         &self,
         operation_name: &str,
         parent_span_id: Option<String>,
     ) -> TracingResult<String> {
-        let span = Span::new(operation_name.to_string(), parent_span_id);
-        let span_id = span.span_id.clone();
-
-        let mut active_spans = self.active_spans.lock().unwrap();
-        active_spans.insert(span_id.clone(), span);
+        self.start_span_with_kind(operation_name, parent_span_id, SpanKind::Internal)
+    }
 
-        Ok(span_id)
+    fn start_span_with_kind(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        self.start_tenant_span(operation_name, parent_span_id, kind, None)
     }
 
     fn end_span(&self, span_id: String) -> TracingResult<()> {
-        let mut active_spans = self.active_spans.lock().unwrap();
+        if !is_recording(&span_id) {
+            return Ok(());
+        }
+        let id = parse_span_id(&span_id)?;
+        let allocations = self.allocations.finish(id);
+        #[cfg(feature = "tokio-metrics")]
+        let runtime_metrics = self
+            .runtime_metrics
+            .as_ref()
+            .and_then(|tracker| tracker.finish(id));
+        let mut trace_ended = false;
+        let (removed, trace_attributes) = {
+            let mut active_spans = self.active_spans.lock().unwrap();
+            let removed = active_spans.remove(&id);
+            let trace_attributes = removed.as_ref().and_then(|span| {
+                let mut traces = self.traces.lock().unwrap();
+                if active_spans.values().any(|s| s.trace_id == span.trace_id) {
+                    traces
+                        .get(&span.trace_id)
+                        .map(|trace| trace.attributes.clone())
+                } else {
+                    trace_ended = true;
+                    traces.remove(&span.trace_id).map(|trace| trace.attributes)
+                }
+            });
+            (removed, trace_attributes)
+        };
+
+        if let Some(mut span) = removed {
+            span.finish();
+            self.spans_ended.fetch_add(1, Ordering::Relaxed);
+            for (key, value) in trace_attributes.into_iter().flatten() {
+                span.attributes.insert_if_absent(key, value);
+            }
+            let baggage_keys = &self.settings().config.baggage_attributes;
+            if !baggage_keys.is_empty() {
+                let context = Context::current();
+                let baggage = self.baggage.lock().unwrap();
+                for key in baggage_keys {
+                    let value = context
+                        .baggage(key)
+                        .or_else(|| baggage.get(key).map(String::as_str));
+                    if let Some(value) = value {
+                        span.attributes
+                            .insert_if_absent(key.clone(), value.to_string().into());
+                    }
+                }
+            }
+            #[cfg(feature = "tokio-metrics")]
+            span.attributes
+                .extend(runtime_metrics.into_iter().flatten());
+            if let Some(allocations) = allocations {
+                span.attributes
+                    .insert(ALLOC_BYTES_ATTRIBUTE.to_string(), allocations.bytes.into());
+                span.attributes
+                    .insert(ALLOC_COUNT_ATTRIBUTE.to_string(), allocations.count.into());
+            }
+            #[cfg(feature = "cpu-time")]
+            if let Some((cpu_ns, utilization)) = self.cpu_clock.finish(span.span_id) {
+                span.attributes
+                    .insert(CPU_TIME_ATTRIBUTE.to_string(), cpu_ns.into());
+                span.attributes.insert(
+                    CPU_UTILIZATION_ATTRIBUTE.to_string(),
+                    serde_json::json!(utilization),
+                );
+            }
 
-        if let Some(mut span) = active_spans.remove(&span_id) {
-            span.complete();
+            if let Some(detector) = &self.anomaly_detector {
+                if let Some(anomaly) = detector.observe(&span) {
+                    span.attributes.insert(
+                        ANOMALY_Z_SCORE_ATTRIBUTE.to_string(),
+                        serde_json::json!(anomaly.z_score),
+                    );
+                    span.attributes.insert(
+                        ANOMALY_EXPECTED_MS_ATTRIBUTE.to_string(),
+                        serde_json::json!(anomaly.expected_ms),
+                    );
+                }
+            }
+
+            self.observers.notify(&mut span);
+
+            if let Some(processor) = &self.processor {
+                if self.settings().config.export_whole_traces {
+                    self.export_with_trace(processor, span.clone(), trace_ended);
+                } else {
+                    processor.on_end(span.clone());
+                }
+            }
 
             let mut completed_spans = self.completed_spans.lock().unwrap();
             completed_spans.push(span);
 
             // Respect max_spans limit
-            if completed_spans.len() > self.config.max_spans {
-                completed_spans.remove(0);
-            }
+            completed_spans.truncate_oldest(self.settings().config.max_spans);
+            drop(completed_spans);
+            self.observe_backpressure();
 
             Ok(())
         } else {
-            Err(TylError::validation(
-                "span_id",
-                format!("invalid span ID: {}", span_id),
-            ))
+            Err(invalid_span_id(&span_id))
         }
     }
 
@@ -114,31 +1304,237 @@ impl TracingManager for SimpleTracer {
         key: &str,
         value: serde_json::Value,
     ) -> TracingResult<()> {
+        if !is_recording(span_id) {
+            return Ok(());
+        }
+        self.check_attribute_key(key)?;
+        let id = parse_span_id(span_id)?;
         let mut active_spans = self.active_spans.lock().unwrap();
 
-        if let Some(span) = active_spans.get_mut(span_id) {
+        if let Some(span) = active_spans.get_mut(&id) {
             span.attributes.insert(key.to_string(), value);
             Ok(())
         } else {
-            Err(TylError::validation(
-                "span_id",
-                format!("invalid span ID: {}", span_id),
-            ))
+            Err(invalid_span_id(span_id))
         }
     }
 
     fn get_completed_spans(&self) -> Vec<Span> {
         let completed_spans = self.completed_spans.lock().unwrap();
-        completed_spans.clone()
+        completed_spans.iter().cloned().collect()
     }
 
+    /// Entries breaking the W3C limits are not stored (see
+    /// [`SimpleTracer::try_set_baggage`] for the reason)
     fn set_baggage(&self, key: &str, value: &str) {
-        let mut baggage = self.baggage.lock().unwrap();
-        baggage.insert(key.to_string(), value.to_string());
+        if self.try_set_baggage(key, value).is_err() {
+            self.rejected_baggage.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
+    /// Request-scoped baggage of the attached [`Context`] wins over the
+    /// tracer's own entries
     fn get_baggage(&self, key: &str) -> Option<String> {
+        if let Some(value) = Context::current().baggage(key) {
+            return Some(value.to_string());
+        }
         let baggage = self.baggage.lock().unwrap();
         baggage.get(key).cloned()
     }
 }
+
+/// Adapter - Tracer that records nothing
+///
+/// Zero-sized and fully inlined; useful as a default dependency and for binaries
+/// that want tracing compiled out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopTracer;
+
+impl NoopTracer {
+    pub fn new() -> Self {
+        NoopTracer
+    }
+}
+
+impl TracingManager for NoopTracer {
+    #[inline]
+    fn start_span(
+        &self,
+        _operation_name: &str,
+        _parent_span_id: Option<String>,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    fn start_span_with_kind(
+        &self,
+        _operation_name: &str,
+        _parent_span_id: Option<String>,
+        _kind: SpanKind,
+    ) -> TracingResult<String> {
+        Ok(NOOP_SPAN_ID.to_string())
+    }
+
+    #[inline]
+    fn end_span(&self, _span_id: String) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn add_span_attribute(
+        &self,
+        _span_id: &str,
+        _key: &str,
+        _value: serde_json::Value,
+    ) -> TracingResult<()> {
+        Ok(())
+    }
+
+    #[inline]
+    fn get_completed_spans(&self) -> Vec<Span> {
+        Vec::new()
+    }
+
+    #[inline]
+    fn set_baggage(&self, _key: &str, _value: &str) {}
+
+    #[inline]
+    fn get_baggage(&self, _key: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Tracer built from configuration by [`Tracer::from_config`]
+pub enum Tracer {
+    Simple(Box<SimpleTracer>),
+    Noop(NoopTracer),
+}
+
+/// The exporter an `exporter` config section selects
+fn build_exporter(
+    service_name: &str,
+    exporter: &ExporterConfig,
+) -> TracingResult<Box<dyn SpanExporter>> {
+    Ok(match exporter.kind {
+        ExporterKind::Console => Box::new(ConsoleExporter::new()),
+        ExporterKind::Otlp => Box::new(OtlpHttpExporter::from_config(service_name, exporter)?),
+        ExporterKind::Tempo => Box::new(TempoExporter::from_config(service_name, exporter)?),
+    })
+}
+
+impl Tracer {
+    /// Validate the config and build the matching adapter stack
+    ///
+    /// The `exporter` section selects a [`ConsoleExporter`] or an
+    /// [`OtlpHttpExporter`] behind a batch processor using its batch size and
+    /// the configured flush interval. With the `disabled` feature this is
+    /// always a [`NoopTracer`].
+    pub fn from_config(config: TraceConfig) -> TracingResult<Self> {
+        if cfg!(feature = "disabled") {
+            return Ok(Tracer::Noop(NoopTracer));
+        }
+        config.validate()?;
+        let processor = match &config.exporter {
+            None => None,
+            Some(exporter) => {
+                let processor =
+                    BatchSpanProcessor::from_boxed(build_exporter(&config.service_name, exporter)?)
+                        .with_batch_size(exporter.batch_size);
+                Some(match config.flush_interval {
+                    Some(interval) => processor.with_flush_interval(interval),
+                    None => processor,
+                })
+            }
+        };
+        let tracer = SimpleTracer::new(config);
+        Ok(Tracer::Simple(Box::new(match processor {
+            Some(processor) => tracer.with_batch_processor(processor),
+            None => tracer,
+        })))
+    }
+
+    pub fn health(&self) -> TracerHealth {
+        match self {
+            Tracer::Simple(tracer) => tracer.health(),
+            Tracer::Noop(_) => TracerHealth::disabled(),
+        }
+    }
+
+    pub fn is_saturated(&self) -> bool {
+        self.as_simple().is_some_and(SimpleTracer::is_saturated)
+    }
+
+    /// The underlying SimpleTracer, unless tracing is compiled out
+    pub fn as_simple(&self) -> Option<&SimpleTracer> {
+        match self {
+            Tracer::Simple(tracer) => Some(tracer),
+            Tracer::Noop(_) => None,
+        }
+    }
+
+    fn manager(&self) -> &dyn TracingManager {
+        match self {
+            Tracer::Simple(tracer) => tracer.as_ref(),
+            Tracer::Noop(tracer) => tracer,
+        }
+    }
+}
+
+/// Build the adapter stack for the config's environment as a [`DynTracer`]
+///
+/// Development pretty-prints to the console and staging/production batch to
+/// OTLP (see [`TraceConfig::with_environment`]); Testing records nothing
+/// unless an exporter is configured explicitly. Fails on invalid config.
+pub fn init(config: TraceConfig) -> TracingResult<DynTracer> {
+    if config.environment == Environment::Testing && config.exporter.is_none() {
+        config.validate()?;
+        return Ok(Arc::new(NoopTracer));
+    }
+    Ok(Arc::new(Tracer::from_config(config)?))
+}
+
+impl TracingManager for Tracer {
+    fn start_span(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+    ) -> TracingResult<String> {
+        self.manager().start_span(operation_name, parent_span_id)
+    }
+
+    fn start_span_with_kind(
+        &self,
+        operation_name: &str,
+        parent_span_id: Option<String>,
+        kind: SpanKind,
+    ) -> TracingResult<String> {
+        self.manager()
+            .start_span_with_kind(operation_name, parent_span_id, kind)
+    }
+
+    fn end_span(&self, span_id: String) -> TracingResult<()> {
+        self.manager().end_span(span_id)
+    }
+
+    fn add_span_attribute(
+        &self,
+        span_id: &str,
+        key: &str,
+        value: serde_json::Value,
+    ) -> TracingResult<()> {
+        self.manager().add_span_attribute(span_id, key, value)
+    }
+
+    fn get_completed_spans(&self) -> Vec<Span> {
+        self.manager().get_completed_spans()
+    }
+
+    fn set_baggage(&self, key: &str, value: &str) {
+        self.manager().set_baggage(key, value)
+    }
+
+    fn get_baggage(&self, key: &str) -> Option<String> {
+        self.manager().get_baggage(key)
+    }
+}
@@ -0,0 +1,63 @@
+//! Trace tree module
+//!
+//! Contains TraceTree, a parent/child index over the spans of a trace. Spans
+//! whose parent is not among them are roots, and siblings are ordered by
+//! start time. The tree answers the structural questions analysis needs,
+//! such as how much of a span's duration was spent in the span itself rather
+//! than in its children.
+
+use crate::ids::SpanId;
+use crate::span::Span;
+use std::collections::HashMap;
+
+/// Spans of a trace indexed by parent
+#[derive(Debug, Clone)]
+pub struct TraceTree<'a> {
+    spans: HashMap<SpanId, &'a Span>,
+    children: HashMap<Option<SpanId>, Vec<&'a Span>>,
+}
+
+impl<'a> TraceTree<'a> {
+    pub fn new(spans: &'a [Span]) -> Self {
+        let by_id: HashMap<SpanId, &Span> = spans.iter().map(|span| (span.span_id, span)).collect();
+        let mut children: HashMap<Option<SpanId>, Vec<&Span>> = HashMap::new();
+        for span in spans {
+            let parent = span
+                .parent_span_id
+                .filter(|parent| by_id.contains_key(parent));
+            children.entry(parent).or_default().push(span);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|span| span.start_time);
+        }
+        Self {
+            spans: by_id,
+            children,
+        }
+    }
+
+    pub fn get(&self, span_id: SpanId) -> Option<&'a Span> {
+        self.spans.get(&span_id).copied()
+    }
+
+    pub fn roots(&self) -> &[&'a Span] {
+        self.children.get(&None).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn children(&self, span_id: SpanId) -> &[&'a Span] {
+        self.children.get(&Some(span_id)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of spans below `span_id`
+    pub fn descendant_count(&self, span_id: SpanId) -> usize {
+        self.children(span_id)
+            .iter()
+            .map(|child| 1 + self.descendant_count(child.span_id))
+            .sum()
+    }
+
+    /// Time spent in the span itself (see `Span::self_time`)
+    pub fn self_time(&self, span_id: SpanId) -> Option<u64> {
+        self.get(span_id)?.self_time(self.children(span_id))
+    }
+}
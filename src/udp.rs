@@ -0,0 +1,120 @@
+//! UDP emission module
+//!
+//! Contains UdpExporter, a StatsD-style fire-and-forget emitter sending one
+//! datagram per completed span. Sends never block and never fail the export:
+//! spans that cannot be sent (full socket buffer, oversized datagram,
+//! unreachable agent) are counted and dropped, which keeps the hot path cheap
+//! during incidents when a TCP exporter would stall.
+
+use crate::export::SpanExporter;
+use crate::span::Span;
+use crate::tracer::TracingResult;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tyl_errors::TylError;
+
+/// Default datagram size limit, safe for typical 1500-byte MTUs
+pub const DEFAULT_MAX_DATAGRAM: usize = 1400;
+/// Largest payload of a UDP datagram over IPv4
+const UDP_PAYLOAD_LIMIT: usize = 65_507;
+
+/// Payload encoding of span datagrams
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DatagramEncoding {
+    /// Compact JSON, one span object per datagram
+    #[default]
+    Json,
+    /// MessagePack (`msgpack` feature)
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    /// CBOR (`cbor` feature)
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+/// Adapter - Emits each span as a UDP datagram without waiting or retrying
+#[derive(Debug)]
+pub struct UdpExporter {
+    socket: UdpSocket,
+    target: SocketAddr,
+    encoding: DatagramEncoding,
+    max_datagram: usize,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl UdpExporter {
+    /// Emit to `address` (`host:port`), resolved once here
+    pub fn new(address: impl ToSocketAddrs) -> TracingResult<Self> {
+        let target = address
+            .to_socket_addrs()
+            .map_err(|e| TylError::configuration(format!("invalid UDP address: {}", e)))?
+            .next()
+            .ok_or_else(|| TylError::configuration("UDP address resolved to nothing"))?;
+        let local: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|e| TylError::network(format!("cannot open UDP socket: {}", e)))?;
+        Ok(Self {
+            socket,
+            target,
+            encoding: DatagramEncoding::default(),
+            max_datagram: DEFAULT_MAX_DATAGRAM,
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    pub fn with_encoding(mut self, encoding: DatagramEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Spans encoding to more bytes than this are dropped (capped at 65507)
+    pub fn with_max_datagram(mut self, max_datagram: usize) -> Self {
+        self.max_datagram = max_datagram.min(UDP_PAYLOAD_LIMIT);
+        self
+    }
+
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// Datagrams handed to the OS so far
+    pub fn sent_spans(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Spans dropped because they were too large or could not be sent
+    pub fn dropped_spans(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn encode(&self, span: &Span) -> Option<Vec<u8>> {
+        match self.encoding {
+            DatagramEncoding::Json => serde_json::to_vec(span).ok(),
+            #[cfg(feature = "msgpack")]
+            DatagramEncoding::MessagePack => span.to_msgpack().ok(),
+            #[cfg(feature = "cbor")]
+            DatagramEncoding::Cbor => span.to_cbor().ok(),
+        }
+    }
+}
+
+impl SpanExporter for UdpExporter {
+    fn export(&self, batch: &[Span]) -> TracingResult<()> {
+        for span in batch {
+            let sent = self
+                .encode(span)
+                .filter(|datagram| datagram.len() <= self.max_datagram)
+                .is_some_and(|datagram| self.socket.send_to(&datagram, self.target).is_ok());
+            let counter = if sent { &self.sent } else { &self.dropped };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
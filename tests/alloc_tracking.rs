@@ -0,0 +1,29 @@
+//! Allocation tracking needs its own test binary: it installs a global allocator
+
+use tyl_tracing::{
+    SimpleTracer, TraceConfig, TracingAllocator, TracingManager, ALLOC_BYTES_ATTRIBUTE,
+    ALLOC_COUNT_ATTRIBUTE,
+};
+
+#[global_allocator]
+static ALLOCATOR: TracingAllocator = TracingAllocator::new();
+
+#[test]
+fn test_allocations_recorded_per_span() {
+    let tracer = SimpleTracer::new(TraceConfig::new("alloc-test"));
+
+    let heavy = tracer.start_span("build_buffer", None).unwrap();
+    let buffer = std::hint::black_box(vec![0u8; 64 * 1024]);
+    drop(buffer);
+    tracer.end_span(heavy).unwrap();
+
+    let light = tracer.start_span("noop", None).unwrap();
+    tracer.end_span(light).unwrap();
+
+    let spans = tracer.get_completed_spans();
+    let heavy_bytes = spans[0].attributes[ALLOC_BYTES_ATTRIBUTE].as_u64().unwrap();
+    let light_bytes = spans[1].attributes[ALLOC_BYTES_ATTRIBUTE].as_u64().unwrap();
+    assert!(heavy_bytes >= 64 * 1024);
+    assert!(light_bytes < 64 * 1024);
+    assert!(spans[0].attributes[ALLOC_COUNT_ATTRIBUTE].as_u64().unwrap() >= 1);
+}
@@ -0,0 +1,66 @@
+// Runs the examples/distributed client and server in one process against
+// `OtlpStub`; with the `disabled` feature it only needs to compile
+
+#[allow(dead_code)]
+#[path = "../examples/distributed/client.rs"]
+mod client;
+#[allow(dead_code)]
+#[path = "../examples/distributed/server.rs"]
+mod server;
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+use tyl_tracing::testing::OtlpStub;
+
+#[test]
+fn test_trace_and_baggage_cross_processes() {
+    let collector = OtlpStub::start().unwrap();
+    let endpoint = collector.endpoint().to_string();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server_tracer = server::tracer(&endpoint).unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        server::handle(&server_tracer, stream).unwrap();
+        server_tracer.flush().unwrap();
+    });
+
+    let client_tracer = client::tracer(&endpoint).unwrap();
+    let reply = client::call(&client_tracer, &address, "alice").unwrap();
+    assert_eq!(reply.body, "Hello, alice!");
+    server.join().unwrap();
+    client_tracer.flush().unwrap();
+
+    let spans = collector.wait_for_spans(3, Duration::from_secs(5));
+    let find = |service: &str, name: &str| {
+        spans
+            .iter()
+            .find(|span| span.service_name == service && span.name == name)
+            .unwrap_or_else(|| panic!("no {} span {:?} in {:?}", service, name, spans))
+    };
+    let client_span = find(client::SERVICE_NAME, "GET");
+    let server_span = find(server::SERVICE_NAME, "GET /greet");
+    let lookup_span = find(server::SERVICE_NAME, "load_profile");
+
+    // One trace across both processes, server span parented to the client's
+    assert_eq!(server_span.trace_id, client_span.trace_id);
+    assert_eq!(lookup_span.trace_id, client_span.trace_id);
+    assert_eq!(
+        server_span.parent_span_id.as_ref(),
+        Some(&client_span.span_id)
+    );
+    assert_eq!(
+        lookup_span.parent_span_id.as_ref(),
+        Some(&server_span.span_id)
+    );
+    assert!(client_span.parent_span_id.is_none());
+
+    // Baggage reached every server span of the request
+    assert_eq!(server_span.attributes["user.id"], "alice");
+    assert_eq!(lookup_span.attributes["user.id"], "alice");
+
+    let traceresponse = reply.traceresponse.unwrap();
+    assert!(traceresponse.contains(&server_span.span_id));
+}